@@ -0,0 +1,34 @@
+#import bevy_pbr::{
+    forward_io::VertexOutput,
+    mesh_view_bindings::view,
+}
+
+struct AtmosphereUniform {
+    // Direction towards the sun, in world space
+    sun_direction: vec3<f32>,
+    falloff: f32,
+    center: vec3<f32>,
+    intensity: f32,
+    rayleigh: vec4<f32>,
+    sunset: vec4<f32>,
+}
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(0) var<uniform> atmosphere: AtmosphereUniform;
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let normal = normalize(in.world_position.xyz - atmosphere.center);
+    let to_camera = normalize(view.world_position - in.world_position.xyz);
+
+    // Grazing rays cross the most air, so the glow peaks at the limb
+    let rim = pow(1.0 - max(dot(normal, to_camera), 0.0), atmosphere.falloff);
+
+    // Lit air fades out across the terminator, reddening where the light skims the surface
+    let sun = dot(normal, atmosphere.sun_direction);
+    let daylight = smoothstep(-0.25, 0.25, sun);
+    let sunset = 1.0 - smoothstep(0.0, 0.4, abs(sun));
+    let color = mix(atmosphere.rayleigh.rgb, atmosphere.sunset.rgb, sunset * 0.7);
+
+    let glow = rim * daylight * atmosphere.intensity;
+    return vec4(color * glow, glow);
+}
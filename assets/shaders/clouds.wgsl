@@ -0,0 +1,28 @@
+#import bevy_pbr::forward_io::VertexOutput
+
+struct CloudUniform {
+    // Direction towards the sun, in world space
+    sun_direction: vec3<f32>,
+    opacity: f32,
+    center: vec3<f32>,
+}
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(0) var<uniform> clouds: CloudUniform;
+@group(#{MATERIAL_BIND_GROUP}) @binding(1) var coverage_texture: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(2) var coverage_sampler: sampler;
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let coverage = textureSample(coverage_texture, coverage_sampler, in.uv).r;
+
+    // Clouds on the night side stay faintly visible against the ground
+    let normal = normalize(in.world_position.xyz - clouds.center);
+    let sun = dot(normal, clouds.sun_direction);
+    let brightness = mix(0.04, 1.0, smoothstep(-0.1, 0.3, sun));
+
+    let alpha = coverage * clouds.opacity;
+    if alpha <= 0.0 {
+        discard;
+    }
+    return vec4(vec3(brightness), alpha);
+}
@@ -0,0 +1,241 @@
+#import bevy_pbr::{
+    mesh_functions,
+    pbr_fragment::pbr_input_from_standard_material,
+    pbr_functions::alpha_discard,
+    pbr_types::{PbrInput, STANDARD_MATERIAL_FLAGS_UNLIT_BIT},
+}
+
+#ifdef PREPASS_PIPELINE
+#import bevy_pbr::{
+    prepass_io::{VertexOutput, FragmentOutput},
+    pbr_deferred_functions::deferred_output,
+}
+#else
+#import bevy_pbr::{
+    forward_io::{VertexOutput, FragmentOutput},
+    pbr_functions::{apply_pbr_lighting, main_pass_post_lighting_processing},
+}
+#endif
+
+struct EarthUniform {
+    // Direction towards the sun, in world space
+    sun_direction: vec3<f32>,
+    normal_strength: f32,
+    // rgb = tint, a = strength
+    ocean_tint: vec4<f32>,
+    // x = night lights, y = cloud shadow, z = ocean tint
+    layer_opacity: vec4<f32>,
+    overlay_opacity: vec4<f32>,
+    // 0 = normal, 1 = multiply, 2 = additive, 3 = screen
+    overlay_blend: vec4<u32>,
+    cloud_offset: vec2<f32>,
+    radius: f32,
+    displacement: f32,
+    // Center of the globe in world space
+    center: vec3<f32>,
+    // 0 = lit, 1 = base color, 2 = roughness, 3 = normal, 4 = UV checker, 5 = chunk, 6 = LOD
+    debug_view: u32,
+    // Desaturation outside the explored mask, 0 when exploration mode is off
+    exploration: f32,
+    // UV offset in xy and size in zw of the streamed tile, zero without one
+    tile_rect: vec4<f32>,
+    // rgb = flat land color, a = 1 in the stylized mode
+    style_land: vec4<f32>,
+    // rgb = flat ocean color, a = cel-shading bands
+    style_ocean: vec4<f32>,
+    // rgb = limb outline color, a = width
+    style_outline: vec4<f32>,
+    // x = wave strength, y = frequency, z = phase, w = water roughness, 0 keeps the specular map
+    water: vec4<f32>,
+}
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(100) var<uniform> earth: EarthUniform;
+@group(#{MATERIAL_BIND_GROUP}) @binding(101) var night_texture: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(102) var earth_sampler: sampler;
+@group(#{MATERIAL_BIND_GROUP}) @binding(103) var cloud_texture: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(104) var ocean_mask: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(105) var overlay_0: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(106) var overlay_1: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(109) var explored_mask: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(110) var tile_texture: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(111) var tile_sampler: sampler;
+
+fn blend_overlay(base: vec3<f32>, overlay: vec4<f32>, opacity: f32, mode: u32) -> vec3<f32> {
+    var blended = overlay.rgb;
+    switch mode {
+        case 1u: {
+            blended = base * overlay.rgb;
+        }
+        case 2u: {
+            blended = base + overlay.rgb;
+        }
+        case 3u: {
+            blended = 1.0 - (1.0 - base) * (1.0 - overlay.rgb);
+        }
+        default: {}
+    }
+    return mix(base, blended, overlay.a * opacity);
+}
+
+// Spreads consecutive ids over distinct colors
+fn id_color(id: u32) -> vec3<f32> {
+    var x = id;
+    x ^= x >> 16u;
+    x *= 0x7feb352du;
+    x ^= x >> 15u;
+    x *= 0x846ca68bu;
+    x ^= x >> 16u;
+    return vec3(f32(x & 255u), f32((x >> 8u) & 255u), f32((x >> 16u) & 255u)) / 255.0;
+}
+
+fn debug_color(mode: u32, base: vec3<f32>, pbr_input: PbrInput, uv: vec2<f32>, tag: u32) -> vec3<f32> {
+    switch mode {
+        case 1u: {
+            return base;
+        }
+        case 2u: {
+            return vec3(pbr_input.material.perceptual_roughness);
+        }
+        case 3u: {
+            return pbr_input.N * 0.5 + 0.5;
+        }
+        case 4u: {
+            // 10 degree cells, shaded by the UVs so flipped or swapped axes stand out
+            let cell = vec2<i32>(floor(uv * vec2(36.0, 18.0)));
+            let checker = f32((cell.x + cell.y) & 1);
+            return vec3(uv, 0.5) * mix(0.4, 1.0, checker);
+        }
+        case 5u: {
+            return id_color(tag);
+        }
+        case 6u: {
+            // `ChunkKey::tag` keeps the depth in bits 3 to 7
+            let depth = f32((tag >> 3u) & 31u);
+            return 0.5 + 0.5 * cos(6.28318 * (depth / 8.0 + vec3(0.0, 0.33, 0.67)));
+        }
+        default: {
+            return base;
+        }
+    }
+}
+
+// Slope of a few sine waves travelling across the point `p` of the scaled unit sphere, along
+// the surface with normal `n`. Their speeds are whole multiples of `phase`.
+fn wave_slope(p: vec3<f32>, phase: f32, n: vec3<f32>) -> vec3<f32> {
+    var directions = array<vec3<f32>, 4>(
+        vec3(1.0, 0.3, 0.2),
+        vec3(-0.4, 1.0, 0.5),
+        vec3(0.6, -0.5, 1.0),
+        vec3(-1.0, -0.2, 0.7),
+    );
+    var gradient = vec3(0.0);
+    for (var i = 0; i < 4; i++) {
+        let octave = f32(i + 1);
+        let k = directions[i] * (0.6 + 0.4 * octave);
+        gradient += k * cos(dot(k, p) + phase * octave) / octave;
+    }
+    return gradient - n * dot(gradient, n);
+}
+
+@fragment
+fn fragment(
+    in: VertexOutput,
+    @builtin(front_facing) is_front: bool,
+) -> FragmentOutput {
+    var pbr_input = pbr_input_from_standard_material(in, is_front);
+    let uv = in.uv;
+    var base = pbr_input.material.base_color.rgb;
+
+    // A streamed tile covers this chunk in more detail, the transparent texels it hasn't got
+    // keep the base color texture
+    if earth.tile_rect.z > 0.0 {
+        let tile = textureSample(tile_texture, tile_sampler, (uv - earth.tile_rect.xy) / earth.tile_rect.zw);
+        base = mix(base, tile.rgb, tile.a);
+    }
+    var color = base;
+
+    // The repacked specular map stores roughness in green, water is smooth
+    let ocean = 1.0 - textureSample(ocean_mask, earth_sampler, uv).g;
+    let stylized = earth.style_land.a > 0.0;
+    var water = 0.0;
+    if stylized {
+        // Flat continents and oceans, the textures only tell water from land
+        water = step(0.5, ocean) * earth.layer_opacity.z;
+        color = mix(earth.style_land.rgb, earth.style_ocean.rgb, water);
+    } else {
+        color = mix(color, color * earth.ocean_tint.rgb, ocean * earth.ocean_tint.a * earth.layer_opacity.z);
+    }
+
+    // Slot 0 is the lower layer
+    color = blend_overlay(color, textureSample(overlay_0, earth_sampler, uv), earth.overlay_opacity.x, earth.overlay_blend.x);
+    color = blend_overlay(color, textureSample(overlay_1, earth_sampler, uv), earth.overlay_opacity.y, earth.overlay_blend.y);
+
+    // The cloud layer turns around the globe, so its shadows wrap around in longitude
+    let cloud_uv = vec2(fract(uv.x + earth.cloud_offset.x), uv.y + earth.cloud_offset.y);
+    let cloud = textureSample(cloud_texture, earth_sampler, cloud_uv).r;
+    color *= 1.0 - cloud * earth.layer_opacity.y;
+
+    // Unvisited regions fade to a darker grey
+    let unexplored = (1.0 - textureSample(explored_mask, earth_sampler, uv).r) * earth.exploration;
+    let grey = dot(color, vec3(0.2126, 0.7152, 0.0722)) * 0.6;
+    color = mix(color, vec3(grey), unexplored);
+
+    pbr_input.material.base_color = vec4(color, pbr_input.material.base_color.a);
+    pbr_input.N = normalize(mix(pbr_input.world_normal, pbr_input.N, earth.normal_strength));
+
+    // Animated waves on the water, faded out before they shrink below a pixel and flicker
+    let water_mask = ocean * earth.layer_opacity.z;
+    let wave_point = normalize(in.world_position.xyz - earth.center) * earth.water.y;
+    let waves = earth.water.x * water_mask * saturate(1.5 - length(fwidth(wave_point)));
+    if waves > 0.0 {
+        pbr_input.N = normalize(pbr_input.N - wave_slope(wave_point, earth.water.z, pbr_input.N) * waves);
+    }
+    // Smoother water makes a sharper and brighter sun glint
+    if earth.water.w > 0.0 {
+        pbr_input.material.perceptual_roughness = mix(pbr_input.material.perceptual_roughness, earth.water.w, water_mask);
+    }
+    pbr_input.material.base_color = alpha_discard(pbr_input.material, pbr_input.material.base_color);
+
+    if stylized && earth.debug_view == 0u {
+        let up = normalize(in.world_position.xyz - earth.center);
+        // Cel-shaded oceans, the sunlight falls off in a few flat bands
+        let bands = max(earth.style_ocean.a, 1.0);
+        let light = ceil(max(dot(up, earth.sun_direction), 0.0) * bands) / bands;
+        var styled = mix(color, color * mix(0.35, 1.0, light), water);
+        // The limb outline, where the globe turns away from the camera
+        let outline = 1.0 - step(earth.style_outline.a, dot(up, pbr_input.V));
+        styled = mix(styled, earth.style_outline.rgb, outline);
+        pbr_input.material.base_color = vec4(styled, 1.0);
+        pbr_input.material.flags |= STANDARD_MATERIAL_FLAGS_UNLIT_BIT;
+    }
+
+    if earth.debug_view != 0u {
+        var tag = 0u;
+#ifdef VERTEX_OUTPUT_INSTANCE_INDEX
+        tag = mesh_functions::get_tag(in.instance_index);
+#endif
+        pbr_input.material.base_color = vec4(debug_color(earth.debug_view, base, pbr_input, uv, tag), 1.0);
+        pbr_input.material.flags |= STANDARD_MATERIAL_FLAGS_UNLIT_BIT;
+    }
+
+#ifdef PREPASS_PIPELINE
+    let out = deferred_output(in, pbr_input);
+#else
+    var out: FragmentOutput;
+    if earth.debug_view != 0u || stylized {
+        out.color = pbr_input.material.base_color;
+    } else {
+        out.color = apply_pbr_lighting(pbr_input);
+
+        // City lights fade in across the terminator
+        let sun = dot(normalize(in.world_position.xyz - earth.center), earth.sun_direction);
+        let night = smoothstep(0.1, -0.1, sun);
+        let lights = textureSample(night_texture, earth_sampler, uv).rgb;
+        out.color += vec4(lights * night * earth.layer_opacity.x, 0.0);
+    }
+
+    out.color = main_pass_post_lighting_processing(pbr_input, out.color);
+#endif
+
+    return out;
+}
@@ -0,0 +1,91 @@
+#import bevy_pbr::{
+    mesh_functions,
+    view_transformations::position_world_to_clip,
+}
+
+// Also used for the prepasses, so shadows and depth follow the displaced surface
+#ifdef PREPASS_PIPELINE
+#import bevy_pbr::prepass_io::{Vertex, VertexOutput}
+#else
+#import bevy_pbr::forward_io::{Vertex, VertexOutput}
+#endif
+
+// Must match `EarthUniform` in earth.wgsl
+struct EarthUniform {
+    sun_direction: vec3<f32>,
+    normal_strength: f32,
+    ocean_tint: vec4<f32>,
+    layer_opacity: vec4<f32>,
+    overlay_opacity: vec4<f32>,
+    overlay_blend: vec4<u32>,
+    cloud_offset: vec2<f32>,
+    radius: f32,
+    // World units at the brightest texel of the height map
+    displacement: f32,
+    // Center of the globe in world space
+    center: vec3<f32>,
+    debug_view: u32,
+    exploration: f32,
+    tile_rect: vec4<f32>,
+    style_land: vec4<f32>,
+    style_ocean: vec4<f32>,
+    style_outline: vec4<f32>,
+    water: vec4<f32>,
+}
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(100) var<uniform> earth: EarthUniform;
+@group(#{MATERIAL_BIND_GROUP}) @binding(107) var height_texture: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(108) var height_sampler: sampler;
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    var out: VertexOutput;
+
+    // Only the direction of the generated vertex matters, the radius comes from the uniform
+    var radius = earth.radius;
+#ifdef VERTEX_UVS_A
+    if earth.displacement > 0.0 {
+        radius += textureSampleLevel(height_texture, height_sampler, vertex.uv, 0.0).r * earth.displacement;
+    }
+#endif
+    // Skirt vertices were generated below the surface, and stay that far below the displaced one
+    let lowered = min(length(vertex.position) / earth.radius, 1.0);
+    let position = normalize(vertex.position) * radius * lowered;
+
+    let world_from_local = mesh_functions::get_world_from_local(vertex.instance_index);
+#ifdef PREPASS_PIPELINE
+#ifdef NORMAL_PREPASS_OR_DEFERRED_PREPASS
+    out.world_normal = mesh_functions::mesh_normal_local_to_world(vertex.normal, vertex.instance_index);
+#endif
+#else
+    out.world_normal = mesh_functions::mesh_normal_local_to_world(vertex.normal, vertex.instance_index);
+#endif
+    out.world_position = mesh_functions::mesh_position_local_to_world(world_from_local, vec4(position, 1.0));
+    out.position = position_world_to_clip(out.world_position.xyz);
+#ifdef UNCLIPPED_DEPTH_ORTHO_EMULATION
+    out.unclipped_depth = out.position.z;
+    out.position.z = min(out.position.z, 1.0);
+#endif
+#ifdef MOTION_VECTOR_PREPASS
+    let previous_world_from_local = mesh_functions::get_previous_world_from_local(vertex.instance_index);
+    out.previous_world_position = mesh_functions::mesh_position_local_to_world(previous_world_from_local, vec4(position, 1.0));
+#endif
+
+#ifdef VERTEX_UVS_A
+    out.uv = vertex.uv;
+#endif
+#ifdef VERTEX_TANGENTS
+#ifdef PREPASS_PIPELINE
+#ifdef NORMAL_PREPASS_OR_DEFERRED_PREPASS
+    out.world_tangent = mesh_functions::mesh_tangent_local_to_world(world_from_local, vertex.tangent, vertex.instance_index);
+#endif
+#else
+    out.world_tangent = mesh_functions::mesh_tangent_local_to_world(world_from_local, vertex.tangent, vertex.instance_index);
+#endif
+#endif
+#ifdef VERTEX_OUTPUT_INSTANCE_INDEX
+    out.instance_index = vertex.instance_index;
+#endif
+
+    return out;
+}
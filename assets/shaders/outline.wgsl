@@ -0,0 +1,57 @@
+#import bevy_pbr::{
+    mesh_functions::{get_world_from_local, mesh_position_local_to_world},
+    mesh_view_bindings::view,
+    view_transformations::position_world_to_clip,
+}
+
+struct OutlineUniform {
+    color: vec4<f32>,
+    // Local point the shell is pushed away from
+    center: vec3<f32>,
+    // Pixels beyond the silhouette
+    width: f32,
+    glow: u32,
+}
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(0) var<uniform> outline: OutlineUniform;
+
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+    @location(0) position: vec3<f32>,
+    @location(1) uv: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+fn to_clip(world_from_local: mat4x4<f32>, position: vec3<f32>) -> vec4<f32> {
+    return position_world_to_clip(mesh_position_local_to_world(world_from_local, vec4(position, 1.0)).xyz);
+}
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    let world_from_local = get_world_from_local(vertex.instance_index);
+    var clip = to_clip(world_from_local, vertex.position);
+    let center = to_clip(world_from_local, outline.center);
+
+    // Away from the center in pixels, so the offset is the same in every direction
+    let away = (clip.xy / clip.w - center.xy / center.w) * view.viewport.zw;
+    let direction = select(vec2(0.0), normalize(away), length(away) > 1e-4);
+    clip = vec4(clip.xy + direction * outline.width * 2.0 / view.viewport.zw * clip.w, clip.zw);
+
+    var out: VertexOutput;
+    out.position = clip;
+    out.uv = vertex.uv;
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    var alpha = outline.color.a;
+    if outline.glow != 0u {
+        alpha *= 1.0 - smoothstep(0.25, 0.5, length(in.uv - 0.5));
+    }
+    return vec4(outline.color.rgb, alpha);
+}
@@ -0,0 +1,148 @@
+#import bevy_pbr::{
+    mesh_functions::{get_world_from_local, mesh_position_local_to_world},
+    mesh_view_bindings::{globals, view},
+    view_transformations::position_world_to_clip,
+}
+
+struct PolylineUniform {
+    color: vec4<f32>,
+    // Width in pixels
+    width: f32,
+    // 0 = miter, 1 = round
+    join: u32,
+    miter_limit: f32,
+    world_per_pixel: f32,
+    // Two dash and gap pairs in pixels
+    dash: vec4<f32>,
+    gap_color: vec4<f32>,
+    // Pixels per second
+    dash_speed: f32,
+    end_color: vec4<f32>,
+    line_length: f32,
+    // x = pulses per second, y = tail length, z = brightness
+    pulse: vec4<f32>,
+}
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(0) var<uniform> polyline: PolylineUniform;
+
+const JOIN_ROUND: u32 = 1u;
+
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+    @location(0) position: vec3<f32>,
+    @location(1) other: vec3<f32>,
+    @location(2) neighbor: vec3<f32>,
+    // x = side, y = 0 at the start of the segment and 1 at its end
+    @location(3) segment: vec2<f32>,
+    @location(4) distance: f32,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    // Pixels along the segment from its start, and across from its center line
+    @location(0) @interpolate(linear) local: vec2<f32>,
+    // Length of the segment in pixels
+    @location(1) @interpolate(flat) length: f32,
+    @location(2) distance: f32,
+}
+
+fn to_clip(world_from_local: mat4x4<f32>, position: vec3<f32>) -> vec4<f32> {
+    return position_world_to_clip(mesh_position_local_to_world(world_from_local, vec4(position, 1.0)).xyz);
+}
+
+fn to_screen(clip: vec4<f32>) -> vec2<f32> {
+    return clip.xy / clip.w * 0.5 * view.viewport.zw;
+}
+
+fn perpendicular(direction: vec2<f32>) -> vec2<f32> {
+    return vec2(-direction.y, direction.x);
+}
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    let world_from_local = get_world_from_local(vertex.instance_index);
+    let clip = to_clip(world_from_local, vertex.position);
+    let own = to_screen(clip);
+    let other = to_screen(to_clip(world_from_local, vertex.other));
+    let neighbor = to_screen(to_clip(world_from_local, vertex.neighbor));
+
+    // One extra pixel on each side for the antialiased edge
+    let half_width = polyline.width * 0.5 + 1.0;
+    let side = vertex.segment.x;
+
+    // Both directions point away from the segment at this vertex's end
+    let segment = own - other;
+    let segment_length = length(segment);
+    let outward = select(vec2(1.0, 0.0), segment / segment_length, segment_length > 1e-4);
+    let normal = perpendicular(outward);
+
+    var offset = normal * side * half_width;
+    if polyline.join == JOIN_ROUND {
+        // Extended past the end, the fragment shader rounds the overhang off
+        offset += outward * half_width;
+    } else {
+        let next = neighbor - own;
+        let sum = normal + perpendicular(normalize(next));
+        // Line ends and full turnarounds keep the plain butt end
+        if length(next) > 1e-4 && length(sum) > 1e-3 {
+            let miter = normalize(sum);
+            let scale = 1.0 / max(dot(miter, normal), 1.0 / polyline.miter_limit);
+            offset = miter * side * half_width * scale;
+        }
+    }
+
+    var out: VertexOutput;
+    out.position = clip + vec4(offset / (0.5 * view.viewport.zw) * clip.w, 0.0, 0.0);
+    let along = dot(offset, outward);
+    out.local = vec2(select(-along, segment_length + along, vertex.segment.y > 0.5), dot(offset, normal) * side);
+    out.length = segment_length;
+    out.distance = vertex.distance;
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let half_width = polyline.width * 0.5;
+
+    var distance = abs(in.local.y);
+    if polyline.join == JOIN_ROUND {
+        let overhang = max(-in.local.x, in.local.x - in.length);
+        if overhang > 0.0 {
+            distance = length(vec2(overhang, in.local.y));
+        }
+    }
+    let coverage = clamp(half_width + 0.5 - distance, 0.0, 1.0);
+
+    let t = clamp(in.distance / polyline.line_length, 0.0, 1.0);
+    var color = mix(polyline.color, polyline.end_color, t);
+    if polyline.pulse.x > 0.0 {
+        color = vec4(color.rgb + pulse(t), color.a);
+    }
+    if polyline.dash.x > 0.0 && !in_dash(in.distance / polyline.world_per_pixel - globals.time * polyline.dash_speed) {
+        color = polyline.gap_color;
+    }
+
+    if coverage * color.a <= 0.0 {
+        discard;
+    }
+    return vec4(color.rgb, color.a * coverage);
+}
+
+// Brightness of the pulse at `t` along the line, slow at takeoff and landing
+fn pulse(t: f32) -> f32 {
+    let progress = fract(globals.time * polyline.pulse.x);
+    let head = progress * progress * (3.0 - 2.0 * progress);
+    let behind = head - t;
+    if behind < 0.0 || behind > polyline.pulse.y {
+        return 0.0;
+    }
+    return polyline.pulse.z * (1.0 - behind / polyline.pulse.y);
+}
+
+// Whether `position` pixels along the line fall on one of the two dashes of the pattern
+fn in_dash(position: f32) -> bool {
+    let dash = polyline.dash;
+    let period = dash.x + dash.y + dash.z + dash.w;
+    let phase = position - floor(position / period) * period;
+    return phase < dash.x || (phase >= dash.x + dash.y && phase < dash.x + dash.y + dash.z);
+}
@@ -0,0 +1,67 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+struct PostProcessUniform {
+    sharpen: f32,
+    aberration: f32,
+    grain: f32,
+    vignette: f32,
+    // Seconds, to animate the grain
+    time: f32,
+}
+
+@group(0) @binding(0) var screen_texture: texture_2d<f32>;
+@group(0) @binding(1) var screen_sampler: sampler;
+@group(0) @binding(2) var<uniform> settings: PostProcessUniform;
+
+fn screen(uv: vec2<f32>) -> vec4<f32> {
+    return textureSample(screen_texture, screen_sampler, uv);
+}
+
+// Unsharp mask: pushes each pixel away from the average of its neighbours
+@fragment
+fn sharpen(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(screen_texture));
+    let center = screen(in.uv);
+    let neighbours = screen(in.uv + vec2(texel.x, 0.0)).rgb
+        + screen(in.uv - vec2(texel.x, 0.0)).rgb
+        + screen(in.uv + vec2(0.0, texel.y)).rgb
+        + screen(in.uv - vec2(0.0, texel.y)).rgb;
+    let amount = settings.sharpen * 2.0;
+    let color = center.rgb * (1.0 + 4.0 * amount) - neighbours * amount;
+    return vec4(max(color, vec3(0.0)), center.a);
+}
+
+// Samples red and blue apart along the direction from the center
+@fragment
+fn chromatic_aberration(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let offset = (in.uv - 0.5) * settings.aberration * 0.01;
+    let center = screen(in.uv);
+    let red = screen(in.uv + offset).r;
+    let blue = screen(in.uv - offset).b;
+    return vec4(red, center.g, blue, center.a);
+}
+
+fn hash(p: vec2<f32>) -> f32 {
+    let q = fract(p * vec2(123.34, 456.21));
+    let r = q + dot(q, q + 45.32);
+    return fract(r.x * r.y);
+}
+
+@fragment
+fn film_grain(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let color = screen(in.uv);
+    let pixel = floor(in.position.xy);
+    // A new pattern every frame at 24 fps, like film
+    let frame = floor(settings.time * 24.0);
+    let noise = hash(pixel + frame * vec2(17.0, 31.0)) - 0.5;
+    return vec4(max(color.rgb + noise * settings.grain, vec3(0.0)), color.a);
+}
+
+@fragment
+fn vignette(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let color = screen(in.uv);
+    // Zero at the center, one in the corners
+    let radius = length(in.uv - 0.5) * sqrt(2.0);
+    let shade = 1.0 - settings.vignette * smoothstep(0.3, 1.0, radius);
+    return vec4(color.rgb * shade, color.a);
+}
@@ -0,0 +1,150 @@
+use bevy::{
+    app::{App, Plugin, PostUpdate},
+    asset::Assets,
+    camera::{Camera, CameraUpdateSystems, Projection},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::{With, Without},
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Query, Res, ResMut, Single},
+    },
+    image::Image,
+    state::condition::in_state,
+    transform::{
+        TransformSystems,
+        components::{GlobalTransform, Transform},
+    },
+};
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, EguiTextureHandle, egui};
+
+use crate::{
+    component::{Earth, MainCamera},
+    gui::format_coordinates,
+    math::Coordinates,
+    offscreen::{OffscreenTarget, sync_offscreen_camera},
+    state::GameState,
+};
+
+/// Width and height of the picture-in-picture, in pixels.
+const ANTIPODE_SIZE: u32 = 256;
+
+/// Picture-in-picture of the point opposite to the view center, seen by a second camera
+/// mirrored through the globe's center.
+#[derive(Resource)]
+pub struct AntipodeView {
+    pub enabled: bool,
+    target: OffscreenTarget,
+}
+
+impl Default for AntipodeView {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            target: OffscreenTarget::new(ANTIPODE_SIZE),
+        }
+    }
+}
+
+/// Offscreen camera of the `AntipodeView`, only alive while it is enabled.
+#[derive(Component)]
+struct AntipodeCamera;
+
+pub struct AntipodePlugin;
+
+impl Plugin for AntipodePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AntipodeView>()
+            .add_systems(
+                PostUpdate,
+                (spawn_antipode_camera, follow_main_camera)
+                    .chain()
+                    .before(CameraUpdateSystems)
+                    .before(TransformSystems::Propagate)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                EguiPrimaryContextPass,
+                display_antipode
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(|view: Res<AntipodeView>| view.enabled),
+            );
+    }
+}
+
+/// Spawns the camera when the view is enabled and despawns it again when it is closed, so it
+/// costs nothing while hidden.
+fn spawn_antipode_camera(
+    mut commands: Commands,
+    mut view: ResMut<AntipodeView>,
+    mut images: ResMut<Assets<Image>>,
+    cameras: Query<Entity, With<AntipodeCamera>>,
+) {
+    let enabled = view.enabled;
+    sync_offscreen_camera(
+        &mut commands,
+        enabled,
+        &cameras,
+        &mut view.target,
+        &mut images,
+        AntipodeCamera,
+        Camera::default(),
+    );
+}
+
+/// Mirrors the main camera through the globe's center each frame, keeping its field of view and
+/// screen-up direction.
+fn follow_main_camera(
+    main: Single<(&Transform, &Projection), (With<MainCamera>, Without<AntipodeCamera>)>,
+    earth: Single<&Transform, (With<Earth>, Without<AntipodeCamera>)>,
+    mut cameras: Query<(&mut Transform, &mut Projection), With<AntipodeCamera>>,
+) {
+    let (main, main_projection) = *main;
+    let center = earth.translation;
+    for (mut transform, mut projection) in &mut cameras {
+        *transform = Transform::from_translation(2. * center - main.translation)
+            .looking_at(center, main.up());
+
+        if let (Projection::Perspective(main), Projection::Perspective(mirror)) =
+            (main_projection, &mut *projection)
+            && mirror.fov != main.fov
+        {
+            mirror.fov = main.fov;
+        }
+    }
+}
+
+fn display_antipode(
+    mut contexts: EguiContexts,
+    mut view: ResMut<AntipodeView>,
+    main: Single<&GlobalTransform, With<MainCamera>>,
+    earth: Single<&GlobalTransform, With<Earth>>,
+) -> bevy::prelude::Result {
+    let Some(image) = view.target.image() else {
+        return Ok(());
+    };
+    let texture = contexts.add_image(EguiTextureHandle::Strong(image.clone()));
+    let ctx = contexts.ctx_mut()?;
+
+    // The view center seen from the other side of the globe, in its local space
+    let (_, rotation, center) = earth.to_scale_rotation_translation();
+    let antipode = rotation.inverse() * (center - main.translation());
+    let coordinates = Coordinates::from(antipode);
+
+    let mut open = view.enabled;
+    egui::Window::new("Antipode")
+        .open(&mut open)
+        .resizable(false)
+        .anchor(egui::Align2::RIGHT_BOTTOM, [-10., -40.])
+        .show(ctx, |ui| {
+            ui.image(egui::load::SizedTexture::new(
+                texture,
+                egui::vec2(ANTIPODE_SIZE as f32, ANTIPODE_SIZE as f32),
+            ));
+            ui.label(format_coordinates(coordinates));
+        });
+    view.enabled = open;
+
+    Ok(())
+}
@@ -0,0 +1,202 @@
+use bevy::{
+    app::{App, Plugin, Startup, Update},
+    asset::{Asset, Assets, Handle},
+    camera::visibility::Visibility,
+    color::LinearRgba,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        hierarchy::ChildOf,
+        query::{Added, With},
+        resource::Resource,
+        schedule::{IntoScheduleConfigs, SystemCondition},
+        system::{Commands, Query, Res, ResMut, Single},
+    },
+    math::{Vec3, primitives::Sphere},
+    mesh::{Mesh, Mesh3d, Meshable},
+    pbr::{Material, MaterialPlugin, MeshMaterial3d},
+    picking::Pickable,
+    reflect::Reflect,
+    render::{
+        alpha::AlphaMode,
+        render_resource::{AsBindGroup, ShaderType},
+    },
+    shader::ShaderRef,
+    state::condition::in_state,
+    transform::components::GlobalTransform,
+};
+
+use crate::{
+    EarthConfig,
+    component::{Earth, RotatingLight},
+    state::GameState,
+};
+
+const SHADER_PATH: &str = "shaders/atmosphere.wgsl";
+
+/// Radius of the shell relative to the globe, a lot thicker than the real one to be visible from
+/// afar.
+const SHELL_SCALE: f32 = 1.025;
+
+/// Sectors and stacks of the shell's UV sphere.
+const SHELL_RESOLUTION: (u32, u32) = (128, 64);
+
+#[derive(ShaderType, Reflect, Debug, Clone, Copy)]
+pub struct AtmosphereUniform {
+    /// Direction towards the sun, in world space
+    pub sun_direction: Vec3,
+    /// Exponent sharpening the glow towards the limb
+    pub falloff: f32,
+    /// Center of the globe in world space
+    pub center: Vec3,
+    pub intensity: f32,
+    /// Scattered color on the day side
+    pub rayleigh: LinearRgba,
+    /// Color along the terminator, where the light crosses the most air
+    pub sunset: LinearRgba,
+}
+
+impl Default for AtmosphereUniform {
+    fn default() -> Self {
+        Self {
+            sun_direction: Vec3::Z,
+            falloff: 3.,
+            center: Vec3::ZERO,
+            intensity: 1.2,
+            rayleigh: LinearRgba::rgb(0.3, 0.55, 1.),
+            sunset: LinearRgba::rgb(1., 0.45, 0.2),
+        }
+    }
+}
+
+/// Glow around the rim of the globe, bright on the day side and fading out across the
+/// terminator.
+#[derive(Asset, AsBindGroup, Reflect, Debug, Clone, Default)]
+pub struct AtmosphereMaterial {
+    #[uniform(0)]
+    pub uniform: AtmosphereUniform,
+}
+
+impl Material for AtmosphereMaterial {
+    fn fragment_shader() -> ShaderRef {
+        SHADER_PATH.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        // Scattered light only ever adds to what is behind the shell
+        AlphaMode::Add
+    }
+
+    fn enable_prepass() -> bool {
+        false
+    }
+
+    fn enable_shadows() -> bool {
+        false
+    }
+}
+
+/// Whether the atmosphere shell is drawn, starting out as set in `EarthConfig`.
+#[derive(Resource, Debug)]
+pub struct Atmosphere {
+    pub enabled: bool,
+    material: Handle<AtmosphereMaterial>,
+    mesh: Handle<Mesh>,
+}
+
+/// The translucent shell, a child of the `Earth`.
+#[derive(Component)]
+struct AtmosphereShell;
+
+pub struct AtmospherePlugin;
+
+impl Plugin for AtmospherePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(MaterialPlugin::<AtmosphereMaterial>::default())
+            .add_systems(Startup, create_atmosphere)
+            .add_systems(
+                Update,
+                (spawn_shell, apply_atmosphere, track_sun)
+                    .chain()
+                    .run_if(in_state(GameState::Loading).or(in_state(GameState::Playing))),
+            );
+    }
+}
+
+fn create_atmosphere(
+    mut commands: Commands,
+    config: Res<EarthConfig>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<AtmosphereMaterial>>,
+) {
+    let (sectors, stacks) = SHELL_RESOLUTION;
+    commands.insert_resource(Atmosphere {
+        enabled: config.atmosphere,
+        material: materials.add(AtmosphereMaterial::default()),
+        mesh: meshes.add(
+            Sphere::new(config.radius * SHELL_SCALE)
+                .mesh()
+                .uv(sectors, stacks),
+        ),
+    });
+}
+
+/// Gives every newly spawned globe its shell.
+fn spawn_shell(
+    mut commands: Commands,
+    atmosphere: Res<Atmosphere>,
+    earths: Query<Entity, Added<Earth>>,
+) {
+    for earth in &earths {
+        commands.spawn((
+            Mesh3d(atmosphere.mesh.clone()),
+            MeshMaterial3d(atmosphere.material.clone()),
+            Visibility::Inherited,
+            ChildOf(earth),
+            // Clicks go through to the globe underneath
+            Pickable::IGNORE,
+            AtmosphereShell,
+        ));
+    }
+}
+
+fn apply_atmosphere(
+    atmosphere: Res<Atmosphere>,
+    mut shells: Query<&mut Visibility, With<AtmosphereShell>>,
+) {
+    let visibility = if atmosphere.enabled {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    for mut shell in &mut shells {
+        if *shell != visibility {
+            *shell = visibility;
+        }
+    }
+}
+
+fn track_sun(
+    atmosphere: Res<Atmosphere>,
+    mut materials: ResMut<Assets<AtmosphereMaterial>>,
+    light: Single<&GlobalTransform, With<RotatingLight>>,
+    earth: Single<&GlobalTransform, With<Earth>>,
+) {
+    if !atmosphere.enabled {
+        return;
+    }
+    let sun_direction = RotatingLight::sun_direction(&light);
+    let center = earth.translation();
+    // Writing the material prepares it again, like the Earth's `track_sun` only do so on change
+    let Some(material) = materials.get(&atmosphere.material) else {
+        return;
+    };
+    if material.uniform.sun_direction == sun_direction && material.uniform.center == center {
+        return;
+    }
+
+    if let Some(material) = materials.get_mut(&atmosphere.material) {
+        material.uniform.sun_direction = sun_direction;
+        material.uniform.center = center;
+    }
+}
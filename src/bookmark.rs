@@ -0,0 +1,219 @@
+use std::time::Duration;
+
+use bevy::{
+    app::{App, Plugin},
+    camera::{Camera, Projection},
+    ecs::{
+        query::{With, Without},
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Local, Res, ResMut, Single},
+    },
+    log::error,
+    state::condition::in_state,
+    transform::components::GlobalTransform,
+};
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    EarthConfig,
+    component::{Earth, MainCamera},
+    gui::format_coordinates,
+    math::{Coordinates, ray_sphere_intersection},
+    navigation::{FlyTo, altitude_for_fov},
+    palette::{PaletteCommand, RegisterCommand},
+    state::GameState,
+    toast::Toasts,
+};
+
+const BOOKMARKS_PATH: &str = "bookmarks.ron";
+
+/// Seconds the flight back to a bookmark takes.
+const BOOKMARK_FLIGHT_SECONDS: f32 = 2.;
+
+/// A saved view: the point at the view center and how far it was zoomed in.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Bookmark {
+    pub name: String,
+    /// In degrees
+    pub latitude: f32,
+    pub longitude: f32,
+    /// Altitude in km the view shows as much ground as, see `FlyTo`
+    pub altitude: f32,
+}
+
+impl Bookmark {
+    pub fn coordinates(&self) -> Option<Coordinates> {
+        Coordinates::from_degrees(self.latitude, self.longitude).ok()
+    }
+
+    /// The flight back to this view.
+    pub fn fly_to(&self) -> Option<FlyTo> {
+        Some(FlyTo {
+            coordinates: self.coordinates()?,
+            altitude: self.altitude,
+            duration: Duration::from_secs_f32(BOOKMARK_FLIGHT_SECONDS),
+        })
+    }
+}
+
+/// Saved views, persisted to `bookmarks.ron` in the working directory.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Bookmarks(pub Vec<Bookmark>);
+
+impl Bookmarks {
+    fn load() -> Self {
+        let Ok(serialized) = std::fs::read_to_string(BOOKMARKS_PATH) else {
+            return Self::default();
+        };
+        ron::from_str(&serialized).unwrap_or_else(|err| {
+            error!("Failed to parse {BOOKMARKS_PATH}: {err}");
+            Self::default()
+        })
+    }
+
+    fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let serialized = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        std::fs::write(BOOKMARKS_PATH, serialized)?;
+        Ok(())
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct BookmarksPanel {
+    pub open: bool,
+}
+
+pub struct BookmarkPlugin;
+
+impl Plugin for BookmarkPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Bookmarks::load())
+            .init_resource::<BookmarksPanel>()
+            .add_systems(
+                EguiPrimaryContextPass,
+                display_bookmarks_panel
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(|panel: Res<BookmarksPanel>| panel.open),
+            )
+            .register_command_provider(bookmark_commands);
+    }
+}
+
+/// A palette command per bookmark, flying to it.
+fn bookmark_commands(bookmarks: Res<Bookmarks>) -> Vec<PaletteCommand> {
+    bookmarks
+        .0
+        .iter()
+        .map(|bookmark| {
+            let bookmark = bookmark.clone();
+            PaletteCommand::new(format!("Go to bookmark {}", bookmark.name), move |world| {
+                if let Some(flight) = bookmark.fly_to() {
+                    world.write_message(flight);
+                }
+            })
+        })
+        .collect()
+}
+
+/// The current view as a bookmark, unless the view center misses the globe.
+fn capture(
+    name: String,
+    camera: (&Camera, &GlobalTransform, &Projection),
+    earth: &GlobalTransform,
+    config: &EarthConfig,
+) -> Option<Bookmark> {
+    let (camera, transform, projection) = camera;
+    let Projection::Perspective(perspective) = projection else {
+        return None;
+    };
+    let hit = camera
+        .logical_viewport_size()
+        .and_then(|viewport| camera.viewport_to_world(transform, viewport / 2.).ok())
+        .and_then(|ray| ray_sphere_intersection(ray, earth.translation(), config.radius))?;
+    let (latitude, longitude) =
+        Coordinates::from(earth.affine().inverse().transform_point3(hit)).as_degrees();
+
+    let camera_altitude = transform.translation().distance(earth.translation()) - config.radius;
+    Some(Bookmark {
+        name,
+        latitude,
+        longitude,
+        altitude: altitude_for_fov(perspective.fov, camera_altitude) * config.km_per_unit(),
+    })
+}
+
+fn display_bookmarks_panel(
+    mut contexts: EguiContexts,
+    mut commands: Commands,
+    mut panel: ResMut<BookmarksPanel>,
+    mut bookmarks: ResMut<Bookmarks>,
+    camera: Single<(&Camera, &GlobalTransform, &Projection), With<MainCamera>>,
+    earth: Single<&GlobalTransform, (With<Earth>, Without<Camera>)>,
+    config: Res<EarthConfig>,
+    mut toasts: ResMut<Toasts>,
+    mut new_name: Local<String>,
+) -> bevy::prelude::Result {
+    let ctx = contexts.ctx_mut()?;
+
+    let mut go = None;
+    let mut remove = None;
+    let mut add = false;
+    egui::Window::new("Bookmarks")
+        .open(&mut panel.open)
+        .resizable(false)
+        .show(ctx, |ui| {
+            egui::Grid::new("Bookmarks").striped(true).show(ui, |ui| {
+                for (index, bookmark) in bookmarks.0.iter().enumerate() {
+                    ui.label(bookmark.name.as_str());
+                    ui.label(
+                        bookmark
+                            .coordinates()
+                            .map_or_else(String::new, format_coordinates),
+                    );
+                    if ui.small_button("Go").clicked() {
+                        go = Some(index);
+                    }
+                    if ui.small_button("🗑").on_hover_text("Delete").clicked() {
+                        remove = Some(index);
+                    }
+                    ui.end_row();
+                }
+            });
+            if bookmarks.0.is_empty() {
+                ui.label("No bookmarks yet");
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut *new_name).hint_text("Name"));
+                add = ui
+                    .add_enabled(
+                        !new_name.trim().is_empty(),
+                        egui::Button::new("Bookmark view"),
+                    )
+                    .clicked();
+            });
+        });
+
+    if let Some(flight) = go.and_then(|index| bookmarks.0[index].fly_to()) {
+        commands.write_message(flight);
+    }
+
+    let mut changed = false;
+    if let Some(index) = remove {
+        bookmarks.0.remove(index);
+        changed = true;
+    }
+    if add && let Some(bookmark) = capture(new_name.trim().to_string(), *camera, *earth, &config) {
+        bookmarks.0.push(bookmark);
+        new_name.clear();
+        changed = true;
+    }
+    if changed && let Err(err) = bookmarks.save() {
+        toasts.error(format!("Failed to save {BOOKMARKS_PATH}: {err}"));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,223 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    camera::Camera,
+    ecs::{
+        query::{With, Without},
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Res, ResMut, Single},
+    },
+    log::error,
+    math::Vec3,
+    state::{condition::in_state, state::OnEnter},
+    time::{Real, Time},
+    transform::components::GlobalTransform,
+};
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    EarthConfig,
+    component::{Earth, MainCamera},
+    math::{Coordinates, point_in_polygon, ray_sphere_intersection},
+    pack::EarthPacks,
+    state::GameState,
+};
+
+/// Country outlines of a pack, looked up for the border crossing toasts.
+const COUNTRIES_FILE: &str = "countries.ron";
+
+/// How long the toast stays on screen, fading out over the last `TOAST_FADE_SECONDS`.
+const TOAST_SECONDS: f32 = 2.5;
+
+const TOAST_FADE_SECONDS: f32 = 0.5;
+
+/// Angle the view center has to move by before the countries are looked up again, in radians.
+const RECHECK_ANGLE: f32 = 1e-3;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Country {
+    pub name: String,
+    /// Outer rings of the country's polygons as (longitude, latitude) in degrees, split at the
+    /// antimeridian
+    pub polygons: Vec<Vec<[f32; 2]>>,
+}
+
+impl Country {
+    /// Bounds of each polygon as [west, south, east, north], to skip most of them cheaply.
+    fn bounds(&self) -> Vec<[f32; 4]> {
+        self.polygons
+            .iter()
+            .map(|ring| {
+                ring.iter().fold(
+                    [f32::MAX, f32::MAX, f32::MIN, f32::MIN],
+                    |[west, south, east, north], &[lon, lat]| {
+                        [west.min(lon), south.min(lat), east.max(lon), north.max(lat)]
+                    },
+                )
+            })
+            .collect()
+    }
+
+    fn contains(&self, bounds: &[[f32; 4]], point: [f32; 2]) -> bool {
+        let [lon, lat] = point;
+        self.polygons
+            .iter()
+            .zip(bounds)
+            .any(|(ring, &[west, south, east, north])| {
+                (west..=east).contains(&lon)
+                    && (south..=north).contains(&lat)
+                    && point_in_polygon(point, ring)
+            })
+    }
+}
+
+/// Shows a toast naming the country the view center moves into, from the active pack's
+/// `countries.ron`.
+#[derive(Resource, Default)]
+pub struct BorderCrossings {
+    pub enabled: bool,
+    countries: Vec<Country>,
+    /// `Country::bounds` of each country
+    bounds: Vec<Vec<[f32; 4]>>,
+    /// Index of the country under the view center
+    current: Option<usize>,
+    /// Direction of the view center when the countries were last looked up
+    checked_at: Option<Vec3>,
+    /// Text and real time the toast was shown at
+    toast: Option<(String, f32)>,
+}
+
+impl BorderCrossings {
+    /// Whether the active pack has any outlines to look up.
+    pub fn is_available(&self) -> bool {
+        !self.countries.is_empty()
+    }
+
+    pub fn countries(&self) -> &[Country] {
+        &self.countries
+    }
+
+    fn country_at(&self, coordinates: Coordinates) -> Option<usize> {
+        let (lat, lon) = coordinates.as_degrees();
+        (0..self.countries.len())
+            .find(|&index| self.countries[index].contains(&self.bounds[index], [lon, lat]))
+    }
+}
+
+pub struct BorderCrossingsPlugin;
+
+impl Plugin for BorderCrossingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BorderCrossings>()
+            .add_systems(OnEnter(GameState::Playing), load_countries)
+            .add_systems(
+                Update,
+                detect_crossings
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(|borders: Res<BorderCrossings>| borders.enabled),
+            )
+            .add_systems(
+                EguiPrimaryContextPass,
+                display_toast
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(|borders: Res<BorderCrossings>| borders.toast.is_some()),
+            );
+    }
+}
+
+fn load_countries(mut borders: ResMut<BorderCrossings>, packs: Res<EarthPacks>) {
+    let path = packs.active().root.join(COUNTRIES_FILE);
+    let countries = match std::fs::read_to_string(&path) {
+        Ok(serialized) => ron::from_str::<Vec<Country>>(&serialized).unwrap_or_else(|err| {
+            error!("Failed to parse {}: {err}", path.display());
+            Vec::new()
+        }),
+        Err(_) => Vec::new(),
+    };
+
+    *borders = BorderCrossings {
+        enabled: borders.enabled,
+        bounds: countries.iter().map(Country::bounds).collect(),
+        countries,
+        ..Default::default()
+    };
+}
+
+fn detect_crossings(
+    mut borders: ResMut<BorderCrossings>,
+    time: Res<Time<Real>>,
+    config: Res<EarthConfig>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera>>,
+    earth: Single<&GlobalTransform, (With<Earth>, Without<Camera>)>,
+) {
+    if !borders.is_available() {
+        return;
+    }
+    let (camera, transform) = *camera;
+    let Some(hit) = camera
+        .logical_viewport_size()
+        .and_then(|size| camera.viewport_to_world(transform, size / 2.).ok())
+        .and_then(|ray| ray_sphere_intersection(ray, earth.translation(), config.radius))
+    else {
+        return;
+    };
+
+    let direction = earth.affine().inverse().transform_point3(hit).normalize();
+    if borders
+        .checked_at
+        .is_some_and(|checked| checked.angle_between(direction) < RECHECK_ANGLE)
+    {
+        return;
+    }
+    // The first lookup only finds out where the view starts
+    let first = borders.checked_at.is_none();
+    borders.checked_at = Some(direction);
+
+    let country = borders.country_at(Coordinates::from(direction));
+    if country == borders.current {
+        return;
+    }
+    borders.current = country;
+    if let Some(index) = country
+        && !first
+    {
+        let toast = format!("Entering {}", borders.countries[index].name);
+        borders.toast = Some((toast, time.elapsed_secs()));
+    }
+}
+
+fn display_toast(
+    mut contexts: EguiContexts,
+    mut borders: ResMut<BorderCrossings>,
+    time: Res<Time<Real>>,
+    camera: Single<&Camera, With<MainCamera>>,
+) -> bevy::prelude::Result {
+    let ctx = contexts.ctx_mut()?;
+    let Some((text, shown_at)) = borders.toast.clone() else {
+        return Ok(());
+    };
+    let age = time.elapsed_secs() - shown_at;
+    if age > TOAST_SECONDS {
+        borders.toast = None;
+        return Ok(());
+    }
+    let Some(size) = camera.logical_viewport_size() else {
+        return Ok(());
+    };
+
+    let opacity = ((TOAST_SECONDS - age) / TOAST_FADE_SECONDS).min(1.);
+    egui::Area::new("Border crossing".into())
+        .order(egui::Order::Tooltip)
+        .pivot(egui::Align2::CENTER_BOTTOM)
+        .fixed_pos(egui::pos2(size.x / 2., size.y - 60.))
+        .interactable(false)
+        .show(ctx, |ui| {
+            ui.set_opacity(opacity);
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.heading(text);
+            });
+        });
+
+    Ok(())
+}
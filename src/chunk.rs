@@ -0,0 +1,324 @@
+use std::{
+    collections::HashMap,
+    f32::consts::{FRAC_PI_2, PI},
+};
+
+use bevy::{
+    asset::Handle,
+    camera::visibility::Visibility,
+    ecs::{entity::Entity, hierarchy::ChildOf, resource::Resource, system::Commands},
+    math::{Rect, Vec3},
+    mesh::{Mesh, Mesh3d, MeshTag},
+    pbr::MeshMaterial3d,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    component::{Chunk, FacePatch},
+    material::EarthMaterial,
+    math::Coordinates,
+};
+
+/// Normals of the cube faces, in the order of `ChunkKey::face`.
+pub const FACES: [Vec3; 6] = [
+    Vec3::X,
+    Vec3::NEG_X,
+    Vec3::Y,
+    Vec3::NEG_Y,
+    Vec3::Z,
+    Vec3::NEG_Z,
+];
+
+/// Identifies the mesh of a chunk by where it sits in the cube sphere.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ChunkKey {
+    /// Cube face, indexing `FACES`
+    pub face: u8,
+    /// Subdivision level, 0 for the coarsest chunks
+    pub depth: u8,
+    /// Position of the chunk within its face at this depth
+    pub index: u32,
+}
+
+impl ChunkKey {
+    /// One of the four coarsest chunks of `face`.
+    pub fn root(face: u8, index: u32) -> Self {
+        Self {
+            face,
+            depth: 0,
+            index,
+        }
+    }
+
+    /// The four chunks covering this one at the next depth.
+    pub fn children(&self) -> [ChunkKey; 4] {
+        [0, 1, 2, 3].map(|child| ChunkKey {
+            face: self.face,
+            depth: self.depth + 1,
+            index: self.index * 4 + child,
+        })
+    }
+
+    pub fn parent(&self) -> Option<ChunkKey> {
+        (self.depth > 0).then(|| ChunkKey {
+            face: self.face,
+            depth: self.depth - 1,
+            index: self.index / 4,
+        })
+    }
+
+    /// Whether `other` lies within this chunk at a greater depth.
+    pub fn is_ancestor_of(&self, other: &ChunkKey) -> bool {
+        other.face == self.face
+            && other.depth > self.depth
+            && other.index >> (2 * (other.depth - self.depth)) == self.index
+    }
+
+    /// The chunk at `depth` containing `direction`, given in the globe's local space.
+    ///
+    /// Walks down the same quarters `extent` walks up, on the face the direction points at.
+    pub fn containing(direction: Vec3, depth: u8) -> Self {
+        let face = (0..FACES.len())
+            .max_by(|&a, &b| FACES[a].dot(direction).total_cmp(&FACES[b].dot(direction)))
+            .unwrap_or(0);
+        let normal = FACES[face];
+        let axis_a = Vec3::new(normal.y, normal.z, normal.x);
+        let axis_b = axis_a.cross(normal);
+        let projected = direction / direction.dot(normal);
+        let (u, v) = (projected.dot(axis_a), projected.dot(axis_b));
+
+        // Lower corner of the current chunk on the face, which spans -1 to 1 on both axes
+        let mut lower = (if u < 0. { -1. } else { 0. }, if v < 0. { -1. } else { 0. });
+        let mut index = (((u < 0.) as u32) << 1) | (v < 0.) as u32;
+        let mut size = 1.;
+        for _ in 0..depth {
+            size /= 2.;
+            let child = (((u >= lower.0 + size) as u32) << 1) | (v >= lower.1 + size) as u32;
+            lower.0 += (child >> 1) as f32 * size;
+            lower.1 += (child & 1) as f32 * size;
+            index = index * 4 + child;
+        }
+
+        Self {
+            face: face as u8,
+            depth,
+            index,
+        }
+    }
+
+    /// Normal of the cube face.
+    pub fn direction(&self) -> Vec3 {
+        FACES[self.face as usize]
+    }
+
+    /// Offset of the chunk within the face and its edge length, as passed to `CubeSphereBuilder::patch`.
+    ///
+    /// The lowest two bits of `index` pick the quarter of the parent, the bits above them the
+    /// parent itself, down to one of the four chunks of the face at depth 0.
+    pub fn extent(&self) -> ((f32, f32), f32) {
+        let root = self.index >> (2 * self.depth as u32);
+        let mut offset = ((root >> 1) as f32, (root & 1) as f32);
+        let mut size = 1.;
+        for level in (0..self.depth as u32).rev() {
+            let child = (self.index >> (2 * level)) & 3;
+            size /= 2.;
+            offset.0 -= (child >> 1) as f32 * size;
+            offset.1 -= (child & 1) as f32 * size;
+        }
+        (offset, size)
+    }
+
+    /// Point on the unit sphere at `(x, y)` across the chunk, both from 0 to 1, in the globe's
+    /// local space. Scaled by `EarthConfig::radius` it lies on the globe's surface.
+    pub fn point(&self, x: f32, y: f32) -> Vec3 {
+        let direction = self.direction();
+        let axis_a = Vec3::new(direction.y, direction.z, direction.x);
+        let axis_b = axis_a.cross(direction);
+        let (offset, size) = self.extent();
+        let point = direction + (x * size - offset.0) * axis_a + (y * size - offset.1) * axis_b;
+        point.normalize()
+    }
+
+    /// Center of the chunk on the unit sphere, in the globe's local space.
+    pub fn center(&self) -> Vec3 {
+        self.point(0.5, 0.5)
+    }
+
+    /// Longitudes along `x` and latitudes along `y` covered by the chunk, in radians.
+    ///
+    /// Chunks across the antimeridian or around a pole span every longitude.
+    pub fn bounds(&self) -> Rect {
+        // Corners and edge midpoints bound the chunk closely enough
+        let samples = (0..3)
+            .flat_map(|x| (0..3).map(move |y| (x as f32 / 2., y as f32 / 2.)))
+            .map(|(x, y)| Coordinates::from(self.point(x, y)));
+        let (mut south, mut north, mut west, mut east) = (FRAC_PI_2, -FRAC_PI_2, PI, -PI);
+        for sample in samples {
+            south = south.min(sample.latitude);
+            north = north.max(sample.latitude);
+            west = west.min(sample.longitude);
+            east = east.max(sample.longitude);
+        }
+        if east - west > PI {
+            (west, east) = (-PI, PI);
+        }
+        for pole in [Vec3::Y, Vec3::NEG_Y] {
+            if ChunkKey::containing(pole, self.depth) == *self {
+                (west, east) = (-PI, PI);
+                if pole.y > 0. {
+                    north = FRAC_PI_2;
+                } else {
+                    south = -FRAC_PI_2;
+                }
+            }
+        }
+        Rect::new(west, south, east, north)
+    }
+
+    /// Packs the key into the `MeshTag` of the chunk, for the debug views of `earth.wgsl`.
+    pub fn tag(&self) -> u32 {
+        (self.face as u32) | ((self.depth as u32) << 3) | (self.index << 8)
+    }
+}
+
+/// Recently used chunk meshes, kept alive after their chunk is despawned so the same chunk can
+/// be shown again without regenerating it, e.g. when zooming back out or reloading the globe.
+///
+/// The least recently used meshes are evicted once `capacity`, taken from
+/// `EarthConfig::pool_capacity`, is exceeded. Meshes of chunks still on screen stay loaded
+/// regardless, the pool only holds an extra handle.
+#[derive(Resource)]
+pub struct ChunkMeshPool {
+    pub capacity: usize,
+    meshes: HashMap<ChunkKey, (Handle<Mesh>, u64)>,
+    /// Incremented on every use, so a lower stamp means less recently used
+    clock: u64,
+}
+
+impl ChunkMeshPool {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            meshes: HashMap::new(),
+            clock: 0,
+        }
+    }
+
+    /// Mesh of `key` if it is still pooled, marking it as just used.
+    pub fn get(&mut self, key: ChunkKey) -> Option<Handle<Mesh>> {
+        self.clock += 1;
+        let clock = self.clock;
+        self.meshes.get_mut(&key).map(|(mesh, used)| {
+            *used = clock;
+            mesh.clone()
+        })
+    }
+
+    pub fn insert(&mut self, key: ChunkKey, mesh: Handle<Mesh>) {
+        self.clock += 1;
+        self.meshes.insert(key, (mesh, self.clock));
+        self.evict();
+    }
+
+    pub fn len(&self) -> usize {
+        self.meshes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.meshes.is_empty()
+    }
+
+    /// Drops the least recently used meshes until the pool fits its capacity again.
+    pub fn evict(&mut self) {
+        while self.meshes.len() > self.capacity {
+            let Some(oldest) = self
+                .meshes
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(key, _)| *key)
+            else {
+                break;
+            };
+            self.meshes.remove(&oldest);
+        }
+    }
+}
+
+/// A chunk waiting for a worker to generate its mesh.
+#[derive(Debug, Clone, Copy)]
+pub struct PendingChunk {
+    pub entity: Entity,
+    pub key: ChunkKey,
+}
+
+/// Chunks still to be generated, handed to the task pool a few at a time.
+#[derive(Resource, Default)]
+pub struct ChunkQueue(Vec<PendingChunk>);
+
+impl ChunkQueue {
+    pub fn push(&mut self, chunk: PendingChunk) {
+        self.0.push(chunk);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    /// Orders the queue so chunks facing `camera`, given in the globe's local space and in radii,
+    /// come first, nearest before farthest.
+    pub fn sort_by_priority(&mut self, camera: Vec3) {
+        self.0.sort_by(|a, b| {
+            let (a, b) = (a.key.center(), b.key.center());
+            // Points in front of the horizon plane are the ones that can be seen
+            let visible = |center: Vec3| center.dot(camera) > 1.;
+            visible(b)
+                .cmp(&visible(a))
+                .then(a.distance(camera).total_cmp(&b.distance(camera)))
+        });
+    }
+
+    /// Removes up to `count` chunks from the front of the queue.
+    pub fn take(&mut self, count: usize) -> Vec<PendingChunk> {
+        let count = count.min(self.0.len());
+        self.0.drain(..count).collect()
+    }
+}
+
+/// Spawns the chunk `key` below `earth`, with its mesh from the pool if it is still there and
+/// queued for generation otherwise. Also returns whether the mesh was pooled.
+pub fn spawn_chunk(
+    commands: &mut Commands,
+    earth: Entity,
+    key: ChunkKey,
+    pool: &mut ChunkMeshPool,
+    queue: &mut ChunkQueue,
+    template: &Handle<EarthMaterial>,
+) -> (Entity, bool) {
+    let mut chunk = commands.spawn((
+        Chunk(key),
+        FacePatch::new(key),
+        MeshTag(key.tag()),
+        ChildOf(earth),
+    ));
+    let entity = chunk.id();
+
+    match pool.get(key) {
+        Some(mesh) => {
+            chunk.insert((
+                Mesh3d(mesh),
+                MeshMaterial3d(template.clone()),
+                Visibility::Inherited,
+            ));
+            (entity, true)
+        }
+        None => {
+            queue.push(PendingChunk { entity, key });
+            (entity, false)
+        }
+    }
+}
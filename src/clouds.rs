@@ -0,0 +1,262 @@
+use std::f32::consts::TAU;
+
+use bevy::{
+    app::{App, Plugin, Update},
+    asset::{Asset, Assets, Handle},
+    camera::visibility::Visibility,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        hierarchy::ChildOf,
+        query::{Added, With},
+        resource::Resource,
+        schedule::{IntoScheduleConfigs, SystemCondition},
+        system::{Commands, Query, Res, ResMut, Single},
+    },
+    image::Image,
+    math::{Quat, Vec3},
+    mesh::{Mesh, Mesh3d},
+    pbr::{Material, MaterialPlugin, MeshMaterial3d},
+    picking::Pickable,
+    reflect::Reflect,
+    render::{
+        alpha::AlphaMode,
+        render_resource::{AsBindGroup, ShaderType},
+    },
+    shader::ShaderRef,
+    state::condition::in_state,
+    transform::components::{GlobalTransform, Transform},
+};
+
+use crate::{
+    EarthConfig,
+    chunk::FACES,
+    component::{Earth, RotatingLight},
+    material::EarthMaterial,
+    math::CubeSphereBuilder,
+    resource::{EarthMaterialTemplate, EarthTexture, SimulationTime},
+    state::GameState,
+};
+
+const SHADER_PATH: &str = "shaders/clouds.wgsl";
+
+/// Height of the clouds relative to the globe's radius, exaggerated to read from orbit.
+const CLOUD_SCALE: f32 = 1.008;
+
+/// Vertices along each edge of the six faces of the cloud sphere.
+const CLOUD_RESOLUTION: u32 = 64;
+
+#[derive(ShaderType, Reflect, Debug, Clone, Copy)]
+pub struct CloudUniform {
+    /// Direction towards the sun, in world space
+    pub sun_direction: Vec3,
+    pub opacity: f32,
+    /// Center of the globe in world space
+    pub center: Vec3,
+}
+
+impl Default for CloudUniform {
+    fn default() -> Self {
+        Self {
+            sun_direction: Vec3::Z,
+            opacity: 0.9,
+            center: Vec3::ZERO,
+        }
+    }
+}
+
+/// White clouds lit by the sun, with their alpha taken from the coverage in the red channel.
+#[derive(Asset, AsBindGroup, Reflect, Debug, Clone, Default)]
+pub struct CloudMaterial {
+    #[uniform(0)]
+    pub uniform: CloudUniform,
+    #[texture(1)]
+    #[sampler(2)]
+    pub coverage: Option<Handle<Image>>,
+}
+
+impl Material for CloudMaterial {
+    fn fragment_shader() -> ShaderRef {
+        SHADER_PATH.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Blend
+    }
+
+    // The ground gets its cloud shadows from the Earth material instead
+    fn enable_shadows() -> bool {
+        false
+    }
+
+    fn enable_prepass() -> bool {
+        false
+    }
+}
+
+/// A cube sphere of clouds floating above the `Earth`, turning around its axis on its own.
+#[derive(Component, Debug)]
+pub struct CloudLayer {
+    /// Radians per simulated second, eastwards
+    pub speed: f32,
+}
+
+impl Default for CloudLayer {
+    fn default() -> Self {
+        Self { speed: 0.01 }
+    }
+}
+
+/// Whether the cloud layer is drawn, if the pack has clouds at all.
+#[derive(Resource, Debug)]
+pub struct Clouds {
+    pub enabled: bool,
+}
+
+impl Default for Clouds {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+pub struct CloudPlugin;
+
+impl Plugin for CloudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(MaterialPlugin::<CloudMaterial>::default())
+            .init_resource::<Clouds>()
+            .add_systems(
+                Update,
+                (
+                    spawn_cloud_layer,
+                    remove_failed_clouds,
+                    apply_clouds,
+                    rotate_clouds,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Loading).or(in_state(GameState::Playing))),
+            );
+    }
+}
+
+fn spawn_cloud_layer(
+    mut commands: Commands,
+    textures: Res<EarthTexture>,
+    config: Res<EarthConfig>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<CloudMaterial>>,
+    earths: Query<Entity, Added<Earth>>,
+) {
+    let Some(coverage) = &textures.clouds else {
+        return;
+    };
+    for earth in &earths {
+        let material = materials.add(CloudMaterial {
+            coverage: Some(coverage.clone()),
+            ..Default::default()
+        });
+        commands
+            .spawn((
+                Transform::default(),
+                Visibility::Inherited,
+                ChildOf(earth),
+                CloudLayer::default(),
+            ))
+            .with_children(|layer| {
+                for normal in FACES {
+                    let face = CubeSphereBuilder::new(normal)
+                        .radius(config.radius * CLOUD_SCALE)
+                        .resolution(CLOUD_RESOLUTION)
+                        .build();
+                    layer.spawn((
+                        Mesh3d(meshes.add(face)),
+                        MeshMaterial3d(material.clone()),
+                        // Clicks go through to the ground
+                        Pickable::IGNORE,
+                    ));
+                }
+            });
+    }
+}
+
+/// Takes the layer down again if its texture turned out to be unreadable.
+fn remove_failed_clouds(
+    mut commands: Commands,
+    textures: Res<EarthTexture>,
+    layers: Query<Entity, With<CloudLayer>>,
+) {
+    if textures.clouds.is_some() {
+        return;
+    }
+    for layer in &layers {
+        commands.entity(layer).despawn();
+    }
+}
+
+fn apply_clouds(clouds: Res<Clouds>, mut layers: Query<&mut Visibility, With<CloudLayer>>) {
+    let visibility = if clouds.enabled {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    for mut layer in &mut layers {
+        if *layer != visibility {
+            *layer = visibility;
+        }
+    }
+}
+
+/// Turns the layer with the simulation clock and keeps the cloud shadows on the ground under it.
+///
+/// Writing a material prepares it again, and the Earth template is also copied into every chunk
+/// with its own instance, so materials are only written when a value changed.
+fn rotate_clouds(
+    simulation: Res<SimulationTime>,
+    handle: Res<EarthMaterialTemplate>,
+    mut earth_materials: ResMut<Assets<EarthMaterial>>,
+    mut cloud_materials: ResMut<Assets<CloudMaterial>>,
+    mut layers: Query<(&mut Transform, &CloudLayer, &ChildOf)>,
+    earths: Query<&GlobalTransform, With<Earth>>,
+    light: Single<&GlobalTransform, With<RotatingLight>>,
+) {
+    let mut center = None;
+    for (mut transform, layer, parent) in &mut layers {
+        let angle = (simulation.elapsed * layer.speed as f64).rem_euclid(TAU as f64) as f32;
+        let rotation = Quat::from_rotation_y(angle);
+        if transform.rotation != rotation {
+            transform.rotation = rotation;
+        }
+
+        // A cloud at longitude 0 of the layer now covers the ground at longitude `angle`
+        let offset = (-angle / TAU).rem_euclid(1.);
+        if earth_materials
+            .get(&**handle)
+            .is_some_and(|material| material.extension.uniform.cloud_offset.x != offset)
+            && let Some(material) = earth_materials.get_mut(&**handle)
+        {
+            material.extension.uniform.cloud_offset.x = offset;
+        }
+
+        if let Ok(earth) = earths.get(parent.parent()) {
+            center = Some(earth.translation());
+        }
+    }
+
+    let Some(center) = center else {
+        return;
+    };
+    let sun_direction = RotatingLight::sun_direction(&light);
+    let stale: Vec<_> = cloud_materials
+        .iter()
+        .filter(|(_, material)| {
+            material.uniform.sun_direction != sun_direction || material.uniform.center != center
+        })
+        .map(|(id, _)| id)
+        .collect();
+    for id in stale {
+        if let Some(material) = cloud_materials.get_mut(id) {
+            material.uniform.sun_direction = sun_direction;
+            material.uniform.center = center;
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    camera::Projection,
+    color::palettes::css::{BLUE, LIME, RED},
+    ecs::{
+        query::With,
+        schedule::IntoScheduleConfigs,
+        system::{Res, ResMut, Single},
+    },
+    gizmos::gizmos::Gizmos,
+    math::Vec3,
+    state::condition::in_state,
+    transform::components::GlobalTransform,
+};
+
+use crate::{
+    component::{Earth, MainCamera},
+    input::{Action, Actions},
+    resource::{CursorHit, ShowNorthArrow},
+    state::GameState,
+};
+
+/// Fraction of the visible height covered by one arrow.
+const ARROW_SCREEN_FRACTION: f32 = 0.05;
+
+pub struct CompassPlugin;
+
+impl Plugin for CompassPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ShowNorthArrow>().add_systems(
+            Update,
+            (
+                toggle_north_arrow,
+                draw_north_arrow.run_if(|show: Res<ShowNorthArrow>| **show),
+            )
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+fn toggle_north_arrow(actions: Actions, mut show: ResMut<ShowNorthArrow>) {
+    if actions.just_pressed(Action::ToggleNorthArrow) {
+        **show = !**show;
+    }
+}
+
+fn draw_north_arrow(
+    mut gizmos: Gizmos,
+    cursor: Res<CursorHit>,
+    earth: Single<&GlobalTransform, With<Earth>>,
+    camera: Single<(&GlobalTransform, &Projection), With<MainCamera>>,
+) {
+    let Some(local) = **cursor else {
+        return;
+    };
+    let (camera_transform, projection) = camera.into_inner();
+
+    let up = local.normalize();
+    // Tangent-plane projection of the pole axis, undefined right at the poles
+    let Some(north) = (Vec3::Y - up * up.y).try_normalize() else {
+        return;
+    };
+    let east = north.cross(up);
+
+    let earth = earth.into_inner();
+    let origin = earth.transform_point(local);
+    let fov = match projection {
+        Projection::Perspective(perspective) => perspective.fov,
+        _ => std::f32::consts::FRAC_PI_4,
+    };
+    let length = camera_transform.translation().distance(origin)
+        * (fov / 2.).tan()
+        * 2.
+        * ARROW_SCREEN_FRACTION;
+
+    let affine = earth.affine();
+    for (axis, color) in [(north, RED), (east, LIME), (up, BLUE)] {
+        let direction = affine.transform_vector3(axis).normalize();
+        gizmos.arrow(origin, origin + direction * length, color);
+    }
+}
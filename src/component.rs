@@ -1,13 +1,157 @@
 use bevy::{
+    asset::Handle,
     ecs::{component::Component, world::CommandQueue},
+    image::Image,
+    math::{Affine2, Quat, Rect, Vec3},
     tasks::Task,
+    time::Timer,
+    transform::components::{GlobalTransform, Transform},
 };
 
+use crate::{chunk::ChunkKey, math::Coordinates};
+
 #[derive(Component)]
 pub struct ComputeMesh(pub Task<CommandQueue>);
 
 #[derive(Component)]
 pub struct RotatingLight;
 
+impl RotatingLight {
+    /// Direction towards the sun in world space. The light shines along its forward axis, so the
+    /// sun is behind it.
+    pub fn sun_direction(light: &GlobalTransform) -> Vec3 {
+        *light.back()
+    }
+}
+
+/// A piece of the globe's surface with its own mesh.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Chunk(pub ChunkKey);
+
+/// Where a chunk faces, so chunks on the far side of the globe can be hidden; see
+/// `lod::cull_back_patches`.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct FacePatch {
+    /// Unit normal at the chunk's center, in the globe's local space
+    pub normal: Vec3,
+    /// Largest angle between `normal` and the directions of the chunk's corners
+    pub angular_radius: f32,
+    /// Whether the chunk is hidden for facing away from the camera
+    pub culled: bool,
+}
+
+impl FacePatch {
+    pub fn new(key: ChunkKey) -> Self {
+        let normal = key.center().normalize();
+        let angular_radius = [(0., 0.), (1., 0.), (0., 1.), (1., 1.)]
+            .into_iter()
+            .map(|(x, y)| normal.angle_between(key.point(x, y)))
+            .fold(0., f32::max);
+        Self {
+            normal,
+            angular_radius,
+            culled: false,
+        }
+    }
+}
+
+/// A chunk split from or merged into chunks still on the globe, kept hidden until it can replace
+/// them all in the same frame so the two levels never overlap; see `lod::update_lod`.
+#[derive(Component)]
+pub struct Unrevealed;
+
 #[derive(Component)]
 pub struct Earth;
+
+/// The camera the globe is viewed and navigated through, as opposed to offscreen ones like the
+/// antipode view.
+#[derive(Component)]
+pub struct MainCamera;
+
+/// Eased rotation of the Earth towards a target orientation, removed once finished.
+#[derive(Component)]
+pub struct RotationAnimation {
+    pub from: Quat,
+    pub to: Quat,
+    pub timer: Timer,
+}
+
+/// Camera field of view being eased towards `target_fov`, removed once reached.
+#[derive(Component)]
+pub struct ZoomAnimation {
+    pub target_fov: f32,
+}
+
+/// Camera field of view eased from `from` to `to` over a fixed time, in step with the
+/// `RotationAnimation` of a fly-to. Removed once finished.
+#[derive(Component)]
+pub struct FovAnimation {
+    pub from: f32,
+    pub to: f32,
+    pub timer: Timer,
+}
+
+/// Transform driven by the fixed-timestep simulation.
+///
+/// Simulation systems only write `current`; the rendered `Transform` is blended between the last
+/// two fixed steps so motion stays smooth regardless of the frame rate.
+#[derive(Component)]
+pub struct SimulatedTransform {
+    pub previous: Transform,
+    pub current: Transform,
+}
+
+impl SimulatedTransform {
+    pub fn new(transform: Transform) -> Self {
+        Self {
+            previous: transform,
+            current: transform,
+        }
+    }
+}
+
+/// Geometry laid onto the globe, such as lines and polygons of overlay layers.
+///
+/// The entity is a child of the `Earth` with its vertices on its surface (`EarthConfig::radius`).
+/// Its scale is kept slightly above `height` depending on the camera altitude, so it never
+/// z-fights with the terrain; see `depth::lift_above_surface`.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct Draped {
+    /// Height above the surface in world units, e.g. for flight paths
+    pub height: f32,
+}
+
+/// Quad turned towards the camera every frame and scaled to `size` pixels high.
+///
+/// Used by icons and labels, which are children of the `Earth` positioned on its surface.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Billboard {
+    pub size: f32,
+}
+
+/// An entity pinned to the globe's surface, see `marker::spawn_marker`.
+///
+/// Changing `coordinates` moves the marker and everything attached to it.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Marker {
+    pub coordinates: Coordinates,
+}
+
+/// A feature that can be hovered and clicked to select it, see `selection::Selection`.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct Selectable;
+
+/// Per-chunk deviations from the `EarthMaterialTemplate`.
+///
+/// A chunk with this component gets its own material instance, re-derived from the template
+/// whenever it changes. Instances are only referenced by their chunk, so they are freed as soon
+/// as the chunk despawns or the component is removed.
+#[derive(Component, Debug, Clone, Default, PartialEq)]
+pub struct MaterialOverrides {
+    pub base_color_texture: Option<Handle<Image>>,
+    /// Maps the chunk's UVs into `base_color_texture`
+    pub uv_transform: Option<Affine2>,
+    /// Drawn over the base color within a rect of the globe's UVs, leaving the UVs of the other
+    /// textures alone
+    pub base_color_tile: Option<(Handle<Image>, Rect)>,
+}
@@ -0,0 +1,143 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    camera::Camera,
+    ecs::{
+        query::{With, Without},
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Res, ResMut, Single},
+    },
+    math::{Vec2, Vec3},
+    state::condition::in_state,
+    transform::components::GlobalTransform,
+};
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+
+use crate::{
+    EarthConfig,
+    component::{Earth, MainCamera},
+    input::{Action, Actions},
+    math::{Coordinates, ray_sphere_intersection},
+    state::GameState,
+};
+
+/// Logical pixels between the screen center and the samples around it.
+const SAMPLE_SPREAD: f32 = 2.;
+
+/// Half the length of each crosshair line, in logical pixels.
+const CROSSHAIR_SIZE: f32 = 8.;
+
+/// Resolves the center of the screen to the point of the globe it shows, every frame whether or
+/// not the pointer moves, for navigating with the keyboard where there is no cursor.
+#[derive(Resource, Default)]
+pub struct Crosshair {
+    pub enabled: bool,
+    /// Point under the crosshair in the Earth's local space
+    hit: Option<Vec3>,
+}
+
+impl Crosshair {
+    pub fn coordinates(&self) -> Option<Coordinates> {
+        self.hit.map(Coordinates::from)
+    }
+}
+
+pub struct CrosshairPlugin;
+
+impl Plugin for CrosshairPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Crosshair>()
+            .add_systems(
+                Update,
+                (
+                    toggle_crosshair,
+                    resolve_crosshair.run_if(|crosshair: Res<Crosshair>| crosshair.enabled),
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                EguiPrimaryContextPass,
+                draw_crosshair
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(|crosshair: Res<Crosshair>| crosshair.enabled),
+            );
+    }
+}
+
+fn toggle_crosshair(actions: Actions, mut crosshair: ResMut<Crosshair>) {
+    if actions.just_pressed(Action::ToggleCrosshair) {
+        crosshair.enabled = !crosshair.enabled;
+        crosshair.hit = None;
+    }
+}
+
+/// Averages the center with four samples around it, so the readout doesn't drop out while the
+/// center ray grazes the limb and only some of them hit.
+fn resolve_crosshair(
+    mut crosshair: ResMut<Crosshair>,
+    config: Res<EarthConfig>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera>>,
+    earth: Single<&GlobalTransform, (With<Earth>, Without<Camera>)>,
+) {
+    let (camera, transform) = *camera;
+    let Some(center) = camera.logical_viewport_size().map(|size| size / 2.) else {
+        crosshair.hit = None;
+        return;
+    };
+    let local = earth.affine().inverse();
+
+    let sum: Vec3 = [Vec2::ZERO, Vec2::X, Vec2::NEG_X, Vec2::Y, Vec2::NEG_Y]
+        .into_iter()
+        .filter_map(|offset| {
+            let ray = camera
+                .viewport_to_world(transform, center + offset * SAMPLE_SPREAD)
+                .ok()?;
+            let hit = ray_sphere_intersection(ray, earth.translation(), config.radius)?;
+            Some(local.transform_point3(hit).normalize())
+        })
+        .sum();
+
+    crosshair.hit = sum
+        .try_normalize()
+        .map(|direction| direction * config.radius);
+}
+
+fn draw_crosshair(
+    mut contexts: EguiContexts,
+    crosshair: Res<Crosshair>,
+    camera: Single<&Camera, With<MainCamera>>,
+) -> bevy::prelude::Result {
+    let ctx = contexts.ctx_mut()?;
+    let Some(size) = camera.logical_viewport_size() else {
+        return Ok(());
+    };
+
+    let center = egui::pos2(size.x / 2., size.y / 2.);
+    let color = if crosshair.hit.is_some() {
+        egui::Color32::WHITE
+    } else {
+        egui::Color32::GRAY
+    };
+    let stroke = egui::Stroke::new(1.5, color);
+    let painter = ctx.layer_painter(egui::LayerId::new(
+        egui::Order::Foreground,
+        "Crosshair".into(),
+    ));
+    painter.line_segment(
+        [
+            center - egui::vec2(CROSSHAIR_SIZE, 0.),
+            center + egui::vec2(CROSSHAIR_SIZE, 0.),
+        ],
+        stroke,
+    );
+    painter.line_segment(
+        [
+            center - egui::vec2(0., CROSSHAIR_SIZE),
+            center + egui::vec2(0., CROSSHAIR_SIZE),
+        ],
+        stroke,
+    );
+
+    Ok(())
+}
@@ -0,0 +1,126 @@
+use bevy::{
+    app::{App, Plugin, Startup, Update},
+    asset::{Assets, Handle, RenderAssetUsages},
+    ecs::{
+        entity::Entity,
+        query::With,
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Query, Res, ResMut, Single},
+    },
+    image::Image,
+    input::{ButtonInput, mouse::MouseButton},
+    math::Vec2,
+    picking::{hover::HoverMap, pointer::PointerId},
+    state::{condition::in_state, state::State},
+    window::{CursorIcon, CustomCursor, CustomCursorImage, PrimaryWindow, SystemCursorIcon},
+};
+use image::{DynamicImage, Rgba, RgbaImage};
+
+use crate::{
+    component::{Billboard, Chunk},
+    resource::PointerOverUi,
+    state::{GameState, ToolMode},
+};
+
+/// Size of the procedurally drawn cursors in pixels.
+const CURSOR_SIZE: u32 = 32;
+
+/// Cursors the OS doesn't provide.
+#[derive(Resource, Default)]
+struct CursorImages {
+    pencil: Handle<Image>,
+}
+
+pub struct CursorPlugin;
+
+impl Plugin for CursorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CursorImages>()
+            .add_systems(Startup, draw_cursors)
+            .add_systems(Update, update_cursor.run_if(in_state(GameState::Playing)));
+    }
+}
+
+/// A pencil pointing to the bottom left, where its hotspot is.
+fn draw_pencil() -> RgbaImage {
+    let tip = Vec2::new(2., CURSOR_SIZE as f32 - 2.);
+    let end = Vec2::new(CURSOR_SIZE as f32 - 4., 4.);
+    RgbaImage::from_fn(CURSOR_SIZE, CURSOR_SIZE, |x, y| {
+        let p = Vec2::new(x as f32, y as f32) + 0.5;
+        // Distance to the pencil's axis and how far along it, from the tip
+        let axis = end - tip;
+        let along = ((p - tip).dot(axis) / axis.length_squared()).clamp(0., 1.);
+        let distance = p.distance(tip + axis * along);
+        // The lead is a cone, widening into the body over the first fifth
+        let width = 4. * (along / 0.2).min(1.);
+        match (distance < width, distance < width + 1.5) {
+            (true, _) if along < 0.08 => Rgba([40, 40, 40, 255]),
+            (true, _) if along < 0.2 => Rgba([230, 200, 150, 255]),
+            (true, _) if along > 0.9 => Rgba([230, 120, 130, 255]),
+            (true, _) => Rgba([250, 200, 40, 255]),
+            (false, true) => Rgba([20, 20, 20, 255]),
+            _ => Rgba([0, 0, 0, 0]),
+        }
+    })
+}
+
+fn draw_cursors(mut cursors: ResMut<CursorImages>, mut images: ResMut<Assets<Image>>) {
+    cursors.pencil = images.add(Image::from_dynamic(
+        DynamicImage::ImageRgba8(draw_pencil()),
+        true,
+        RenderAssetUsages::default(),
+    ));
+}
+
+/// Picks the cursor from the active tool and what the mouse hovers.
+///
+/// Over egui windows the cursor is left to egui, which sets its own for text fields and resize
+/// handles.
+fn update_cursor(
+    mut commands: Commands,
+    window: Single<(Entity, Option<&CursorIcon>), With<PrimaryWindow>>,
+    over_ui: Res<PointerOverUi>,
+    mode: Res<State<ToolMode>>,
+    hover_map: Res<HoverMap>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    cursors: Res<CursorImages>,
+    markers: Query<(), With<Billboard>>,
+    chunks: Query<(), With<Chunk>>,
+) {
+    if **over_ui {
+        return;
+    }
+    let (window, current) = *window;
+
+    let hovered: Vec<Entity> = hover_map
+        .get(&PointerId::Mouse)
+        .map(|hits| hits.keys().copied().collect())
+        .unwrap_or_default();
+    let dragging = mouse.pressed(MouseButton::Left)
+        && current == Some(&CursorIcon::System(SystemCursorIcon::Grabbing));
+
+    let icon = if hovered.iter().any(|&entity| markers.contains(entity)) {
+        CursorIcon::System(SystemCursorIcon::Pointer)
+    } else if hovered.iter().any(|&entity| chunks.contains(entity)) || dragging {
+        match **mode {
+            ToolMode::Measuring => CursorIcon::System(SystemCursorIcon::Crosshair),
+            ToolMode::Drawing => CursorIcon::Custom(CustomCursor::Image(CustomCursorImage {
+                handle: cursors.pencil.clone(),
+                hotspot: (2, CURSOR_SIZE as u16 - 2),
+                ..Default::default()
+            })),
+            ToolMode::Touring => CursorIcon::System(SystemCursorIcon::Default),
+            ToolMode::Idle | ToolMode::Quiz if mouse.pressed(MouseButton::Left) => {
+                CursorIcon::System(SystemCursorIcon::Grabbing)
+            }
+            ToolMode::Idle | ToolMode::Quiz => CursorIcon::System(SystemCursorIcon::Grab),
+        }
+    } else {
+        CursorIcon::System(SystemCursorIcon::Default)
+    };
+
+    if current != Some(&icon) {
+        commands.entity(window).insert(icon);
+    }
+}
@@ -0,0 +1,132 @@
+use bevy::{
+    app::{App, Plugin, PostUpdate},
+    camera::{Camera, CameraUpdateSystems, Projection},
+    ecs::{
+        change_detection::DetectChangesMut,
+        query::{With, Without},
+        schedule::IntoScheduleConfigs,
+        system::{Query, Res, Single},
+    },
+    math::Vec3,
+    pbr::StandardMaterial,
+    transform::{TransformSystems, components::Transform},
+};
+
+use crate::{
+    EarthConfig,
+    component::{Draped, Earth, MainCamera},
+    orbit::OrbitingBody,
+    space::SpaceView,
+};
+
+/// Smallest near plane in radii, so the depth range never collapses right at the surface.
+const MIN_NEAR: f32 = 1e-5;
+
+/// Share of the altitude kept between the camera and the near plane.
+const NEAR_FRACTION: f32 = 0.5;
+
+/// Radial lift of draped geometry per world unit of camera altitude.
+const LIFT_PER_ALTITUDE: f32 = 0.002;
+
+/// Lift right at the surface in radii, about 60 m.
+const MIN_LIFT: f32 = 1e-5;
+
+/// Constant depth bias for the materials of draped geometry, on top of the radial lift.
+///
+/// With reverse-z a positive bias pulls fragments towards the camera.
+pub const DRAPED_DEPTH_BIAS: f32 = 16.;
+
+pub struct DepthPlugin;
+
+impl Plugin for DepthPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            (update_clip_planes, lift_draped)
+                .before(CameraUpdateSystems)
+                .before(TransformSystems::Propagate),
+        );
+    }
+}
+
+/// Distance of the camera above the surface of a globe of `radius`, in world units.
+pub fn camera_altitude(camera: Vec3, earth: Vec3, radius: f32) -> f32 {
+    (camera.distance(earth) - radius).max(0.)
+}
+
+/// How far draped geometry has to float above the terrain of a globe of `radius` to stay in
+/// front of it.
+///
+/// Depth precision falls off with distance, so the offset grows with the altitude and stays
+/// invisible from any zoom level.
+pub fn lift_above_surface(altitude: f32, radius: f32) -> f32 {
+    (altitude * LIFT_PER_ALTITUDE).max(MIN_LIFT * radius)
+}
+
+/// Material settings shared by every kind of draped geometry.
+pub fn draped_material(material: StandardMaterial) -> StandardMaterial {
+    StandardMaterial {
+        depth_bias: DRAPED_DEPTH_BIAS,
+        ..material
+    }
+}
+
+fn lift_draped(
+    config: Res<EarthConfig>,
+    camera: Single<&Transform, (With<MainCamera>, Without<Draped>)>,
+    earth: Single<&Transform, (With<Earth>, Without<Draped>)>,
+    mut draped: Query<(&Draped, &mut Transform)>,
+) {
+    let radius = config.radius;
+    let altitude = camera_altitude(camera.translation, earth.translation, radius);
+    let lift = lift_above_surface(altitude, radius);
+    for (draped, mut transform) in &mut draped {
+        let scale = Vec3::splat((radius + draped.height + lift) / radius);
+        if !transform.scale.abs_diff_eq(scale, 1e-6) {
+            transform.scale = scale;
+        }
+    }
+}
+
+/// Fits the near and far planes tightly around the globe.
+///
+/// A near plane far in front of the surface wastes depth precision on empty space, which is what
+/// makes geometry draped just above the terrain flicker. Nothing behind the horizon is visible
+/// but the bodies orbiting the globe, so the far plane ends there or just past them. Camera,
+/// globe and bodies are all root entities, so their local transforms are already final and the
+/// projection can be updated before Bevy derives the clip-from-view matrix and frusta from it.
+fn update_clip_planes(
+    mut cameras: Query<(&Transform, &mut Projection), With<Camera>>,
+    earth: Single<&Transform, With<Earth>>,
+    bodies: Query<&Transform, (With<OrbitingBody>, Without<Camera>)>,
+    space: Res<SpaceView>,
+    config: Res<EarthConfig>,
+) {
+    let radius = config.radius;
+    for (transform, mut projection) in &mut cameras {
+        // Change detection is only triggered below, once the planes actually moved
+        let Projection::Perspective(perspective) = projection.bypass_change_detection() else {
+            continue;
+        };
+
+        let altitude = camera_altitude(transform.translation, earth.translation, radius);
+        let distance = altitude + radius;
+        let near = (altitude * NEAR_FRACTION).max(MIN_NEAR * radius);
+        // Line of sight to the horizon, plus a margin for anything standing on it
+        let mut far = (distance * distance - radius * radius).sqrt() + radius * 0.1;
+        // The Sun and the Moon lie far beyond the horizon
+        if space.is_active() {
+            far = far.max(distance + space.extent(&config));
+        }
+        for body in &bodies {
+            far = far
+                .max(transform.translation.distance(body.translation) + body.scale.max_element());
+        }
+
+        if (perspective.near - near).abs() > f32::EPSILON || (perspective.far - far).abs() > 1. {
+            perspective.near = near;
+            perspective.far = far;
+            projection.set_changed();
+        }
+    }
+}
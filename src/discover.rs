@@ -0,0 +1,397 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        message::{Message, MessageReader, MessageWriter},
+        query::With,
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Query, Res, ResMut, Single},
+    },
+    state::{condition::in_state, state::State},
+};
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+
+use crate::{
+    component::{MainCamera, Marker, ZoomAnimation},
+    marker::MarkerLabel,
+    math::Coordinates,
+    navigation::Navigate,
+    palette::RegisterCommand,
+    state::{GameState, ToolMode},
+};
+
+/// Field of view the camera zooms to when arriving at a place.
+const ARRIVAL_FOV: f32 = 0.3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoiKind {
+    NaturalWonder,
+    City,
+    Crater,
+}
+
+impl PoiKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PoiKind::NaturalWonder => "Natural wonder",
+            PoiKind::City => "City",
+            PoiKind::Crater => "Impact crater",
+        }
+    }
+}
+
+/// A place worth a visit, with coordinates in degrees.
+#[derive(Debug, Clone, Copy)]
+pub struct Poi {
+    pub name: &'static str,
+    pub kind: PoiKind,
+    pub latitude: f32,
+    pub longitude: f32,
+    pub description: &'static str,
+}
+
+const fn poi(
+    name: &'static str,
+    kind: PoiKind,
+    latitude: f32,
+    longitude: f32,
+    description: &'static str,
+) -> Poi {
+    Poi {
+        name,
+        kind,
+        latitude,
+        longitude,
+        description,
+    }
+}
+
+/// Curated places the discover button picks from.
+pub const POIS: [Poi; 24] = [
+    poi(
+        "Grand Canyon",
+        PoiKind::NaturalWonder,
+        36.1069,
+        -112.1129,
+        "Carved by the Colorado River over some six million years, up to 1.8 km deep.",
+    ),
+    poi(
+        "Mount Everest",
+        PoiKind::NaturalWonder,
+        27.9881,
+        86.925,
+        "The highest summit above sea level at 8,849 m, on the border of Nepal and China.",
+    ),
+    poi(
+        "Great Barrier Reef",
+        PoiKind::NaturalWonder,
+        -18.2871,
+        147.6992,
+        "The largest coral reef system, stretching over 2,300 km along Queensland.",
+    ),
+    poi(
+        "Victoria Falls",
+        PoiKind::NaturalWonder,
+        -17.9243,
+        25.8572,
+        "The Zambezi drops 108 m along a 1.7 km wide curtain of water.",
+    ),
+    poi(
+        "Sahara Eye",
+        PoiKind::NaturalWonder,
+        21.1241,
+        -11.3995,
+        "The Richat Structure in Mauritania, an eroded dome 40 km across and easy to spot from orbit.",
+    ),
+    poi(
+        "Amazon Delta",
+        PoiKind::NaturalWonder,
+        -0.5,
+        -49.,
+        "The river with the largest discharge meets the Atlantic around Marajó island.",
+    ),
+    poi(
+        "Lake Baikal",
+        PoiKind::NaturalWonder,
+        53.5587,
+        108.165,
+        "The deepest and oldest lake, holding about a fifth of the world's unfrozen fresh water.",
+    ),
+    poi(
+        "Iguazu Falls",
+        PoiKind::NaturalWonder,
+        -25.6953,
+        -54.4367,
+        "Around 275 waterfalls on the border of Argentina and Brazil.",
+    ),
+    poi(
+        "Salar de Uyuni",
+        PoiKind::NaturalWonder,
+        -20.1338,
+        -67.4891,
+        "The largest salt flat, so level that satellites calibrate their altimeters on it.",
+    ),
+    poi(
+        "Ha Long Bay",
+        PoiKind::NaturalWonder,
+        20.9101,
+        107.1839,
+        "Thousands of limestone islets rising out of the Gulf of Tonkin.",
+    ),
+    poi(
+        "Tokyo",
+        PoiKind::City,
+        35.6762,
+        139.6503,
+        "The most populous metropolitan area, home to around 37 million people.",
+    ),
+    poi(
+        "Venice",
+        PoiKind::City,
+        45.4408,
+        12.3155,
+        "Built on 118 islands in a lagoon, linked by canals and some 400 bridges.",
+    ),
+    poi(
+        "Cairo",
+        PoiKind::City,
+        30.0444,
+        31.2357,
+        "On the Nile at the head of its delta, with the pyramids of Giza just west of it.",
+    ),
+    poi(
+        "Rio de Janeiro",
+        PoiKind::City,
+        -22.9068,
+        -43.1729,
+        "Squeezed between granite peaks and the Atlantic around Guanabara Bay.",
+    ),
+    poi(
+        "Dubai",
+        PoiKind::City,
+        25.2048,
+        55.2708,
+        "Its artificial Palm islands are among the few human works recognizable from orbit.",
+    ),
+    poi(
+        "Reykjavík",
+        PoiKind::City,
+        64.1466,
+        -21.9426,
+        "The northernmost capital of a sovereign state.",
+    ),
+    poi(
+        "La Paz",
+        PoiKind::City,
+        -16.4897,
+        -68.1193,
+        "The seat of Bolivia's government, at about 3,600 m the highest of any capital.",
+    ),
+    poi(
+        "Singapore",
+        PoiKind::City,
+        1.3521,
+        103.8198,
+        "A city state at the tip of the Malay Peninsula, one of the busiest ports in the world.",
+    ),
+    poi(
+        "Meteor Crater",
+        PoiKind::Crater,
+        35.0275,
+        -111.0225,
+        "A 1.2 km wide crater in Arizona, left by an iron meteorite some 50,000 years ago.",
+    ),
+    poi(
+        "Chicxulub",
+        PoiKind::Crater,
+        21.4,
+        -89.5167,
+        "Buried under the Yucatán, the impact linked to the extinction of the dinosaurs.",
+    ),
+    poi(
+        "Manicouagan",
+        PoiKind::Crater,
+        51.3833,
+        -68.7,
+        "A ring shaped reservoir in Quebec, filling a 214 million year old impact structure.",
+    ),
+    poi(
+        "Vredefort",
+        PoiKind::Crater,
+        -27.,
+        27.5,
+        "The largest verified impact structure, once about 300 km across.",
+    ),
+    poi(
+        "Pingualuit",
+        PoiKind::Crater,
+        61.2767,
+        -73.66,
+        "A round lake in Nunavik holding some of the clearest fresh water on Earth.",
+    ),
+    poi(
+        "Aorounga",
+        PoiKind::Crater,
+        19.1,
+        19.25,
+        "Wind carved ridges in the Sahara of Chad, ringing a 12.6 km wide crater.",
+    ),
+];
+
+/// Request to fly to a random place out of `POIS`.
+#[derive(Message, Debug, Clone, Copy)]
+pub struct Discover;
+
+/// The place last flown to by the discover button, shown on a card until closed.
+#[derive(Resource, Default)]
+pub struct Discovery {
+    /// Index into `POIS`
+    current: Option<usize>,
+    /// State of the xorshift generator picking the places
+    seed: u64,
+}
+
+impl Discovery {
+    pub fn current(&self) -> Option<&'static Poi> {
+        self.current.map(|index| &POIS[index])
+    }
+
+    /// Picks a random place other than the current one.
+    fn next(&mut self) -> usize {
+        if self.seed == 0 {
+            let seed = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(1, |elapsed| elapsed.as_nanos() as u64);
+            // Xorshift gets stuck at zero
+            self.seed = seed.max(1);
+        }
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+
+        let mut next = (self.seed % POIS.len() as u64) as usize;
+        if Some(next) == self.current {
+            next = (next + 1) % POIS.len();
+        }
+        self.current = Some(next);
+        next
+    }
+}
+
+/// The labelled marker on the place being shown.
+#[derive(Component)]
+struct DiscoveryMarker;
+
+pub struct DiscoverPlugin;
+
+impl Plugin for DiscoverPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Discovery>()
+            .add_message::<Discover>()
+            .add_systems(Update, discover.run_if(in_state(GameState::Playing)))
+            .add_systems(
+                EguiPrimaryContextPass,
+                display_discovery
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(|discovery: Res<Discovery>| discovery.current.is_some()),
+            )
+            .register_command(
+                "Fly somewhere interesting",
+                |mut discover: MessageWriter<Discover>| {
+                    discover.write(Discover);
+                },
+            );
+
+        for poi in POIS {
+            let Ok(coordinates) = Coordinates::from_degrees(poi.latitude, poi.longitude) else {
+                continue;
+            };
+            app.register_command(
+                format!("Fly to {}", poi.name),
+                move |mut navigate: MessageWriter<Navigate>| {
+                    navigate.write(Navigate::Location(coordinates));
+                },
+            );
+        }
+    }
+}
+
+fn discover(
+    mut commands: Commands,
+    mut requests: MessageReader<Discover>,
+    mut discovery: ResMut<Discovery>,
+    mut navigate: MessageWriter<Navigate>,
+    mode: Res<State<ToolMode>>,
+    camera: Single<Entity, With<MainCamera>>,
+    markers: Query<Entity, With<DiscoveryMarker>>,
+) {
+    if requests.read().last().is_none() || !mode.allows_navigation() {
+        return;
+    }
+
+    let poi = POIS[discovery.next()];
+    let Ok(coordinates) = Coordinates::from_degrees(poi.latitude, poi.longitude) else {
+        return;
+    };
+    for entity in &markers {
+        commands.entity(entity).despawn();
+    }
+    commands.spawn((
+        Marker { coordinates },
+        MarkerLabel(poi.name.into()),
+        DiscoveryMarker,
+    ));
+
+    navigate.write(Navigate::Location(coordinates));
+    commands.entity(*camera).insert(ZoomAnimation {
+        target_fov: ARRIVAL_FOV,
+    });
+}
+
+fn display_discovery(
+    mut contexts: EguiContexts,
+    mut commands: Commands,
+    mut discovery: ResMut<Discovery>,
+    mut requests: MessageWriter<Discover>,
+    markers: Query<Entity, With<DiscoveryMarker>>,
+) -> bevy::prelude::Result {
+    let ctx = contexts.ctx_mut()?;
+    let Some(poi) = discovery.current() else {
+        return Ok(());
+    };
+
+    let mut close = false;
+    egui::Window::new("Discovery")
+        .anchor(egui::Align2::RIGHT_BOTTOM, [-10., -40.])
+        .resizable(false)
+        .collapsible(false)
+        .default_width(260.)
+        .show(ctx, |ui| {
+            ui.heading(poi.name);
+            ui.weak(format!(
+                "{}, {:.2}°, {:.2}°",
+                poi.kind.label(),
+                poi.latitude,
+                poi.longitude
+            ));
+            ui.label(poi.description);
+
+            ui.horizontal(|ui| {
+                if ui.button("Somewhere else").clicked() {
+                    requests.write(Discover);
+                }
+                close = ui.button("Close").clicked();
+            });
+        });
+
+    if close {
+        for entity in &markers {
+            commands.entity(entity).despawn();
+        }
+        discovery.current = None;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,350 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use bevy::{
+    app::{App, Plugin, Update},
+    ecs::{
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Res, ResMut},
+    },
+    state::{
+        condition::in_state,
+        state::{NextState, OnEnter},
+    },
+    tasks::{IoTaskPool, Task, futures},
+};
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    EarthConfig,
+    http::HttpClient,
+    pack::{EarthPack, EarthPacks, texture_file},
+    resource::LoadingProgress,
+    state::GameState,
+    toast::Toasts,
+};
+
+/// A texture that is too large for the repository and is fetched into the pack on first run.
+#[derive(Debug, Clone)]
+pub struct RemoteTexture {
+    /// File name inside the pack
+    pub file: String,
+    pub url: String,
+    /// Digest the download has to match. NASA doesn't publish checksums, so without one the
+    /// first download is trusted as is, see `download`.
+    pub sha256: Option<String>,
+}
+
+impl RemoteTexture {
+    pub fn new(file: impl Into<String>, url: impl Into<String>) -> Self {
+        Self {
+            file: file.into(),
+            url: url.into(),
+            sha256: None,
+        }
+    }
+
+    /// Pins the SHA-256 digest of the file, in hex. A download that doesn't match it fails and
+    /// starts over on the next try.
+    pub fn with_sha256(mut self, sha256: impl Into<String>) -> Self {
+        self.sha256 = Some(sha256.into());
+        self
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct DownloadProgress {
+    pub downloaded: Arc<AtomicU64>,
+    /// Zero until the server reported the size
+    pub total: Arc<AtomicU64>,
+}
+
+impl DownloadProgress {
+    pub fn fraction(&self) -> f32 {
+        let total = self.total.load(Ordering::Relaxed);
+        if total == 0 {
+            return 0.;
+        }
+        self.downloaded.load(Ordering::Relaxed) as f32 / total as f32
+    }
+}
+
+pub struct ActiveDownload {
+    pub file: String,
+    pub progress: DownloadProgress,
+    task: Task<Result<(), String>>,
+}
+
+#[derive(Resource, Default)]
+pub struct Downloads {
+    pub active: Option<ActiveDownload>,
+    /// Remaining textures, downloaded one after another
+    pub queue: Vec<RemoteTexture>,
+    /// Textures queued by the last `start`
+    pub total: usize,
+    pub error: Option<String>,
+}
+
+impl Downloads {
+    /// Queues the textures of `remote` missing from `root`.
+    pub fn start(&mut self, root: &Path, remote: &[RemoteTexture]) {
+        self.error = None;
+        self.queue = remote
+            .iter()
+            .filter(|texture| texture_file(root, &texture.file).is_none())
+            .cloned()
+            .collect();
+        self.total = self.queue.len();
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.active.is_some() || !self.queue.is_empty()
+    }
+
+    /// Fraction of the queued textures downloaded so far.
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            return 1.;
+        }
+        let pending = self.queue.len() + usize::from(self.active.is_some());
+        let active = self
+            .active
+            .as_ref()
+            .map_or(0., |active| active.progress.fraction());
+        ((self.total - pending) as f32 + active) / self.total as f32
+    }
+}
+
+/// Whether `pack` can be loaded once the textures of `remote` it misses are downloaded.
+pub fn downloadable(pack: &EarthPack, remote: &[RemoteTexture]) -> bool {
+    pack.builtin
+        && pack
+            .missing
+            .iter()
+            .all(|file| remote.iter().any(|texture| texture.file == *file))
+}
+
+/// Fetches the remote textures the active pack misses in `GameState::DownloadAssets`, before
+/// anything is loaded, caching them in the pack so this only happens on the first run.
+pub struct DownloadPlugin;
+
+impl Plugin for DownloadPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Downloads>()
+            .add_systems(OnEnter(GameState::DownloadAssets), start_downloads)
+            .add_systems(
+                Update,
+                handle_downloads.run_if(in_state(GameState::DownloadAssets)),
+            )
+            .add_systems(
+                EguiPrimaryContextPass,
+                display_download_error.run_if(in_state(GameState::DownloadAssets)),
+            );
+    }
+}
+
+fn sidecar_path(destination: &Path) -> PathBuf {
+    let mut path = destination.as_os_str().to_owned();
+    path.push(".sha256");
+    PathBuf::from(path)
+}
+
+/// Downloads `url` into `destination`, resuming a previous partial download when possible.
+///
+/// The result is checked against `expected` if given. Otherwise only later downloads are checked,
+/// against the digest recorded next to `destination` after the first one, so a file that changed
+/// on the server or got corrupted isn't picked up silently. The first download is unverified.
+pub fn download(
+    client: &HttpClient,
+    url: &str,
+    destination: &Path,
+    expected: Option<&str>,
+    progress: &DownloadProgress,
+) -> Result<(), String> {
+    let partial = destination.with_extension("part");
+    let offset = fs::metadata(&partial).map(|meta| meta.len()).unwrap_or(0);
+
+    let range = format!("bytes={offset}-");
+    let mut response = client.get(url, &[("Range", &range)])?;
+
+    // Range Not Satisfiable: nothing is left past the end of the partial file, which is complete
+    // unless the file on the server has a different length now
+    if response.status() == 416 {
+        let total = response
+            .header("Content-Range")
+            .and_then(|range| range.rsplit('/').next()?.trim().parse::<u64>().ok());
+        if offset == 0 || total.is_some_and(|total| total != offset) {
+            let _ = fs::remove_file(&partial);
+            return Err(format!(
+                "Server rejected the range {range}, the next try starts over"
+            ));
+        }
+        progress.downloaded.store(offset, Ordering::Relaxed);
+        progress.total.store(offset, Ordering::Relaxed);
+    } else {
+        // Servers that ignore the range send the whole file again
+        let resumed = response.status() == 206;
+        let offset = if resumed { offset } else { 0 };
+        let length = response.content_length().unwrap_or(0);
+        progress.downloaded.store(offset, Ordering::Relaxed);
+        progress.total.store(offset + length, Ordering::Relaxed);
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(resumed)
+            .truncate(!resumed)
+            .open(&partial)
+            .map_err(|err| err.to_string())?;
+        let mut buffer = vec![0; 1 << 16];
+        loop {
+            let read = response.read(&mut buffer).map_err(|err| err.to_string())?;
+            if read == 0 {
+                break;
+            }
+            file.write_all(&buffer[..read])
+                .map_err(|err| err.to_string())?;
+            progress
+                .downloaded
+                .fetch_add(read as u64, Ordering::Relaxed);
+        }
+    }
+    drop(response);
+
+    let digest = sha256_file(&partial)?;
+    let sidecar = sidecar_path(destination);
+    let pinned = expected
+        .map(str::to_string)
+        .or_else(|| fs::read_to_string(&sidecar).ok())
+        .map(|pinned| pinned.trim().to_lowercase());
+    if let Some(pinned) = pinned
+        && pinned != digest
+    {
+        // A corrupt partial file would fail the same way on every resume
+        let _ = fs::remove_file(&partial);
+        return Err(format!(
+            "Checksum mismatch: expected {pinned}, got {digest}"
+        ));
+    }
+
+    fs::rename(&partial, destination).map_err(|err| err.to_string())?;
+    fs::write(&sidecar, &digest).map_err(|err| err.to_string())?;
+    Ok(())
+}
+
+fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file = File::open(path).map_err(|err| err.to_string())?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).map_err(|err| err.to_string())?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+fn start_downloads(
+    mut downloads: ResMut<Downloads>,
+    packs: Res<EarthPacks>,
+    config: Res<EarthConfig>,
+) {
+    let pack = packs.active();
+    // External packs bring all their textures along
+    if pack.builtin {
+        downloads.start(&pack.root, &config.remote_textures);
+    }
+}
+
+fn handle_downloads(
+    client: Res<HttpClient>,
+    mut downloads: ResMut<Downloads>,
+    mut packs: ResMut<EarthPacks>,
+    mut progress: ResMut<LoadingProgress>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut toasts: ResMut<Toasts>,
+) {
+    let downloads = &mut *downloads;
+
+    if let Some(active) = &mut downloads.active {
+        if let Some(result) = futures::check_ready(&mut active.task) {
+            match result {
+                Ok(()) => toasts.info(format!("Downloaded {}", active.file)),
+                Err(err) => {
+                    toasts.error(format!("Failed to download {}: {err}", active.file));
+                    downloads.error = Some(format!("{}: {err}", active.file));
+                    downloads.queue.clear();
+                }
+            }
+            downloads.active = None;
+            packs.refresh();
+        }
+    } else if let Some(texture) = downloads.queue.pop() {
+        let destination = packs.active().root.join(&texture.file);
+        let progress = DownloadProgress::default();
+        let task_progress = progress.clone();
+        let client = client.clone();
+        let file = texture.file.clone();
+        let task = IoTaskPool::get().spawn(async move {
+            download(
+                &client,
+                &texture.url,
+                &destination,
+                texture.sha256.as_deref(),
+                &task_progress,
+            )
+        });
+        downloads.active = Some(ActiveDownload {
+            file,
+            progress,
+            task,
+        });
+    } else if downloads.error.is_none() {
+        next_state.set(GameState::Loading);
+    }
+
+    progress.download = downloads.fraction();
+    progress.downloading = downloads.active.as_ref().map(|active| active.file.clone());
+}
+
+/// Offers to retry a failed download, or to pick another pack.
+fn display_download_error(
+    mut contexts: EguiContexts,
+    mut downloads: ResMut<Downloads>,
+    mut packs: ResMut<EarthPacks>,
+    config: Res<EarthConfig>,
+    client: Res<HttpClient>,
+    mut next_state: ResMut<NextState<GameState>>,
+) -> bevy::prelude::Result {
+    let Some(error) = downloads.error.clone() else {
+        return Ok(());
+    };
+
+    let ctx = contexts.ctx_mut()?;
+    egui::Window::new("Download failed")
+        .anchor(egui::Align2::CENTER_BOTTOM, [0., -40.])
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.colored_label(egui::Color32::RED, error);
+            if client.is_offline() {
+                ui.label("Offline mode is on, see settings.ron");
+            }
+            ui.horizontal(|ui| {
+                if ui.button("Retry").clicked() {
+                    downloads.start(&packs.active().root, &config.remote_textures);
+                }
+                if ui.button("Choose another pack").clicked() {
+                    downloads.error = None;
+                    packs.confirmed = false;
+                    next_state.set(GameState::PreLoading);
+                }
+            });
+        });
+
+    Ok(())
+}
@@ -0,0 +1,276 @@
+use std::{
+    collections::BTreeSet,
+    f32::consts::{PI, TAU},
+};
+
+use bevy::{
+    app::{App, AppExit, Last, Plugin, Startup, Update},
+    asset::{Assets, Handle, RenderAssetUsages},
+    camera::Camera,
+    ecs::{
+        change_detection::DetectChanges,
+        message::MessageReader,
+        query::{With, Without},
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Local, Res, ResMut, Single},
+    },
+    image::Image,
+    log::error,
+    math::Vec2,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+    state::condition::in_state,
+    transform::components::GlobalTransform,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    EarthConfig,
+    chunk::ChunkKey,
+    component::{Earth, MainCamera},
+    material::EarthMaterial,
+    math::{Coordinates, ground_distance_per_pixel, ray_sphere_intersection},
+    resource::EarthMaterialTemplate,
+    state::GameState,
+};
+
+const EXPLORED_PATH: &str = "explored.ron";
+
+/// Depth of the chunk quadtree cells that get visited, about 1.4 degrees across.
+const CELL_DEPTH: u8 = 5;
+
+/// Ground distance per pixel the camera has to zoom in below before cells count as visited.
+const VISIT_KM_PER_PIXEL: f32 = 5.;
+
+/// Radius of the spotlight around the screen center, as a share of the viewport height.
+const SPOTLIGHT_RADIUS: f32 = 0.25;
+
+/// Samples across the spotlight on each side of the center.
+const SPOTLIGHT_SAMPLES: i32 = 4;
+
+/// Size of the equirectangular mask telling the shader which cells are visited.
+const MASK_SIZE: (u32, u32) = (1024, 512);
+
+/// Cells of the globe zoomed into so far, persisted to `explored.ron`.
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct VisitedCells(BTreeSet<ChunkKey>);
+
+impl VisitedCells {
+    fn load() -> Self {
+        let Ok(serialized) = std::fs::read_to_string(EXPLORED_PATH) else {
+            return Self::default();
+        };
+        ron::from_str(&serialized).unwrap_or_else(|err| {
+            error!("Failed to parse {EXPLORED_PATH}: {err}");
+            Self::default()
+        })
+    }
+
+    fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let serialized = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        std::fs::write(EXPLORED_PATH, serialized)?;
+        Ok(())
+    }
+}
+
+/// Exploration mode: only the regions zoomed into are shown in full color, the rest of the
+/// globe is desaturated until it gets visited.
+#[derive(Resource)]
+pub struct Exploration {
+    pub enabled: bool,
+    visited: VisitedCells,
+    /// Whether the mask still shows cells cleared by `reset`
+    cleared: bool,
+}
+
+impl Exploration {
+    pub fn visited(&self) -> usize {
+        self.visited.0.len()
+    }
+
+    /// Share of the globe visited, from 0 to 1.
+    pub fn progress(&self) -> f32 {
+        let cells = 6 * 4usize.pow(CELL_DEPTH as u32 + 1);
+        self.visited() as f32 / cells as f32
+    }
+
+    pub fn reset(&mut self) {
+        self.visited.0.clear();
+        self.cleared = true;
+        if let Err(err) = self.visited.save() {
+            error!("Failed to save {EXPLORED_PATH}: {err}");
+        }
+    }
+}
+
+/// Visited cells painted white, sampled with the globe's UVs.
+#[derive(Resource)]
+struct ExplorationMask(Handle<Image>);
+
+pub struct ExplorationPlugin;
+
+impl Plugin for ExplorationPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Exploration {
+            enabled: false,
+            visited: VisitedCells::load(),
+            cleared: false,
+        })
+        .add_systems(Startup, create_mask)
+        .add_systems(
+            Update,
+            (visit_cells, apply_exploration)
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(Last, save_on_exit);
+    }
+}
+
+/// Marks the texels of the mask whose direction lies in `key`.
+fn paint_cell(data: &mut [u8], key: ChunkKey) {
+    let (width, height) = MASK_SIZE;
+
+    let bounds = key.bounds();
+    let (west, south, east, north) = (bounds.min.x, bounds.min.y, bounds.max.x, bounds.max.y);
+
+    let texel = |fraction: f32, size: u32| (fraction * size as f32) as i64;
+    let rows = (texel(0.5 - north / PI, height) - 1).max(0)
+        ..=(texel(0.5 - south / PI, height) + 1).min(height as i64 - 1);
+    let columns = (texel(west / TAU + 0.5, width) - 1).max(0)
+        ..=(texel(east / TAU + 0.5, width) + 1).min(width as i64 - 1);
+    for row in rows {
+        let latitude = PI * (0.5 - (row as f32 + 0.5) / height as f32);
+        for column in columns.clone() {
+            let longitude = TAU * ((column as f32 + 0.5) / width as f32 - 0.5);
+            let direction = Coordinates {
+                latitude,
+                longitude,
+            }
+            .direction();
+            if ChunkKey::containing(direction, key.depth) == key {
+                data[(row * width as i64 + column) as usize] = 255;
+            }
+        }
+    }
+}
+
+fn create_mask(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    exploration: Res<Exploration>,
+) {
+    let (width, height) = MASK_SIZE;
+    let mut mask = Image::new_fill(
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0],
+        TextureFormat::R8Unorm,
+        RenderAssetUsages::default(),
+    );
+    if let Some(data) = mask.data.as_mut() {
+        for &key in &exploration.visited.0 {
+            paint_cell(data, key);
+        }
+    }
+    commands.insert_resource(ExplorationMask(images.add(mask)));
+}
+
+/// Visits the cells under a spotlight around the screen center while zoomed in.
+fn visit_cells(
+    mut exploration: ResMut<Exploration>,
+    mask: Res<ExplorationMask>,
+    mut images: ResMut<Assets<Image>>,
+    config: Res<EarthConfig>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera>>,
+    earth: Single<&GlobalTransform, (With<Earth>, Without<Camera>)>,
+) {
+    if exploration.cleared {
+        exploration.cleared = false;
+        if let Some(data) = images
+            .get_mut(&mask.0)
+            .and_then(|image| image.data.as_mut())
+        {
+            data.fill(0);
+        }
+    }
+    if !exploration.enabled {
+        return;
+    }
+
+    let (camera, transform) = *camera;
+    let center = earth.translation();
+    let zoomed_in = ground_distance_per_pixel(camera, transform, center, config.radius)
+        .is_some_and(|distance| distance * config.km_per_unit() < VISIT_KM_PER_PIXEL);
+    let Some(viewport) = camera.logical_viewport_size() else {
+        return;
+    };
+    if !zoomed_in {
+        return;
+    }
+
+    let local = earth.affine().inverse();
+    let radius = viewport.y * SPOTLIGHT_RADIUS;
+    let mut visited = Vec::new();
+    for x in -SPOTLIGHT_SAMPLES..=SPOTLIGHT_SAMPLES {
+        for y in -SPOTLIGHT_SAMPLES..=SPOTLIGHT_SAMPLES {
+            let offset = Vec2::new(x as f32, y as f32) / SPOTLIGHT_SAMPLES as f32;
+            if offset.length() > 1. {
+                continue;
+            }
+            let Some(hit) = camera
+                .viewport_to_world(transform, viewport / 2. + offset * radius)
+                .ok()
+                .and_then(|ray| ray_sphere_intersection(ray, center, config.radius))
+            else {
+                continue;
+            };
+            let key = ChunkKey::containing(local.transform_point3(hit), CELL_DEPTH);
+            if !exploration.visited.0.contains(&key) {
+                exploration.visited.0.insert(key);
+                visited.push(key);
+            }
+        }
+    }
+
+    if !visited.is_empty()
+        && let Some(data) = images
+            .get_mut(&mask.0)
+            .and_then(|image| image.data.as_mut())
+    {
+        for key in visited {
+            paint_cell(data, key);
+        }
+    }
+}
+
+/// Hands the mask to the Earth material, whenever the mode is toggled or the globe reloaded.
+fn apply_exploration(
+    exploration: Res<Exploration>,
+    mask: Res<ExplorationMask>,
+    handle: Res<EarthMaterialTemplate>,
+    mut materials: ResMut<Assets<EarthMaterial>>,
+    mut applied: Local<Option<bool>>,
+) {
+    if *applied == Some(exploration.enabled) && !handle.is_changed() {
+        return;
+    }
+
+    if let Some(material) = materials.get_mut(&**handle) {
+        material.extension.explored = Some(mask.0.clone());
+        material.extension.uniform.exploration = if exploration.enabled { 1. } else { 0. };
+        *applied = Some(exploration.enabled);
+    }
+}
+
+fn save_on_exit(mut exit: MessageReader<AppExit>, exploration: Res<Exploration>) {
+    if exit.read().next().is_some()
+        && let Err(err) = exploration.visited.save()
+    {
+        error!("Failed to save {EXPLORED_PATH}: {err}");
+    }
+}
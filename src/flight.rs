@@ -0,0 +1,230 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    asset::Assets,
+    color::LinearRgba,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        hierarchy::ChildOf,
+        query::{Added, With},
+        system::{Commands, Query, Res, ResMut, Single},
+    },
+    math::Vec3,
+    mesh::{Mesh, Mesh3d, MeshBuilder},
+    pbr::MeshMaterial3d,
+    time::Time,
+    transform::components::Transform,
+};
+
+use crate::{
+    EarthConfig,
+    component::{Draped, Earth, Marker},
+    math::{Coordinates, great_circle_point},
+    polyline::{LineJoin, Polyline, PolylineMaterial},
+};
+
+/// Segments per radian of arc, enough for a smooth curve across the whole globe.
+const SEGMENTS_PER_RADIAN: f32 = 64.;
+
+/// Height of routes drawn by `spawn_great_circle`, as a share of the globe's radius.
+const ROUTE_HEIGHT: f32 = 0.002;
+
+/// Shape of an arc between takeoff and landing.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum HeightProfile {
+    /// A single parabola peaking halfway
+    #[default]
+    Parabolic,
+    /// Climbs and descends over `ramp` of the route at each end, flat in between
+    Cruise { ramp: f32 },
+    /// Hovers at the peak height all the way, for routes along the ground
+    Flat,
+}
+
+impl HeightProfile {
+    /// Height at `t` along the route as a fraction of the peak.
+    pub fn height(&self, t: f32) -> f32 {
+        match *self {
+            HeightProfile::Parabolic => 4. * t * (1. - t),
+            HeightProfile::Cruise { ramp } => {
+                let ramp = ramp.clamp(1e-3, 0.5);
+                let climb = (t.min(1. - t) / ramp).min(1.);
+                // Smoothstep, so the ends leave and meet the ground tangentially
+                climb * climb * (3. - 2. * climb)
+            }
+            HeightProfile::Flat => 1.,
+        }
+    }
+}
+
+/// An animated great-circle arc between two places, with pulses travelling towards `to`.
+///
+/// The entity is parented to the globe and gets its mesh and material once spawned.
+#[derive(Component, Debug, Clone)]
+pub struct FlightPath {
+    pub from: Coordinates,
+    pub to: Coordinates,
+    /// Highest point of the arc above the surface, as a share of the globe's radius
+    pub peak_height: f32,
+    pub profile: HeightProfile,
+    /// Colors at takeoff and landing
+    pub colors: (LinearRgba, LinearRgba),
+    /// Width in pixels
+    pub width: f32,
+    /// Pulses per second
+    pub speed: f32,
+}
+
+impl FlightPath {
+    pub fn new(from: Coordinates, to: Coordinates) -> Self {
+        Self {
+            from,
+            to,
+            peak_height: 0.,
+            profile: HeightProfile::default(),
+            colors: (LinearRgba::rgb(1., 0.8, 0.2), LinearRgba::rgb(1., 0.2, 0.4)),
+            width: 2.,
+            speed: 0.25,
+        }
+    }
+
+    fn angle(&self) -> f32 {
+        self.from.direction().angle_between(self.to.direction())
+    }
+
+    /// Point at `t` along the arc, from 0 at takeoff to 1 at landing, in the local space of the
+    /// globe scaled to a radius of one.
+    ///
+    /// Without an explicit `peak_height` longer routes fly higher, a fifth of their ground
+    /// distance.
+    pub fn point(&self, t: f32) -> Vec3 {
+        let peak = if self.peak_height > 0. {
+            self.peak_height
+        } else {
+            self.angle() * 0.2
+        };
+        let direction = great_circle_point(self.from.direction(), self.to.direction(), t);
+        direction * (1. + peak * self.profile.height(t))
+    }
+
+    /// Points of the arc in the local space of a globe of `radius`.
+    pub fn points(&self, radius: f32) -> Vec<Vec3> {
+        let segments = ((self.angle() * SEGMENTS_PER_RADIAN).ceil() as usize).max(1);
+        (0..=segments)
+            .map(|segment| self.point(segment as f32 / segments as f32) * radius)
+            .collect()
+    }
+}
+
+/// Draws the great circle route between two places, hovering just above the surface.
+///
+/// Returns the entity of the route, insert a `RouteTraveler` into it to send a marker along.
+pub fn spawn_great_circle(commands: &mut Commands, from: Coordinates, to: Coordinates) -> Entity {
+    commands
+        .spawn(FlightPath {
+            peak_height: ROUTE_HEIGHT,
+            profile: HeightProfile::Flat,
+            ..FlightPath::new(from, to)
+        })
+        .id()
+}
+
+/// Moves a `Marker` along the `FlightPath` of the same entity, from takeoff to landing and over
+/// again.
+#[derive(Component, Debug, Clone)]
+pub struct RouteTraveler {
+    /// Duration of one trip
+    pub seconds: f32,
+    progress: f32,
+    marker: Option<Entity>,
+}
+
+impl RouteTraveler {
+    pub fn new(seconds: f32) -> Self {
+        Self {
+            seconds,
+            progress: 0.,
+            marker: None,
+        }
+    }
+}
+
+/// The route a traveling marker belongs to, which takes the marker along when it despawns.
+#[derive(Component)]
+struct TravelerOf(Entity);
+
+pub struct FlightPlugin;
+
+impl Plugin for FlightPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (spawn_flight_paths, move_travelers, despawn_stray_travelers),
+        );
+    }
+}
+
+fn spawn_flight_paths(
+    mut commands: Commands,
+    flights: Query<(Entity, &FlightPath), Added<FlightPath>>,
+    earth: Single<Entity, With<Earth>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<PolylineMaterial>>,
+    config: Res<EarthConfig>,
+) {
+    for (entity, flight) in &flights {
+        let line = Polyline::new(flight.points(config.radius));
+        let length = line
+            .points
+            .windows(2)
+            .map(|pair| pair[0].distance(pair[1]))
+            .sum();
+
+        let material = PolylineMaterial::new(flight.colors.0, flight.width)
+            .with_join(LineJoin::Round)
+            .with_gradient(flight.colors.1, length)
+            .with_pulse(flight.speed, 0.15, 1.5);
+
+        commands.entity(entity).insert((
+            Mesh3d(meshes.add(line.build())),
+            MeshMaterial3d(materials.add(material)),
+            Draped::default(),
+            Transform::default(),
+            ChildOf(*earth),
+        ));
+    }
+}
+
+fn move_travelers(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut routes: Query<(Entity, &FlightPath, &mut RouteTraveler)>,
+    mut markers: Query<&mut Marker>,
+) {
+    for (route, flight, mut traveler) in &mut routes {
+        traveler.progress =
+            (traveler.progress + time.delta_secs() / traveler.seconds.max(1e-3)).fract();
+        let coordinates = Coordinates::from(flight.point(traveler.progress));
+
+        match traveler.marker.map(|marker| markers.get_mut(marker)) {
+            Some(Ok(mut marker)) => marker.coordinates = coordinates,
+            // Also respawned when something else despawned it
+            _ => {
+                let marker = commands.spawn((Marker { coordinates }, TravelerOf(route)));
+                traveler.marker = Some(marker.id());
+            }
+        }
+    }
+}
+
+fn despawn_stray_travelers(
+    mut commands: Commands,
+    markers: Query<(Entity, &TravelerOf)>,
+    routes: Query<&RouteTraveler>,
+) {
+    for (entity, route) in &markers {
+        if routes.get(route.0).is_err() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    camera::{Camera, Projection},
+    ecs::{
+        entity::Entity,
+        message::MessageReader,
+        query::{Has, With},
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Local, Res, ResMut, Single},
+    },
+    math::{Ray3d, Vec3},
+    post_process::dof::{DepthOfField, DepthOfFieldMode},
+    state::condition::in_state,
+    time::{Real, Time},
+    transform::components::GlobalTransform,
+};
+
+use crate::{
+    EarthConfig,
+    component::{Earth, MainCamera},
+    math::{Coordinates, ray_sphere_intersection},
+    observer::EarthClicked,
+    state::GameState,
+};
+
+/// Altitudes per second the camera may travel over the globe and keep the depth of field.
+const MAX_FOCUS_SPEED: f32 = 0.5;
+
+/// Seconds the camera has to stay slow before the depth of field comes back.
+const SETTLE_TIME: f32 = 0.3;
+
+/// Height of the simulated sensor in meters, Bevy's default of a Super 35 film frame.
+const SENSOR_HEIGHT: f32 = 0.01866;
+
+/// Depth of field of the main camera, with the focal plane on the globe's surface.
+///
+/// Turned off while the camera moves fast, where the blur would only smear the view.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct DepthOfFieldSettings {
+    pub enabled: bool,
+    /// Blur in pixels of whatever is twice as far as the focal plane. The aperture follows the
+    /// focal distance to keep it, so the look doesn't change with the zoom.
+    pub blur: f32,
+    /// Hexagonal bokeh instead of a gaussian blur, sharper highlights but slower
+    pub bokeh: bool,
+    /// Surface point kept in focus, the last one clicked. Without one the point at the center
+    /// of the view is.
+    pub focus: Option<Coordinates>,
+}
+
+impl Default for DepthOfFieldSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            blur: 8.,
+            bokeh: false,
+            focus: None,
+        }
+    }
+}
+
+pub struct FocusPlugin;
+
+impl Plugin for FocusPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DepthOfFieldSettings>().add_systems(
+            Update,
+            (pick_focus, update_depth_of_field)
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+fn pick_focus(
+    mut clicked: MessageReader<EarthClicked>,
+    mut settings: ResMut<DepthOfFieldSettings>,
+) {
+    let Some(click) = clicked.read().last() else {
+        return;
+    };
+    if settings.enabled
+        && let Ok(coordinates) = Coordinates::from_degrees(click.lat, click.lon)
+    {
+        settings.focus = Some(coordinates);
+    }
+}
+
+/// F-number making something twice as far as `focal_distance` blur over `blur` pixels.
+///
+/// Inverts the thin lens circle of confusion Bevy works with, for a lens with the focal length
+/// giving the camera's field of view on its sensor.
+fn aperture_for_blur(blur: f32, focal_distance: f32, fov: f32, viewport_height: f32) -> f32 {
+    let focal_length = 0.5 * SENSOR_HEIGHT / (fov / 2.).tan();
+    let diameter = focal_length * focal_length / (focal_distance - focal_length).max(f32::EPSILON);
+    // Pixels across a circle of confusion at the doubled distance, for an aperture of f/1
+    let blur_at_f1 = diameter * 0.5 / SENSOR_HEIGHT * viewport_height;
+    blur_at_f1 / blur.max(f32::EPSILON)
+}
+
+fn update_depth_of_field(
+    mut commands: Commands,
+    settings: Res<DepthOfFieldSettings>,
+    time: Res<Time<Real>>,
+    config: Res<EarthConfig>,
+    camera: Single<
+        (
+            Entity,
+            &Camera,
+            &Projection,
+            &GlobalTransform,
+            Has<DepthOfField>,
+        ),
+        With<MainCamera>,
+    >,
+    earth: Single<&GlobalTransform, With<Earth>>,
+    mut previous: Local<Option<Vec3>>,
+    mut settled_at: Local<f32>,
+) {
+    let (entity, camera, projection, transform, blurred) = camera.into_inner();
+    let now = time.elapsed_secs();
+
+    // Measured against the globe, so turning it counts as much as flying over it
+    let position = earth
+        .affine()
+        .inverse()
+        .transform_point3(transform.translation());
+    let altitude = (position.length() - config.radius).max(f32::EPSILON);
+    let moved = previous
+        .replace(position)
+        .map_or(0., |last| last.distance(position));
+    if moved / time.delta_secs().max(f32::EPSILON) > MAX_FOCUS_SPEED * altitude {
+        *settled_at = now + SETTLE_TIME;
+    }
+
+    let focal_point = match settings.focus {
+        Some(focus) => Some(earth.transform_point(focus.direction() * config.radius)),
+        None => ray_sphere_intersection(
+            Ray3d::new(transform.translation(), transform.forward()),
+            earth.translation(),
+            config.radius,
+        ),
+    };
+    if settings.enabled
+        && now >= *settled_at
+        && let Projection::Perspective(perspective) = projection
+        && let Some(focal_point) = focal_point
+        && let Some(size) = camera.physical_viewport_size()
+    {
+        let focal_distance = transform.translation().distance(focal_point);
+        commands.entity(entity).insert(DepthOfField {
+            mode: if settings.bokeh {
+                DepthOfFieldMode::Bokeh
+            } else {
+                DepthOfFieldMode::Gaussian
+            },
+            focal_distance,
+            sensor_height: SENSOR_HEIGHT,
+            aperture_f_stops: aperture_for_blur(
+                settings.blur,
+                focal_distance,
+                perspective.fov,
+                size.y as f32,
+            ),
+            ..Default::default()
+        });
+    } else if blurred {
+        commands.entity(entity).remove::<DepthOfField>();
+    }
+}
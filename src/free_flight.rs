@@ -0,0 +1,146 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    ecs::{
+        query::{With, Without},
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Res, ResMut, Single},
+    },
+    input::{
+        ButtonInput,
+        mouse::{AccumulatedMouseMotion, MouseButton},
+    },
+    math::{Vec2, Vec3},
+    state::{condition::in_state, state::State},
+    time::Time,
+    transform::components::Transform,
+};
+
+use crate::{
+    EarthConfig,
+    component::{Earth, MainCamera},
+    depth::camera_altitude,
+    input::{Action, Actions},
+    resource::PointerOverUi,
+    space::{SpaceView, animate_space_view},
+    state::{GameState, ToolMode},
+};
+
+/// Share of the threshold below which the camera snaps back to orbiting the globe, so hovering
+/// around the threshold doesn't toggle between the two every frame.
+const SNAP_BACK_FRACTION: f32 = 0.8;
+
+/// Distance covered per second, as a share of the current altitude.
+const FLY_SPEED: f32 = 0.5;
+
+/// Radians per pixel of mouse movement while looking around.
+const LOOK_SENSITIVITY: f32 = 0.003;
+
+/// Radians per second while a roll key is held.
+const ROLL_SPEED: f32 = 1.;
+
+/// Six degrees of freedom camera controller for exploring space view, taking over from the
+/// orbit camera above `threshold`.
+#[derive(Resource, Debug)]
+pub struct FreeFlight {
+    /// Altitude above which free flight takes over once in space, in Earth radii
+    pub threshold: f32,
+    pub active: bool,
+}
+
+impl Default for FreeFlight {
+    fn default() -> Self {
+        Self {
+            threshold: 20.,
+            active: false,
+        }
+    }
+}
+
+pub struct FreeFlightPlugin;
+
+impl Plugin for FreeFlightPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FreeFlight>().add_systems(
+            Update,
+            (switch_camera_mode, fly)
+                .chain()
+                .after(animate_space_view)
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// Hands the camera to free flight high enough in space, and back to the orbit camera once it
+/// approaches the globe or space view is left.
+fn switch_camera_mode(
+    mut flight: ResMut<FreeFlight>,
+    mut space: ResMut<SpaceView>,
+    config: Res<EarthConfig>,
+    camera: Single<&mut Transform, With<MainCamera>>,
+    earth: Single<&Transform, (With<Earth>, Without<MainCamera>)>,
+) {
+    let center = earth.translation;
+    let altitude = camera_altitude(camera.translation, center, config.radius);
+    let threshold = flight.threshold * config.radius;
+
+    if !flight.active {
+        flight.active = space.is_in_space() && altitude > threshold;
+        return;
+    }
+
+    if !space.enabled {
+        // Left from the menu or the wheel, return to the globe from wherever the flight got to
+        space.return_from(camera.translation - center, camera.rotation);
+        flight.active = false;
+    } else if altitude < threshold * SNAP_BACK_FRACTION {
+        camera.into_inner().look_at(center, Vec3::Y);
+        space.leave();
+        flight.active = false;
+    }
+}
+
+fn fly(
+    flight: Res<FreeFlight>,
+    actions: Actions,
+    mouse: Res<ButtonInput<MouseButton>>,
+    motion: Res<AccumulatedMouseMotion>,
+    time: Res<Time>,
+    mode: Res<State<ToolMode>>,
+    over_ui: Res<PointerOverUi>,
+    config: Res<EarthConfig>,
+    camera: Single<&mut Transform, With<MainCamera>>,
+    earth: Single<&Transform, (With<Earth>, Without<MainCamera>)>,
+) {
+    if !flight.active || !mode.allows_navigation() {
+        return;
+    }
+
+    let axis = |positive: Action, negative: Action| {
+        actions.pressed(positive) as i8 as f32 - actions.pressed(negative) as i8 as f32
+    };
+    let movement = Vec3::new(
+        axis(Action::FlyRight, Action::FlyLeft),
+        axis(Action::FlyUp, Action::FlyDown),
+        axis(Action::FlyBackward, Action::FlyForward),
+    );
+    let roll = axis(Action::RollLeft, Action::RollRight);
+    let look = if mouse.pressed(MouseButton::Right) && !**over_ui {
+        motion.delta * LOOK_SENSITIVITY
+    } else {
+        Vec2::ZERO
+    };
+    if movement == Vec3::ZERO && roll == 0. && look == Vec2::ZERO {
+        return;
+    }
+
+    let mut transform = camera.into_inner();
+    // Slower closer to the Earth, so it can be approached precisely from far out
+    let speed =
+        camera_altitude(transform.translation, earth.translation, config.radius) * FLY_SPEED;
+    let step = transform.rotation * movement * speed * time.delta_secs();
+    transform.translation += step;
+    transform.rotate_local_y(-look.x);
+    transform.rotate_local_x(-look.y);
+    transform.rotate_local_z(roll * ROLL_SPEED * time.delta_secs());
+}
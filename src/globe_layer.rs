@@ -0,0 +1,145 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    ecs::{
+        change_detection::DetectChangesMut,
+        entity::Entity,
+        query::With,
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        world::{Mut, World},
+    },
+    state::{condition::in_state, state::OnExit},
+};
+use bevy_egui::egui;
+use serde::{Deserialize, Serialize};
+
+use crate::{component::Earth, state::GameState, sun::SunClock};
+
+/// A layer type provided by another crate, listed in the layers panel next to the raster and
+/// GeoJSON layers, saved with workspaces and driven by the `SunClock`.
+///
+/// Register it with `RegisterGlobeLayer::register_globe_layer`. Nothing of a hidden layer is
+/// spawned: `setup` runs when it is shown and `despawn` when it is hidden again or the Earth is
+/// recreated.
+pub trait GlobeLayer: Send + Sync + 'static {
+    /// Name in the layers panel and the palette, which also matches the layer to workspaces
+    fn name(&self) -> &str;
+
+    /// Spawns what the layer draws, as children of the `earth` to turn with it.
+    fn setup(&mut self, world: &mut World, earth: Entity);
+
+    /// Runs every frame while the layer is shown, with the `SunClock` in seconds since the Unix
+    /// epoch, so the layer follows the time set in the sun panel.
+    fn update(&mut self, _world: &mut World, _unix_secs: f64) {}
+
+    /// Removes what `setup` spawned. The entities may be gone already, along with the Earth.
+    fn despawn(&mut self, world: &mut World);
+
+    /// Controls shown under the layer in the layers panel while it is visible.
+    fn gui(&mut self, _ui: &mut egui::Ui) {}
+
+    /// Settings saved with workspaces, in any text form `deserialize` reads back.
+    fn serialize(&self) -> String {
+        String::new()
+    }
+
+    /// Restores settings written by `serialize`.
+    fn deserialize(&mut self, _saved: &str) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+pub struct RegisteredLayer {
+    pub layer: Box<dyn GlobeLayer>,
+    pub visible: bool,
+    /// Whether `setup` ran since the last `despawn`
+    spawned: bool,
+}
+
+/// Every registered `GlobeLayer`, in the order they were registered.
+#[derive(Resource, Default)]
+pub struct GlobeLayers(pub Vec<RegisteredLayer>);
+
+impl GlobeLayers {
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut RegisteredLayer> {
+        self.0
+            .iter_mut()
+            .find(|registered| registered.layer.name() == name)
+    }
+}
+
+/// How a `GlobeLayer` is shown, matched to the registered layers by name.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GlobeLayerState {
+    pub name: String,
+    pub visible: bool,
+    /// What the layer's `serialize` returned
+    pub settings: String,
+}
+
+/// Registration of third-party layer types while building the app.
+pub trait RegisterGlobeLayer {
+    /// Adds `layer` to the layers panel, hidden.
+    fn register_globe_layer(&mut self, layer: impl GlobeLayer) -> &mut Self;
+}
+
+impl RegisterGlobeLayer for App {
+    fn register_globe_layer(&mut self, layer: impl GlobeLayer) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_init::<GlobeLayers>()
+            .0
+            .push(RegisteredLayer {
+                layer: Box::new(layer),
+                visible: false,
+                spawned: false,
+            });
+        self
+    }
+}
+
+pub struct GlobeLayerPlugin;
+
+impl Plugin for GlobeLayerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GlobeLayers>()
+            .add_systems(
+                Update,
+                update_globe_layers.run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(OnExit(GameState::Playing), despawn_globe_layers);
+    }
+}
+
+/// Sets up the layers just shown, despawns the ones just hidden and updates the visible ones.
+fn update_globe_layers(world: &mut World) {
+    let Ok(earth) = world.query_filtered::<Entity, With<Earth>>().single(world) else {
+        return;
+    };
+    let unix_secs = world.resource::<SunClock>().unix_secs;
+
+    world.resource_scope(|world, mut layers: Mut<GlobeLayers>| {
+        for registered in &mut layers.bypass_change_detection().0 {
+            match (registered.visible, registered.spawned) {
+                (true, false) => registered.layer.setup(world, earth),
+                (false, true) => registered.layer.despawn(world),
+                _ => {}
+            }
+            registered.spawned = registered.visible;
+            if registered.visible {
+                registered.layer.update(world, unix_secs);
+            }
+        }
+    });
+}
+
+/// Despawns every layer along with the Earth, they are set up again once it is back.
+fn despawn_globe_layers(world: &mut World) {
+    world.resource_scope(|world, mut layers: Mut<GlobeLayers>| {
+        for registered in &mut layers.bypass_change_detection().0 {
+            if registered.spawned {
+                registered.layer.despawn(world);
+                registered.spawned = false;
+            }
+        }
+    });
+}
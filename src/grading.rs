@@ -0,0 +1,98 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    ecs::{
+        change_detection::DetectChanges,
+        query::With,
+        resource::Resource,
+        system::{Res, Single},
+    },
+    render::view::ColorGrading,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::component::MainCamera;
+
+/// Starting points for `ColorGradingSettings`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GradingPreset {
+    /// The imagery as it is
+    Natural,
+    /// Brighter and more saturated, to make up for a washed out projector
+    Projector,
+    /// Punchy colors for a stylized look
+    Vivid,
+    /// Warm and slightly desaturated
+    Cinematic,
+}
+
+impl GradingPreset {
+    pub const ALL: [GradingPreset; 4] = [
+        GradingPreset::Natural,
+        GradingPreset::Projector,
+        GradingPreset::Vivid,
+        GradingPreset::Cinematic,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            GradingPreset::Natural => "Natural",
+            GradingPreset::Projector => "Projector",
+            GradingPreset::Vivid => "Vivid",
+            GradingPreset::Cinematic => "Cinematic",
+        }
+    }
+}
+
+/// Color grading of the main camera, applied after the tonemapping.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ColorGradingSettings {
+    /// Offset of the exposure in EV, positive is brighter
+    pub exposure: f32,
+    /// Multiplier of the saturation, zero for grayscale
+    pub saturation: f32,
+    /// White balance from cool (negative) to warm (positive)
+    pub temperature: f32,
+}
+
+impl Default for ColorGradingSettings {
+    fn default() -> Self {
+        Self::preset(GradingPreset::Natural)
+    }
+}
+
+impl ColorGradingSettings {
+    pub fn preset(preset: GradingPreset) -> Self {
+        let (exposure, saturation, temperature) = match preset {
+            GradingPreset::Natural => (0., 1., 0.),
+            GradingPreset::Projector => (0.5, 1.3, 0.),
+            GradingPreset::Vivid => (0.2, 1.6, 0.),
+            GradingPreset::Cinematic => (-0.2, 0.8, 0.3),
+        };
+        Self {
+            exposure,
+            saturation,
+            temperature,
+        }
+    }
+}
+
+pub struct ColorGradingPlugin;
+
+impl Plugin for ColorGradingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ColorGradingSettings>()
+            .add_systems(Update, apply_color_grading);
+    }
+}
+
+fn apply_color_grading(
+    settings: Res<ColorGradingSettings>,
+    mut grading: Single<&mut ColorGrading, With<MainCamera>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    grading.global.exposure = settings.exposure;
+    grading.global.post_saturation = settings.saturation;
+    grading.global.temperature = settings.temperature;
+}
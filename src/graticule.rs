@@ -0,0 +1,223 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    asset::Assets,
+    camera::{Camera, visibility::Visibility},
+    color::LinearRgba,
+    ecs::{
+        change_detection::DetectChangesMut,
+        component::Component,
+        entity::Entity,
+        hierarchy::ChildOf,
+        query::{With, Without},
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Query, Res, ResMut, Single},
+    },
+    math::Vec3,
+    mesh::{Mesh, Mesh3d, MeshBuilder},
+    pbr::MeshMaterial3d,
+    state::condition::in_state,
+    transform::components::{GlobalTransform, Transform},
+};
+
+use crate::{
+    EarthConfig,
+    component::{Draped, Earth, MainCamera},
+    math::{Coordinates, ground_distance_per_pixel},
+    polyline::{Polyline, PolylineMaterial},
+    state::GameState,
+    vector::VectorView,
+};
+
+/// Spacings in degrees the grid can use, each dividing the others' multiples of 90.
+pub const GRATICULE_SPACINGS: [f32; 6] = [1., 2., 5., 10., 15., 30.];
+
+/// Pixels lines have to stay apart at the center of the view, before the grid gets coarser.
+const MIN_LINE_GAP: f32 = 60.;
+
+/// Degrees between the vertices of a line, close enough to follow the curve of the globe.
+const VERTEX_SPACING: f32 = 1.;
+
+/// Latitude and longitude lines over the globe, with the equator and prime meridian highlighted.
+#[derive(Resource, Debug)]
+pub struct Graticule {
+    pub enabled: bool,
+    /// Degrees between lines when zoomed in, one of `GRATICULE_SPACINGS`. Zoomed out the grid
+    /// skips lines so they don't clutter the view.
+    pub spacing: f32,
+}
+
+impl Default for Graticule {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            spacing: 10.,
+        }
+    }
+}
+
+/// The lines of the grid, rebuilt whenever the spacing in use changes.
+#[derive(Component, Debug, Default)]
+struct GraticuleGrid {
+    /// Spacing the mesh was built with, zero before the first build
+    spacing: f32,
+}
+
+/// The equator and prime meridian.
+#[derive(Component)]
+struct GraticuleAxes;
+
+pub struct GraticulePlugin;
+
+impl Plugin for GraticulePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Graticule>().add_systems(
+            Update,
+            (spawn_graticule, update_graticule)
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// Point at the given latitude and longitude in degrees, on the surface of a globe of `radius`.
+fn point(latitude: f32, longitude: f32, radius: f32) -> Vec3 {
+    Coordinates {
+        latitude: latitude.to_radians(),
+        longitude: longitude.to_radians(),
+    }
+    .direction()
+        * radius
+}
+
+fn parallel(latitude: f32, radius: f32) -> Polyline {
+    let vertices = (360. / VERTEX_SPACING) as usize;
+    Polyline::new(
+        (0..vertices).map(|vertex| point(latitude, vertex as f32 * VERTEX_SPACING - 180., radius)),
+    )
+    .closed()
+}
+
+fn meridian(longitude: f32, radius: f32) -> Polyline {
+    let vertices = (180. / VERTEX_SPACING) as usize;
+    Polyline::new(
+        (0..=vertices).map(|vertex| point(vertex as f32 * VERTEX_SPACING - 90., longitude, radius)),
+    )
+}
+
+fn merge(lines: impl IntoIterator<Item = Polyline>) -> Option<Mesh> {
+    let mut meshes = lines.into_iter().map(|line| line.build());
+    let mut mesh = meshes.next()?;
+    for line in meshes {
+        // Every line has the same attributes
+        mesh.merge(&line).ok()?;
+    }
+    Some(mesh)
+}
+
+/// Parallels and meridians every `spacing` degrees, leaving out the axes and the poles.
+fn grid_mesh(spacing: f32, radius: f32) -> Option<Mesh> {
+    let parallels = (1..(180. / spacing) as usize)
+        .map(|line| line as f32 * spacing - 90.)
+        .filter(|&latitude| latitude != 0.)
+        .map(|latitude| parallel(latitude, radius));
+    let meridians = (0..(360. / spacing) as usize)
+        .map(|line| line as f32 * spacing - 180.)
+        .filter(|&longitude| longitude != 0.)
+        .map(|longitude| meridian(longitude, radius));
+    merge(parallels.chain(meridians))
+}
+
+/// Finest spacing, no finer than `spacing`, that keeps the lines `MIN_LINE_GAP` pixels apart on
+/// a globe of `radius`.
+fn visible_spacing(spacing: f32, distance_per_pixel: Option<f32>, radius: f32) -> f32 {
+    let coarsest = GRATICULE_SPACINGS[GRATICULE_SPACINGS.len() - 1].max(spacing);
+    let Some(per_pixel) = distance_per_pixel else {
+        return coarsest;
+    };
+    GRATICULE_SPACINGS
+        .into_iter()
+        .filter(|&candidate| candidate >= spacing)
+        .find(|candidate| candidate.to_radians() * radius / per_pixel >= MIN_LINE_GAP)
+        .unwrap_or(coarsest)
+}
+
+/// Puts the lines on the globe whenever it was (re)spawned, hidden until enabled.
+fn spawn_graticule(
+    mut commands: Commands,
+    grid: Query<(), With<GraticuleGrid>>,
+    earth: Option<Single<Entity, With<Earth>>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<PolylineMaterial>>,
+    config: Res<EarthConfig>,
+) {
+    let Some(earth) = earth else {
+        return;
+    };
+    if !grid.is_empty() {
+        return;
+    }
+
+    let grid_material = PolylineMaterial::new(LinearRgba::new(1., 1., 1., 0.35), 1.);
+    commands.spawn((
+        GraticuleGrid::default(),
+        MeshMaterial3d(materials.add(grid_material)),
+        Draped::default(),
+        Transform::default(),
+        Visibility::Hidden,
+        ChildOf(*earth),
+    ));
+    if let Some(axes) = merge([parallel(0., config.radius), meridian(0., config.radius)]) {
+        let axes_material = PolylineMaterial::new(LinearRgba::rgb(1., 0.8, 0.2), 2.);
+        commands.spawn((
+            GraticuleAxes,
+            Mesh3d(meshes.add(axes)),
+            MeshMaterial3d(materials.add(axes_material)),
+            Draped::default(),
+            Transform::default(),
+            Visibility::Hidden,
+            ChildOf(*earth),
+        ));
+    }
+}
+
+fn update_graticule(
+    mut commands: Commands,
+    graticule: Res<Graticule>,
+    vector: Res<VectorView>,
+    grid: Single<(Entity, &mut GraticuleGrid, &mut Visibility), Without<GraticuleAxes>>,
+    mut axes: Query<&mut Visibility, With<GraticuleAxes>>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera>>,
+    earth: Single<&GlobalTransform, With<Earth>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    config: Res<EarthConfig>,
+) {
+    let (entity, mut grid, mut visibility) = grid.into_inner();
+    // The vector view is made of the grid
+    let enabled = graticule.enabled || vector.enabled;
+    let shown = if enabled {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    visibility.set_if_neq(shown);
+    for mut visibility in &mut axes {
+        visibility.set_if_neq(shown);
+    }
+    if !enabled {
+        return;
+    }
+
+    let (camera, transform) = camera.into_inner();
+    let per_pixel =
+        ground_distance_per_pixel(camera, transform, earth.translation(), config.radius);
+    let spacing = visible_spacing(graticule.spacing, per_pixel, config.radius);
+    if spacing == grid.spacing {
+        return;
+    }
+    grid.spacing = spacing;
+    match grid_mesh(spacing, config.radius) {
+        Some(mesh) => commands.entity(entity).insert(Mesh3d(meshes.add(mesh))),
+        None => commands.entity(entity).remove::<Mesh3d>(),
+    };
+}
@@ -0,0 +1,217 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    asset::{Assets, Handle},
+    camera::{Camera, visibility::Visibility},
+    color::LinearRgba,
+    ecs::{
+        change_detection::DetectChangesMut,
+        component::Component,
+        entity::Entity,
+        hierarchy::ChildOf,
+        query::{With, Without},
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Local, Query, Res, ResMut, Single},
+    },
+    math::Vec3,
+    mesh::{Mesh, Mesh3d, MeshBuilder},
+    pbr::MeshMaterial3d,
+    state::condition::in_state,
+    time::{Real, Time},
+    transform::components::{GlobalTransform, Transform},
+};
+
+use crate::{
+    EarthConfig,
+    component::{Draped, Earth, MainCamera},
+    math::{Coordinates, great_circle_point, ray_sphere_intersection},
+    polyline::{LineJoin, Polyline, PolylineMaterial},
+    state::GameState,
+};
+
+/// Seconds between samples of the view center.
+const SAMPLE_INTERVAL: f32 = 0.5;
+
+/// Degrees the view center has to move before another sample is taken.
+const MIN_SAMPLE_ANGLE: f32 = 0.1;
+
+/// Oldest samples are dropped beyond this many.
+const MAX_SAMPLES: usize = 4000;
+
+/// Degrees between the vertices of the track, close enough to follow the curve of the globe.
+const VERTEX_SPACING: f32 = 1.;
+
+/// Color at the view center, fading to transparent at the oldest sample.
+const TRACK_COLOR: LinearRgba = LinearRgba::new(1., 0.5, 0.1, 0.9);
+
+/// Trail of where the view center has been during the session, fading out towards its oldest
+/// end. It keeps recording while hidden.
+#[derive(Resource, Debug)]
+pub struct GroundTrack {
+    pub enabled: bool,
+    samples: Vec<Coordinates>,
+    /// Whether the samples changed since the mesh was last built
+    dirty: bool,
+}
+
+impl Default for GroundTrack {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            samples: Vec::new(),
+            dirty: true,
+        }
+    }
+}
+
+impl GroundTrack {
+    pub fn samples(&self) -> &[Coordinates] {
+        &self.samples
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+        self.dirty = true;
+    }
+}
+
+/// The line drawn through the samples.
+#[derive(Component)]
+struct GroundTrackLine(Handle<PolylineMaterial>);
+
+pub struct GroundTrackPlugin;
+
+impl Plugin for GroundTrackPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GroundTrack>().add_systems(
+            Update,
+            (sample_ground_track, spawn_ground_track, update_ground_track)
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+fn sample_ground_track(
+    mut track: ResMut<GroundTrack>,
+    time: Res<Time<Real>>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera>>,
+    earth: Single<&GlobalTransform, (With<Earth>, Without<Camera>)>,
+    config: Res<EarthConfig>,
+    mut last_sample: Local<f32>,
+) {
+    let now = time.elapsed_secs();
+    if now - *last_sample < SAMPLE_INTERVAL {
+        return;
+    }
+    *last_sample = now;
+
+    let (camera, transform) = *camera;
+    let Some(hit) = camera
+        .logical_viewport_size()
+        .and_then(|viewport| camera.viewport_to_world(transform, viewport / 2.).ok())
+        .and_then(|ray| ray_sphere_intersection(ray, earth.translation(), config.radius))
+    else {
+        return;
+    };
+    let center = Coordinates::from(earth.affine().inverse().transform_point3(hit));
+    if let Some(last) = track.samples.last()
+        && last.direction().angle_between(center.direction()) < MIN_SAMPLE_ANGLE.to_radians()
+    {
+        return;
+    }
+
+    if track.samples.len() >= MAX_SAMPLES {
+        track.samples.remove(0);
+    }
+    track.samples.push(center);
+    track.dirty = true;
+}
+
+/// Points along the track on a globe of `radius`, following great circles between samples far
+/// apart.
+fn track_points(samples: &[Coordinates], radius: f32) -> Vec<Vec3> {
+    let mut points = Vec::new();
+    for pair in samples.windows(2) {
+        let (a, b) = (pair[0].direction(), pair[1].direction());
+        let steps = (a.angle_between(b).to_degrees() / VERTEX_SPACING)
+            .ceil()
+            .max(1.) as usize;
+        points.extend(
+            (0..steps).map(|step| great_circle_point(a, b, step as f32 / steps as f32) * radius),
+        );
+    }
+    points.extend(samples.last().map(|last| last.direction() * radius));
+    points
+}
+
+fn spawn_ground_track(
+    mut commands: Commands,
+    mut track: ResMut<GroundTrack>,
+    lines: Query<(), With<GroundTrackLine>>,
+    earth: Option<Single<Entity, With<Earth>>>,
+    mut materials: ResMut<Assets<PolylineMaterial>>,
+) {
+    let Some(earth) = earth else {
+        return;
+    };
+    if !lines.is_empty() {
+        return;
+    }
+
+    let start = LinearRgba {
+        alpha: 0.,
+        ..TRACK_COLOR
+    };
+    let material = materials.add(
+        PolylineMaterial::new(start, 2.)
+            .with_join(LineJoin::Round)
+            .with_gradient(TRACK_COLOR, 0.),
+    );
+    commands.spawn((
+        GroundTrackLine(material.clone()),
+        MeshMaterial3d(material),
+        Draped::default(),
+        Transform::default(),
+        Visibility::Hidden,
+        ChildOf(*earth),
+    ));
+    // A new globe needs the mesh again
+    track.dirty = true;
+}
+
+fn update_ground_track(
+    mut commands: Commands,
+    mut track: ResMut<GroundTrack>,
+    line: Single<(Entity, &GroundTrackLine, &mut Visibility)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<PolylineMaterial>>,
+    config: Res<EarthConfig>,
+) {
+    let (entity, line, mut visibility) = line.into_inner();
+    visibility.set_if_neq(if track.enabled {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    });
+    if !track.enabled || !track.dirty {
+        return;
+    }
+    track.dirty = false;
+
+    let points = track_points(&track.samples, config.radius);
+    if points.len() < 2 {
+        commands.entity(entity).remove::<Mesh3d>();
+        return;
+    }
+    let length = points
+        .windows(2)
+        .map(|pair| pair[0].distance(pair[1]))
+        .sum();
+    if let Some(material) = materials.get_mut(&line.0) {
+        material.uniform.line_length = length;
+    }
+    commands
+        .entity(entity)
+        .insert(Mesh3d(meshes.add(Polyline::new(points).build())));
+}
@@ -1,21 +1,93 @@
 use bevy::{
     app::Plugin,
-    camera::ClearColor,
+    camera::{Camera, ClearColor},
     color::Color,
     ecs::{
+        change_detection::DetectChangesMut,
+        message::{MessageReader, MessageWriter},
+        query::With,
+        resource::Resource,
         schedule::{IntoScheduleConfigs, SystemCondition},
-        system::{Local, Res, ResMut},
+        system::{Local, Res, ResMut, Single, SystemParam},
     },
     input::keyboard::KeyCode,
     state::{
         condition::in_state,
         state::{NextState, State},
     },
+    time::{Real, Time},
+    transform::components::GlobalTransform,
 };
 use bevy_egui::{EguiContexts, EguiPlugin, EguiPrimaryContextPass, egui};
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
 
-use crate::{resource::LoadingProgress, state::GameState};
+use crate::{
+    EarthConfig, MAX_FOV, MIN_FOV,
+    antipode::AntipodeView,
+    atmosphere::Atmosphere,
+    bookmark::BookmarksPanel,
+    borders::BorderCrossings,
+    clouds::Clouds,
+    component::{Earth, MainCamera},
+    crosshair::Crosshair,
+    depth::camera_altitude,
+    discover::Discover,
+    download::downloadable,
+    exploration::Exploration,
+    focus::DepthOfFieldSettings,
+    free_flight::FreeFlight,
+    grading::{ColorGradingSettings, GradingPreset},
+    graticule::{GRATICULE_SPACINGS, Graticule},
+    ground_track::GroundTrack,
+    http::NetworkPanel,
+    input::{Action, KeyBindings, KeyBindingsEditor},
+    layer::{LayerInfo, LayersPanel, RasterLayers},
+    lighting::{FillLighting, LightingMode, LightingPreset},
+    magnifier::Magnifier,
+    material::{MaterialInspector, MaterialSettings},
+    math::{Coordinates, ground_distance_per_pixel},
+    navigation::{FlyToOnDoubleClick, Navigate},
+    observer::EarthClicked,
+    pack::{EarthPack, EarthPacks},
+    palette::RegisterCommand,
+    post_process::PostProcessing,
+    power::PowerSaving,
+    quality::{Quality, QualityLevel},
+    replay::{Replay, ReplayCommand},
+    resource::{
+        CursorHit, KeyboardOverUi, LoadingProgress, PointerOverUi, ShowNorthArrow, SimulationTime,
+    },
+    satellite::SatellitesPanel,
+    script::ScriptsPanel,
+    settings::{EarthSettings, SettingsPanel},
+    simulation::EarthSpin,
+    sky::StarField,
+    snapshot::SnapshotRunner,
+    space::{SpaceScale, SpaceView},
+    state::{GameState, ToolMode},
+    stats::MeshStatsPanel,
+    stylized::StylizedView,
+    sun::{SunClock, SunMode},
+    tiles::TileStream,
+    toast::NotificationsPanel,
+    vector::VectorView,
+    window::{DisplayMode, RESOLUTIONS, WindowSettings},
+    workspace::WorkspacesPanel,
+};
+
+/// Widest the scale bar is allowed to grow, in logical pixels.
+const SCALE_BAR_MAX_WIDTH: f32 = 120.;
+
+/// Seconds the coordinates of a click stay next to the pointer.
+const CLICK_TOOLTIP_SECONDS: f32 = 3.;
+
+/// Shows the coordinates of each `EarthClicked` in a tooltip where the click landed.
+#[derive(Resource, Default)]
+pub struct ClickTooltip {
+    pub enabled: bool,
+    /// Position on screen, the click, and when it happened
+    shown: Option<(egui::Pos2, EarthClicked, f32)>,
+}
 
 pub struct GuiPlugin;
 
@@ -33,12 +105,47 @@ impl Plugin for GuiPlugin {
                 EguiPrimaryContextPass,
                 display_loading_screen.run_if(
                     in_state(GameState::Loading)
-                        .or(in_state(GameState::PostLoading).or(in_state(GameState::PreLoading))),
+                        .or(in_state(GameState::PostLoading).or(in_state(GameState::PreLoading)))
+                        .or(in_state(GameState::DownloadAssets)),
                 ),
+            )
+            .init_resource::<PointerOverUi>()
+            .init_resource::<ClickTooltip>()
+            .add_systems(EguiPrimaryContextPass, track_pointer_over_ui)
+            .add_systems(
+                EguiPrimaryContextPass,
+                (
+                    display_menu_bar,
+                    display_status_bar,
+                    display_settings_panel.run_if(|panel: Res<SettingsPanel>| panel.open),
+                    display_click_tooltip,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+
+        for tool in ToolMode::ALL {
+            app.register_command(
+                format!("Tool: {}", tool.label()),
+                move |mut next_mode: ResMut<NextState<ToolMode>>| next_mode.set(tool),
             );
+        }
     }
 }
 
+/// Mirrors egui's claims on the pointer and the keyboard into `PointerOverUi` and
+/// `KeyboardOverUi`, as of the last egui pass.
+fn track_pointer_over_ui(
+    mut contexts: EguiContexts,
+    mut over_ui: ResMut<PointerOverUi>,
+    mut keyboard_over_ui: ResMut<KeyboardOverUi>,
+) -> bevy::prelude::Result {
+    let ctx = contexts.ctx_mut()?;
+    over_ui.set_if_neq(PointerOverUi(ctx.wants_pointer_input()));
+    keyboard_over_ui.set_if_neq(KeyboardOverUi(ctx.wants_keyboard_input()));
+    Ok(())
+}
+
 fn display_loading_screen(
     mut contexts: EguiContexts,
     progress: Res<LoadingProgress>,
@@ -46,6 +153,8 @@ fn display_loading_screen(
     mut frames_rendered: Local<u8>,
     state: Res<State<GameState>>,
     mut next_state: ResMut<NextState<GameState>>,
+    mut packs: ResMut<EarthPacks>,
+    config: Res<EarthConfig>,
 ) -> bevy::prelude::Result {
     let ctx = contexts.ctx_mut()?;
 
@@ -69,6 +178,26 @@ fn display_loading_screen(
             ui.vertical_centered(|ui| {
                 ui.add_space(10.);
 
+                if !packs.confirmed {
+                    select_pack(ui, &mut packs, &config);
+                    return;
+                }
+
+                if *state == GameState::DownloadAssets {
+                    ui.heading("Downloading textures...");
+                    ui.add_space(20.);
+                    let bar = egui::ProgressBar::new(progress.download)
+                        .desired_width(300.)
+                        .show_percentage();
+                    ui.add(bar);
+                    ui.add_space(10.);
+                    if let Some(file) = &progress.downloading {
+                        ui.label(format!("Downloading {file}"));
+                    }
+                    ui.add_space(10.);
+                    return;
+                }
+
                 ui.heading("Loading...");
                 ui.add_space(20.);
 
@@ -94,11 +223,934 @@ fn display_loading_screen(
             ui.image(egui::include_image!("../assets/loading_right.gif"));
         });
 
-    if *state == GameState::PreLoading {
+    if *state == GameState::PreLoading && packs.confirmed {
         *frames_rendered += 1;
         if *frames_rendered >= 3 {
-            next_state.set(GameState::Loading);
+            next_state.set(GameState::DownloadAssets);
         }
     }
     Ok(())
 }
+
+fn select_pack(ui: &mut egui::Ui, packs: &mut EarthPacks, config: &EarthConfig) {
+    ui.heading("Select asset pack");
+    ui.add_space(10.);
+
+    let startable =
+        |pack: &EarthPack| pack.is_valid() || downloadable(pack, &config.remote_textures);
+    for (index, pack) in packs.available.iter().enumerate() {
+        let label = if pack.is_valid() {
+            pack.name.clone()
+        } else if startable(pack) {
+            format!("{} (downloads {})", pack.name, pack.missing.join(", "))
+        } else {
+            format!("{} (missing {})", pack.name, pack.missing.join(", "))
+        };
+        ui.add_enabled_ui(startable(pack), |ui| {
+            ui.radio_value(&mut packs.selected, index, label)
+                .on_hover_text(pack.root.display().to_string());
+        });
+    }
+
+    ui.add_space(10.);
+    if ui
+        .add_enabled(startable(packs.active()), egui::Button::new("Start"))
+        .clicked()
+    {
+        packs.confirmed = true;
+    }
+    ui.add_space(10.);
+}
+
+fn with_key(label: &str, bindings: &KeyBindings, action: Action) -> String {
+    format!("{label} ({})", bindings.label(action))
+}
+
+/// Windows toggled from the View menu.
+#[derive(SystemParam)]
+struct Windows<'w> {
+    material_inspector: ResMut<'w, MaterialInspector>,
+    layers: ResMut<'w, LayersPanel>,
+    key_bindings: ResMut<'w, KeyBindingsEditor>,
+    snapshots: ResMut<'w, SnapshotRunner>,
+    mesh_stats: ResMut<'w, MeshStatsPanel>,
+    bookmarks: ResMut<'w, BookmarksPanel>,
+    notifications: ResMut<'w, NotificationsPanel>,
+    network: ResMut<'w, NetworkPanel>,
+    satellites: ResMut<'w, SatellitesPanel>,
+    scripts: ResMut<'w, ScriptsPanel>,
+    workspaces: ResMut<'w, WorkspacesPanel>,
+    settings: ResMut<'w, SettingsPanel>,
+}
+
+/// Settings changed right in the View menu.
+#[derive(SystemParam)]
+struct ViewSettings<'w> {
+    quality: ResMut<'w, Quality>,
+    power: ResMut<'w, PowerSaving>,
+    window: ResMut<'w, WindowSettings>,
+    exploration: ResMut<'w, Exploration>,
+    atmosphere: ResMut<'w, Atmosphere>,
+    borders: ResMut<'w, BorderCrossings>,
+    clouds: ResMut<'w, Clouds>,
+    antipode: ResMut<'w, AntipodeView>,
+    tiles: ResMut<'w, TileStream>,
+    fly_to: ResMut<'w, FlyToOnDoubleClick>,
+    overlays: OverlaySettings<'w>,
+    config: Res<'w, EarthConfig>,
+}
+
+/// Lines drawn over the globe from the View menu.
+#[derive(SystemParam)]
+struct OverlaySettings<'w> {
+    graticule: ResMut<'w, Graticule>,
+    ground_track: ResMut<'w, GroundTrack>,
+}
+
+/// Presentation settings of the View menu, changing how the globe looks but not what it shows.
+#[derive(SystemParam)]
+struct LookSettings<'w> {
+    lighting: ResMut<'w, LightingMode>,
+    fill: ResMut<'w, FillLighting>,
+    grading: ResMut<'w, ColorGradingSettings>,
+    stylized: ResMut<'w, StylizedView>,
+    vector: ResMut<'w, VectorView>,
+    post_processing: ResMut<'w, PostProcessing>,
+    depth_of_field: ResMut<'w, DepthOfFieldSettings>,
+    stars: ResMut<'w, StarField>,
+}
+
+/// Settings of the Simulation menu moving the Earth and the Sun.
+#[derive(SystemParam)]
+struct SkySettings<'w> {
+    spin: ResMut<'w, EarthSpin>,
+    sun: ResMut<'w, SunMode>,
+    clock: ResMut<'w, SunClock>,
+}
+
+fn display_menu_bar(
+    mut contexts: EguiContexts,
+    mut navigate: MessageWriter<Navigate>,
+    mut discover: MessageWriter<Discover>,
+    mut simulation: ResMut<SimulationTime>,
+    replay: Res<Replay>,
+    mut replay_commands: MessageWriter<ReplayCommand>,
+    mut windows: Windows,
+    mut next_state: ResMut<NextState<GameState>>,
+    mode: Res<State<ToolMode>>,
+    mut next_mode: ResMut<NextState<ToolMode>>,
+    bindings: Res<KeyBindings>,
+    mut space: ResMut<SpaceView>,
+    mut flight: ResMut<FreeFlight>,
+    mut view: ViewSettings,
+    mut looks: LookSettings,
+    mut sky: SkySettings,
+) -> bevy::prelude::Result {
+    let ctx = contexts.ctx_mut()?;
+
+    egui::TopBottomPanel::top("Menu").show(ctx, |ui| {
+        egui::MenuBar::new().ui(ui, |ui| {
+            ui.menu_button("Navigate", |ui| {
+                for (label, action, target) in [
+                    ("North Pole", Action::NorthPole, Navigate::NorthPole),
+                    ("South Pole", Action::SouthPole, Navigate::SouthPole),
+                    ("Antipode", Action::Antipode, Navigate::Antipode),
+                ] {
+                    if ui.button(with_key(label, &bindings, action)).clicked() {
+                        navigate.write(target);
+                        ui.close();
+                    }
+                }
+                ui.separator();
+                if ui.button("Somewhere interesting").clicked() {
+                    discover.write(Discover);
+                    ui.close();
+                }
+                ui.checkbox(&mut view.fly_to.0, "Fly to double-clicked points");
+            });
+
+            ui.menu_button("Simulation", |ui| {
+                let label = if simulation.paused { "Resume" } else { "Pause" };
+                if ui
+                    .button(with_key(label, &bindings, Action::TogglePause))
+                    .clicked()
+                {
+                    simulation.toggle_pause();
+                }
+                if ui
+                    .add_enabled(
+                        simulation.paused,
+                        egui::Button::new(with_key("Step", &bindings, Action::Step)),
+                    )
+                    .clicked()
+                {
+                    simulation.step();
+                }
+
+                let mut speed = simulation.speed;
+                let slider = egui::Slider::new(
+                    &mut speed,
+                    SimulationTime::MIN_SPEED..=SimulationTime::MAX_SPEED,
+                )
+                .logarithmic(true)
+                .text(format!(
+                    "Speed ({} / {})",
+                    bindings.label(Action::SlowDown),
+                    bindings.label(Action::SpeedUp)
+                ));
+                if ui.add(slider).changed() {
+                    simulation.set_speed(speed);
+                }
+                ui.checkbox(&mut sky.spin.enabled, "Rotate the Earth")
+                    .on_hover_text("One turn per sidereal day at 1x");
+                ui.menu_button("Sun", |ui| sun_menu(ui, &mut sky));
+            });
+
+            ui.menu_button("Session", |ui| {
+                let command = if replay.is_recording() {
+                    ui.button(with_key(
+                        "Stop recording",
+                        &bindings,
+                        Action::ToggleRecording,
+                    ))
+                    .clicked()
+                    .then_some(ReplayCommand::Stop)
+                } else if replay.is_playing() {
+                    ui.button(with_key("Stop replay", &bindings, Action::ToggleReplay))
+                        .clicked()
+                        .then_some(ReplayCommand::Stop)
+                } else if ui
+                    .button(with_key(
+                        "Start recording",
+                        &bindings,
+                        Action::ToggleRecording,
+                    ))
+                    .clicked()
+                {
+                    Some(ReplayCommand::StartRecording)
+                } else {
+                    ui.button(with_key("Play replay", &bindings, Action::ToggleReplay))
+                        .clicked()
+                        .then_some(ReplayCommand::Play)
+                };
+
+                if let Some(command) = command {
+                    replay_commands.write(command);
+                    ui.close();
+                }
+            });
+
+            ui.menu_button("Tools", |ui| {
+                for tool in ToolMode::ALL {
+                    if ui.radio(**mode == tool, tool.label()).clicked() {
+                        next_mode.set(tool);
+                        ui.close();
+                    }
+                }
+            });
+
+            ui.menu_button("View", |ui| {
+                ui.checkbox(&mut windows.material_inspector.open, "Material inspector");
+                ui.checkbox(&mut windows.layers.open, "Layers");
+                ui.checkbox(&mut windows.key_bindings.open, "Key bindings");
+                ui.checkbox(&mut windows.snapshots.open, "Snapshot tests");
+                ui.checkbox(&mut windows.mesh_stats.open, "Mesh statistics");
+                ui.checkbox(&mut windows.bookmarks.open, "Bookmarks");
+                ui.checkbox(&mut windows.notifications.open, "Notifications");
+                ui.checkbox(&mut windows.network.open, "Network activity");
+                ui.checkbox(&mut windows.settings.open, "Settings");
+                ui.checkbox(&mut windows.satellites.open, "Satellites");
+                ui.checkbox(&mut windows.scripts.open, "Scripts");
+                ui.checkbox(&mut windows.workspaces.open, "Workspaces");
+                ui.separator();
+                ui.checkbox(&mut view.atmosphere.enabled, "Atmosphere");
+                ui.checkbox(&mut view.clouds.enabled, "Clouds");
+                ui.checkbox(&mut view.antipode.enabled, "Antipode view")
+                    .on_hover_text("Show the opposite side of the globe in a corner");
+                ui.add_enabled(
+                    view.borders.is_available(),
+                    egui::Checkbox::new(&mut view.borders.enabled, "Border crossings"),
+                )
+                .on_disabled_hover_text("The active pack has no countries.ron");
+                ui.add_enabled(
+                    view.config.tile_url.is_some(),
+                    egui::Checkbox::new(&mut view.tiles.enabled, "Stream imagery tiles"),
+                )
+                .on_hover_text(if view.tiles.is_offline() {
+                    "Offline, only cached tiles are shown".to_string()
+                } else {
+                    format!("{} chunks loading", view.tiles.pending())
+                })
+                .on_disabled_hover_text("No tile server is configured");
+                ui.menu_button("Graticule", |ui| {
+                    ui.checkbox(&mut view.overlays.graticule.enabled, "Show");
+                    ui.separator();
+                    for spacing in GRATICULE_SPACINGS {
+                        ui.radio_value(
+                            &mut view.overlays.graticule.spacing,
+                            spacing,
+                            format!("Every {spacing}°"),
+                        );
+                    }
+                })
+                .response
+                .on_hover_text("Fewer lines are drawn while zoomed out");
+                ui.menu_button("Ground track", |ui| {
+                    let track = &mut view.overlays.ground_track;
+                    ui.checkbox(&mut track.enabled, "Show")
+                        .on_hover_text("Where the view center has been, fading with age");
+                    ui.label(format!("{} samples", track.samples().len()));
+                    if ui.button("Clear").clicked() {
+                        track.clear();
+                    }
+                });
+                ui.menu_button("Lighting", |ui| {
+                    for mode in LightingMode::ALL {
+                        ui.radio_value(&mut *looks.lighting, mode, mode.label());
+                    }
+                    ui.separator();
+                    let mut fill = *looks.fill;
+                    ui.horizontal(|ui| {
+                        for preset in LightingPreset::ALL {
+                            let selected = fill == FillLighting::preset(preset);
+                            if ui.selectable_label(selected, preset.label()).clicked() {
+                                fill = FillLighting::preset(preset);
+                            }
+                        }
+                    });
+                    ui.add(
+                        egui::Slider::new(&mut fill.ambient, 0.0..=2000.)
+                            .suffix(" cd/m²")
+                            .text("Ambient"),
+                    );
+                    ui.add(
+                        egui::Slider::new(&mut fill.fill, 0.0..=10000.)
+                            .suffix(" lx")
+                            .text("Fill light"),
+                    );
+                    if fill != *looks.fill {
+                        *looks.fill = fill;
+                    }
+                });
+                ui.menu_button("Stars", |ui| {
+                    let mut stars = *looks.stars;
+                    ui.checkbox(&mut stars.enabled, "Show")
+                        .on_hover_text("The pack's stars.png, or generated stars without one");
+                    ui.add_enabled_ui(stars.enabled, |ui| {
+                        ui.add(
+                            egui::Slider::new(&mut stars.brightness, 0.0..=5000.)
+                                .logarithmic(true)
+                                .suffix(" cd/m²")
+                                .text("Brightness"),
+                        );
+                        ui.add(
+                            egui::Slider::new(&mut stars.rotation, 0.0..=360.)
+                                .suffix("°")
+                                .text("Rotation"),
+                        );
+                    });
+                    if stars != *looks.stars {
+                        *looks.stars = stars;
+                    }
+                });
+                ui.menu_button("Stylized", |ui| {
+                    let mut stylized = *looks.stylized;
+                    ui.checkbox(&mut stylized.enabled, "Stylized globe")
+                        .on_hover_text("Flat continents, cel-shaded oceans and an outline");
+                    ui.add_enabled_ui(stylized.enabled, |ui| {
+                        for (label, color) in [
+                            ("Land", &mut stylized.land),
+                            ("Ocean", &mut stylized.ocean),
+                            ("Outline", &mut stylized.outline),
+                        ] {
+                            ui.horizontal(|ui| {
+                                ui.color_edit_button_rgb(color);
+                                ui.label(label);
+                            });
+                        }
+                        ui.add(
+                            egui::Slider::new(&mut stylized.outline_width, 0.0..=0.5)
+                                .text("Outline width"),
+                        );
+                        ui.add(egui::Slider::new(&mut stylized.bands, 1..=8).text("Ocean bands"));
+                    });
+                    if stylized != *looks.stylized {
+                        *looks.stylized = stylized;
+                    }
+
+                    ui.separator();
+                    let mut vector = *looks.vector;
+                    ui.add_enabled(
+                        view.borders.is_available(),
+                        egui::Checkbox::new(&mut vector.enabled, "Vector globe"),
+                    )
+                    .on_hover_text("Glowing coastlines and graticule on a dark sphere")
+                    .on_disabled_hover_text("The active pack has no countries.ron");
+                    ui.add_enabled_ui(vector.enabled, |ui| {
+                        for (label, color) in [
+                            ("Lines", &mut vector.color),
+                            ("Background", &mut vector.background),
+                        ] {
+                            ui.horizontal(|ui| {
+                                ui.color_edit_button_rgb(color);
+                                ui.label(label);
+                            });
+                        }
+                    });
+                    if vector != *looks.vector {
+                        *looks.vector = vector;
+                    }
+                });
+                ui.menu_button("Color grading", |ui| {
+                    let mut grading = *looks.grading;
+                    ui.horizontal(|ui| {
+                        for preset in GradingPreset::ALL {
+                            let selected = grading == ColorGradingSettings::preset(preset);
+                            if ui.selectable_label(selected, preset.label()).clicked() {
+                                grading = ColorGradingSettings::preset(preset);
+                            }
+                        }
+                    });
+                    ui.add(
+                        egui::Slider::new(&mut grading.exposure, -3.0..=3.)
+                            .suffix(" EV")
+                            .text("Exposure"),
+                    );
+                    ui.add(egui::Slider::new(&mut grading.saturation, 0.0..=2.).text("Saturation"));
+                    ui.add(
+                        egui::Slider::new(&mut grading.temperature, -1.0..=1.).text("Temperature"),
+                    );
+                    if grading != *looks.grading {
+                        *looks.grading = grading;
+                    }
+                });
+                ui.menu_button("Depth of field", |ui| {
+                    let mut depth_of_field = *looks.depth_of_field;
+                    ui.checkbox(&mut depth_of_field.enabled, "Enabled")
+                        .on_hover_text("Paused while the camera moves fast");
+                    ui.add_enabled_ui(depth_of_field.enabled, |ui| {
+                        ui.add(
+                            egui::Slider::new(&mut depth_of_field.blur, 1.0..=32.)
+                                .suffix(" px")
+                                .text("Blur"),
+                        );
+                        ui.checkbox(&mut depth_of_field.bokeh, "Bokeh");
+                        ui.separator();
+                        ui.label(match depth_of_field.focus {
+                            Some(focus) => {
+                                let (latitude, longitude) = focus.as_degrees();
+                                format!("Focused on {latitude:.2}°, {longitude:.2}°")
+                            }
+                            None => "Focused on the center".into(),
+                        });
+                        if ui
+                            .add_enabled(
+                                depth_of_field.focus.is_some(),
+                                egui::Button::new("Focus on the center"),
+                            )
+                            .on_hover_text("Click the globe to focus elsewhere")
+                            .clicked()
+                        {
+                            depth_of_field.focus = None;
+                        }
+                    });
+                    if depth_of_field != *looks.depth_of_field {
+                        *looks.depth_of_field = depth_of_field;
+                    }
+                });
+                ui.menu_button("Post-processing", |ui| {
+                    let mut post_processing = looks.post_processing.clone();
+                    let mut raise = None;
+                    for (index, settings) in post_processing.stack.iter_mut().enumerate() {
+                        ui.horizontal(|ui| {
+                            if ui
+                                .add_enabled(index > 0, egui::Button::new("⏶").small())
+                                .on_hover_text("Apply earlier")
+                                .clicked()
+                            {
+                                raise = Some(index);
+                            }
+                            ui.checkbox(&mut settings.enabled, settings.effect.label());
+                            ui.add_enabled(
+                                settings.enabled,
+                                egui::Slider::new(&mut settings.strength, 0.0..=1.),
+                            );
+                        });
+                    }
+                    if let Some(index) = raise {
+                        post_processing.move_up(index);
+                    }
+                    if post_processing != *looks.post_processing {
+                        *looks.post_processing = post_processing;
+                    }
+                })
+                .response
+                .on_hover_text("Effects are applied from top to bottom");
+                ui.menu_button("Quality", |ui| {
+                    for level in QualityLevel::ALL {
+                        ui.radio_value(&mut view.quality.preferred, level, level.label());
+                    }
+                    ui.separator();
+                    ui.checkbox(&mut view.quality.automatic, "Adapt to frame rate");
+                    ui.label(format!(
+                        "{} at {:.0} fps",
+                        view.quality.level().label(),
+                        1. / view.quality.frame_time().max(f32::EPSILON)
+                    ));
+                });
+                ui.checkbox(&mut view.power.enabled, "Power saving")
+                    .on_hover_text("Only render while something changes");
+                ui.menu_button("Window", |ui| {
+                    let mut settings = *view.window;
+                    for mode in DisplayMode::ALL {
+                        ui.radio_value(&mut settings.mode, mode, mode.label());
+                    }
+                    ui.checkbox(&mut settings.vsync, "Vsync");
+                    ui.separator();
+                    ui.add_enabled_ui(settings.mode == DisplayMode::Windowed, |ui| {
+                        for resolution in RESOLUTIONS {
+                            let (width, height) = resolution;
+                            ui.radio_value(
+                                &mut settings.resolution,
+                                resolution,
+                                format!("{width} × {height}"),
+                            );
+                        }
+                    });
+                    if settings != *view.window {
+                        *view.window = settings;
+                    }
+                });
+                ui.menu_button("Exploration", |ui| {
+                    ui.checkbox(&mut view.exploration.enabled, "Fog of war")
+                        .on_hover_text("Only regions you zoomed into are shown in color");
+                    ui.label(format!(
+                        "{} cells, {:.1}% explored",
+                        view.exploration.visited(),
+                        view.exploration.progress() * 100.
+                    ));
+                    if ui.button("Reset").clicked() {
+                        view.exploration.reset();
+                    }
+                });
+                ui.checkbox(
+                    &mut space.enabled,
+                    with_key("Space view", &bindings, Action::ToggleSpaceView),
+                );
+                let mut true_scale = space.scale == SpaceScale::True;
+                if ui
+                    .checkbox(&mut true_scale, "True scale in space")
+                    .changed()
+                {
+                    space.scale = if true_scale {
+                        SpaceScale::True
+                    } else {
+                        SpaceScale::Compressed
+                    };
+                }
+                ui.add(
+                    egui::Slider::new(&mut flight.threshold, 2.0..=1000.)
+                        .logarithmic(true)
+                        .suffix(" R")
+                        .text("Free flight above"),
+                );
+                ui.separator();
+                if ui.button("Reload globe").clicked() {
+                    next_state.set(GameState::Loading);
+                    ui.close();
+                }
+            });
+        });
+    });
+
+    Ok(())
+}
+
+/// Side panel with the `EarthSettings`, toggled with `Action::ToggleSettings`.
+fn display_settings_panel(
+    mut contexts: EguiContexts,
+    mut panel: ResMut<SettingsPanel>,
+    mut settings: ResMut<EarthSettings>,
+    mut config: ResMut<EarthConfig>,
+    mut material: ResMut<MaterialSettings>,
+    mut clouds: ResMut<Clouds>,
+    mut atmosphere: ResMut<Atmosphere>,
+    mut graticule: ResMut<Graticule>,
+    bindings: Res<KeyBindings>,
+) -> bevy::prelude::Result {
+    let ctx = contexts.ctx_mut()?;
+
+    // Mirrored values are taken from the resources in charge of them, which the View menu may
+    // have just changed
+    let mut edited = EarthSettings {
+        resolution: config.resolution,
+        exaggeration: material.exaggeration,
+        clouds: clouds.enabled,
+        atmosphere: atmosphere.enabled,
+        graticule: graticule.enabled,
+        ..*settings
+    };
+    egui::SidePanel::right("Settings")
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.heading("Settings");
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui
+                        .small_button("✖")
+                        .on_hover_text(bindings.label(Action::ToggleSettings))
+                        .clicked()
+                    {
+                        panel.open = false;
+                    }
+                });
+            });
+            ui.separator();
+
+            ui.label("Globe");
+            ui.add(egui::Slider::new(&mut edited.resolution, 16..=256).text("Mesh resolution"))
+                .on_hover_text("Vertices along each chunk edge, for chunks built from now on");
+            ui.add(
+                egui::Slider::new(&mut edited.exaggeration, 1.0..=100.)
+                    .logarithmic(true)
+                    .text("Exaggeration"),
+            )
+            .on_hover_text("Of the displacement, when it is on in the material inspector");
+            ui.checkbox(&mut edited.clouds, "Clouds");
+            ui.checkbox(&mut edited.atmosphere, "Atmosphere");
+            ui.checkbox(&mut edited.graticule, "Graticule");
+
+            ui.separator();
+            ui.label("Camera");
+            ui.add(
+                egui::Slider::new(&mut edited.rotation_sensitivity, 0.1..=5.)
+                    .logarithmic(true)
+                    .text("Rotation sensitivity"),
+            );
+            ui.add(
+                egui::Slider::new(&mut edited.zoom_sensitivity, 0.1..=5.)
+                    .logarithmic(true)
+                    .text("Zoom sensitivity"),
+            );
+            let mut min_fov = edited.min_fov.to_degrees();
+            let mut max_fov = edited.max_fov.to_degrees();
+            ui.add(
+                egui::Slider::new(&mut min_fov, MIN_FOV.to_degrees()..=max_fov)
+                    .suffix("°")
+                    .text("Narrowest view"),
+            );
+            ui.add(
+                egui::Slider::new(&mut max_fov, min_fov..=MAX_FOV.to_degrees())
+                    .suffix("°")
+                    .text("Widest view"),
+            )
+            .on_hover_text("Zooming out into the space view needs the widest view at its limit");
+            edited.min_fov = min_fov.to_radians().max(MIN_FOV);
+            edited.max_fov = max_fov.to_radians().min(MAX_FOV);
+
+            ui.separator();
+            ui.label("Light");
+            ui.add(
+                egui::Slider::new(&mut edited.light_speed, 0.0..=10.)
+                    .suffix("×")
+                    .text("Demo speed"),
+            )
+            .on_hover_text("How fast the light circles the globe in the demo sun mode");
+            ui.add(
+                egui::Slider::new(&mut edited.illuminance, 0.0..=100_000.)
+                    .logarithmic(true)
+                    .suffix(" lx")
+                    .text("Sun illuminance"),
+            );
+
+            ui.separator();
+            ui.label("Network");
+            ui.checkbox(&mut edited.offline, "Offline mode").on_hover_text(
+                "Never go on the network, imagery tiles only come from their cache and downloads \
+                 are off",
+            );
+
+            ui.separator();
+            if ui.button("Reset to defaults").clicked() {
+                edited = EarthSettings::default();
+            }
+        });
+
+    if edited.resolution != config.resolution {
+        config.resolution = edited.resolution;
+    }
+    if edited.exaggeration != material.exaggeration {
+        material.exaggeration = edited.exaggeration;
+    }
+    if edited.clouds != clouds.enabled {
+        clouds.enabled = edited.clouds;
+    }
+    if edited.atmosphere != atmosphere.enabled {
+        atmosphere.enabled = edited.atmosphere;
+    }
+    if edited.graticule != graticule.enabled {
+        graticule.enabled = edited.graticule;
+    }
+    settings.set_if_neq(edited);
+
+    Ok(())
+}
+
+fn display_status_bar(
+    mut contexts: EguiContexts,
+    cursor: Res<CursorHit>,
+    mut show_north_arrow: ResMut<ShowNorthArrow>,
+    simulation: Res<SimulationTime>,
+    mode: Res<State<ToolMode>>,
+    mut next_mode: ResMut<NextState<ToolMode>>,
+    bindings: Res<KeyBindings>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera>>,
+    earth: Single<&GlobalTransform, With<Earth>>,
+    packs: Res<EarthPacks>,
+    layers: Res<RasterLayers>,
+    mut click_tooltip: ResMut<ClickTooltip>,
+    mut crosshair: ResMut<Crosshair>,
+    mut magnifier: ResMut<Magnifier>,
+    config: Res<EarthConfig>,
+) -> bevy::prelude::Result {
+    let ctx = contexts.ctx_mut()?;
+    let (camera, transform) = camera.into_inner();
+
+    let center = earth.translation();
+    let altitude = camera_altitude(transform.translation(), center, config.radius);
+    let km_per_pixel = ground_distance_per_pixel(camera, transform, center, config.radius)
+        .map(|distance| distance * config.km_per_unit());
+
+    egui::TopBottomPanel::bottom("Status").show(ctx, |ui| {
+        ui.horizontal(|ui| {
+            match cursor.map(Coordinates::from) {
+                Some(coordinates) => ui.label(format_coordinates(coordinates)),
+                None => ui.label("--"),
+            };
+            if crosshair.enabled {
+                ui.separator();
+                match crosshair.coordinates() {
+                    Some(coordinates) => {
+                        ui.label(format!("Center: {}", format_coordinates(coordinates)))
+                    }
+                    None => ui.label("Center: --"),
+                };
+            }
+            ui.separator();
+
+            ui.label(format!(
+                "Altitude: {}",
+                format_distance(altitude * config.km_per_unit())
+            ));
+            ui.separator();
+
+            if let Some(km_per_pixel) = km_per_pixel {
+                scale_bar(ui, km_per_pixel);
+            }
+
+            ui.separator();
+            ui.checkbox(
+                &mut show_north_arrow.0,
+                with_key("North arrow", &bindings, Action::ToggleNorthArrow),
+            );
+            ui.checkbox(
+                &mut crosshair.enabled,
+                with_key("Crosshair", &bindings, Action::ToggleCrosshair),
+            );
+            ui.checkbox(
+                &mut magnifier.enabled,
+                with_key("Magnifier", &bindings, Action::ToggleMagnifier),
+            );
+            ui.checkbox(&mut click_tooltip.enabled, "Click coordinates")
+                .on_hover_text("Show the coordinates of each click on the globe");
+            let mut measuring = **mode == ToolMode::Measuring;
+            if ui
+                .toggle_value(
+                    &mut measuring,
+                    with_key("Ruler", &bindings, Action::Measure),
+                )
+                .on_hover_text("Measure the distance between two clicks on the globe")
+                .changed()
+            {
+                next_mode.set(if measuring {
+                    ToolMode::Measuring
+                } else {
+                    ToolMode::Idle
+                });
+            }
+            ui.separator();
+
+            if simulation.paused {
+                ui.label("Paused");
+            } else {
+                ui.label(format!("{}x", simulation.speed));
+            }
+            ui.separator();
+            ui.label(mode.label());
+
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                attribution_line(ui, &packs, &layers);
+            });
+        });
+    });
+
+    Ok(())
+}
+
+fn display_click_tooltip(
+    mut contexts: EguiContexts,
+    mut tooltip: ResMut<ClickTooltip>,
+    mut clicks: MessageReader<EarthClicked>,
+    time: Res<Time<Real>>,
+) -> bevy::prelude::Result {
+    let ctx = contexts.ctx_mut()?;
+    let now = time.elapsed_secs();
+
+    if let Some(&click) = clicks.read().last()
+        && let Some(position) = ctx.input(|input| input.pointer.interact_pos())
+    {
+        tooltip.shown = Some((position, click, now));
+    }
+    if !tooltip.enabled {
+        return Ok(());
+    }
+    let Some((position, click, clicked_at)) = tooltip.shown else {
+        return Ok(());
+    };
+    if now - clicked_at > CLICK_TOOLTIP_SECONDS {
+        tooltip.shown = None;
+        return Ok(());
+    }
+
+    let coordinates = Coordinates {
+        latitude: click.lat.to_radians(),
+        longitude: click.lon.to_radians(),
+    };
+    egui::Area::new("Click coordinates".into())
+        .order(egui::Order::Tooltip)
+        .fixed_pos(position + egui::vec2(12., 12.))
+        .interactable(false)
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.label(format_coordinates(coordinates));
+            });
+        });
+
+    Ok(())
+}
+
+/// Attributions of the base imagery and every overlay on screen, each linking to its source.
+fn attribution_line(ui: &mut egui::Ui, packs: &EarthPacks, layers: &RasterLayers) {
+    let mut shown: Vec<&LayerInfo> = Vec::new();
+    let infos = std::iter::once(&packs.active().manifest.imagery)
+        .chain(layers.rendered().map(|layer| &layer.info));
+    for info in infos {
+        if !info.attribution.is_empty()
+            && !shown
+                .iter()
+                .any(|other| other.attribution == info.attribution)
+        {
+            shown.push(info);
+        }
+    }
+
+    // Laid out right to left, added in reverse so the base imagery reads first
+    for (index, info) in shown.into_iter().rev().enumerate() {
+        if index > 0 {
+            ui.label("·");
+        }
+        match &info.url {
+            Some(url) => ui.hyperlink_to(&info.attribution, url),
+            None => ui.label(&info.attribution),
+        };
+    }
+}
+
+fn sun_menu(ui: &mut egui::Ui, sky: &mut SkySettings) {
+    let mut mode = *sky.sun;
+    ui.radio_value(&mut mode, SunMode::Demo, SunMode::Demo.label())
+        .on_hover_text("Circle the globe, whatever the date");
+    let realtime = matches!(mode, SunMode::Realtime { .. });
+    if ui.radio(realtime, "Real time").clicked() && !realtime {
+        mode = SunMode::Realtime { speedup: 1. };
+        *sky.clock = SunClock::now();
+    }
+    ui.radio_value(&mut mode, SunMode::Fixed, SunMode::Fixed.label());
+    if let SunMode::Realtime { speedup } = &mut mode {
+        ui.add(
+            egui::Slider::new(speedup, 1.0..=100_000.)
+                .logarithmic(true)
+                .suffix("x")
+                .text("Speedup"),
+        );
+    }
+    if mode != *sky.sun {
+        *sky.sun = mode;
+    }
+
+    ui.separator();
+    ui.add_enabled_ui(mode != SunMode::Demo, |ui| {
+        let mut day = sky.clock.day_of_year();
+        if ui
+            .add(egui::Slider::new(&mut day, 0..=365).text("Day of the year"))
+            .changed()
+        {
+            sky.clock.set_day_of_year(day);
+        }
+        let mut hours = sky.clock.seconds_of_day() / 3600.;
+        if ui
+            .add(
+                egui::Slider::new(&mut hours, 0.0..=24.)
+                    .suffix(" h")
+                    .text("Time of day (UTC)"),
+            )
+            .changed()
+        {
+            sky.clock.set_seconds_of_day(hours * 3600.);
+        }
+        ui.label(sky.clock.to_string());
+        ui.label(format!(
+            "Overhead at {}",
+            format_coordinates(sky.clock.subsolar_point())
+        ));
+        if ui.button("Now").clicked() {
+            *sky.clock = SunClock::now();
+        }
+    });
+}
+
+pub fn format_coordinates(coordinates: Coordinates) -> String {
+    let (lat, lon) = coordinates.as_degrees();
+    let ns = if lat >= 0. { 'N' } else { 'S' };
+    let ew = if lon >= 0. { 'E' } else { 'W' };
+    format!("{:.4}°{ns} {:.4}°{ew}", lat.abs(), lon.abs())
+}
+
+pub fn format_distance(km: f32) -> String {
+    if km >= 1. {
+        format!("{km:.0} km")
+    } else {
+        format!("{:.0} m", km * 1000.)
+    }
+}
+
+/// Draws a bar whose length is the largest 1/2/5 step that fits in `SCALE_BAR_MAX_WIDTH`.
+fn scale_bar(ui: &mut egui::Ui, km_per_pixel: f32) {
+    let max_km = km_per_pixel * SCALE_BAR_MAX_WIDTH;
+    let magnitude = 10f32.powf(max_km.log10().floor());
+    let step = [5., 2., 1.]
+        .into_iter()
+        .map(|factor| factor * magnitude)
+        .find(|&step| step <= max_km)
+        .unwrap_or(magnitude);
+
+    let width = step / km_per_pixel;
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(width, 10.), egui::Sense::hover());
+    let stroke = egui::Stroke::new(2., ui.visuals().text_color());
+    let painter = ui.painter();
+    painter.line_segment([rect.left_bottom(), rect.right_bottom()], stroke);
+    painter.line_segment([rect.left_top(), rect.left_bottom()], stroke);
+    painter.line_segment([rect.right_top(), rect.right_bottom()], stroke);
+
+    ui.label(format_distance(step));
+}
@@ -0,0 +1,485 @@
+use std::{
+    collections::HashMap,
+    io::Read,
+    sync::{
+        Arc, Condvar, Mutex,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use bevy::{
+    app::{App, First, Plugin},
+    ecs::{
+        message::{Message, MessageReader, MessageWriter},
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Res, ResMut},
+    },
+    state::condition::in_state,
+};
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+
+use crate::{settings::EarthSettings, state::GameState};
+
+/// Requests sent at the same time, the rest wait for a slot.
+const MAX_CONCURRENT: usize = 6;
+
+/// Attempts after the first failed one.
+const MAX_RETRIES: u32 = 3;
+
+/// Wait before the first retry, doubling with each further one.
+const BASE_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Longest wait a `Retry-After` header is honored for.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Least time between two requests to the same host.
+const POLITENESS_DELAY: Duration = Duration::from_millis(100);
+
+/// Bytes received between two `HttpProgress::Receiving` messages.
+const PROGRESS_STEP: u64 = 1 << 20;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RequestState {
+    Started,
+    /// Waiting to try again after `error`
+    Retrying {
+        attempt: u32,
+        error: String,
+    },
+    Receiving {
+        received: u64,
+        total: Option<u64>,
+    },
+    Finished,
+    Failed(String),
+}
+
+/// Progress of a request sent by the `HttpClient`, written in `First` once it happened.
+#[derive(Message, Debug, Clone)]
+pub struct HttpProgress {
+    pub id: u64,
+    pub url: String,
+    pub state: RequestState,
+}
+
+#[derive(Default)]
+struct Slots {
+    used: Mutex<usize>,
+    freed: Condvar,
+}
+
+struct ClientInner {
+    agent: ureq::Agent,
+    slots: Slots,
+    /// Earliest time the next request to each host may be sent
+    next_request: Mutex<HashMap<String, Instant>>,
+    /// Progress of requests on the task pool, waiting to be written as messages
+    progress: Mutex<Vec<HttpProgress>>,
+    next_id: AtomicU64,
+    /// Set by `EarthSettings::offline`, failing every request right away
+    offline: AtomicBool,
+}
+
+/// Every request to the network goes through here: tiles, texture downloads and anything fetched
+/// later. The client limits how many requests run at once, retries failed ones with exponential
+/// backoff and spaces out requests to the same host.
+///
+/// Requests block, waiting for a free slot and sleeping between retries, so they are sent from
+/// tasks on the `IoTaskPool` and never hold the `AsyncComputeTaskPool` workers chunk meshes are
+/// generated on. Cloning the client to move it into a task shares its connections and limits.
+///
+/// In offline mode every request fails without touching the network, leaving providers to their
+/// caches and the bundled data.
+#[derive(Resource, Clone)]
+pub struct HttpClient(Arc<ClientInner>);
+
+impl Default for HttpClient {
+    fn default() -> Self {
+        Self(Arc::new(ClientInner {
+            agent: ureq::AgentBuilder::new()
+                .timeout_connect(Duration::from_secs(10))
+                .user_agent(concat!("bevy-earth/", env!("CARGO_PKG_VERSION")))
+                .build(),
+            slots: Slots::default(),
+            next_request: Mutex::default(),
+            progress: Mutex::default(),
+            next_id: AtomicU64::new(0),
+            offline: AtomicBool::new(false),
+        }))
+    }
+}
+
+/// A slot of `MAX_CONCURRENT`, given back when dropped.
+struct Permit(Arc<ClientInner>);
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        *self.0.slots.used.lock().unwrap() -= 1;
+        self.0.slots.freed.notify_one();
+    }
+}
+
+/// `scheme://host:port` part of `url`, the unit requests are spaced out by.
+fn host(url: &str) -> &str {
+    let start = url.find("://").map_or(0, |index| index + 3);
+    let end = url[start..]
+        .find('/')
+        .map_or(url.len(), |index| start + index);
+    &url[..end]
+}
+
+/// Whether a request that failed this way may succeed when sent again.
+fn is_transient(err: &ureq::Error) -> bool {
+    match err {
+        ureq::Error::Status(code, _) => *code == 408 || *code == 429 || *code >= 500,
+        ureq::Error::Transport(_) => true,
+    }
+}
+
+/// Wait the server asked for in a `Retry-After` header given in seconds.
+fn retry_after(err: &ureq::Error) -> Option<Duration> {
+    let ureq::Error::Status(_, response) = err else {
+        return None;
+    };
+    let seconds: u64 = response.header("Retry-After")?.trim().parse().ok()?;
+    Some(Duration::from_secs(seconds).min(MAX_BACKOFF))
+}
+
+impl HttpClient {
+    pub fn is_offline(&self) -> bool {
+        self.0.offline.load(Ordering::Relaxed)
+    }
+
+    fn report(&self, id: u64, url: &str, state: RequestState) {
+        self.0.progress.lock().unwrap().push(HttpProgress {
+            id,
+            url: url.to_string(),
+            state,
+        });
+    }
+
+    fn acquire(&self) -> Permit {
+        let slots = &self.0.slots;
+        let mut used = slots
+            .freed
+            .wait_while(slots.used.lock().unwrap(), |used| *used >= MAX_CONCURRENT)
+            .unwrap();
+        *used += 1;
+        Permit(self.0.clone())
+    }
+
+    /// Sleeps until the host of `url` may be asked again.
+    fn wait_for_host(&self, url: &str) {
+        let now = Instant::now();
+        let wait = {
+            let mut next_request = self.0.next_request.lock().unwrap();
+            let next = next_request.entry(host(url).to_string()).or_insert(now);
+            let send_at = (*next).max(now);
+            *next = send_at + POLITENESS_DELAY;
+            send_at - now
+        };
+        if !wait.is_zero() {
+            thread::sleep(wait);
+        }
+    }
+
+    /// Sends a GET request for `url` with extra `headers`, retrying transient failures.
+    ///
+    /// The request keeps its slot until the response is dropped, reading the body included.
+    pub fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<HttpResponse, String> {
+        let id = self.0.next_id.fetch_add(1, Ordering::Relaxed);
+        self.report(id, url, RequestState::Started);
+        if self.is_offline() {
+            let err = "Offline mode is on".to_string();
+            self.report(id, url, RequestState::Failed(err.clone()));
+            return Err(err);
+        }
+
+        let mut attempt = 0;
+        loop {
+            let permit = self.acquire();
+            self.wait_for_host(url);
+            let request = headers
+                .iter()
+                .fold(self.0.agent.get(url), |request, (name, value)| {
+                    request.set(name, value)
+                });
+            let err = match request.call() {
+                Ok(response) => {
+                    return Ok(HttpResponse::new(self.clone(), id, url, response, permit));
+                }
+                // A range past the end only tells a resumed download that it is complete, which
+                // the caller checks the status for
+                Err(ureq::Error::Status(416, response)) => {
+                    return Ok(HttpResponse::new(self.clone(), id, url, response, permit));
+                }
+                Err(err) => err,
+            };
+            // Waiting out the backoff doesn't hold up other requests
+            drop(permit);
+
+            if attempt >= MAX_RETRIES || !is_transient(&err) {
+                let err = err.to_string();
+                self.report(id, url, RequestState::Failed(err.clone()));
+                return Err(err);
+            }
+            attempt += 1;
+            let backoff = retry_after(&err).unwrap_or(BASE_BACKOFF * 2u32.pow(attempt - 1));
+            self.report(
+                id,
+                url,
+                RequestState::Retrying {
+                    attempt,
+                    error: err.to_string(),
+                },
+            );
+            thread::sleep(backoff);
+        }
+    }
+
+    /// Fetches the whole body of `url`.
+    pub fn get_bytes(&self, url: &str) -> Result<Vec<u8>, String> {
+        let mut bytes = Vec::new();
+        self.get(url, &[])?
+            .read_to_end(&mut bytes)
+            .map_err(|err| err.to_string())?;
+        Ok(bytes)
+    }
+}
+
+/// A response of the `HttpClient`, read for its body. Reading reports the progress, and the
+/// request counts as finished once the response is dropped.
+pub struct HttpResponse {
+    client: HttpClient,
+    id: u64,
+    url: String,
+    status: u16,
+    headers: Vec<(String, String)>,
+    /// Length of the body, if the server told
+    total: Option<u64>,
+    received: u64,
+    /// `received` when progress was last reported
+    reported: u64,
+    reader: Box<dyn Read + Send + Sync>,
+    /// Whether the request's last state was reported already
+    done: bool,
+    _permit: Permit,
+}
+
+impl HttpResponse {
+    fn new(
+        client: HttpClient,
+        id: u64,
+        url: &str,
+        response: ureq::Response,
+        permit: Permit,
+    ) -> Self {
+        let headers = response
+            .headers_names()
+            .into_iter()
+            .filter_map(|name| {
+                let value = response.header(&name)?.to_string();
+                Some((name, value))
+            })
+            .collect();
+        Self {
+            client,
+            id,
+            url: url.to_string(),
+            status: response.status(),
+            total: response
+                .header("Content-Length")
+                .and_then(|length| length.parse().ok()),
+            headers,
+            received: 0,
+            reported: 0,
+            reader: response.into_reader(),
+            done: false,
+            _permit: permit,
+        }
+    }
+
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn content_length(&self) -> Option<u64> {
+        self.total
+    }
+
+    fn finish(&mut self, state: RequestState) {
+        if !self.done {
+            self.done = true;
+            self.client.report(self.id, &self.url, state);
+        }
+    }
+}
+
+impl Read for HttpResponse {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self
+            .reader
+            .read(buf)
+            .inspect_err(|err| self.finish(RequestState::Failed(err.to_string())))?;
+        self.received += read as u64;
+        if read == 0 {
+            self.finish(RequestState::Finished);
+        } else if self.received - self.reported >= PROGRESS_STEP {
+            self.reported = self.received;
+            self.client.report(
+                self.id,
+                &self.url,
+                RequestState::Receiving {
+                    received: self.received,
+                    total: self.total,
+                },
+            );
+        }
+        Ok(read)
+    }
+}
+
+impl Drop for HttpResponse {
+    fn drop(&mut self) {
+        self.finish(RequestState::Finished);
+    }
+}
+
+/// Requests in flight, as last reported by their `HttpProgress`.
+#[derive(Resource, Default)]
+pub struct NetworkActivity {
+    pub requests: HashMap<u64, HttpProgress>,
+    pub finished: u64,
+    pub failed: u64,
+}
+
+#[derive(Resource, Default)]
+pub struct NetworkPanel {
+    pub open: bool,
+}
+
+pub struct HttpPlugin;
+
+impl Plugin for HttpPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HttpClient>()
+            .init_resource::<NetworkActivity>()
+            .init_resource::<NetworkPanel>()
+            .add_message::<HttpProgress>()
+            .add_systems(
+                First,
+                (apply_offline, write_progress, track_activity).chain(),
+            )
+            .add_systems(
+                EguiPrimaryContextPass,
+                display_network_panel
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(|panel: Res<NetworkPanel>| panel.open),
+            );
+    }
+}
+
+fn apply_offline(client: Res<HttpClient>, settings: Res<EarthSettings>) {
+    client.0.offline.store(settings.offline, Ordering::Relaxed);
+}
+
+/// Writes the progress reported by tasks since the last frame as messages.
+fn write_progress(client: Res<HttpClient>, mut messages: MessageWriter<HttpProgress>) {
+    let progress = std::mem::take(&mut *client.0.progress.lock().unwrap());
+    messages.write_batch(progress);
+}
+
+fn track_activity(
+    mut messages: MessageReader<HttpProgress>,
+    mut activity: ResMut<NetworkActivity>,
+) {
+    for progress in messages.read() {
+        match progress.state {
+            RequestState::Finished => {
+                activity.requests.remove(&progress.id);
+                activity.finished += 1;
+            }
+            RequestState::Failed(_) => {
+                activity.requests.remove(&progress.id);
+                activity.failed += 1;
+            }
+            _ => {
+                activity.requests.insert(progress.id, progress.clone());
+            }
+        }
+    }
+}
+
+fn display_network_panel(
+    mut contexts: EguiContexts,
+    mut panel: ResMut<NetworkPanel>,
+    activity: Res<NetworkActivity>,
+    client: Res<HttpClient>,
+) -> bevy::prelude::Result {
+    let ctx = contexts.ctx_mut()?;
+
+    let mut requests: Vec<_> = activity.requests.values().collect();
+    requests.sort_by_key(|progress| progress.id);
+    egui::Window::new("Network activity")
+        .open(&mut panel.open)
+        .default_width(360.)
+        .show(ctx, |ui| {
+            ui.label(format!(
+                "{} in flight, {} finished, {} failed",
+                requests.len(),
+                activity.finished,
+                activity.failed
+            ));
+            if client.is_offline() {
+                ui.colored_label(
+                    egui::Color32::from_rgb(240, 190, 60),
+                    "⚠ Offline mode is on, every request fails",
+                );
+            }
+            ui.separator();
+            egui::ScrollArea::vertical()
+                .max_height(300.)
+                .show(ui, |ui| {
+                    for progress in requests {
+                        ui.label(egui::RichText::new(&progress.url).small())
+                            .on_hover_text(&progress.url);
+                        match &progress.state {
+                            RequestState::Started => {
+                                ui.weak("Waiting");
+                            }
+                            RequestState::Retrying { attempt, error } => {
+                                ui.colored_label(
+                                    egui::Color32::YELLOW,
+                                    format!("Retry {attempt}: {error}"),
+                                );
+                            }
+                            RequestState::Receiving {
+                                received,
+                                total: Some(total),
+                            } if *total > 0 => {
+                                ui.add(
+                                    egui::ProgressBar::new(*received as f32 / *total as f32)
+                                        .show_percentage(),
+                                );
+                            }
+                            RequestState::Receiving { received, .. } => {
+                                ui.weak(format!("{:.1} MB", *received as f32 / 1e6));
+                            }
+                            RequestState::Finished | RequestState::Failed(_) => {}
+                        }
+                    }
+                });
+        });
+
+    Ok(())
+}
@@ -0,0 +1,270 @@
+use std::collections::BTreeMap;
+
+use bevy::{
+    app::{App, Plugin, PostUpdate, Update},
+    asset::{Assets, Handle, RenderAssetUsages},
+    camera::{Camera, Projection},
+    color::Color,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        hierarchy::ChildOf,
+        query::{Added, With, Without},
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Query, Res, ResMut, Single},
+    },
+    image::Image,
+    log::warn,
+    math::{Affine2, Vec2, Vec3, primitives::Rectangle},
+    mesh::{Mesh, Mesh3d},
+    pbr::{MeshMaterial3d, StandardMaterial},
+    render::alpha::AlphaMode,
+    state::state::OnEnter,
+    transform::{TransformSystems, components::Transform},
+};
+use image::{DynamicImage, Rgba, RgbaImage, imageops::FilterType};
+
+use crate::{
+    EarthConfig,
+    component::{Billboard, Earth, MainCamera, Selectable},
+    math::Coordinates,
+    pack::EarthPacks,
+    state::GameState,
+};
+
+/// Directory of a pack with additional `<name>.png` icons, replacing built-in ones of the same name.
+const ICONS_DIR: &str = "icons";
+
+/// Size of one atlas cell in pixels.
+const CELL: u32 = 64;
+
+/// Map symbols every pack has, drawn procedurally.
+const BUILTIN_ICONS: [&str; 5] = ["marker", "city", "airport", "volcano", "port"];
+
+/// All icons packed into a single row of cells, with one unlit material per icon.
+#[derive(Resource, Default)]
+pub struct IconAtlas {
+    pub image: Handle<Image>,
+    /// Cell of each icon
+    pub cells: BTreeMap<String, usize>,
+    materials: BTreeMap<String, Handle<StandardMaterial>>,
+    quad: Handle<Mesh>,
+}
+
+impl IconAtlas {
+    pub fn contains(&self, name: &str) -> bool {
+        self.cells.contains_key(name)
+    }
+
+    /// Material of `name`, falling back to the generic marker for unknown icons.
+    fn material(&self, name: &str) -> Option<Handle<StandardMaterial>> {
+        self.materials
+            .get(name)
+            .or_else(|| self.materials.get("marker"))
+            .cloned()
+    }
+}
+
+/// A named icon pinned to a place on the globe, drawn as a billboard of constant screen size.
+#[derive(Component, Debug, Clone)]
+pub struct Icon {
+    pub name: String,
+    pub location: Coordinates,
+    /// Height in pixels
+    pub size: f32,
+    pub color: Color,
+}
+
+impl Icon {
+    pub fn new(name: impl Into<String>, location: Coordinates) -> Self {
+        Self {
+            name: name.into(),
+            location,
+            size: 24.,
+            color: Color::WHITE,
+        }
+    }
+}
+
+pub struct IconPlugin;
+
+impl Plugin for IconPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<IconAtlas>()
+            .add_systems(OnEnter(GameState::Loading), build_atlas)
+            .add_systems(Update, spawn_icons)
+            .add_systems(
+                PostUpdate,
+                orient_billboards.before(TransformSystems::Propagate),
+            );
+    }
+}
+
+/// Coverage of a built-in symbol at `p`, in cell coordinates from -1 to 1, as (fill, outline).
+fn builtin_shape(name: &str, p: Vec2) -> (bool, bool) {
+    let inside = |distance: f32| (distance < 0., distance < 0.12);
+    match name {
+        "city" => inside(p.length() - 0.55),
+        "airport" => {
+            // A plus sign
+            let arm = (p.x.abs() - 0.75).max(p.y.abs() - 0.18);
+            let cross = (p.y.abs() - 0.75).max(p.x.abs() - 0.18);
+            inside(arm.min(cross))
+        }
+        "volcano" => {
+            // Upwards triangle, image y grows downwards
+            let edge = (p.y * -0.5 + p.x.abs() * 0.87).max(p.y) - 0.45;
+            inside(edge)
+        }
+        "port" => inside((p.x.abs() - 0.6).max(p.y.abs() - 0.6)),
+        _ => {
+            // Ring with a dot, the generic marker
+            let ring = (p.length() - 0.6).abs() - 0.15;
+            inside(ring.min(p.length() - 0.2))
+        }
+    }
+}
+
+fn draw_builtin(name: &str) -> RgbaImage {
+    RgbaImage::from_fn(CELL, CELL, |x, y| {
+        let p = (Vec2::new(x as f32, y as f32) + 0.5) / CELL as f32 * 2. - 1.;
+        match builtin_shape(name, p) {
+            (true, _) => Rgba([255, 255, 255, 255]),
+            // Dark outline keeps light icons readable on bright ground
+            (false, true) => Rgba([20, 20, 20, 200]),
+            _ => Rgba([0, 0, 0, 0]),
+        }
+    })
+}
+
+fn build_atlas(
+    mut atlas: ResMut<IconAtlas>,
+    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    packs: Res<EarthPacks>,
+) {
+    let mut icons: BTreeMap<String, RgbaImage> = BUILTIN_ICONS
+        .into_iter()
+        .map(|name| (name.to_string(), draw_builtin(name)))
+        .collect();
+
+    if let Ok(entries) = std::fs::read_dir(packs.active().root.join(ICONS_DIR)) {
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.extension().is_none_or(|ext| ext != "png") {
+                continue;
+            }
+            let Some(name) = path.file_stem() else {
+                continue;
+            };
+            match image::open(&path) {
+                Ok(icon) => {
+                    let icon = icon.resize_exact(CELL, CELL, FilterType::Triangle);
+                    icons.insert(name.to_string_lossy().into_owned(), icon.to_rgba8());
+                }
+                Err(err) => warn!("Skipping icon {}: {err}", path.display()),
+            }
+        }
+    }
+
+    let mut packed = RgbaImage::new(CELL * icons.len() as u32, CELL);
+    let mut cells = BTreeMap::new();
+    for (cell, (name, icon)) in icons.into_iter().enumerate() {
+        image::imageops::replace(&mut packed, &icon, (cell as u32 * CELL) as i64, 0);
+        cells.insert(name, cell);
+    }
+
+    let count = cells.len() as f32;
+    let image = images.add(Image::from_dynamic(
+        DynamicImage::ImageRgba8(packed),
+        true,
+        RenderAssetUsages::default(),
+    ));
+    atlas.materials = cells
+        .iter()
+        .map(|(name, &cell)| {
+            let material = StandardMaterial {
+                base_color_texture: Some(image.clone()),
+                uv_transform: Affine2::from_scale_angle_translation(
+                    Vec2::new(1. / count, 1.),
+                    0.,
+                    Vec2::new(cell as f32 / count, 0.),
+                ),
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..Default::default()
+            };
+            (name.clone(), materials.add(material))
+        })
+        .collect();
+    atlas.cells = cells;
+    atlas.image = image;
+    atlas.quad = meshes.add(Rectangle::new(1., 1.));
+}
+
+fn spawn_icons(
+    mut commands: Commands,
+    icons: Query<(Entity, &Icon), Added<Icon>>,
+    atlas: Res<IconAtlas>,
+    earth: Single<Entity, With<Earth>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    config: Res<EarthConfig>,
+) {
+    for (entity, icon) in &icons {
+        let Some(mut material) = atlas.material(&icon.name) else {
+            continue;
+        };
+        // Tinted icons get their own copy of the shared material
+        if icon.color != Color::WHITE
+            && let Some(tinted) = materials.get(&material).cloned()
+        {
+            material = materials.add(StandardMaterial {
+                base_color: icon.color,
+                ..tinted
+            });
+        }
+
+        commands.entity(entity).insert((
+            Mesh3d(atlas.quad.clone()),
+            MeshMaterial3d(material),
+            Transform::from_translation(icon.location.direction() * config.radius),
+            Billboard { size: icon.size },
+            Selectable,
+            ChildOf(*earth),
+        ));
+    }
+}
+
+/// Turns billboards towards the camera and scales them to their size in pixels.
+///
+/// Billboards are children of the globe, so the camera is brought into its local space first.
+fn orient_billboards(
+    camera: Single<(&Camera, &Transform, &Projection), (With<MainCamera>, Without<Billboard>)>,
+    earth: Single<&Transform, (With<Earth>, Without<Billboard>)>,
+    mut billboards: Query<(&Billboard, &mut Transform)>,
+    config: Res<EarthConfig>,
+) {
+    let (camera, camera_transform, projection) = *camera;
+    let (Projection::Perspective(perspective), Some(viewport)) =
+        (projection, camera.logical_viewport_size())
+    else {
+        return;
+    };
+
+    let to_local = earth.compute_affine().inverse();
+    let camera_position = to_local.transform_point3(camera_transform.translation);
+    let rotation = earth.rotation.inverse() * camera_transform.rotation;
+    // World units covered by one pixel at unit distance from the camera
+    let pixel_at_unit = 2. * (perspective.fov / 2.).tan() / viewport.y;
+
+    for (billboard, mut transform) in &mut billboards {
+        let distance = camera_position.distance(transform.translation);
+        transform.rotation = rotation;
+        transform.scale = Vec3::splat(billboard.size * pixel_at_unit * distance);
+        // Lifted by half its height, so the icon stands on its location rather than in the ground
+        let up = transform.translation.normalize_or_zero();
+        let ground = up * config.radius;
+        transform.translation = ground + up * transform.scale.y * 0.5;
+    }
+}
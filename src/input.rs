@@ -0,0 +1,407 @@
+use std::collections::BTreeMap;
+
+use bevy::{
+    app::{App, First, Plugin},
+    ecs::{
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Res, ResMut, SystemParam},
+    },
+    input::{ButtonInput, keyboard::KeyCode},
+    log::error,
+    state::condition::in_state,
+};
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+use serde::{Deserialize, Serialize};
+
+use crate::{resource::KeyboardOverUi, state::GameState};
+
+const BINDINGS_PATH: &str = "keybindings.ron";
+
+/// Everything that can be triggered from the keyboard.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Action {
+    RotateLeft,
+    RotateRight,
+    RotateUp,
+    RotateDown,
+    ZoomIn,
+    ZoomOut,
+    NorthPole,
+    SouthPole,
+    Antipode,
+    ToggleNorthArrow,
+    ToggleCrosshair,
+    ToggleSpaceView,
+    FlyForward,
+    FlyBackward,
+    FlyLeft,
+    FlyRight,
+    FlyUp,
+    FlyDown,
+    RollLeft,
+    RollRight,
+    Measure,
+    TogglePause,
+    Step,
+    SpeedUp,
+    SlowDown,
+    ToggleRecording,
+    ToggleReplay,
+    ToggleMagnifier,
+    ToggleSettings,
+    Workspace1,
+    Workspace2,
+    Workspace3,
+    Workspace4,
+    Workspace5,
+    Workspace6,
+    Workspace7,
+    Workspace8,
+    Workspace9,
+}
+
+impl Action {
+    pub const ALL: [Action; 38] = [
+        Action::RotateLeft,
+        Action::RotateRight,
+        Action::RotateUp,
+        Action::RotateDown,
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::NorthPole,
+        Action::SouthPole,
+        Action::Antipode,
+        Action::ToggleNorthArrow,
+        Action::ToggleCrosshair,
+        Action::ToggleSpaceView,
+        Action::FlyForward,
+        Action::FlyBackward,
+        Action::FlyLeft,
+        Action::FlyRight,
+        Action::FlyUp,
+        Action::FlyDown,
+        Action::RollLeft,
+        Action::RollRight,
+        Action::Measure,
+        Action::TogglePause,
+        Action::Step,
+        Action::SpeedUp,
+        Action::SlowDown,
+        Action::ToggleRecording,
+        Action::ToggleReplay,
+        Action::ToggleMagnifier,
+        Action::ToggleSettings,
+        Action::Workspace1,
+        Action::Workspace2,
+        Action::Workspace3,
+        Action::Workspace4,
+        Action::Workspace5,
+        Action::Workspace6,
+        Action::Workspace7,
+        Action::Workspace8,
+        Action::Workspace9,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::RotateLeft => "Rotate left",
+            Action::RotateRight => "Rotate right",
+            Action::RotateUp => "Rotate up",
+            Action::RotateDown => "Rotate down",
+            Action::ZoomIn => "Zoom in",
+            Action::ZoomOut => "Zoom out",
+            Action::NorthPole => "Go to North Pole",
+            Action::SouthPole => "Go to South Pole",
+            Action::Antipode => "Go to antipode",
+            Action::ToggleNorthArrow => "Toggle north arrow",
+            Action::ToggleCrosshair => "Toggle crosshair",
+            Action::ToggleSpaceView => "Toggle space view",
+            Action::FlyForward => "Fly forward",
+            Action::FlyBackward => "Fly backward",
+            Action::FlyLeft => "Fly left",
+            Action::FlyRight => "Fly right",
+            Action::FlyUp => "Fly up",
+            Action::FlyDown => "Fly down",
+            Action::RollLeft => "Roll left",
+            Action::RollRight => "Roll right",
+            Action::Measure => "Toggle measuring",
+            Action::TogglePause => "Pause / resume",
+            Action::Step => "Single step",
+            Action::SpeedUp => "Double speed",
+            Action::SlowDown => "Halve speed",
+            Action::ToggleRecording => "Start / stop recording",
+            Action::ToggleReplay => "Play / stop replay",
+            Action::ToggleMagnifier => "Toggle magnifier",
+            Action::ToggleSettings => "Toggle settings panel",
+            Action::Workspace1 => "Switch to workspace 1",
+            Action::Workspace2 => "Switch to workspace 2",
+            Action::Workspace3 => "Switch to workspace 3",
+            Action::Workspace4 => "Switch to workspace 4",
+            Action::Workspace5 => "Switch to workspace 5",
+            Action::Workspace6 => "Switch to workspace 6",
+            Action::Workspace7 => "Switch to workspace 7",
+            Action::Workspace8 => "Switch to workspace 8",
+            Action::Workspace9 => "Switch to workspace 9",
+        }
+    }
+
+    fn default_key(&self) -> KeyCode {
+        match self {
+            Action::RotateLeft => KeyCode::ArrowLeft,
+            Action::RotateRight => KeyCode::ArrowRight,
+            Action::RotateUp => KeyCode::ArrowUp,
+            Action::RotateDown => KeyCode::ArrowDown,
+            Action::ZoomIn => KeyCode::Equal,
+            Action::ZoomOut => KeyCode::Minus,
+            Action::NorthPole => KeyCode::PageUp,
+            Action::SouthPole => KeyCode::PageDown,
+            Action::Antipode => KeyCode::KeyO,
+            Action::ToggleNorthArrow => KeyCode::KeyN,
+            Action::ToggleCrosshair => KeyCode::KeyC,
+            Action::ToggleSpaceView => KeyCode::KeyV,
+            Action::FlyForward => KeyCode::KeyW,
+            Action::FlyBackward => KeyCode::KeyS,
+            Action::FlyLeft => KeyCode::KeyA,
+            Action::FlyRight => KeyCode::KeyD,
+            Action::FlyUp => KeyCode::KeyR,
+            Action::FlyDown => KeyCode::KeyF,
+            Action::RollLeft => KeyCode::KeyQ,
+            Action::RollRight => KeyCode::KeyE,
+            Action::Measure => KeyCode::KeyM,
+            Action::TogglePause => KeyCode::Space,
+            Action::Step => KeyCode::Period,
+            Action::SpeedUp => KeyCode::BracketRight,
+            Action::SlowDown => KeyCode::BracketLeft,
+            Action::ToggleRecording => KeyCode::F9,
+            Action::ToggleReplay => KeyCode::F10,
+            Action::ToggleMagnifier => KeyCode::KeyL,
+            Action::ToggleSettings => KeyCode::F2,
+            Action::Workspace1 => KeyCode::Digit1,
+            Action::Workspace2 => KeyCode::Digit2,
+            Action::Workspace3 => KeyCode::Digit3,
+            Action::Workspace4 => KeyCode::Digit4,
+            Action::Workspace5 => KeyCode::Digit5,
+            Action::Workspace6 => KeyCode::Digit6,
+            Action::Workspace7 => KeyCode::Digit7,
+            Action::Workspace8 => KeyCode::Digit8,
+            Action::Workspace9 => KeyCode::Digit9,
+        }
+    }
+}
+
+/// Key bound to each action, persisted to `keybindings.ron`.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct KeyBindings(pub BTreeMap<Action, KeyCode>);
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self(
+            Action::ALL
+                .into_iter()
+                .map(|action| (action, action.default_key()))
+                .collect(),
+        )
+    }
+}
+
+impl KeyBindings {
+    fn load() -> Self {
+        let Ok(serialized) = std::fs::read_to_string(BINDINGS_PATH) else {
+            return Self::default();
+        };
+        match ron::from_str::<KeyBindings>(&serialized) {
+            // Actions added since the file was written keep their default key
+            Ok(saved) => {
+                let mut bindings = Self::default();
+                bindings.0.extend(saved.0);
+                bindings
+            }
+            Err(err) => {
+                error!("Failed to parse {BINDINGS_PATH}: {err}");
+                Self::default()
+            }
+        }
+    }
+
+    fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let serialized = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        std::fs::write(BINDINGS_PATH, serialized)?;
+        Ok(())
+    }
+
+    pub fn key(&self, action: Action) -> Option<KeyCode> {
+        self.0.get(&action).copied()
+    }
+
+    /// Short name of the bound key for menus, e.g. "PageUp" or "N".
+    pub fn label(&self, action: Action) -> String {
+        self.key(action).map_or_else(String::new, key_label)
+    }
+}
+
+fn key_label(key: KeyCode) -> String {
+    let name = format!("{key:?}");
+    ["Key", "Digit"]
+        .into_iter()
+        .find_map(|prefix| name.strip_prefix(prefix).map(str::to_string))
+        .unwrap_or(name)
+}
+
+/// Actions triggered without their key, e.g. from the command palette. They count as pressed
+/// for one frame, starting with the next one.
+#[derive(Resource, Default, Debug)]
+pub struct TriggeredActions {
+    pending: Vec<Action>,
+    current: Vec<Action>,
+}
+
+impl TriggeredActions {
+    pub fn trigger(&mut self, action: Action) {
+        self.pending.push(action);
+    }
+}
+
+/// Keyboard state looked up through the current `KeyBindings`, plus the `TriggeredActions`.
+///
+/// Keys are ignored while a text field of the UI has the focus.
+#[derive(SystemParam)]
+pub struct Actions<'w> {
+    keyboard: Res<'w, ButtonInput<KeyCode>>,
+    bindings: Res<'w, KeyBindings>,
+    editor: Res<'w, KeyBindingsEditor>,
+    keyboard_over_ui: Res<'w, KeyboardOverUi>,
+    triggered: Res<'w, TriggeredActions>,
+}
+
+impl Actions<'_> {
+    fn listening(&self) -> bool {
+        !self.editor.is_capturing() && !**self.keyboard_over_ui
+    }
+
+    pub fn pressed(&self, action: Action) -> bool {
+        self.triggered.current.contains(&action)
+            || self.listening()
+                && self
+                    .bindings
+                    .key(action)
+                    .is_some_and(|key| self.keyboard.pressed(key))
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.triggered.current.contains(&action)
+            || self.listening()
+                && self
+                    .bindings
+                    .key(action)
+                    .is_some_and(|key| self.keyboard.just_pressed(key))
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct KeyBindingsEditor {
+    pub open: bool,
+    /// Action waiting for its new key
+    rebinding: Option<Action>,
+}
+
+impl KeyBindingsEditor {
+    /// Whether a key press is currently being captured, so it shouldn't trigger anything.
+    pub fn is_capturing(&self) -> bool {
+        self.rebinding.is_some()
+    }
+}
+
+pub struct InputPlugin;
+
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(KeyBindings::load())
+            .init_resource::<KeyBindingsEditor>()
+            .init_resource::<KeyboardOverUi>()
+            .init_resource::<TriggeredActions>()
+            .add_systems(First, advance_triggered_actions)
+            .add_systems(
+                EguiPrimaryContextPass,
+                display_key_bindings_editor
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(|editor: Res<KeyBindingsEditor>| editor.open),
+            );
+    }
+}
+
+fn advance_triggered_actions(mut triggered: ResMut<TriggeredActions>) {
+    if triggered.pending.is_empty() && triggered.current.is_empty() {
+        return;
+    }
+    let triggered = &mut *triggered;
+    triggered.current = std::mem::take(&mut triggered.pending);
+}
+
+fn display_key_bindings_editor(
+    mut contexts: EguiContexts,
+    mut editor: ResMut<KeyBindingsEditor>,
+    mut bindings: ResMut<KeyBindings>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+) -> bevy::prelude::Result {
+    let ctx = contexts.ctx_mut()?;
+
+    if let Some(action) = editor.rebinding
+        && let Some(&key) = keyboard.get_just_pressed().next()
+    {
+        editor.rebinding = None;
+        if key != KeyCode::Escape {
+            bindings.0.insert(action, key);
+            if let Err(err) = bindings.save() {
+                error!("Failed to save {BINDINGS_PATH}: {err}");
+            }
+        }
+    }
+
+    let editor = &mut *editor;
+    let mut reset = false;
+    egui::Window::new("Key bindings")
+        .open(&mut editor.open)
+        .show(ctx, |ui| {
+            egui::Grid::new("Bindings").striped(true).show(ui, |ui| {
+                for action in Action::ALL {
+                    ui.label(action.label());
+
+                    let text = if editor.rebinding == Some(action) {
+                        "Press a key (Esc to cancel)".to_string()
+                    } else {
+                        bindings.label(action)
+                    };
+                    if ui.button(text).clicked() {
+                        editor.rebinding = Some(action);
+                    }
+
+                    // Flag keys bound to more than one action
+                    let key = bindings.key(action);
+                    let conflicts = Action::ALL
+                        .into_iter()
+                        .filter(|other| *other != action && bindings.key(*other) == key)
+                        .count();
+                    if conflicts > 0 {
+                        ui.colored_label(egui::Color32::YELLOW, "conflict");
+                    } else {
+                        ui.label("");
+                    }
+                    ui.end_row();
+                }
+            });
+
+            if ui.button("Reset to defaults").clicked() {
+                reset = true;
+            }
+        });
+
+    if reset {
+        *bindings = KeyBindings::default();
+        if let Err(err) = bindings.save() {
+            error!("Failed to save {BINDINGS_PATH}: {err}");
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,813 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use bevy::{
+    app::{App, Plugin, Update},
+    asset::{AssetPath, AssetServer, Assets, Handle},
+    camera::visibility::Visibility,
+    ecs::{
+        change_detection::{DetectChanges, DetectChangesMut},
+        entity::Entity,
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Local, Query, Res, ResMut},
+    },
+    image::Image,
+    log::warn,
+    math::{UVec4, Vec4},
+    state::{condition::in_state, state::OnEnter},
+    tasks::{IoTaskPool, Task, futures},
+    time::{Real, Time, Timer, TimerMode},
+};
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    EarthConfig,
+    download::{DownloadProgress, download},
+    globe_layer::GlobeLayers,
+    http::HttpClient,
+    material::EarthMaterial,
+    overlay::GeoJsonLayer,
+    pack::{EarthPack, EarthPacks},
+    palette::{PaletteCommand, RegisterCommand},
+    resource::EarthMaterialTemplate,
+    state::GameState,
+    tiles::TileStream,
+    toast::Toasts,
+};
+
+/// Raster overlays the Earth material can composite at once.
+pub const OVERLAY_SLOTS: usize = 2;
+
+/// Folder inside an asset pack holding equirectangular overlay images and GeoJSON files.
+pub const OVERLAYS_DIR: &str = "overlays";
+
+/// Optional layer declarations of a pack, read from this file in the pack root.
+pub const LAYERS_FILE: &str = "layers.ron";
+
+/// Remote layer sources are downloaded once into this folder.
+const LAYER_CACHE_DIR: &str = "layer_cache";
+
+/// How often `layers.ron` is checked for changes.
+const MANIFEST_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlendMode {
+    #[default]
+    Normal,
+    Multiply,
+    Additive,
+    Screen,
+}
+
+impl BlendMode {
+    pub const ALL: [BlendMode; 4] = [
+        BlendMode::Normal,
+        BlendMode::Multiply,
+        BlendMode::Additive,
+        BlendMode::Screen,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            BlendMode::Normal => "Normal",
+            BlendMode::Multiply => "Multiply",
+            BlendMode::Additive => "Additive",
+            BlendMode::Screen => "Screen",
+        }
+    }
+
+    /// Matches the `overlay_blend` switch in `earth.wgsl`.
+    fn shader_id(&self) -> u32 {
+        *self as u32
+    }
+}
+
+/// Provenance of a layer's data, read from `<name>.ron` next to an overlay image.
+///
+/// Most imagery providers, e.g. OpenStreetMap or NASA GIBS, require their attribution to be
+/// visible whenever their data is on screen.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct LayerInfo {
+    pub source: String,
+    pub license: String,
+    /// Text shown in the attribution line while the layer is visible
+    pub attribution: String,
+    pub url: Option<String>,
+}
+
+impl LayerInfo {
+    /// The imagery of the default pack.
+    pub fn blue_marble() -> Self {
+        Self {
+            source: "NASA Visible Earth".to_string(),
+            license: "Public domain".to_string(),
+            attribution: "Imagery: NASA Visible Earth / Blue Marble".to_string(),
+            url: Some("https://visibleearth.nasa.gov".to_string()),
+        }
+    }
+
+    fn load(path: &Path) -> Self {
+        let Ok(serialized) = std::fs::read_to_string(path) else {
+            return Self::default();
+        };
+        ron::from_str(&serialized)
+            .inspect_err(|err| warn!("Ignoring invalid {}: {err}", path.display()))
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayerKind {
+    /// An equirectangular image composited by the Earth material
+    Raster,
+    /// Lines and points draped over the globe, see `GeoJsonLayer`
+    GeoJson,
+}
+
+impl LayerKind {
+    fn extension(&self) -> &'static str {
+        match self {
+            LayerKind::Raster => "png",
+            LayerKind::GeoJson => "geojson",
+        }
+    }
+}
+
+/// How a declared layer is drawn. Raster layers use the opacity and blend mode, GeoJSON layers
+/// the rest.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct LayerStyle {
+    pub opacity: f32,
+    pub blend: BlendMode,
+    /// sRGB color of the lines and points, taking the next palette color if unset
+    pub color: Option<[f32; 3]>,
+    /// Width of the lines in pixels
+    pub width: f32,
+    /// Diameter of the points in pixels
+    pub point_size: f32,
+}
+
+impl Default for LayerStyle {
+    fn default() -> Self {
+        Self {
+            opacity: 1.,
+            blend: BlendMode::Normal,
+            color: None,
+            width: 1.5,
+            point_size: 8.,
+        }
+    }
+}
+
+/// A layer declared in `layers.ron`, so a globe can be set up without touching the code.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LayerDefinition {
+    pub name: String,
+    pub kind: LayerKind,
+    /// File relative to the pack root, or an `http(s)` URL downloaded once into `layer_cache`
+    pub source: String,
+    #[serde(default)]
+    pub style: LayerStyle,
+    /// Raster layers are stacked by it from bottom to top, the ones found in the `overlays`
+    /// folder sit at 0
+    #[serde(default)]
+    pub z: i32,
+    /// Whether the layer is shown from the start
+    #[serde(default)]
+    pub visible: bool,
+    #[serde(default)]
+    pub info: LayerInfo,
+}
+
+impl LayerDefinition {
+    pub fn is_remote(&self) -> bool {
+        self.source.starts_with("http://") || self.source.starts_with("https://")
+    }
+
+    /// Where a remote source is cached, named after its URL. The extension of the URL is kept,
+    /// images are decoded by it.
+    fn cache_path(&self) -> PathBuf {
+        let digest = format!("{:x}", Sha256::digest(self.source.as_bytes()));
+        let url_path = self.source.split(['?', '#']).next().unwrap_or_default();
+        let extension = Path::new(url_path)
+            .extension()
+            .map_or(self.kind.extension().into(), |ext| ext.to_string_lossy());
+        Path::new(LAYER_CACHE_DIR).join(format!("{}.{extension}", &digest[..16]))
+    }
+
+    /// The file of the layer: inside the pack, or the download of a remote source.
+    pub fn path(&self, pack: &EarthPack) -> PathBuf {
+        if self.is_remote() {
+            self.cache_path()
+        } else {
+            pack.root.join(&self.source)
+        }
+    }
+
+    fn asset_path(&self, pack: &EarthPack) -> AssetPath<'static> {
+        if self.is_remote() {
+            let path = self.cache_path();
+            AssetPath::from(std::path::absolute(&path).unwrap_or(path))
+        } else {
+            pack.asset_path(&self.source)
+        }
+    }
+
+    pub fn visibility(&self) -> Visibility {
+        if self.visible {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        }
+    }
+
+    /// The raster layer drawn from the source, hidden without an image while it downloads.
+    fn raster_layer(
+        &self,
+        pack: &EarthPack,
+        downloads: &LayerDownloads,
+        asset_server: &AssetServer,
+    ) -> RasterLayer {
+        let pending = downloads.is_pending(self);
+        RasterLayer {
+            name: self.name.clone(),
+            image: if pending {
+                Handle::default()
+            } else {
+                asset_server.load(self.asset_path(pack))
+            },
+            info: self.info.clone(),
+            opacity: self.style.opacity,
+            blend: self.style.blend,
+            visible: self.visible && !pending,
+        }
+    }
+}
+
+/// The layers a pack declares in `layers.ron`, next to the ones found in its `overlays` folder.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct LayerManifest {
+    pub layers: Vec<LayerDefinition>,
+}
+
+impl LayerManifest {
+    /// Reads `layers.ron` in `root`, or an empty manifest if there is none.
+    pub fn load(root: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let Ok(serialized) = std::fs::read_to_string(root.join(LAYERS_FILE)) else {
+            return Ok(Self::default());
+        };
+        Ok(ron::from_str(&serialized)?)
+    }
+
+    /// Whether `source` is declared, so discovering the `overlays` folder skips it.
+    pub fn declares(&self, source: &str) -> bool {
+        self.layers.iter().any(|layer| layer.source == source)
+    }
+}
+
+/// Remote layer sources being downloaded into `layer_cache`. The raster layers among them are
+/// listed hidden until their image arrives, GeoJSON layers are spawned once downloaded.
+#[derive(Resource, Default)]
+pub struct LayerDownloads(Vec<(LayerDefinition, Task<Result<(), String>>)>);
+
+impl LayerDownloads {
+    pub fn is_busy(&self) -> bool {
+        !self.0.is_empty()
+    }
+
+    pub fn is_pending(&self, definition: &LayerDefinition) -> bool {
+        self.0
+            .iter()
+            .any(|(pending, _)| pending.source == definition.source)
+    }
+
+    /// Downloads a remote source that isn't cached yet.
+    fn fetch(&mut self, client: &HttpClient, definition: &LayerDefinition) {
+        if !definition.is_remote()
+            || definition.cache_path().is_file()
+            || self.is_pending(definition)
+        {
+            return;
+        }
+        let client = client.clone();
+        let url = definition.source.clone();
+        let path = definition.cache_path();
+        let task = IoTaskPool::get().spawn(async move {
+            std::fs::create_dir_all(LAYER_CACHE_DIR).map_err(|err| err.to_string())?;
+            download(&client, &url, &path, None, &DownloadProgress::default())
+        });
+        self.0.push((definition.clone(), task));
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RasterLayer {
+    pub name: String,
+    pub image: Handle<Image>,
+    pub info: LayerInfo,
+    pub opacity: f32,
+    pub blend: BlendMode,
+    pub visible: bool,
+}
+
+/// Raster overlays ordered from bottom to top.
+#[derive(Resource, Debug, Default, Clone, PartialEq)]
+pub struct RasterLayers(pub Vec<RasterLayer>);
+
+impl RasterLayers {
+    /// The lowest visible layers, which get the material's overlay slots.
+    pub fn rendered(&self) -> impl Iterator<Item = &RasterLayer> {
+        self.0
+            .iter()
+            .filter(|layer| layer.visible)
+            .take(OVERLAY_SLOTS)
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct LayersPanel {
+    pub open: bool,
+}
+
+pub struct LayerPlugin;
+
+impl Plugin for LayerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RasterLayers>()
+            .init_resource::<LayersPanel>()
+            .init_resource::<LayerDownloads>()
+            .add_systems(OnEnter(GameState::Loading), discover_overlays)
+            .add_systems(
+                Update,
+                (reload_layer_manifest, finish_layer_downloads, sync_overlays)
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                EguiPrimaryContextPass,
+                display_layers_panel
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(|panel: Res<LayersPanel>| panel.open),
+            )
+            .register_command_provider(layer_commands);
+    }
+}
+
+/// Lists the images in the pack's `overlays` folder and the raster layers of its `layers.ron`,
+/// and starts downloading the remote sources that aren't cached yet.
+fn discover_overlays(
+    mut layers: ResMut<RasterLayers>,
+    mut downloads: ResMut<LayerDownloads>,
+    packs: Res<EarthPacks>,
+    asset_server: Res<AssetServer>,
+    client: Res<HttpClient>,
+) {
+    // Downloads of the previous pack are cancelled
+    downloads.0.clear();
+
+    let pack = packs.active();
+    let mut files: Vec<_> = std::fs::read_dir(pack.root.join(OVERLAYS_DIR))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "png"))
+        .collect();
+    files.sort();
+
+    let mut stack = Vec::new();
+    for path in files {
+        let Some(name) = path.file_stem() else {
+            continue;
+        };
+        let name = name.to_string_lossy().into_owned();
+        let file = format!("{OVERLAYS_DIR}/{name}.png");
+        if pack.layers.declares(&file) {
+            continue;
+        }
+        let layer = RasterLayer {
+            image: asset_server.load(pack.asset_path(&file)),
+            info: LayerInfo::load(&path.with_extension("ron")),
+            name,
+            opacity: 1.,
+            blend: BlendMode::Normal,
+            visible: false,
+        };
+        stack.push((0, layer));
+    }
+
+    for definition in &pack.layers.layers {
+        downloads.fetch(&client, definition);
+        if definition.kind == LayerKind::Raster {
+            let layer = definition.raster_layer(pack, &downloads, &asset_server);
+            stack.push((definition.z, layer));
+        }
+    }
+
+    // Stable, so layers at the same z keep the order they were listed in
+    stack.sort_by_key(|(z, _)| *z);
+    layers.0 = stack.into_iter().map(|(_, layer)| layer).collect();
+}
+
+/// Hands the finished downloads of remote sources to their layers.
+fn finish_layer_downloads(
+    mut commands: Commands,
+    mut downloads: ResMut<LayerDownloads>,
+    mut layers: ResMut<RasterLayers>,
+    packs: Res<EarthPacks>,
+    asset_server: Res<AssetServer>,
+    mut toasts: ResMut<Toasts>,
+) {
+    let mut finished = Vec::new();
+    downloads
+        .0
+        .retain_mut(|(definition, task)| match futures::check_ready(task) {
+            Some(result) => {
+                finished.push((definition.clone(), result));
+                false
+            }
+            None => true,
+        });
+
+    let pack = packs.active();
+    for (definition, result) in finished {
+        if let Err(err) = result {
+            toasts.warning(format!("Layer {} is unavailable: {err}", definition.name));
+            continue;
+        }
+        match definition.kind {
+            LayerKind::Raster => {
+                let image = asset_server.load(definition.asset_path(pack));
+                if let Some(layer) = layers
+                    .0
+                    .iter_mut()
+                    .find(|layer| layer.name == definition.name)
+                {
+                    layer.image = image;
+                    layer.visible = definition.visible;
+                }
+            }
+            LayerKind::GeoJson => {
+                commands.spawn((
+                    GeoJsonLayer::from_definition(&definition, definition.path(pack)),
+                    definition.visibility(),
+                ));
+            }
+        }
+    }
+}
+
+/// Checks the active pack's `layers.ron` for changes and applies them in place, leaving the
+/// camera and everything else alone.
+///
+/// Layers whose definition is unchanged keep what was changed in the layers panel. Changed ones
+/// are rebuilt from their new definition, keeping their visibility unless that changed too.
+fn reload_layer_manifest(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut timer: Local<Option<Timer>>,
+    mut watched: Local<Option<(PathBuf, Option<SystemTime>)>>,
+    mut packs: ResMut<EarthPacks>,
+    mut layers: ResMut<RasterLayers>,
+    mut downloads: ResMut<LayerDownloads>,
+    vector_layers: Query<(Entity, &GeoJsonLayer, &Visibility)>,
+    asset_server: Res<AssetServer>,
+    client: Res<HttpClient>,
+    mut toasts: ResMut<Toasts>,
+) {
+    let timer =
+        timer.get_or_insert_with(|| Timer::new(MANIFEST_POLL_INTERVAL, TimerMode::Repeating));
+    if !timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let path = packs.active().root.join(LAYERS_FILE);
+    let modified = std::fs::metadata(&path)
+        .and_then(|meta| meta.modified())
+        .ok();
+    // The first check of a pack only takes note, its manifest was read along with the pack
+    let previous = watched.replace((path.clone(), modified));
+    if previous.is_none_or(|(previous_path, previous_modified)| {
+        previous_path != path || previous_modified == modified
+    }) {
+        return;
+    }
+
+    let manifest = match LayerManifest::load(&packs.active().root) {
+        Ok(manifest) => manifest,
+        Err(err) => {
+            toasts.error(format!("Failed to reload {}: {err}", path.display()));
+            return;
+        }
+    };
+    let old = std::mem::replace(&mut packs.active_mut().layers, manifest);
+    let pack = packs.active();
+    let manifest = &pack.layers;
+    let changed = |definition: &LayerDefinition| !old.layers.contains(definition);
+    let previous = |kind: LayerKind, name: &str| {
+        old.layers
+            .iter()
+            .find(|definition| definition.kind == kind && definition.name == name)
+    };
+
+    downloads
+        .0
+        .retain(|(definition, _)| manifest.layers.contains(definition));
+    for definition in &manifest.layers {
+        downloads.fetch(&client, definition);
+    }
+
+    // Layers found in the `overlays` folder stay, unless their file is declared now
+    let mut stack = Vec::new();
+    for layer in &layers.0 {
+        let file = format!("{OVERLAYS_DIR}/{}.png", layer.name);
+        if previous(LayerKind::Raster, &layer.name).is_none() && !manifest.declares(&file) {
+            stack.push((0, layer.clone()));
+        }
+    }
+    for definition in &manifest.layers {
+        if definition.kind != LayerKind::Raster {
+            continue;
+        }
+        let current = layers.0.iter().find(|layer| layer.name == definition.name);
+        let layer = match current {
+            Some(current) if !changed(definition) => current.clone(),
+            _ => {
+                let mut layer = definition.raster_layer(pack, &downloads, &asset_server);
+                if let Some(current) = current
+                    && previous(LayerKind::Raster, &definition.name)
+                        .is_some_and(|previous| previous.visible == definition.visible)
+                    && !downloads.is_pending(definition)
+                {
+                    layer.visible = current.visible;
+                }
+                layer
+            }
+        };
+        stack.push((definition.z, layer));
+    }
+    stack.sort_by_key(|(z, _)| *z);
+    layers.set_if_neq(RasterLayers(
+        stack.into_iter().map(|(_, layer)| layer).collect(),
+    ));
+
+    // GeoJSON layers are respawned, which rebuilds their meshes
+    let mut visibilities = HashMap::new();
+    for (entity, layer, visibility) in &vector_layers {
+        if let Some(previous) = previous(LayerKind::GeoJson, &layer.name)
+            && changed(previous)
+        {
+            commands.entity(entity).despawn();
+            if manifest.layers.iter().any(|definition| {
+                definition.kind == LayerKind::GeoJson
+                    && definition.name == previous.name
+                    && definition.visible == previous.visible
+            }) {
+                visibilities.insert(layer.name.clone(), *visibility);
+            }
+        }
+    }
+    for definition in &manifest.layers {
+        if definition.kind != LayerKind::GeoJson
+            || !changed(definition)
+            || downloads.is_pending(definition)
+        {
+            continue;
+        }
+        let visibility = visibilities
+            .get(&definition.name)
+            .copied()
+            .unwrap_or_else(|| definition.visibility());
+        commands.spawn((
+            GeoJsonLayer::from_definition(definition, definition.path(pack)),
+            visibility,
+        ));
+    }
+
+    toasts.info(format!("Reloaded {LAYERS_FILE} of {}", pack.name));
+}
+
+/// Assigns the lowest visible layers to the material's overlay slots.
+fn sync_overlays(
+    layers: Res<RasterLayers>,
+    handle: Res<EarthMaterialTemplate>,
+    mut materials: ResMut<Assets<EarthMaterial>>,
+) {
+    if !layers.is_changed() {
+        return;
+    }
+    let Some(material) = materials.get_mut(&**handle) else {
+        return;
+    };
+
+    let mut visible = layers.rendered();
+    let mut opacity = Vec4::ZERO;
+    let mut blend = UVec4::ZERO;
+    let extension = &mut material.extension;
+    for (slot, texture) in [&mut extension.overlay_0, &mut extension.overlay_1]
+        .into_iter()
+        .enumerate()
+    {
+        *texture = visible.next().map(|layer| {
+            opacity[slot] = layer.opacity;
+            blend[slot] = layer.blend.shader_id();
+            layer.image.clone()
+        });
+    }
+    extension.uniform.overlay_opacity = opacity;
+    extension.uniform.overlay_blend = blend;
+}
+
+/// A palette command per overlay, raster, GeoJSON and third-party alike, showing or hiding it.
+fn layer_commands(
+    layers: Res<RasterLayers>,
+    vector_layers: Query<(Entity, &GeoJsonLayer)>,
+    globe_layers: Res<GlobeLayers>,
+) -> Vec<PaletteCommand> {
+    let raster = layers.0.iter().map(|layer| {
+        let name = layer.name.clone();
+        PaletteCommand::new(format!("Toggle layer {name}"), move |world| {
+            let mut layers = world.resource_mut::<RasterLayers>();
+            if let Some(layer) = layers.0.iter_mut().find(|layer| layer.name == name) {
+                layer.visible = !layer.visible;
+            }
+        })
+    });
+    let vector = vector_layers.iter().map(|(entity, layer)| {
+        PaletteCommand::new(format!("Toggle layer {}", layer.name), move |world| {
+            if let Some(mut visibility) = world.get_mut::<Visibility>(entity) {
+                *visibility = if *visibility == Visibility::Hidden {
+                    Visibility::Inherited
+                } else {
+                    Visibility::Hidden
+                };
+            }
+        })
+    });
+    let globe = globe_layers.0.iter().map(|registered| {
+        let name = registered.layer.name().to_string();
+        PaletteCommand::new(format!("Toggle layer {name}"), move |world| {
+            if let Some(registered) = world.resource_mut::<GlobeLayers>().get_mut(&name) {
+                registered.visible = !registered.visible;
+            }
+        })
+    });
+    raster.chain(vector).chain(globe).collect()
+}
+
+fn layer_info(ui: &mut egui::Ui, info: &LayerInfo) {
+    if *info == LayerInfo::default() {
+        ui.label("No source or license known for this layer");
+        return;
+    }
+
+    egui::Grid::new("layer_info").num_columns(2).show(ui, |ui| {
+        ui.label("Source");
+        ui.label(&info.source);
+        ui.end_row();
+        ui.label("License");
+        ui.label(&info.license);
+        ui.end_row();
+        ui.label("Attribution");
+        ui.label(&info.attribution);
+        ui.end_row();
+        if let Some(url) = &info.url {
+            ui.label("Link");
+            ui.hyperlink(url);
+            ui.end_row();
+        }
+    });
+}
+
+fn display_layers_panel(
+    mut contexts: EguiContexts,
+    mut panel: ResMut<LayersPanel>,
+    mut layers: ResMut<RasterLayers>,
+    mut vector_layers: Query<(&GeoJsonLayer, &mut Visibility)>,
+    mut globe_layers: ResMut<GlobeLayers>,
+    config: Res<EarthConfig>,
+    mut tiles: ResMut<TileStream>,
+) -> bevy::prelude::Result {
+    let ctx = contexts.ctx_mut()?;
+    let streamed = config.tile_url.is_some();
+
+    // Edit a copy so change detection only fires when something actually changed
+    let mut edited = layers.clone();
+    egui::Window::new("Layers")
+        .open(&mut panel.open)
+        .resizable(false)
+        .show(ctx, |ui| {
+            if edited.0.is_empty()
+                && vector_layers.is_empty()
+                && globe_layers.0.is_empty()
+                && !streamed
+            {
+                ui.label(format!(
+                    "No overlays found in the pack's {OVERLAYS_DIR} folder or {LAYERS_FILE}"
+                ));
+                return;
+            }
+
+            if streamed {
+                ui.horizontal(|ui| {
+                    let mut enabled = tiles.enabled;
+                    if ui.checkbox(&mut enabled, "Imagery tiles").changed() {
+                        tiles.enabled = enabled;
+                    }
+                    if tiles.is_offline() {
+                        ui.colored_label(egui::Color32::from_rgb(240, 190, 60), "⚠ Offline")
+                            .on_hover_text("Only tiles cached before going offline are shown");
+                    }
+                });
+                if !edited.0.is_empty() || !vector_layers.is_empty() || !globe_layers.0.is_empty() {
+                    ui.separator();
+                }
+            }
+
+            raster_layer_list(ui, &mut edited);
+
+            if !vector_layers.is_empty() {
+                ui.separator();
+                for (layer, mut visibility) in &mut vector_layers {
+                    let mut visible = *visibility != Visibility::Hidden;
+                    if ui.checkbox(&mut visible, layer.name.as_str()).changed() {
+                        *visibility = if visible {
+                            Visibility::Inherited
+                        } else {
+                            Visibility::Hidden
+                        };
+                    }
+                }
+            }
+
+            if !globe_layers.0.is_empty() {
+                ui.separator();
+                for (index, registered) in globe_layers.0.iter_mut().enumerate() {
+                    let name = registered.layer.name().to_string();
+                    ui.checkbox(&mut registered.visible, name);
+                    if registered.visible {
+                        ui.indent(("globe_layer", index), |ui| registered.layer.gui(ui));
+                    }
+                }
+            }
+        });
+
+    if edited != *layers {
+        *layers = edited;
+    }
+
+    Ok(())
+}
+
+fn raster_layer_list(ui: &mut egui::Ui, edited: &mut RasterLayers) {
+    let mut slot = 0;
+    let mut slots = vec![false; edited.0.len()];
+    for (index, layer) in edited.0.iter().enumerate() {
+        if layer.visible && slot < OVERLAY_SLOTS {
+            slots[index] = true;
+            slot += 1;
+        }
+    }
+
+    // Listed top to bottom, drag the handle onto another row to move a layer there
+    let mut moved = None;
+    for (index, layer) in edited.0.iter_mut().enumerate().rev() {
+        let (_, dropped) = ui.dnd_drop_zone::<usize, ()>(egui::Frame::default(), |ui| {
+            ui.horizontal(|ui| {
+                ui.dnd_drag_source(egui::Id::new(("raster_layer", index)), index, |ui| {
+                    ui.label("☰");
+                });
+                ui.checkbox(&mut layer.visible, layer.name.as_str());
+                if layer.visible && !slots[index] {
+                    ui.label("(no free slot)");
+                }
+
+                egui::ComboBox::from_id_salt(("blend", index))
+                    .selected_text(layer.blend.label())
+                    .show_ui(ui, |ui| {
+                        for mode in BlendMode::ALL {
+                            ui.selectable_value(&mut layer.blend, mode, mode.label());
+                        }
+                    });
+                ui.add(egui::Slider::new(&mut layer.opacity, 0.0..=1.));
+                ui.menu_button("ℹ", |ui| layer_info(ui, &layer.info));
+            });
+        });
+        if let Some(from) = dropped {
+            moved = Some((*from, index));
+        }
+    }
+
+    if let Some((from, to)) = moved {
+        let layer = edited.0.remove(from);
+        edited.0.insert(to, layer);
+    }
+}
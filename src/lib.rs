@@ -0,0 +1,759 @@
+use std::path::PathBuf;
+
+use bevy::{
+    ecs::{system::SystemState, world::CommandQueue},
+    picking::prelude::*,
+    platform::time::Instant,
+    prelude::*,
+    tasks::{AsyncComputeTaskPool, futures},
+};
+
+use crate::{
+    antipode::AntipodePlugin,
+    atmosphere::AtmospherePlugin,
+    bookmark::BookmarkPlugin,
+    borders::BorderCrossingsPlugin,
+    chunk::{ChunkKey, ChunkMeshPool, ChunkQueue, FACES, PendingChunk, spawn_chunk},
+    clouds::CloudPlugin,
+    compass::CompassPlugin,
+    component::{ComputeMesh, MainCamera, RotatingLight, SimulatedTransform, Unrevealed},
+    crosshair::CrosshairPlugin,
+    cursor::CursorPlugin,
+    depth::DepthPlugin,
+    discover::DiscoverPlugin,
+    download::DownloadPlugin,
+    exploration::ExplorationPlugin,
+    flight::FlightPlugin,
+    focus::FocusPlugin,
+    free_flight::FreeFlightPlugin,
+    globe_layer::GlobeLayerPlugin,
+    grading::ColorGradingPlugin,
+    graticule::GraticulePlugin,
+    ground_track::GroundTrackPlugin,
+    gui::GuiPlugin,
+    http::HttpPlugin,
+    icon::IconPlugin,
+    input::InputPlugin,
+    layer::LayerPlugin,
+    lighting::LightingPlugin,
+    lod::LodPlugin,
+    magnifier::MagnifierPlugin,
+    marker::MarkerPlugin,
+    material::{EarthExtension, EarthMaterial, EarthMaterialPlugin},
+    measure::MeasurePlugin,
+    mesh_cache::MeshCachePlugin,
+    mesh_view::MeshViewPlugin,
+    navigation::NavigationPlugin,
+    observer::{
+        OrbitCamera, capture_ui_drag, clear_cursor, orbit_camera, orbit_inertia, record_press,
+        release_orbit, release_ui_drag, report_click, track_cursor, zoom,
+    },
+    orbit::OrbitPlugin,
+    origin::OriginPlugin,
+    overlay::GeoJsonPlugin,
+    pack::EarthPacks,
+    paint::PaintPlugin,
+    palette::CommandPalettePlugin,
+    polyline::PolylinePlugin,
+    post_process::PostProcessPlugin,
+    power::PowerSavingPlugin,
+    quality::QualityPlugin,
+    quiz::QuizPlugin,
+    replay::ReplayPlugin,
+    resource::{CursorHit, EarthMaterialTemplate, EarthTexture, LoadingProgress, SimulationTime},
+    satellite::SatellitePlugin,
+    script::ScriptPlugin,
+    selection::SelectionPlugin,
+    session::SessionPlugin,
+    settings::SettingsPlugin,
+    simulation::SimulationPlugin,
+    sky::SkyPlugin,
+    snapshot::SnapshotPlugin,
+    space::{SpacePlugin, animate_space_view},
+    stats::{GenerationTimes, MeshStatsPlugin},
+    stylized::StylizedPlugin,
+    sun::SunPlugin,
+    texture::TexturePlugin,
+    tiles::TilePlugin,
+    toast::{ToastPlugin, Toasts},
+    vector::VectorPlugin,
+    window::WindowSettingsPlugin,
+    workspace::WorkspacePlugin,
+};
+
+pub use crate::{
+    bookmark::{Bookmark, Bookmarks},
+    component::{Earth, Marker},
+    download::RemoteTexture,
+    flight::{FlightPath, HeightProfile, RouteTraveler, spawn_great_circle},
+    focus::DepthOfFieldSettings,
+    globe_layer::{GlobeLayer, GlobeLayerState, GlobeLayers, RegisterGlobeLayer},
+    gui::ClickTooltip,
+    http::{HttpClient, HttpProgress, HttpResponse, NetworkActivity, RequestState},
+    marker::{MarkerLabel, spawn_marker},
+    math::{Coordinates, CubeSphereBuilder, UvMode},
+    measure::MeasureState,
+    navigation::{EarthCommands, FlyTo, FlyToOnDoubleClick},
+    observer::{EarthClicked, EarthDoubleClicked},
+    orbit::{Moon, OrbitingBody},
+    overlay::GeoJsonLayer,
+    palette::{ActionRegistry, CommandPalette, PaletteCommand, RegisterCommand},
+    post_process::{PostEffect, PostEffectSettings, PostProcessing},
+    satellite::{OrbitalElements, Satellite},
+    script::{ScriptRunner, ScriptSpawned},
+    settings::EarthSettings,
+    sky::StarField,
+    state::{GameState, ToolMode},
+    sun::{SunClock, SunMode},
+    toast::{Severity, Toast, Toasts},
+    workspace::{LayerState, Workspace, WorkspaceStyle, Workspaces},
+};
+
+mod antipode;
+mod atmosphere;
+mod bookmark;
+mod borders;
+mod chunk;
+mod clouds;
+mod compass;
+mod component;
+mod crosshair;
+mod cursor;
+mod depth;
+mod discover;
+mod download;
+mod exploration;
+mod flight;
+mod focus;
+mod free_flight;
+mod globe_layer;
+mod grading;
+mod graticule;
+mod ground_track;
+mod gui;
+mod http;
+mod icon;
+mod input;
+mod layer;
+mod lighting;
+mod lod;
+mod magnifier;
+mod marker;
+mod material;
+mod math;
+mod measure;
+mod mesh_cache;
+mod mesh_view;
+mod navigation;
+mod observer;
+mod offscreen;
+mod orbit;
+mod origin;
+mod overlay;
+mod pack;
+mod paint;
+mod palette;
+mod polyline;
+mod post_process;
+mod power;
+mod quality;
+mod quiz;
+mod replay;
+mod resource;
+mod satellite;
+mod script;
+mod selection;
+mod session;
+mod settings;
+mod simulation;
+mod sky;
+mod snapshot;
+mod space;
+mod state;
+mod stats;
+mod stylized;
+mod sun;
+mod texture;
+mod tiles;
+mod toast;
+mod vector;
+mod window;
+mod workspace;
+
+/// Default radius of the globe in world units, see `EarthConfig::radius`.
+///
+/// With the `true-scale` feature one unit is a meter, relying on `OriginPlugin` to keep the
+/// camera precise.
+#[cfg(not(feature = "true-scale"))]
+pub const EARTH_RADIUS: Vec3 = Vec3::new(1000., 1000., 1000.);
+#[cfg(feature = "true-scale")]
+pub const EARTH_RADIUS: Vec3 = Vec3::new(6_371_000., 6_371_000., 6_371_000.);
+
+/// Mean radius of the real Earth in kilometers, which distances are measured on.
+const EARTH_RADIUS_KM: f32 = 6371.;
+
+/// Initial distance of the camera from the center of the globe, in radii.
+const CAMERA_DISTANCE: f32 = 3.;
+
+/// Radius of the light's orbit around the globe's axis, and its height above the equator, in
+/// radii.
+const LIGHT_ORBIT: f32 = 2.;
+const LIGHT_HEIGHT: f32 = 1.;
+
+/// Radians per simulated second the light travels around the globe, so a day lasts 4π seconds.
+const LIGHT_ROTATION_SPEED: f32 = 0.5;
+
+/// Field of view range of the camera in radians, zoomed in to zoomed out.
+const MIN_FOV: f32 = 0.05;
+const MAX_FOV: f32 = std::f32::consts::FRAC_PI_4;
+
+/// Settings of the globe, read whenever it is loaded.
+#[derive(Resource, Debug, Clone)]
+pub struct EarthConfig {
+    /// Radius of the globe in world units. The camera limits, clip planes and everything placed
+    /// on the globe are derived from it, and meshes already in the `ChunkMeshPool` keep the
+    /// radius they were generated with.
+    pub radius: f32,
+    /// Vertices along each edge of a chunk. Meshes already in the `ChunkMeshPool` keep the
+    /// resolution they were generated with.
+    pub resolution: u32,
+    /// Times the four chunks of each cube face can be split into four as the camera zooms in
+    pub max_depth: u8,
+    /// Chunk meshes the `ChunkMeshPool` keeps around after their chunk is despawned
+    pub pool_capacity: usize,
+    /// Equirectangular color texture, relative to the active asset pack. Like the other
+    /// textures, a `.ktx2` version of it is loaded instead when the pack has one.
+    pub base_color: String,
+    /// Specular map with water bright, repacked into the glTF roughness layout on load
+    pub metallic_roughness: String,
+    /// Height map, used for the normal map and the displacement
+    pub height: String,
+    /// Emissive city lights such as NASA's Black Marble, shown on the night side. Optional, the
+    /// dark side stays dark in packs without it.
+    pub night_lights: String,
+    /// Cloud coverage in the red channel, drawn on a sphere above the ground and as its shadows.
+    /// Optional like the night lights.
+    pub clouds: String,
+    /// Star map behind the globe, either equirectangular or a cubemap of six square faces
+    /// stacked vertically. Optional, stars are generated for packs without one.
+    pub sky: String,
+    /// Equirectangular albedo of the Moon. Optional, the Moon stays a plain gray without it.
+    pub moon: String,
+    /// Whether the atmosphere glows around the globe, can be toggled at runtime
+    pub atmosphere: bool,
+    /// Folder the coarsest chunk meshes are cached in between runs, see `MeshCachePlugin`. `None`
+    /// generates them on every launch.
+    pub mesh_cache: Option<PathBuf>,
+    /// Textures fetched into the default pack on first run when it misses them
+    pub remote_textures: Vec<RemoteTexture>,
+    /// XYZ or WMTS tile server streaming more detailed imagery over `base_color`, with `{z}`,
+    /// `{x}` and `{y}` or `{TileMatrix}`, `{TileCol}` and `{TileRow}` placeholders. Fetched
+    /// tiles are cached, `base_color` stays underneath wherever tiles are missing.
+    pub tile_url: Option<String>,
+}
+
+impl Default for EarthConfig {
+    fn default() -> Self {
+        Self {
+            radius: EARTH_RADIUS.x,
+            resolution: 128,
+            max_depth: 6,
+            pool_capacity: 256,
+            // Too large for the repository, it is downloaded from `remote_textures`
+            base_color: "world.png".into(),
+            metallic_roughness: "specular_map_inverted_8k.png".into(),
+            height: "height.png".into(),
+            // https://earthobservatory.nasa.gov/features/NightLights
+            night_lights: "night_lights.png".into(),
+            clouds: "clouds.png".into(),
+            sky: "stars.png".into(),
+            moon: "moon.png".into(),
+            atmosphere: true,
+            mesh_cache: Some(std::env::temp_dir().join("bevy-earth-mesh-cache")),
+            // Not pinned with `with_sha256` until the digest of NASA's file is recorded, so the
+            // first download is trusted as is
+            remote_textures: vec![RemoteTexture::new(
+                "world.png",
+                "https://eoimages.gsfc.nasa.gov/images/imagerecords/74000/74167/world.200410.3x21600x10800.png",
+            )],
+            tile_url: None,
+        }
+    }
+}
+
+impl EarthConfig {
+    /// Kilometers represented by one world unit, given the real Earth radius.
+    pub fn km_per_unit(&self) -> f32 {
+        EARTH_RADIUS_KM / self.radius
+    }
+}
+
+/// The cube sphere Earth with its picking observers, GUI and loading state machine.
+///
+/// Expects `DefaultPlugins`, with `UnapprovedPathMode::Allow` for asset packs outside the asset
+/// folder, and the shaders of this crate's `assets` folder.
+#[derive(Default)]
+pub struct EarthPlugin {
+    pub config: EarthConfig,
+}
+
+impl Plugin for EarthPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<MeshPickingPlugin>() {
+            app.add_plugins(MeshPickingPlugin);
+        }
+
+        app.insert_resource(self.config.clone())
+            .insert_resource(EarthPacks::discover())
+            .add_plugins(GuiPlugin)
+            .add_plugins(InputPlugin)
+            .add_plugins(CommandPalettePlugin)
+            .add_plugins(CursorPlugin)
+            .add_plugins(CompassPlugin)
+            .add_plugins(CrosshairPlugin)
+            .add_plugins(MagnifierPlugin)
+            .add_plugins(NavigationPlugin)
+            .add_plugins(AntipodePlugin)
+            .add_plugins(DiscoverPlugin)
+            .add_plugins(BorderCrossingsPlugin)
+            .add_plugins(DepthPlugin)
+            .add_plugins(OriginPlugin)
+            .add_plugins(SpacePlugin)
+            .add_plugins(OrbitPlugin)
+            .add_plugins(SatellitePlugin)
+            .add_plugins(ScriptPlugin)
+            .add_plugins(FreeFlightPlugin)
+            .add_plugins(SimulationPlugin)
+            .add_plugins(SunPlugin)
+            .add_plugins(ReplayPlugin)
+            .add_plugins(SessionPlugin)
+            .add_plugins(WorkspacePlugin)
+            .add_plugins(BookmarkPlugin)
+            .add_plugins(HttpPlugin)
+            .add_plugins(DownloadPlugin)
+            .add_plugins(TexturePlugin)
+            .add_plugins(EarthMaterialPlugin)
+            .add_plugins(LightingPlugin)
+            .add_plugins(ColorGradingPlugin)
+            .add_plugins(SkyPlugin)
+            .add_plugins(PostProcessPlugin)
+            .add_plugins(FocusPlugin)
+            .add_plugins(StylizedPlugin)
+            .add_plugins(VectorPlugin)
+            .add_plugins(AtmospherePlugin)
+            .add_plugins(CloudPlugin)
+            .add_plugins(LodPlugin)
+            .add_plugins(TilePlugin)
+            .add_plugins(ToastPlugin)
+            .add_plugins(MeshCachePlugin)
+            .add_plugins(MeshViewPlugin)
+            .add_plugins(MeshStatsPlugin)
+            .add_plugins(QualityPlugin)
+            .add_plugins(PowerSavingPlugin)
+            .add_plugins(ExplorationPlugin)
+            .add_plugins(LayerPlugin)
+            .add_plugins(GlobeLayerPlugin)
+            .add_plugins(GeoJsonPlugin)
+            .add_plugins(GraticulePlugin)
+            .add_plugins(GroundTrackPlugin)
+            .add_plugins(PaintPlugin)
+            .add_plugins(PolylinePlugin)
+            .add_plugins(FlightPlugin)
+            .add_plugins(MeasurePlugin)
+            .add_plugins(IconPlugin)
+            .add_plugins(MarkerPlugin)
+            .add_plugins(QuizPlugin)
+            .add_plugins(SelectionPlugin)
+            .add_plugins(SnapshotPlugin)
+            .add_plugins(WindowSettingsPlugin)
+            .add_plugins(SettingsPlugin)
+            .init_state::<GameState>()
+            .add_sub_state::<ToolMode>()
+            .init_resource::<LoadingProgress>()
+            .init_resource::<CursorHit>()
+            .insert_resource(ChunkMeshPool::new(self.config.pool_capacity))
+            .init_resource::<ChunkQueue>()
+            .add_message::<EarthClicked>()
+            .add_message::<EarthDoubleClicked>()
+            .add_systems(Startup, setup_camera)
+            .add_systems(
+                OnEnter(GameState::Loading),
+                (add_assets, spawn_task).chain(),
+            )
+            .add_systems(
+                Update,
+                (
+                    check_ready.run_if(in_state(GameState::Loading)),
+                    (dispatch_chunks, handle_tasks)
+                        .run_if(in_state(GameState::Loading).or(in_state(GameState::Playing))),
+                ),
+            )
+            .add_systems(
+                Update,
+                orbit_inertia
+                    .after(animate_space_view)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                FixedUpdate,
+                rotate_light
+                    .run_if(|mode: Res<SunMode>| *mode == SunMode::Demo)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                OnEnter(GameState::PostLoading),
+                |mut next_state: ResMut<NextState<GameState>>,
+                 earth: Single<&mut Visibility, With<Earth>>| {
+                    next_state.set(GameState::Playing);
+                    *earth.into_inner() = Visibility::Visible;
+                },
+            )
+            .add_systems(OnExit(GameState::Playing), |mut commands: Commands| {
+                despawn_earth(&mut commands)
+            });
+    }
+}
+
+fn setup_camera(mut commands: Commands, config: Res<EarthConfig>) {
+    // Camera
+    commands.spawn((
+        Camera3d::default(),
+        Transform::from_xyz(0.0, 0.0, CAMERA_DISTANCE * config.radius)
+            .looking_at(Vec3::ZERO, Vec3::Y),
+        OrbitCamera::default(),
+        MainCamera,
+    ));
+
+    // Light
+    let (orbit, height) = (LIGHT_ORBIT * config.radius, LIGHT_HEIGHT * config.radius);
+    let transform = Transform::from_xyz(orbit, height, orbit).looking_at(Vec3::ZERO, Vec3::Y);
+    commands.spawn((
+        DirectionalLight {
+            illuminance: 10000.0,
+            ..default()
+        },
+        transform,
+        SimulatedTransform::new(transform),
+        RotatingLight,
+    ));
+}
+
+fn add_assets(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<EarthMaterial>>,
+    asset_server: Res<AssetServer>,
+    packs: Res<EarthPacks>,
+    config: Res<EarthConfig>,
+    mut toasts: ResMut<Toasts>,
+) {
+    let pack = packs.active();
+    // The `.ktx2` version of each texture is preferred, see `texture_file`
+    let optional = |file: &str| {
+        pack.texture_file(file)
+            .map(|file| asset_server.load(pack.asset_path(&file)))
+    };
+    let mut required = |file: &str| {
+        optional(file).unwrap_or_else(|| {
+            toasts.error(format!(
+                "{} has no {file}, add it or a .ktx2 version of it to the pack",
+                pack.name
+            ));
+            asset_server.load(pack.asset_path(file))
+        })
+    };
+    let textures = EarthTexture {
+        base_color: required(&config.base_color),
+        metallic_roughness: required(&config.metallic_roughness),
+
+        normal_map: required(&config.height),
+        night_lights: optional(&config.night_lights),
+        clouds: optional(&config.clouds),
+        moon: optional(&config.moon),
+        repacked: false,
+        repacking: None,
+    };
+
+    let material_template = materials.add(EarthMaterial {
+        base: StandardMaterial {
+            base_color_texture: Some(textures.base_color.clone()),
+            metallic_roughness_texture: Some(textures.metallic_roughness.clone()),
+            perceptual_roughness: 1.,
+            normal_map_texture: Some(textures.normal_map.clone()),
+            ..default()
+        },
+        extension: EarthExtension {
+            night: textures.night_lights.clone(),
+            clouds: textures.clouds.clone(),
+            ocean_mask: Some(textures.metallic_roughness.clone()),
+            height: Some(textures.normal_map.clone()),
+            ..default()
+        },
+    });
+    commands.insert_resource(EarthMaterialTemplate(material_template));
+
+    commands.insert_resource(textures);
+}
+
+fn check_ready(
+    mut progress: ResMut<LoadingProgress>,
+    mut textures: ResMut<EarthTexture>,
+    asset_server: Res<AssetServer>,
+    template: Res<EarthMaterialTemplate>,
+    mut materials: ResMut<Assets<EarthMaterial>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut toasts: ResMut<Toasts>,
+) {
+    let mut loaded = 0;
+    if asset_server.is_loaded_with_dependencies(&textures.base_color) {
+        loaded += 1;
+    }
+    if asset_server.is_loaded_with_dependencies(&textures.metallic_roughness) && textures.repacked {
+        loaded += 1;
+    }
+    if asset_server.is_loaded_with_dependencies(&textures.normal_map) {
+        loaded += 1;
+    }
+
+    progress.texture = loaded;
+
+    // Optional textures don't count towards the progress. A material waiting on a texture that
+    // failed is never drawn, so it goes without it instead
+    if drop_failed(&mut textures.night_lights, &asset_server) {
+        toasts.warning("Failed to load the night lights, the night side stays dark");
+        if let Some(material) = materials.get_mut(&**template) {
+            material.extension.night = None;
+            material.extension.uniform.layer_opacity.x = 0.;
+        }
+    }
+    if drop_failed(&mut textures.clouds, &asset_server) {
+        toasts.warning("Failed to load the clouds, the sky stays clear");
+        if let Some(material) = materials.get_mut(&**template) {
+            material.extension.clouds = None;
+            material.extension.uniform.layer_opacity.y = 0.;
+        }
+    }
+    if drop_failed(&mut textures.moon, &asset_server) {
+        toasts.warning("Failed to load the Moon, it stays gray");
+    }
+    let optional = [&textures.night_lights, &textures.clouds, &textures.moon]
+        .into_iter()
+        .flatten()
+        .all(|handle| asset_server.is_loaded_with_dependencies(handle));
+    if progress.is_complete() && optional {
+        next_state.set(GameState::PostLoading);
+    }
+}
+
+/// Forgets an optional texture that failed to load, returning whether it did.
+fn drop_failed(texture: &mut Option<Handle<Image>>, asset_server: &AssetServer) -> bool {
+    let failed = texture
+        .as_ref()
+        .is_some_and(|handle| asset_server.load_state(handle).is_failed());
+    if failed {
+        *texture = None;
+    }
+    failed
+}
+
+fn rotate_light(
+    time: Res<SimulationTime>,
+    settings: Res<EarthSettings>,
+    config: Res<EarthConfig>,
+    mut transform: Single<&mut SimulatedTransform, With<RotatingLight>>,
+) {
+    // rotate around y-axis
+    let angle = time.elapsed_secs() * LIGHT_ROTATION_SPEED * settings.light_speed;
+
+    let x = angle.cos() * LIGHT_ORBIT * config.radius;
+    let z = angle.sin() * LIGHT_ORBIT * config.radius;
+    let height = LIGHT_HEIGHT * config.radius;
+
+    transform.current = Transform::from_xyz(x, height, z).looking_at(Vec3::ZERO, Vec3::Y);
+}
+
+fn spawn_task(
+    mut commands: Commands,
+    mut pool: ResMut<ChunkMeshPool>,
+    mut queue: ResMut<ChunkQueue>,
+    template: Res<EarthMaterialTemplate>,
+    mut progress: ResMut<LoadingProgress>,
+) {
+    let id = commands
+        .spawn((
+            Transform::default(),
+            Visibility::Hidden,
+            Earth,
+            Name::new("Earth"),
+        ))
+        .observe(capture_ui_drag)
+        .observe(release_ui_drag)
+        .observe(orbit_camera)
+        .observe(release_orbit)
+        .observe(zoom)
+        .observe(track_cursor)
+        .observe(clear_cursor)
+        .observe(record_press)
+        .observe(report_click)
+        .id();
+
+    for face in 0..FACES.len() as u8 {
+        for index in 0..4 {
+            let key = ChunkKey::root(face, index);
+            if spawn_chunk(&mut commands, id, key, &mut pool, &mut queue, &template.0).1 {
+                progress.mesh += 1;
+            }
+        }
+    }
+}
+
+/// Starts generating the most relevant queued chunks, as long as there are idle workers.
+///
+/// The queue is re-sorted every frame, so chunks that rotate into view overtake the rest.
+fn dispatch_chunks(
+    mut commands: Commands,
+    mut queue: ResMut<ChunkQueue>,
+    running: Query<(), With<ComputeMesh>>,
+    camera: Single<&Transform, With<MainCamera>>,
+    earth: Single<&Transform, With<Earth>>,
+    config: Res<EarthConfig>,
+) {
+    let thread_pool = AsyncComputeTaskPool::get();
+    let idle = thread_pool
+        .thread_num()
+        .saturating_sub(running.iter().count());
+    if queue.is_empty() || idle == 0 {
+        return;
+    }
+
+    let camera = earth
+        .compute_affine()
+        .inverse()
+        .transform_point3(camera.translation);
+    queue.sort_by_priority(camera / config.radius);
+
+    let (radius, resolution) = (config.radius, config.resolution);
+    for PendingChunk { entity, key } in queue.take(idle) {
+        // The globe may have been torn down while the chunk was waiting
+        let Ok(mut chunk) = commands.get_entity(entity) else {
+            continue;
+        };
+
+        let cache = config.mesh_cache.clone();
+        let task = thread_pool.spawn(async move {
+            let mut command_queue = CommandQueue::default();
+
+            let started = Instant::now();
+            let path = cache
+                .as_deref()
+                .and_then(|root| mesh_cache::cache_path(root, key, radius, resolution));
+            let face = path
+                .as_deref()
+                .and_then(mesh_cache::load)
+                .unwrap_or_else(|| {
+                    let (offset, size) = key.extent();
+                    let face = CubeSphereBuilder::new(key.direction())
+                        .radius(radius)
+                        .resolution(resolution)
+                        .patch(offset, size)
+                        .skirt(true)
+                        .build();
+                    if let Some(path) = &path
+                        && let Err(err) = mesh_cache::store(path, &face)
+                    {
+                        warn!("Failed to cache the mesh of {key:?}: {err}");
+                    }
+                    face
+                });
+            let elapsed = started.elapsed();
+
+            command_queue.push(move |world: &mut World| {
+                let (mesh, materal) = {
+                    let (mut mesh_handle, materal_handle, mut pool, mut times) =
+                        SystemState::<(
+                            ResMut<Assets<Mesh>>,
+                            Res<EarthMaterialTemplate>,
+                            ResMut<ChunkMeshPool>,
+                            ResMut<GenerationTimes>,
+                        )>::new(world)
+                        .get_mut(world);
+
+                    let mesh = mesh_handle.add(face);
+                    pool.insert(key, mesh.clone());
+                    times.record(elapsed);
+                    (mesh, materal_handle.clone())
+                };
+                // Level of detail may have merged the chunk away in the meantime
+                if let Ok(mut entity) = world.get_entity_mut(entity) {
+                    // Chunks replacing others stay hidden until `update_lod` swaps them in
+                    let visibility = if entity.contains::<Unrevealed>() {
+                        Visibility::Hidden
+                    } else {
+                        Visibility::Inherited
+                    };
+                    entity.insert((Mesh3d(mesh), visibility));
+                    // Chunks with `MaterialOverrides` may have their own instance by now
+                    if !entity.contains::<MeshMaterial3d<EarthMaterial>>() {
+                        entity.insert(MeshMaterial3d(materal));
+                    }
+                }
+            });
+
+            command_queue
+        });
+
+        chunk.insert(ComputeMesh(task));
+    }
+}
+
+/// Tears down the Earth hierarchy and the resources owning its textures and material, so the
+/// globe can be created again by re-entering `GameState::Loading`.
+///
+/// Materials are only referenced by the despawned entities and resources, so their assets are
+/// freed along with them and unfinished mesh tasks are cancelled. Chunk meshes stay in the
+/// `ChunkMeshPool`, so the next globe doesn't have to generate them again.
+pub fn despawn_earth(commands: &mut Commands) {
+    commands.queue(|world: &mut World| {
+        let earths: Vec<Entity> = world
+            .query_filtered::<Entity, With<Earth>>()
+            .iter(world)
+            .collect();
+        for earth in earths {
+            world.despawn(earth);
+        }
+
+        world.resource_mut::<ChunkQueue>().clear();
+        world.remove_resource::<EarthTexture>();
+        world.remove_resource::<EarthMaterialTemplate>();
+        world.insert_resource(LoadingProgress::default());
+        world.insert_resource(CursorHit::default());
+    });
+}
+
+fn handle_tasks(
+    mut commands: Commands,
+    mut transform_tasks: Query<(Entity, &mut ComputeMesh)>,
+    mut progress: ResMut<LoadingProgress>,
+) {
+    // Limit how many tasks we process per frame to avoid freezing the main thread
+    // when dealing with large meshes (e.g., a resolution of 800)
+    // const MAX_TASKS_PER_FRAME: usize = 1;
+    // let mut processed = 0;
+
+    for (entity, mut task) in &mut transform_tasks {
+        // IMPORTANT: Check the limit BEFORE calling check_ready to avoid dropping CommandQueues
+        // if processed >= MAX_TASKS_PER_FRAME {
+        //     break; // Skip checking this task, leave it for next frame
+        // }
+
+        // Use `check_ready` to efficiently poll the task without blocking the main thread.
+        if let Some(mut commands_queue) = futures::check_ready(&mut task.0) {
+            // Append the returned command queue to execute it later.
+            commands.append(&mut commands_queue);
+            // Task is complete, so remove the task component from the entity.
+            commands.entity(entity).remove::<ComputeMesh>();
+
+            progress.mesh += 1;
+            // processed += 1;
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use bevy::{
+    app::{App, Plugin, PostUpdate, Startup, Update},
+    ecs::{
+        change_detection::DetectChanges,
+        component::Component,
+        query::{With, Without},
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Res, ResMut, Single},
+    },
+    light::{DirectionalLight, GlobalAmbientLight},
+    state::condition::in_state,
+    transform::{TransformSystems, components::Transform},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    component::{Earth, MainCamera, RotatingLight},
+    state::GameState,
+};
+
+/// Where the light shining on the globe comes from.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LightingMode {
+    /// The simulated sun, leaving the far side in the night
+    #[default]
+    Sun,
+    /// Straight from the camera, so whatever is looked at is lit, e.g. to examine the night side
+    Camera,
+}
+
+impl LightingMode {
+    pub const ALL: [LightingMode; 2] = [LightingMode::Sun, LightingMode::Camera];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            LightingMode::Sun => "Sun",
+            LightingMode::Camera => "Follow camera",
+        }
+    }
+}
+
+/// Starting points for `FillLighting`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LightingPreset {
+    /// Only the sun, the night side stays dark
+    Realistic,
+    /// A soft fill so the night side shows its shape
+    Studio,
+    /// Bright enough to see every continent on the night side
+    Presentation,
+}
+
+impl LightingPreset {
+    pub const ALL: [LightingPreset; 3] = [
+        LightingPreset::Realistic,
+        LightingPreset::Studio,
+        LightingPreset::Presentation,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            LightingPreset::Realistic => "Realistic",
+            LightingPreset::Studio => "Studio",
+            LightingPreset::Presentation => "Presentation",
+        }
+    }
+}
+
+/// Light on top of the sun, so the dark side isn't pitch black.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct FillLighting {
+    /// Brightness of the ambient light in cd/m², lighting everything evenly
+    pub ambient: f32,
+    /// Illuminance in lux of a light from above and beside the camera, zero to turn it off
+    pub fill: f32,
+}
+
+impl Default for FillLighting {
+    fn default() -> Self {
+        Self::preset(LightingPreset::Realistic)
+    }
+}
+
+impl FillLighting {
+    pub fn preset(preset: LightingPreset) -> Self {
+        let (ambient, fill) = match preset {
+            // Bevy's default ambient light
+            LightingPreset::Realistic => (80., 0.),
+            LightingPreset::Studio => (400., 1500.),
+            LightingPreset::Presentation => (1000., 4000.),
+        };
+        Self { ambient, fill }
+    }
+}
+
+/// The directional light of `FillLighting::fill`, without shadows.
+#[derive(Component)]
+struct FillLight;
+
+pub struct LightingPlugin;
+
+impl Plugin for LightingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LightingMode>()
+            .init_resource::<FillLighting>()
+            .add_systems(Startup, spawn_fill_light)
+            .add_systems(Update, apply_fill_lighting)
+            .add_systems(
+                PostUpdate,
+                (
+                    follow_camera.run_if(|mode: Res<LightingMode>| *mode == LightingMode::Camera),
+                    place_fill_light,
+                )
+                    .before(TransformSystems::Propagate)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+fn spawn_fill_light(mut commands: Commands) {
+    commands.spawn((
+        DirectionalLight {
+            illuminance: 0.,
+            shadows_enabled: false,
+            ..Default::default()
+        },
+        Transform::default(),
+        FillLight,
+    ));
+}
+
+fn apply_fill_lighting(
+    lighting: Res<FillLighting>,
+    mut ambient: ResMut<GlobalAmbientLight>,
+    mut light: Single<&mut DirectionalLight, With<FillLight>>,
+) {
+    if !lighting.is_changed() {
+        return;
+    }
+    ambient.brightness = lighting.ambient;
+    light.illuminance = lighting.fill;
+}
+
+/// Keeps the fill light shining from above and to the left of the camera, so it models the
+/// globe instead of flattening it.
+fn place_fill_light(
+    camera: Single<&Transform, (With<MainCamera>, Without<FillLight>)>,
+    earth: Single<&Transform, (With<Earth>, Without<FillLight>)>,
+    mut light: Single<&mut Transform, With<FillLight>>,
+) {
+    let distance = camera.translation.distance(earth.translation);
+    let offset = (camera.left() + camera.up()) * distance;
+    **light = Transform::from_translation(camera.translation + offset)
+        .looking_at(earth.translation, camera.up());
+}
+
+/// Points the light along the camera's line of sight to the globe, after the simulation has
+/// placed it, so switching back to the sun resumes from where it is now.
+fn follow_camera(
+    camera: Single<&Transform, (With<MainCamera>, Without<RotatingLight>)>,
+    earth: Single<&Transform, (With<Earth>, Without<RotatingLight>)>,
+    mut light: Single<&mut Transform, With<RotatingLight>>,
+) {
+    **light =
+        Transform::from_translation(camera.translation).looking_at(earth.translation, camera.up());
+}
@@ -0,0 +1,318 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::{
+    app::{App, Plugin, PostUpdate, Update},
+    camera::{
+        Camera, Projection,
+        visibility::{Visibility, VisibilitySystems},
+    },
+    ecs::{
+        change_detection::DetectChangesMut,
+        entity::Entity,
+        query::{Has, With, Without},
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Query, Res, ResMut, Single},
+    },
+    math::Vec3,
+    mesh::Mesh3d,
+    state::condition::in_state,
+    transform::components::Transform,
+};
+
+use crate::{
+    EarthConfig,
+    antipode::AntipodeView,
+    chunk::{ChunkKey, ChunkMeshPool, ChunkQueue, spawn_chunk},
+    component::{Chunk, Earth, FacePatch, MainCamera, MaterialOverrides, Unrevealed},
+    material::MaterialSettings,
+    mesh_view::MeshView,
+    quality::Quality,
+    resource::EarthMaterialTemplate,
+    state::GameState,
+};
+
+/// Distance on screen between neighboring vertices above which a chunk splits into four.
+const SPLIT_PIXELS: f32 = 6.;
+
+/// Share of `SPLIT_PIXELS` the merged chunk has to stay below before four chunks merge again,
+/// so a chunk right at the threshold doesn't keep flipping between both.
+const MERGE_RATIO: f32 = 0.75;
+
+/// Radians a shown chunk has to be past the horizon before it is hidden. A hidden chunk only
+/// comes back once it is within the horizon again, so a chunk right at the horizon doesn't pop in
+/// and out while the camera moves.
+const CULL_HYSTERESIS: f32 = 0.05;
+
+/// Hides the chunks on the far side of the globe, which face away from the camera but would
+/// still be drawn. Off while the `AntipodeView` looks at that side.
+#[derive(Resource)]
+pub struct PatchCulling {
+    pub enabled: bool,
+}
+
+impl Default for PatchCulling {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// Splits the chunks of each cube face into a quadtree as the camera zooms in, and merges them
+/// back as it zooms out.
+///
+/// A chunk stays until the chunks replacing it have their meshes, which are generated by the
+/// same `ComputeMesh` tasks as the globe itself, or taken from the `ChunkMeshPool`. Until then
+/// they are `Unrevealed`, and they are shown in the frame it is despawned.
+pub struct LodPlugin;
+
+impl Plugin for LodPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PatchCulling>()
+            .add_systems(Update, update_lod.run_if(in_state(GameState::Playing)))
+            .add_systems(
+                PostUpdate,
+                cull_back_patches
+                    .before(VisibilitySystems::VisibilityPropagate)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// The camera in the local space of the globe, scaled to a radius of one.
+struct Viewer {
+    position: Vec3,
+    forward: Vec3,
+    /// Radians covered by one pixel
+    pixel_angle: f32,
+    /// Angle between the view direction and the corners of the screen
+    half_diagonal: f32,
+    /// Angle between the camera and the horizon, seen from the globe's center
+    horizon: f32,
+}
+
+impl Viewer {
+    /// Distance on screen between neighboring vertices of the chunk, zero if it is out of sight.
+    fn vertex_spacing(&self, key: ChunkKey, resolution: u32) -> f32 {
+        let center = key.center();
+        let corners = [
+            key.point(0., 0.),
+            key.point(1., 0.),
+            key.point(0., 1.),
+            key.point(1., 1.),
+        ];
+        let radius = corners
+            .iter()
+            .map(|corner| corner.distance(center))
+            .fold(0., f32::max);
+
+        if center.angle_between(self.position) > self.horizon + radius {
+            return 0.;
+        }
+        let offset = center - self.position;
+        let distance = offset.length();
+        if offset.angle_between(self.forward) > self.half_diagonal + (radius / distance).atan() {
+            return 0.;
+        }
+
+        let edge = corners[0].distance(corners[1]) / (resolution - 1) as f32;
+        let nearest = (distance - radius).max(1e-5);
+        edge / nearest / self.pixel_angle
+    }
+}
+
+fn update_lod(
+    mut commands: Commands,
+    config: Res<EarthConfig>,
+    quality: Res<Quality>,
+    mut pool: ResMut<ChunkMeshPool>,
+    mut queue: ResMut<ChunkQueue>,
+    template: Res<EarthMaterialTemplate>,
+    camera: Single<(&Transform, &Camera, &Projection), With<MainCamera>>,
+    earth: Single<(Entity, &Transform), (With<Earth>, Without<Camera>)>,
+    view: Res<MeshView>,
+    chunks: Query<(
+        Entity,
+        &Chunk,
+        Has<Mesh3d>,
+        Has<Unrevealed>,
+        Option<&MaterialOverrides>,
+    )>,
+) {
+    let (transform, camera, projection) = *camera;
+    let Projection::Perspective(perspective) = projection else {
+        return;
+    };
+    let Some(viewport) = camera.logical_viewport_size() else {
+        return;
+    };
+    let (earth, earth_transform) = *earth;
+
+    let local = earth_transform.compute_affine().inverse();
+    let position = local.transform_point3(transform.translation) / config.radius;
+    let viewer = Viewer {
+        position,
+        forward: local.transform_vector3(*transform.forward()).normalize(),
+        pixel_angle: perspective.fov / viewport.y,
+        half_diagonal: ((perspective.fov / 2.).tan()
+            * (1. + perspective.aspect_ratio * perspective.aspect_ratio).sqrt())
+        .atan(),
+        horizon: (1. / position.length()).min(1.).acos(),
+    };
+    let threshold = SPLIT_PIXELS * quality.level().lod_scale();
+    let spacing = |key: ChunkKey| viewer.vertex_spacing(key, config.resolution);
+
+    // Whether each chunk on the globe has its mesh yet
+    let existing: HashMap<ChunkKey, (Entity, bool)> = chunks
+        .iter()
+        .map(|(entity, chunk, has_mesh, ..)| (chunk.0, (entity, has_mesh)))
+        .collect();
+    let is_ready = |key: &ChunkKey| existing.get(key).is_some_and(|(_, ready)| *ready);
+    let is_leaf = |key: &ChunkKey| {
+        key.children()
+            .iter()
+            .all(|child| !existing.contains_key(child))
+    };
+
+    let mut despawned = HashSet::new();
+    let mut revealed = Vec::new();
+    for (entity, chunk, has_mesh, unrevealed, overrides) in &chunks {
+        let key = chunk.0;
+        if !has_mesh {
+            continue;
+        }
+
+        if unrevealed
+            && is_leaf(&key)
+            && key
+                .parent()
+                .is_none_or(|parent| !existing.contains_key(&parent))
+        {
+            // Nothing is left for the chunk to replace
+            revealed.push(entity);
+        }
+
+        let children = key.children();
+        if key.depth < config.max_depth && spacing(key) > threshold {
+            // A hidden chunk only splits once it replaced its parent, which would otherwise
+            // overlap its children
+            if is_leaf(&key) && !unrevealed {
+                for child in children {
+                    let (child, _) = spawn_chunk(
+                        &mut commands,
+                        earth,
+                        child,
+                        &mut pool,
+                        &mut queue,
+                        &template.0,
+                    );
+                    commands
+                        .entity(child)
+                        .insert((Unrevealed, Visibility::Hidden));
+                    if let Some(overrides) = overrides {
+                        commands.entity(child).insert(overrides.clone());
+                    }
+                }
+            } else if children.iter().all(is_ready) {
+                despawned.insert(entity);
+                revealed.extend(children.iter().map(|child| existing[child].0));
+            }
+        } else if !is_leaf(&key) {
+            // Zoomed back out, this chunk replaces everything below it
+            despawned.extend(
+                existing
+                    .iter()
+                    .filter(|(descendant, _)| key.is_ancestor_of(descendant))
+                    .map(|(_, (descendant, _))| *descendant),
+            );
+            if unrevealed {
+                revealed.push(entity);
+            }
+        }
+
+        // The first of four siblings brings back their parent once it is detailed enough
+        if let Some(parent) = key.parent()
+            && key.index % 4 == 0
+            && !existing.contains_key(&parent)
+            && parent
+                .children()
+                .iter()
+                .all(|sibling| is_ready(sibling) && is_leaf(sibling))
+            && spacing(parent) < threshold * MERGE_RATIO
+        {
+            let (parent, _) = spawn_chunk(
+                &mut commands,
+                earth,
+                parent,
+                &mut pool,
+                &mut queue,
+                &template.0,
+            );
+            commands
+                .entity(parent)
+                .insert((Unrevealed, Visibility::Hidden));
+            if let Some(overrides) = overrides {
+                commands.entity(parent).insert(overrides.clone());
+            }
+        }
+    }
+
+    // Swapped in within the frame the chunks they replace are despawned
+    for entity in revealed {
+        if !despawned.contains(&entity)
+            && let Ok((.., true, _)) = chunks.get(entity)
+        {
+            commands
+                .entity(entity)
+                .remove::<Unrevealed>()
+                .insert(view.chunk_visibility());
+        }
+    }
+    for entity in despawned {
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Hides the chunks more than `CULL_HYSTERESIS` beyond the horizon, including the one of the
+/// highest displaced peaks, and shows them again once they are back within the horizon.
+fn cull_back_patches(
+    culling: Res<PatchCulling>,
+    antipode: Res<AntipodeView>,
+    material: Res<MaterialSettings>,
+    view: Res<MeshView>,
+    config: Res<EarthConfig>,
+    camera: Single<&Transform, With<MainCamera>>,
+    earth: Single<&Transform, (With<Earth>, Without<MainCamera>)>,
+    mut patches: Query<(&mut FacePatch, &mut Visibility), (With<Mesh3d>, Without<Unrevealed>)>,
+) {
+    let position = earth
+        .compute_affine()
+        .inverse()
+        .transform_point3(camera.translation);
+    let direction = position.normalize_or_zero();
+    let radius = config.radius;
+    // Angles from the camera's direction to the horizon, and beyond it to the farthest surface
+    // point the highest peak can still be seen from
+    let horizon = (radius / position.length()).min(1.).acos()
+        + (radius / (radius + material.max_displacement(&config))).acos();
+    let enabled = culling.enabled && !antipode.enabled;
+
+    for (mut patch, mut visibility) in &mut patches {
+        let nearest = patch.normal.angle_between(direction) - patch.angular_radius;
+        let limit = if patch.culled {
+            horizon
+        } else {
+            horizon + CULL_HYSTERESIS
+        };
+        let culled = enabled && nearest > limit;
+        if culled {
+            // Meshes arriving from their task are inserted visible
+            visibility.set_if_neq(Visibility::Hidden);
+        } else if patch.culled {
+            // The point view hides the surface itself
+            *visibility = view.chunk_visibility();
+        }
+        if patch.culled != culled {
+            patch.culled = culled;
+        }
+    }
+}
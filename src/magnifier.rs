@@ -0,0 +1,200 @@
+use bevy::{
+    app::{App, Plugin, PostUpdate, Update},
+    asset::Assets,
+    camera::{Camera, CameraUpdateSystems, Projection},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::{With, Without},
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Query, Res, ResMut, Single},
+    },
+    image::Image,
+    state::condition::in_state,
+    transform::{
+        TransformSystems,
+        components::{GlobalTransform, Transform},
+    },
+};
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, EguiTextureHandle, egui};
+
+use crate::{
+    component::{Earth, MainCamera},
+    input::{Action, Actions},
+    offscreen::{OffscreenTarget, sync_offscreen_camera},
+    resource::{CursorHit, PointerOverUi},
+    state::GameState,
+};
+
+/// Diameter of the loupe, in pixels of its render target and logical pixels on screen.
+const LOUPE_SIZE: u32 = 192;
+
+/// A round loupe following the cursor, showing the surface under it enlarged by a second camera,
+/// for placing markers precisely on the detailed texture.
+#[derive(Resource)]
+pub struct Magnifier {
+    pub enabled: bool,
+    /// How many times larger the surface appears in the loupe than in the main view
+    pub magnification: f32,
+    target: OffscreenTarget,
+}
+
+impl Default for Magnifier {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            magnification: 4.,
+            target: OffscreenTarget::new(LOUPE_SIZE),
+        }
+    }
+}
+
+/// Offscreen camera of the `Magnifier`, only alive while it is enabled.
+#[derive(Component)]
+struct MagnifierCamera;
+
+pub struct MagnifierPlugin;
+
+impl Plugin for MagnifierPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Magnifier>()
+            .add_systems(
+                Update,
+                toggle_magnifier.run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                PostUpdate,
+                (spawn_magnifier_camera, aim_magnifier)
+                    .chain()
+                    .before(CameraUpdateSystems)
+                    .before(TransformSystems::Propagate)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                EguiPrimaryContextPass,
+                display_loupe
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(|magnifier: Res<Magnifier>| magnifier.enabled),
+            );
+    }
+}
+
+fn toggle_magnifier(actions: Actions, mut magnifier: ResMut<Magnifier>) {
+    if actions.just_pressed(Action::ToggleMagnifier) {
+        magnifier.enabled = !magnifier.enabled;
+    }
+}
+
+fn spawn_magnifier_camera(
+    mut commands: Commands,
+    mut magnifier: ResMut<Magnifier>,
+    mut images: ResMut<Assets<Image>>,
+    cameras: Query<Entity, With<MagnifierCamera>>,
+) {
+    let enabled = magnifier.enabled;
+    sync_offscreen_camera(
+        &mut commands,
+        enabled,
+        &cameras,
+        &mut magnifier.target,
+        &mut images,
+        MagnifierCamera,
+        // Only renders once `aim_magnifier` found the surface under the cursor
+        Camera {
+            is_active: false,
+            ..Default::default()
+        },
+    );
+}
+
+/// Points the loupe's camera from the main camera at the surface under the cursor, narrowing
+/// its field of view so one of its pixels covers `magnification` times less than one on screen.
+///
+/// The camera only renders while the cursor is over the globe.
+fn aim_magnifier(
+    magnifier: Res<Magnifier>,
+    cursor: Res<CursorHit>,
+    over_ui: Res<PointerOverUi>,
+    main: Single<(&Camera, &Transform, &Projection), (With<MainCamera>, Without<MagnifierCamera>)>,
+    earth: Single<&GlobalTransform, With<Earth>>,
+    mut cameras: Query<(&mut Camera, &mut Transform, &mut Projection), With<MagnifierCamera>>,
+) {
+    let (main, main_transform, main_projection) = *main;
+    let target = cursor
+        .filter(|_| !**over_ui)
+        .map(|hit| earth.transform_point(hit));
+    let height = main.logical_viewport_size().map(|size| size.y);
+
+    for (mut camera, mut transform, mut projection) in &mut cameras {
+        let (Some(target), Some(height), Projection::Perspective(main_perspective)) =
+            (target, height, main_projection)
+        else {
+            camera.is_active = false;
+            continue;
+        };
+        camera.is_active = true;
+
+        *transform = Transform::from_translation(main_transform.translation)
+            .looking_at(target, main_transform.up());
+        if let Projection::Perspective(perspective) = &mut *projection {
+            let extent = (main_perspective.fov / 2.).tan() * LOUPE_SIZE as f32
+                / height
+                / magnifier.magnification;
+            perspective.fov = 2. * extent.atan();
+        }
+    }
+}
+
+fn display_loupe(
+    mut contexts: EguiContexts,
+    magnifier: Res<Magnifier>,
+    cursor: Res<CursorHit>,
+    over_ui: Res<PointerOverUi>,
+) -> bevy::prelude::Result {
+    let Some(image) = magnifier.target.image() else {
+        return Ok(());
+    };
+    if cursor.is_none() || **over_ui {
+        return Ok(());
+    }
+    let texture = contexts.add_image(EguiTextureHandle::Strong(image.clone()));
+    let ctx = contexts.ctx_mut()?;
+    let Some(pointer) = ctx.input(|input| input.pointer.hover_pos()) else {
+        return Ok(());
+    };
+
+    let size = LOUPE_SIZE as f32;
+    egui::Area::new("Magnifier".into())
+        .order(egui::Order::Foreground)
+        .pivot(egui::Align2::CENTER_CENTER)
+        .fixed_pos(pointer)
+        .interactable(false)
+        .show(ctx, |ui| {
+            let response = ui.add(
+                egui::Image::new(egui::load::SizedTexture::new(
+                    texture,
+                    egui::vec2(size, size),
+                ))
+                .corner_radius(size / 2.),
+            );
+            let center = response.rect.center();
+            let stroke = egui::Stroke::new(1., egui::Color32::WHITE);
+            let painter = ui.painter();
+            painter.circle_stroke(
+                center,
+                size / 2.,
+                egui::Stroke::new(2., egui::Color32::WHITE),
+            );
+            painter.line_segment(
+                [center - egui::vec2(6., 0.), center + egui::vec2(6., 0.)],
+                stroke,
+            );
+            painter.line_segment(
+                [center - egui::vec2(0., 6.), center + egui::vec2(0., 6.)],
+                stroke,
+            );
+        });
+
+    Ok(())
+}
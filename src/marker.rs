@@ -0,0 +1,210 @@
+use std::f32::consts::PI;
+
+use bevy::{
+    app::{App, Plugin, PostUpdate, Startup, Update},
+    asset::{Assets, Handle},
+    camera::{Camera, Projection, visibility::Visibility},
+    color::Color,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        hierarchy::ChildOf,
+        query::{Added, Changed, With, Without},
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Query, Res, ResMut, Single},
+    },
+    math::{Quat, Vec3, primitives::Cone},
+    mesh::{Mesh, Mesh3d},
+    pbr::{MeshMaterial3d, StandardMaterial},
+    state::condition::in_state,
+    transform::{
+        TransformSystems,
+        components::{GlobalTransform, Transform},
+    },
+};
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+
+use crate::{
+    EarthConfig,
+    component::{Earth, MainCamera, Marker},
+    math::Coordinates,
+    state::GameState,
+};
+
+/// Height of the pin drawn at each marker, in logical pixels.
+const PIN_PIXELS: f32 = 28.;
+
+/// Text shown above a `Marker`, always facing the camera.
+#[derive(Component, Debug, Clone)]
+pub struct MarkerLabel(pub String);
+
+/// The pin drawn at a `Marker`, kept the same size on screen as the camera zooms.
+#[derive(Component)]
+struct MarkerPin;
+
+#[derive(Resource)]
+struct PinAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+/// Pins an entity to the globe at the given coordinates in degrees.
+///
+/// The marker becomes a child of the `Earth` with its Y axis pointing away from the surface, so
+/// children added to it stand upright on the globe and turn with it. Insert a `MarkerLabel` to
+/// name it on screen.
+pub fn spawn_marker(
+    commands: &mut Commands,
+    latitude: f32,
+    longitude: f32,
+) -> Result<Entity, String> {
+    let coordinates = Coordinates::from_degrees(latitude, longitude)?;
+    Ok(commands.spawn(Marker { coordinates }).id())
+}
+
+pub struct MarkerPlugin;
+
+impl Plugin for MarkerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, create_pin_assets)
+            .add_systems(
+                Update,
+                (attach_markers, place_markers)
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(PostUpdate, scale_pins.before(TransformSystems::Propagate))
+            .add_systems(
+                EguiPrimaryContextPass,
+                draw_labels.run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+fn create_pin_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(PinAssets {
+        mesh: meshes.add(Cone {
+            radius: 0.2,
+            height: 1.,
+        }),
+        material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.9, 0.15, 0.1),
+            ..Default::default()
+        }),
+    });
+}
+
+fn attach_markers(
+    mut commands: Commands,
+    pin: Res<PinAssets>,
+    earth: Single<Entity, With<Earth>>,
+    markers: Query<Entity, Added<Marker>>,
+) {
+    for entity in &markers {
+        commands
+            .entity(entity)
+            .insert((Visibility::default(), ChildOf(*earth)))
+            .with_child((
+                Mesh3d(pin.mesh.clone()),
+                MeshMaterial3d(pin.material.clone()),
+                Transform::default(),
+                MarkerPin,
+            ));
+    }
+}
+
+/// Moves markers onto the surface whenever their coordinates change, facing outwards.
+fn place_markers(
+    mut commands: Commands,
+    markers: Query<(Entity, &Marker), Changed<Marker>>,
+    config: Res<EarthConfig>,
+) {
+    for (entity, marker) in &markers {
+        let direction = marker.coordinates.direction();
+        let rotation = Quat::from_rotation_arc(Vec3::Y, direction);
+        commands
+            .entity(entity)
+            .insert(Transform::from_translation(direction * config.radius).with_rotation(rotation));
+    }
+}
+
+/// Scales each pin to `PIN_PIXELS`, with its tip on the marker and its head up.
+///
+/// Like billboards, markers sit in the globe's local space, so the camera is brought into it.
+fn scale_pins(
+    camera: Single<(&Camera, &Transform, &Projection), (With<MainCamera>, Without<MarkerPin>)>,
+    earth: Single<&Transform, (With<Earth>, Without<MarkerPin>)>,
+    markers: Query<&Transform, (With<Marker>, Without<MarkerPin>)>,
+    mut pins: Query<(&ChildOf, &mut Transform), With<MarkerPin>>,
+) {
+    let (camera, camera_transform, projection) = *camera;
+    let (Projection::Perspective(perspective), Some(viewport)) =
+        (projection, camera.logical_viewport_size())
+    else {
+        return;
+    };
+
+    let camera_position = earth
+        .compute_affine()
+        .inverse()
+        .transform_point3(camera_transform.translation);
+    let pixel_at_unit = 2. * (perspective.fov / 2.).tan() / viewport.y;
+
+    for (parent, mut transform) in &mut pins {
+        let Ok(marker) = markers.get(parent.parent()) else {
+            continue;
+        };
+        let height = PIN_PIXELS * pixel_at_unit * camera_position.distance(marker.translation);
+        *transform = Transform::from_translation(Vec3::Y * height * 0.5)
+            .with_rotation(Quat::from_rotation_x(PI))
+            .with_scale(Vec3::splat(height));
+    }
+}
+
+/// Paints the labels above their pins, skipping markers on the far side of the globe.
+fn draw_labels(
+    mut contexts: EguiContexts,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera>>,
+    earth: Single<&GlobalTransform, (With<Earth>, Without<Camera>)>,
+    labels: Query<(&MarkerLabel, &GlobalTransform), Without<Camera>>,
+) -> bevy::prelude::Result {
+    let ctx = contexts.ctx_mut()?;
+    let (camera, camera_transform) = *camera;
+    let painter = ctx.layer_painter(egui::LayerId::background());
+
+    for (label, transform) in &labels {
+        let position = transform.translation();
+        let outward = position - earth.translation();
+        if outward.dot(camera_transform.translation() - position) < 0. {
+            continue;
+        }
+        let Ok(screen) = camera.world_to_viewport(camera_transform, position) else {
+            continue;
+        };
+
+        let anchor = egui::pos2(screen.x, screen.y - PIN_PIXELS - 2.);
+        let font = egui::FontId::proportional(14.);
+        // Dark shadow keeps the label readable on bright ground
+        painter.text(
+            anchor + egui::vec2(1., 1.),
+            egui::Align2::CENTER_BOTTOM,
+            &label.0,
+            font.clone(),
+            egui::Color32::BLACK,
+        );
+        painter.text(
+            anchor,
+            egui::Align2::CENTER_BOTTOM,
+            &label.0,
+            font,
+            egui::Color32::WHITE,
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,667 @@
+use std::collections::BTreeMap;
+
+use bevy::{
+    app::{App, Plugin, Update},
+    asset::{Asset, AssetEvent, Assets, Handle},
+    color::{Color, LinearRgba},
+    ecs::{
+        change_detection::{DetectChanges, DetectChangesMut},
+        entity::Entity,
+        lifecycle::RemovedComponents,
+        message::MessageReader,
+        query::{Changed, With},
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Local, Query, Res, ResMut, Single},
+    },
+    image::Image,
+    log::error,
+    math::{UVec4, Vec2, Vec3, Vec4},
+    mesh::MeshVertexBufferLayoutRef,
+    pbr::{
+        ExtendedMaterial, MaterialExtension, MaterialExtensionKey, MaterialExtensionPipeline,
+        MaterialPlugin, MeshMaterial3d, StandardMaterial,
+    },
+    reflect::Reflect,
+    render::render_resource::{
+        AsBindGroup, RenderPipelineDescriptor, ShaderType, SpecializedMeshPipelineError,
+    },
+    shader::ShaderRef,
+    state::condition::in_state,
+    transform::components::GlobalTransform,
+};
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    EARTH_RADIUS, EarthConfig,
+    component::{Earth, MaterialOverrides, RotatingLight},
+    mesh_view::MeshView,
+    resource::{EarthMaterialTemplate, EarthTexture, SimulationTime},
+    state::GameState,
+};
+
+const PRESETS_PATH: &str = "material_presets.ron";
+
+const SHADER_PATH: &str = "shaders/earth.wgsl";
+
+const VERTEX_SHADER_PATH: &str = "shaders/earth_vertex.wgsl";
+
+/// Height of Mount Everest, the brightest texel of the height map.
+const MAX_ELEVATION_KM: f32 = 8.849;
+
+/// Waves across the globe's radius, so each is about 20 km long and only shows up close.
+const WAVE_FREQUENCY: f32 = 2000.;
+
+/// Radians per simulated second the slowest wave moves by.
+const WAVE_SPEED: f64 = 0.5;
+
+pub type EarthMaterial = ExtendedMaterial<StandardMaterial, EarthExtension>;
+
+#[derive(ShaderType, Reflect, Debug, Clone, Copy)]
+pub struct EarthUniform {
+    /// Direction towards the sun, in world space
+    pub sun_direction: Vec3,
+    pub normal_strength: f32,
+    /// rgb = tint, a = strength
+    pub ocean_tint: Vec4,
+    /// x = night lights, y = cloud shadow, z = ocean tint; zero for layers without a texture
+    pub layer_opacity: Vec4,
+    pub overlay_opacity: Vec4,
+    /// `BlendMode` of each overlay slot
+    pub overlay_blend: UVec4,
+    /// UV scroll of the cloud texture
+    pub cloud_offset: Vec2,
+    /// Radius the vertex shader projects the chunks onto
+    pub radius: f32,
+    /// Height in world units of the brightest texel of the height map, zero to keep the mesh
+    pub displacement: f32,
+    /// Center of the globe in world space, which moves with the floating origin
+    pub center: Vec3,
+    /// `DebugView` shown instead of the lit surface
+    pub debug_view: u32,
+    /// How far the regions outside the `explored` mask are desaturated
+    pub exploration: f32,
+    /// UV offset in xy and size in zw of the streamed `tile`, zero without one
+    pub tile_rect: Vec4,
+    /// Flat color of the land, with a = 1 in the stylized mode of `StylizedView`
+    pub style_land: Vec4,
+    /// Flat color of the oceans, with the number of cel-shading bands in a
+    pub style_ocean: Vec4,
+    /// Color of the limb outline, with its width in a
+    pub style_outline: Vec4,
+    /// x = wave strength, y = waves per unit of the globe's radius, z = phase of the waves,
+    /// w = roughness of the water, zero to keep the specular map's
+    pub water: Vec4,
+}
+
+impl Default for EarthUniform {
+    fn default() -> Self {
+        Self {
+            sun_direction: Vec3::Z,
+            normal_strength: 1.,
+            ocean_tint: Vec4::ONE,
+            layer_opacity: Vec4::ZERO,
+            overlay_opacity: Vec4::ZERO,
+            overlay_blend: UVec4::ZERO,
+            cloud_offset: Vec2::ZERO,
+            radius: EARTH_RADIUS.x,
+            displacement: 0.,
+            center: Vec3::ZERO,
+            debug_view: 0,
+            exploration: 0.,
+            tile_rect: Vec4::ZERO,
+            style_land: Vec4::ZERO,
+            style_ocean: Vec4::ZERO,
+            style_outline: Vec4::ZERO,
+            water: Vec4::ZERO,
+        }
+    }
+}
+
+/// Layers composited on top of the `StandardMaterial` of the Earth, all sampled with the
+/// globe's equirectangular UVs.
+#[derive(Asset, AsBindGroup, Reflect, Debug, Clone, Default)]
+pub struct EarthExtension {
+    #[uniform(100)]
+    pub uniform: EarthUniform,
+    /// Emissive city lights, only shown on the hemisphere facing away from the sun
+    #[texture(101)]
+    #[sampler(102)]
+    pub night: Option<Handle<Image>>,
+    /// Cloud coverage in the red channel, darkening the ground below
+    #[texture(103)]
+    pub clouds: Option<Handle<Image>>,
+    /// Texture with roughness in green, where smooth texels are water
+    #[texture(104)]
+    pub ocean_mask: Option<Handle<Image>>,
+    #[texture(105)]
+    pub overlay_0: Option<Handle<Image>>,
+    #[texture(106)]
+    pub overlay_1: Option<Handle<Image>>,
+    /// Elevation in the red channel, sampled by the vertex shader
+    #[texture(107)]
+    #[sampler(108)]
+    pub height: Option<Handle<Image>>,
+    /// Regions visited in exploration mode in the red channel
+    #[texture(109)]
+    pub explored: Option<Handle<Image>>,
+    /// Imagery streamed for a single chunk, see `MaterialOverrides::base_color_tile`
+    #[texture(110)]
+    #[sampler(111)]
+    pub tile: Option<Handle<Image>>,
+}
+
+/// The vertex shader only uses the direction of each vertex, and how far below the radius the
+/// skirts hang, so radius and displacement can change without regenerating any chunk. It runs
+/// for the prepasses as well, so shadows and depth follow the displaced terrain; picking and
+/// culling still see the meshes as generated.
+impl MaterialExtension for EarthExtension {
+    fn vertex_shader() -> ShaderRef {
+        VERTEX_SHADER_PATH.into()
+    }
+
+    fn prepass_vertex_shader() -> ShaderRef {
+        VERTEX_SHADER_PATH.into()
+    }
+
+    fn deferred_vertex_shader() -> ShaderRef {
+        VERTEX_SHADER_PATH.into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        SHADER_PATH.into()
+    }
+
+    fn deferred_fragment_shader() -> ShaderRef {
+        SHADER_PATH.into()
+    }
+
+    fn specialize(
+        _pipeline: &MaterialExtensionPipeline,
+        descriptor: &mut RenderPipelineDescriptor,
+        _layout: &MeshVertexBufferLayoutRef,
+        _key: MaterialExtensionKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        // The chunk debug views read the `MeshTag` of the instance in the fragment shader
+        descriptor
+            .vertex
+            .shader_defs
+            .push("VERTEX_OUTPUT_INSTANCE_INDEX".into());
+        if let Some(fragment) = &mut descriptor.fragment {
+            fragment
+                .shader_defs
+                .push("VERTEX_OUTPUT_INSTANCE_INDEX".into());
+        }
+        Ok(())
+    }
+}
+
+/// Replaces the lit surface of the Earth with one of its inputs, to track down mapping issues.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DebugView {
+    #[default]
+    Off,
+    BaseColor,
+    Roughness,
+    /// World space normal after the normal map
+    Normal,
+    /// Checkerboard of 10 degree cells, shaded by the UVs
+    UvChecker,
+    /// A distinct color for every chunk
+    ChunkId,
+    /// A color for every subdivision depth
+    Lod,
+}
+
+impl DebugView {
+    pub const ALL: [DebugView; 7] = [
+        DebugView::Off,
+        DebugView::BaseColor,
+        DebugView::Roughness,
+        DebugView::Normal,
+        DebugView::UvChecker,
+        DebugView::ChunkId,
+        DebugView::Lod,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DebugView::Off => "Off",
+            DebugView::BaseColor => "Base color",
+            DebugView::Roughness => "Roughness",
+            DebugView::Normal => "Normal map",
+            DebugView::UvChecker => "UV checker",
+            DebugView::ChunkId => "Chunk ID",
+            DebugView::Lod => "LOD level",
+        }
+    }
+
+    /// Matches the `debug_color` switch in `earth.wgsl`.
+    fn shader_id(&self) -> u32 {
+        *self as u32
+    }
+}
+
+/// Live-tunable parameters of the Earth material.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct MaterialSettings {
+    pub perceptual_roughness: f32,
+    pub metallic: f32,
+    pub reflectance: f32,
+    /// Multiplied with the base color texture
+    pub tint: [f32; 3],
+    pub emissive: [f32; 3],
+    pub emissive_intensity: f32,
+    pub normal_map: bool,
+    pub normal_strength: f32,
+    pub night_lights: f32,
+    pub cloud_shadow: f32,
+    pub water_tint: [f32; 3],
+    pub water_tint_strength: f32,
+    /// Displaces the surface by the height map on the GPU
+    pub displacement: bool,
+    /// Vertical exaggeration of the displacement
+    pub exaggeration: f32,
+    /// Strength of the animated waves tilting the normals on the water
+    pub waves: f32,
+    /// Roughness of the water, lower for a sharper and brighter sun glint
+    pub water_roughness: f32,
+}
+
+impl Default for MaterialSettings {
+    fn default() -> Self {
+        Self {
+            perceptual_roughness: 1.,
+            metallic: 0.,
+            reflectance: 0.5,
+            tint: [1., 1., 1.],
+            emissive: [0., 0., 0.],
+            emissive_intensity: 0.,
+            normal_map: true,
+            normal_strength: 1.,
+            night_lights: 1.,
+            cloud_shadow: 0.5,
+            water_tint: [1., 1., 1.],
+            water_tint_strength: 0.,
+            displacement: false,
+            exaggeration: 10.,
+            waves: 0.2,
+            water_roughness: 0.15,
+        }
+    }
+}
+
+impl MaterialSettings {
+    /// Height the displacement raises the highest peak by, in world units.
+    pub fn max_displacement(&self, config: &EarthConfig) -> f32 {
+        if self.displacement {
+            MAX_ELEVATION_KM / config.km_per_unit() * self.exaggeration
+        } else {
+            0.
+        }
+    }
+
+    fn apply(&self, material: &mut EarthMaterial, textures: &EarthTexture, config: &EarthConfig) {
+        let extension = &mut material.extension;
+        let present = |texture: &Option<Handle<Image>>| texture.is_some() as u8 as f32;
+        let uniform = &mut extension.uniform;
+        uniform.normal_strength = self.normal_strength;
+        let [r, g, b] = self.water_tint;
+        uniform.ocean_tint = Vec4::new(r, g, b, self.water_tint_strength);
+        uniform.layer_opacity = Vec4::new(
+            self.night_lights * present(&extension.night),
+            self.cloud_shadow * present(&extension.clouds),
+            present(&extension.ocean_mask),
+            0.,
+        );
+        uniform.water.x = self.waves;
+        uniform.water.y = WAVE_FREQUENCY;
+        uniform.water.w = self.water_roughness;
+        uniform.radius = config.radius;
+        uniform.displacement = if extension.height.is_some() {
+            self.max_displacement(config)
+        } else {
+            0.
+        };
+
+        let material = &mut material.base;
+        let [r, g, b] = self.tint;
+        material.base_color = Color::linear_rgb(r, g, b);
+        material.perceptual_roughness = self.perceptual_roughness;
+        material.metallic = self.metallic;
+        material.reflectance = self.reflectance;
+
+        let [r, g, b] = self.emissive;
+        material.emissive = LinearRgba::rgb(r, g, b) * self.emissive_intensity;
+        material.normal_map_texture = self.normal_map.then(|| textures.normal_map.clone());
+    }
+}
+
+#[derive(Resource, Serialize, Deserialize, Debug, Default)]
+pub struct MaterialPresets(pub BTreeMap<String, MaterialSettings>);
+
+impl MaterialPresets {
+    fn load() -> Self {
+        let Ok(serialized) = std::fs::read_to_string(PRESETS_PATH) else {
+            return Self::default();
+        };
+        ron::from_str(&serialized).unwrap_or_else(|err| {
+            error!("Failed to parse {PRESETS_PATH}: {err}");
+            Self::default()
+        })
+    }
+
+    fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let serialized = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        std::fs::write(PRESETS_PATH, serialized)?;
+        Ok(())
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct MaterialInspector {
+    pub open: bool,
+}
+
+pub struct EarthMaterialPlugin;
+
+impl Plugin for EarthMaterialPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(MaterialPlugin::<EarthMaterial>::default())
+            .init_resource::<MaterialSettings>()
+            .init_resource::<MaterialInspector>()
+            .init_resource::<DebugView>()
+            .insert_resource(MaterialPresets::load())
+            .add_systems(
+                Update,
+                (
+                    (
+                        apply_material_settings,
+                        apply_debug_view,
+                        track_sun,
+                        animate_water,
+                    ),
+                    instance_chunk_materials,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                EguiPrimaryContextPass,
+                display_material_inspector
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(|inspector: Res<MaterialInspector>| inspector.open),
+            );
+    }
+}
+
+fn apply_material_settings(
+    settings: Res<MaterialSettings>,
+    handle: Res<EarthMaterialTemplate>,
+    textures: Res<EarthTexture>,
+    config: Res<EarthConfig>,
+    mut materials: ResMut<Assets<EarthMaterial>>,
+    mut applied: Local<bool>,
+) {
+    if *applied && !settings.is_changed() {
+        return;
+    }
+
+    if let Some(material) = materials.get_mut(&**handle) {
+        settings.apply(material, &textures, &config);
+        *applied = true;
+    }
+}
+
+fn apply_debug_view(
+    view: Res<DebugView>,
+    handle: Res<EarthMaterialTemplate>,
+    mut materials: ResMut<Assets<EarthMaterial>>,
+) {
+    if !view.is_changed() {
+        return;
+    }
+
+    if let Some(material) = materials.get_mut(&**handle) {
+        material.extension.uniform.debug_view = view.shader_id();
+    }
+}
+
+fn track_sun(
+    handle: Res<EarthMaterialTemplate>,
+    mut materials: ResMut<Assets<EarthMaterial>>,
+    light: Single<&GlobalTransform, With<RotatingLight>>,
+    earth: Single<&GlobalTransform, With<Earth>>,
+) {
+    let sun_direction = RotatingLight::sun_direction(&light);
+    let center = earth.translation();
+    // Writing the material prepares it again and copies it to every chunk, only do so on change
+    let Some(material) = materials.get(&**handle) else {
+        return;
+    };
+    let uniform = &material.extension.uniform;
+    if uniform.sun_direction == sun_direction && uniform.center == center {
+        return;
+    }
+
+    if let Some(material) = materials.get_mut(&**handle) {
+        let uniform = &mut material.extension.uniform;
+        uniform.sun_direction = sun_direction;
+        uniform.center = center;
+    }
+}
+
+/// Moves the waves on with the simulation clock, and leaves them be while it is paused.
+fn animate_water(
+    simulation: Res<SimulationTime>,
+    handle: Res<EarthMaterialTemplate>,
+    mut materials: ResMut<Assets<EarthMaterial>>,
+) {
+    // Paused without a pending step, so the clock didn't move
+    if simulation.paused && simulation.delta == 0. {
+        return;
+    }
+    // Every wave moves by a whole multiple of the phase, so wrapping it keeps them smooth
+    let phase = (simulation.elapsed * WAVE_SPEED).rem_euclid(std::f64::consts::TAU) as f32;
+    // Like `track_sun`, only write the material when the phase moved
+    if materials
+        .get(&**handle)
+        .is_none_or(|material| material.extension.uniform.water.z == phase)
+    {
+        return;
+    }
+
+    if let Some(material) = materials.get_mut(&**handle) {
+        material.extension.uniform.water.z = phase;
+    }
+}
+
+impl MaterialOverrides {
+    fn apply(&self, material: &mut EarthMaterial) {
+        if let Some(texture) = &self.base_color_texture {
+            material.base.base_color_texture = Some(texture.clone());
+        }
+        if let Some(uv_transform) = self.uv_transform {
+            material.base.uv_transform = uv_transform;
+        }
+        if let Some((tile, rect)) = &self.base_color_tile {
+            material.extension.tile = Some(tile.clone());
+            material.extension.uniform.tile_rect =
+                Vec4::new(rect.min.x, rect.min.y, rect.width(), rect.height());
+        }
+    }
+}
+
+/// Gives chunks with `MaterialOverrides` their own copy of the template and keeps it in sync.
+fn instance_chunk_materials(
+    mut commands: Commands,
+    template: Res<EarthMaterialTemplate>,
+    mut materials: ResMut<Assets<EarthMaterial>>,
+    mut events: MessageReader<AssetEvent<EarthMaterial>>,
+    changed: Query<(Entity, &MaterialOverrides), Changed<MaterialOverrides>>,
+    instances: Query<(&MaterialOverrides, &MeshMaterial3d<EarthMaterial>)>,
+    mut removed: RemovedComponents<MaterialOverrides>,
+) {
+    let Some(base) = materials.get(&**template).cloned() else {
+        return;
+    };
+
+    for (entity, overrides) in &changed {
+        let mut material = base.clone();
+        overrides.apply(&mut material);
+        commands
+            .entity(entity)
+            .insert(MeshMaterial3d(materials.add(material)));
+    }
+
+    let template_modified = events.read().any(|event| event.is_modified(template.id()));
+    if template_modified {
+        for (overrides, instance) in &instances {
+            if instance.0.id() == template.id() {
+                continue;
+            }
+            if let Some(material) = materials.get_mut(&instance.0) {
+                *material = base.clone();
+                overrides.apply(material);
+            }
+        }
+    }
+
+    // Dropping the instance handle frees it, the chunk falls back to the shared template
+    for entity in removed.read() {
+        commands
+            .entity(entity)
+            .try_insert(MeshMaterial3d(template.0.clone()));
+    }
+}
+
+fn display_material_inspector(
+    mut contexts: EguiContexts,
+    mut inspector: ResMut<MaterialInspector>,
+    mut settings: ResMut<MaterialSettings>,
+    mut presets: ResMut<MaterialPresets>,
+    mut debug_view: ResMut<DebugView>,
+    mut mesh_view: ResMut<MeshView>,
+    mut preset_name: Local<String>,
+) -> bevy::prelude::Result {
+    let ctx = contexts.ctx_mut()?;
+
+    // Edit a copy so change detection only fires when a value actually changed
+    let mut edited = *settings;
+    let mut view = *debug_view;
+    let mut mesh = *mesh_view;
+    egui::Window::new("Material")
+        .open(&mut inspector.open)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.add(
+                egui::Slider::new(&mut edited.perceptual_roughness, 0.089..=1.).text("Roughness"),
+            );
+            ui.add(egui::Slider::new(&mut edited.metallic, 0.0..=1.).text("Metallic"));
+            ui.add(egui::Slider::new(&mut edited.reflectance, 0.0..=1.).text("Reflectance"));
+            ui.horizontal(|ui| {
+                ui.color_edit_button_rgb(&mut edited.tint);
+                ui.label("Tint");
+            });
+            ui.horizontal(|ui| {
+                ui.color_edit_button_rgb(&mut edited.emissive);
+                ui.add(
+                    egui::Slider::new(&mut edited.emissive_intensity, 0.0..=10_000.)
+                        .logarithmic(true)
+                        .text("Emissive"),
+                );
+            });
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut edited.normal_map, "Normal map");
+                ui.add_enabled(
+                    edited.normal_map,
+                    egui::Slider::new(&mut edited.normal_strength, 0.0..=2.).text("Strength"),
+                );
+            });
+            ui.horizontal(|ui| {
+                ui.color_edit_button_rgb(&mut edited.water_tint);
+                ui.add(
+                    egui::Slider::new(&mut edited.water_tint_strength, 0.0..=1.).text("Water tint"),
+                );
+            });
+            ui.add(egui::Slider::new(&mut edited.waves, 0.0..=1.).text("Waves"));
+            ui.add(
+                egui::Slider::new(&mut edited.water_roughness, 0.0..=1.).text("Water roughness"),
+            );
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut edited.displacement, "Displacement");
+                ui.add_enabled(
+                    edited.displacement,
+                    egui::Slider::new(&mut edited.exaggeration, 1.0..=100.)
+                        .logarithmic(true)
+                        .text("Exaggeration"),
+                );
+            });
+            ui.add(egui::Slider::new(&mut edited.night_lights, 0.0..=10.).text("Night lights"));
+            ui.add(egui::Slider::new(&mut edited.cloud_shadow, 0.0..=1.).text("Cloud shadow"));
+
+            if ui.button("Reset").clicked() {
+                edited = MaterialSettings::default();
+            }
+
+            ui.separator();
+            egui::ComboBox::from_label("Debug view")
+                .selected_text(view.label())
+                .show_ui(ui, |ui| {
+                    for mode in DebugView::ALL {
+                        ui.selectable_value(&mut view, mode, mode.label());
+                    }
+                });
+            egui::ComboBox::from_label("Mesh")
+                .selected_text(mesh.label())
+                .show_ui(ui, |ui| {
+                    for mode in MeshView::ALL {
+                        ui.selectable_value(&mut mesh, mode, mode.label());
+                    }
+                });
+
+            ui.separator();
+            ui.heading("Presets");
+            let mut removed = None;
+            for (name, preset) in &presets.0 {
+                ui.horizontal(|ui| {
+                    if ui.button(name).clicked() {
+                        edited = *preset;
+                    }
+                    if ui.small_button("x").clicked() {
+                        removed = Some(name.clone());
+                    }
+                });
+            }
+
+            let mut modified = false;
+            if let Some(name) = removed {
+                presets.0.remove(&name);
+                modified = true;
+            }
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut *preset_name);
+                if ui
+                    .add_enabled(!preset_name.is_empty(), egui::Button::new("Save as preset"))
+                    .clicked()
+                {
+                    presets.0.insert(std::mem::take(&mut *preset_name), edited);
+                    modified = true;
+                }
+            });
+
+            if modified && let Err(err) = presets.save() {
+                error!("Failed to save {PRESETS_PATH}: {err}");
+            }
+        });
+
+    if edited != *settings {
+        *settings = edited;
+    }
+    debug_view.set_if_neq(view);
+    mesh_view.set_if_neq(mesh);
+
+    Ok(())
+}
@@ -1,13 +1,23 @@
-use std::f32::consts::PI;
+use std::{
+    collections::HashMap,
+    f32::consts::{PI, TAU},
+    sync::{Mutex, OnceLock},
+};
 
 use bevy::{
     asset::RenderAssetUsages,
-    math::Vec3,
+    camera::Camera,
+    math::{Mat3, Quat, Ray3d, Vec3},
     mesh::{self, Mesh, PrimitiveTopology},
+    tasks::{AsyncComputeTaskPool, TaskPool},
+    transform::components::GlobalTransform,
 };
 use bevy_egui::egui::Vec2;
 
-use crate::EARTH_RADIUS;
+use crate::{MAX_FOV, MIN_FOV};
+
+/// Natural logarithm of the factor one wheel notch shrinks the visible extent by.
+const ZOOM_PER_STEP: f32 = 0.15;
 
 fn map(input_range: (f32, f32), output_range: (f32, f32), value: f32) -> f32 {
     let (in_min, in_max) = input_range;
@@ -46,7 +56,7 @@ fn map_longitude(lon: f32) -> Result<f32, String> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Coordinates {
     // Stored internally in radians
     pub latitude: f32,
@@ -79,103 +89,653 @@ impl Coordinates {
         (u, v)
     }
 
-    // pub fn from_degrees(latitude: f32, longitude: f32) -> Result<Self, String> {
-    //     if !(-90.0..=90.0).contains(&latitude) {
-    //         return Err("Invalid latitude: {lat:?}".to_string());
-    //     }
-    //     if !(-180.0..=180.0).contains(&longitude) {
-    //         return Err("Invalid longitude: {lon:?}".to_string());
-    //     }
-    //     let latitude = latitude / (180.0 / PI);
-    //     let longitude = longitude / (180.0 / PI);
-    //     Ok(Coordinates {
-    //         latitude,
-    //         longitude,
-    //     })
-    // }
-
-    // pub fn get_point_on_sphere(&self) -> Vec3 {
-    //     let y = self.latitude.sin();
-    //     let r = self.latitude.cos();
-    //     let x = self.longitude.sin() * -r;
-    //     let z = self.longitude.cos() * r;
-    //     Vec3::new(x, y, z).normalize() * EARTH_RADIUS
-    // }
-}
-
-pub fn generate_face(normal: Vec3, resolution: u32, x_offset: f32, y_offset: f32) -> Mesh {
-    let axis_a = Vec3::new(normal.y, normal.z, normal.x); // Horizontal
-    let axis_b = axis_a.cross(normal); // Vertical
-
-    // Create a vec of verticies and indicies
-    let mut verticies: Vec<Vec3> = Vec::new();
-
-    let mut indicies: Vec<u32> = Vec::new();
-    let mut normals = Vec::new();
-    let mut first_longitude = 0.;
-
-    // Create a new vec containing our uv coords
-    let mut uvs = Vec::new();
-
-    for y in 0..(resolution) {
-        for x in 0..(resolution) {
-            let i = x + y * resolution;
-
-            let percent = Vec2::new(x as f32, y as f32) / (resolution - 1) as f32;
-            let point_on_unit_cube =
-                normal + (percent.x - x_offset) * axis_a + (percent.y - y_offset) * axis_b;
-
-            // Convert our point_coords into `Coordinates`
-            let point_coords: Coordinates = point_on_unit_cube.normalize().into();
-            let normalized_point = point_on_unit_cube.normalize() * EARTH_RADIUS;
-
-            verticies.push(normalized_point);
-
-            let (mut u, v) = point_coords.convert_to_uv_mercator();
-            let lon = point_coords.longitude;
-            let lat = point_coords.latitude;
-
-            if y == 0 && x == 0 {
-                first_longitude = lon;
+    pub fn from_degrees(latitude: f32, longitude: f32) -> Result<Self, String> {
+        if !(-90.0..=90.0).contains(&latitude) {
+            return Err(format!("Invalid latitude: {latitude:?}"));
+        }
+        if !(-180.0..=180.0).contains(&longitude) {
+            return Err(format!("Invalid longitude: {longitude:?}"));
+        }
+        let latitude = latitude / (180.0 / PI);
+        let longitude = longitude / (180.0 / PI);
+        Ok(Coordinates {
+            latitude,
+            longitude,
+        })
+    }
+
+    /// Unit vector from the globe's center through this place in its local space, the inverse of
+    /// `From<Vec3>`. Scaled by `EarthConfig::radius` it lies on the globe's surface.
+    pub fn direction(&self) -> Vec3 {
+        let y = self.latitude.sin();
+        let r = self.latitude.cos();
+        let x = self.longitude.sin() * r;
+        let z = self.longitude.cos() * r;
+        Vec3::new(x, y, z).normalize()
+    }
+}
+
+/// Nearest point where `ray` enters a sphere of `radius` around `center`.
+pub fn ray_sphere_intersection(ray: Ray3d, center: Vec3, radius: f32) -> Option<Vec3> {
+    let direction = *ray.direction;
+    let origin = ray.origin - center;
+    let b = origin.dot(direction);
+    let c = origin.length_squared() - radius * radius;
+    let discriminant = b * b - c;
+    if discriminant < 0. {
+        return None;
+    }
+
+    let t = -b - discriminant.sqrt();
+    (t >= 0.).then(|| ray.origin + direction * t)
+}
+
+/// Distance along the sphere surface between two points lying on it.
+pub fn great_circle_distance(a: Vec3, b: Vec3, radius: f32) -> f32 {
+    // Going through the chord keeps precision for nearby points, where acos of the dot product
+    // would collapse to zero in f32
+    let chord = a.distance(b);
+    2. * radius * (chord / (2. * radius)).clamp(-1., 1.).asin()
+}
+
+/// Great circle distance between two places on a sphere of `radius`, by the haversine formula.
+pub fn haversine_distance(a: Coordinates, b: Coordinates, radius: f32) -> f32 {
+    let half_latitude = ((b.latitude - a.latitude) / 2.).sin();
+    let half_longitude = ((b.longitude - a.longitude) / 2.).sin();
+    let h = half_latitude * half_latitude
+        + a.latitude.cos() * b.latitude.cos() * half_longitude * half_longitude;
+    2. * radius * h.sqrt().clamp(0., 1.).asin()
+}
+
+/// Compass heading in radians, clockwise from north, to set off on from `from` to follow the
+/// great circle to `to`.
+pub fn initial_bearing(from: Coordinates, to: Coordinates) -> f32 {
+    let delta = to.longitude - from.longitude;
+    let y = delta.sin() * to.latitude.cos();
+    let x = from.latitude.cos() * to.latitude.sin()
+        - from.latitude.sin() * to.latitude.cos() * delta.cos();
+    y.atan2(x).rem_euclid(TAU)
+}
+
+/// Direction at `t` along the shorter great circle from direction `a` to `b`.
+pub fn great_circle_point(a: Vec3, b: Vec3, t: f32) -> Vec3 {
+    let (a, b) = (a.normalize(), b.normalize());
+    let angle = a.angle_between(b);
+    if angle < 1e-6 {
+        return a;
+    }
+    // Antipodal points have no unique great circle, any perpendicular one will do
+    if (PI - angle) < 1e-4 {
+        let axis = a.any_orthonormal_vector();
+        return Quat::from_axis_angle(axis, angle * t) * a;
+    }
+    (a * ((1. - t) * angle).sin() + b * (t * angle).sin()) / angle.sin()
+}
+
+/// Whether `point` lies inside the closed `ring` by the even-odd rule, both given as (longitude,
+/// latitude) pairs. Rings crossing the antimeridian have to be split first.
+pub fn point_in_polygon(point: [f32; 2], ring: &[[f32; 2]]) -> bool {
+    let [x, y] = point;
+    let Some(&last) = ring.last() else {
+        return false;
+    };
+
+    let mut inside = false;
+    let mut previous = last;
+    for &[xi, yi] in ring {
+        let [xj, yj] = previous;
+        // Count the edges a ray towards +x crosses
+        if (yi > y) != (yj > y) && x < (xj - xi) * (y - yi) / (yj - yi) + xi {
+            inside = !inside;
+        }
+        previous = [xi, yi];
+    }
+    inside
+}
+
+/// Ground distance covered by one logical pixel at the center of the viewport, in world units,
+/// for a globe centered at `globe`.
+///
+/// Rays through the center pixel and its neighbour are intersected with the globe, so both the
+/// perspective projection and the curvature of the surface are taken into account.
+pub fn ground_distance_per_pixel(
+    camera: &Camera,
+    transform: &GlobalTransform,
+    globe: Vec3,
+    radius: f32,
+) -> Option<f32> {
+    let center = camera.logical_viewport_size()? / 2.;
+    let neighbour = center + bevy::math::Vec2::Y;
+
+    let hit = |pixel| {
+        let ray = camera.viewport_to_world(transform, pixel).ok()?;
+        ray_sphere_intersection(ray, globe, radius)
+    };
+    Some(great_circle_distance(hit(center)?, hit(neighbour)?, radius))
+}
+
+/// Field of view after zooming by `steps` wheel notches, positive zooming in.
+///
+/// The visible ground extent scales with `tan(fov / 2)`, so each step scales that by the same
+/// factor and feels the same whether the camera is close to the surface or far away.
+pub fn zoom_fov(fov: f32, steps: f32) -> f32 {
+    let extent = (fov / 2.).tan() * (-steps * ZOOM_PER_STEP).exp();
+    (2. * extent.atan()).clamp(MIN_FOV, MAX_FOV)
+}
+
+/// Orientation of the globe that brings the local direction `center` onto `view` (pointing from the
+/// globe towards the camera) with local north aligned to `view_up`.
+///
+/// At the poles north is undefined, so the heading of the screen-up direction under `current` is
+/// kept instead, which avoids the sudden spin a degenerate up-vector would cause.
+pub fn rotation_to_center(center: Vec3, current: Quat, view: Vec3, view_up: Vec3) -> Quat {
+    let up = center.normalize();
+    let north = (Vec3::Y - up * up.y).try_normalize().unwrap_or_else(|| {
+        let screen_up = current.inverse() * view_up;
+        (screen_up - up * screen_up.dot(up))
+            .try_normalize()
+            .unwrap_or_else(|| up.any_orthonormal_vector())
+    });
+    let east = north.cross(up);
+
+    let view = view.normalize();
+    let view_north = (view_up - view * view_up.dot(view)).normalize();
+    let view_east = view_north.cross(view);
+
+    let local = Mat3::from_cols(east, north, up);
+    let world = Mat3::from_cols(view_east, view_north, view);
+    Quat::from_mat3(&(world * local.transpose())).normalize()
+}
+
+/// Depth of the skirts below the globe's surface, in radii per unit of patch size, so a root
+/// chunk's skirts reach 1 % of the radius down and each split halves them.
+const SKIRT_DEPTH: f32 = 0.01;
+
+/// Triangle indices of a `resolution` × `resolution` vertex grid, and of its skirts with `skirt`.
+///
+/// Skirts are strips hanging down from the four edges of a patch, built from `4 * resolution`
+/// extra vertices after the grid, one row per edge in the order bottom, top, left and right. They
+/// fill the cracks between neighboring chunks at different depths, whose edges don't line up.
+///
+/// The topology only depends on the resolution, so it is built once and cloned into each mesh
+/// instead of being rebuilt by every generation task. Bevy gives every mesh its own index buffer,
+/// so the indices aren't shared on the GPU: the memory saved comes from grids of up to 65536
+/// vertices taking 16 bit indices, which halves the index memory of every chunk.
+pub fn grid_indices(resolution: u32, skirt: bool) -> mesh::Indices {
+    static CACHE: OnceLock<Mutex<HashMap<(u32, bool), mesh::Indices>>> = OnceLock::new();
+
+    let mut cache = CACHE
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    cache
+        .entry((resolution, skirt))
+        .or_insert_with(|| {
+            let cells = resolution.saturating_sub(1);
+            let grid = (0..cells).flat_map(|y| {
+                (0..cells).flat_map(move |x| {
+                    let i = x + y * resolution;
+                    [
+                        // First triangle
+                        i,
+                        i + resolution,
+                        i + resolution + 1,
+                        // Second triangle
+                        i,
+                        i + resolution + 1,
+                        i + 1,
+                    ]
+                })
+            });
+
+            // Each skirt continues the grid past its edge with the same winding, folded down
+            let grid_vertex = |x: u32, y: u32| x + y * resolution;
+            let skirt_vertex = |edge: u32, i: u32| resolution * resolution + edge * resolution + i;
+            let last = cells;
+            let skirts = (0..cells).filter(|_| skirt).flat_map(move |i| {
+                [
+                    // Bottom, a row below the first one
+                    skirt_vertex(0, i),
+                    grid_vertex(i, 0),
+                    grid_vertex(i + 1, 0),
+                    skirt_vertex(0, i),
+                    grid_vertex(i + 1, 0),
+                    skirt_vertex(0, i + 1),
+                    // Top, a row above the last one
+                    grid_vertex(i, last),
+                    skirt_vertex(1, i),
+                    skirt_vertex(1, i + 1),
+                    grid_vertex(i, last),
+                    skirt_vertex(1, i + 1),
+                    grid_vertex(i + 1, last),
+                    // Left, a column before the first one
+                    skirt_vertex(2, i),
+                    skirt_vertex(2, i + 1),
+                    grid_vertex(0, i + 1),
+                    skirt_vertex(2, i),
+                    grid_vertex(0, i + 1),
+                    grid_vertex(0, i),
+                    // Right, a column after the last one
+                    grid_vertex(last, i),
+                    grid_vertex(last, i + 1),
+                    skirt_vertex(3, i + 1),
+                    grid_vertex(last, i),
+                    skirt_vertex(3, i + 1),
+                    skirt_vertex(3, i),
+                ]
+            });
+
+            let indices = grid.chain(skirts);
+            let vertices = resolution * resolution + if skirt { 4 * resolution } else { 0 };
+            if vertices <= u32::from(u16::MAX) + 1 {
+                mesh::Indices::U16(indices.map(|index| index as u16).collect())
+            } else {
+                mesh::Indices::U32(indices.collect())
             }
+        })
+        .clone()
+}
+
+/// Texture coordinates given to the vertices of a `CubeSphereBuilder` mesh.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UvMode {
+    /// Longitude and latitude mapped onto an equirectangular texture, like the Earth's, with the
+    /// seam at the antimeridian patched up
+    #[default]
+    Equirectangular,
+    /// Each patch spans the whole texture, for procedural or per-patch textures
+    Patch,
+}
+
+/// Builds the mesh of a square patch of one cube face, projected onto a sphere.
+///
+/// The cube faces span -1 to 1 along both of their axes. `offset` shifts the patch's corner
+/// from the face's center the way `ChunkKey::extent` does, so the defaults cover a whole face.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CubeSphereBuilder {
+    pub radius: f32,
+    /// Vertices along each edge of the patch, at least 2
+    pub resolution: u32,
+    /// Normal of the cube face, one of `chunk::FACES`
+    pub face: Vec3,
+    pub offset: (f32, f32),
+    /// Edge length of the patch, 2 for a whole face
+    pub size: f32,
+    pub uv_mode: UvMode,
+    /// Whether skirts hang down from the edges, see `grid_indices`
+    pub skirt: bool,
+}
+
+impl CubeSphereBuilder {
+    /// A whole face of a unit sphere with 16 vertices along its edges.
+    pub fn new(face: Vec3) -> Self {
+        Self {
+            radius: 1.,
+            resolution: 16,
+            face,
+            offset: (1., 1.),
+            size: 2.,
+            uv_mode: UvMode::default(),
+            skirt: false,
+        }
+    }
+
+    pub fn radius(mut self, radius: f32) -> Self {
+        self.radius = radius;
+        self
+    }
+
+    pub fn resolution(mut self, resolution: u32) -> Self {
+        self.resolution = resolution.max(2);
+        self
+    }
+
+    /// Restricts the mesh to the part of the face `size` wide at `offset`.
+    pub fn patch(mut self, offset: (f32, f32), size: f32) -> Self {
+        self.offset = offset;
+        self.size = size;
+        self
+    }
+
+    pub fn uv_mode(mut self, uv_mode: UvMode) -> Self {
+        self.uv_mode = uv_mode;
+        self
+    }
+
+    /// Adds skirts along the edges, which the vertex shader of the `EarthMaterial` keeps below
+    /// the displaced surface.
+    pub fn skirt(mut self, skirt: bool) -> Self {
+        self.skirt = skirt;
+        self
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        let skirt = if self.skirt { 4 * self.resolution } else { 0 };
+        (self.resolution * self.resolution + skirt) as usize
+    }
+
+    pub fn index_count(&self) -> usize {
+        let cells = self.resolution.saturating_sub(1);
+        let skirt = if self.skirt { 4 * cells * 6 } else { 0 };
+        (cells * cells * 6 + skirt) as usize
+    }
+
+    /// Horizontal and vertical axes of the face, along which `x` and `y` of the grid run.
+    fn axes(&self) -> (Vec3, Vec3) {
+        let axis_a = Vec3::new(self.face.y, self.face.z, self.face.x);
+        (axis_a, axis_a.cross(self.face))
+    }
+
+    /// Vertex `x`, `y` of the grid on the unit cube.
+    fn cube_point(&self, (axis_a, axis_b): (Vec3, Vec3), x: u32, y: u32) -> Vec3 {
+        let (x_offset, y_offset) = self.offset;
+        let percent = Vec2::new(x as f32, y as f32) / (self.resolution - 1) as f32;
+        self.face
+            + (percent.x * self.size - x_offset) * axis_a
+            + (percent.y * self.size - y_offset) * axis_b
+    }
+
+    /// Position, normal, uv and tangent of vertex `x`, `y`.
+    ///
+    /// The tangent follows the direction `u` grows in on the sphere, so it doesn't need
+    /// `Mesh::generate_tangents`: east for equirectangular uvs, and the face's horizontal axis
+    /// laid onto the surface for patch uvs.
+    fn vertex(
+        &self,
+        axes: (Vec3, Vec3),
+        x: u32,
+        y: u32,
+        first_longitude: f32,
+    ) -> (Vec3, Vec3, [f32; 2], [f32; 4]) {
+        let point = self.cube_point(axes, x, y).normalize();
+        let normal = -point;
+
+        if self.uv_mode == UvMode::Patch {
+            let (axis_a, axis_b) = axes;
+            let percent = Vec2::new(x as f32, y as f32) / (self.resolution - 1) as f32;
+            let tangent = (axis_a - point * point.dot(axis_a)).normalize();
+            // `v` grows along the vertical axis, the sign makes the bitangent follow it
+            let sign = normal.cross(tangent).dot(axis_b).signum();
+            return (
+                point * self.radius,
+                normal,
+                [percent.x, percent.y],
+                tangent.extend(sign).to_array(),
+            );
+        }
+
+        // Convert our point_coords into `Coordinates`
+        let point_coords: Coordinates = point.into();
+        let (mut u, v) = point_coords.convert_to_uv_mercator();
+        let lon = point_coords.longitude;
+        let lat = point_coords.latitude;
+
+        // In the middle latitudes, if we start on a
+        // negative longitude but then wind up crossing to a
+        // positive longitude, set u to 0.0 to prevent a seam
+        if first_longitude < 0.0 && lon > 0.0 && lat < 89.0 && lat > -89.0 {
+            u = 0.0;
+        }
+
+        // If we are below -40 degrees latitude and the tile
+        // starts at 180 degrees, set u to 0.0 to prevent a seam
+        if x == 0 && lon == 180.0 && lat < -40.0 {
+            u = 0.0;
+        }
+
+        // East, where the longitude and so `u` grows. `v` grows southwards, which the inward
+        // normal crossed with east points to, hence the positive sign
+        let east = Vec3::new(point.z, 0., -point.x)
+            .try_normalize()
+            .unwrap_or(Vec3::X);
+        (
+            point * self.radius,
+            normal,
+            [u, v],
+            east.extend(1.).to_array(),
+        )
+    }
+
+    /// Builds the mesh, its rows split among the threads of the `AsyncComputeTaskPool` it is built
+    /// on, so it doesn't take the threads the frame's systems run on.
+    pub fn build(&self) -> Mesh {
+        let axes = self.axes();
+        let resolution = self.resolution;
+        let first_longitude = Coordinates::from(self.cube_point(axes, 0, 0)).longitude;
 
-            // In the middle latitudes, if we start on a
-            // negative longitude but then wind up crossing to a
-            // positive longitude, set u to 0.0 to prevent a seam
-            if first_longitude < 0.0 && lon > 0.0 && lat < 89.0 && lat > -89.0 {
-                u = 0.0;
+        let vertex_count = (resolution * resolution) as usize;
+        let mut positions = vec![Vec3::ZERO; vertex_count];
+        let mut normals = vec![Vec3::ZERO; vertex_count];
+        let mut uvs = vec![[0.; 2]; vertex_count];
+        let mut tangents = vec![[0.; 4]; vertex_count];
+
+        let pool = AsyncComputeTaskPool::get_or_init(TaskPool::default);
+        let rows_per_task = (resolution as usize).div_ceil(pool.thread_num().max(1));
+        let band = rows_per_task * resolution as usize;
+        pool.scope(|scope| {
+            let bands = positions
+                .chunks_mut(band)
+                .zip(normals.chunks_mut(band))
+                .zip(uvs.chunks_mut(band))
+                .zip(tangents.chunks_mut(band))
+                .enumerate();
+            for (index, (((positions, normals), uvs), tangents)) in bands {
+                let builder = *self;
+                scope.spawn(async move {
+                    let first = index * band;
+                    for (offset, vertex) in positions
+                        .iter_mut()
+                        .zip(normals.iter_mut())
+                        .zip(uvs.iter_mut())
+                        .zip(tangents.iter_mut())
+                        .enumerate()
+                    {
+                        let (((position, normal), uv), tangent) = vertex;
+                        let index = (first + offset) as u32;
+                        (*position, *normal, *uv, *tangent) = builder.vertex(
+                            axes,
+                            index % resolution,
+                            index / resolution,
+                            first_longitude,
+                        );
+                    }
+                });
             }
+        });
 
-            // If we are below -40 degrees latitude and the tile
-            // starts at 180 degrees, set u to 0.0 to prevent a seam
-            if x == 0 && lon == 180.0 && lat < -40.0 {
-                u = 0.0;
+        if self.skirt {
+            // Copies of the edge vertices, lowered towards the center
+            let last = resolution - 1;
+            let edges = [
+                (0..resolution).map(|i| (i, 0)).collect::<Vec<_>>(),
+                (0..resolution).map(|i| (i, last)).collect(),
+                (0..resolution).map(|i| (0, i)).collect(),
+                (0..resolution).map(|i| (last, i)).collect(),
+            ];
+            let lowered = 1. - SKIRT_DEPTH * self.size;
+            for (x, y) in edges.into_iter().flatten() {
+                let index = (x + y * resolution) as usize;
+                positions.push(positions[index] * lowered);
+                normals.push(normals[index]);
+                uvs.push(uvs[index]);
+                tangents.push(tangents[index]);
             }
+        }
+
+        let indicies = grid_indices(resolution, self.skirt);
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::all());
+        mesh.insert_indices(indicies);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+        // Insert the UV attribute along with our uv vec
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_TANGENT, tangents);
+        mesh
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        camera::{CameraProjection, PerspectiveProjection, RenderTargetInfo},
+        math::{Dir3, UVec2},
+        transform::components::Transform,
+    };
+
+    use super::*;
+
+    #[test]
+    fn ray_through_center_hits_at_radius() {
+        let ray = Ray3d::new(Vec3::new(0., 0., 10.), Dir3::NEG_Z);
+        let hit = ray_sphere_intersection(ray, Vec3::ZERO, 2.).unwrap();
+        assert!(hit.distance(Vec3::new(0., 0., 2.)) < 1e-5);
+
+        let center = Vec3::new(3., -1., 0.);
+        let ray = Ray3d::new(center + Vec3::new(0., 0., 10.), Dir3::NEG_Z);
+        let hit = ray_sphere_intersection(ray, center, 2.).unwrap();
+        assert!((hit.distance(center) - 2.).abs() < 1e-5);
+    }
+
+    #[test]
+    fn tangent_ray_touches_and_offset_ray_misses() {
+        let tangent = Ray3d::new(Vec3::new(2., 0., 10.), Dir3::NEG_Z);
+        let hit = ray_sphere_intersection(tangent, Vec3::ZERO, 2.).unwrap();
+        assert!(hit.distance(Vec3::new(2., 0., 0.)) < 1e-5);
 
-            normals.push(-point_on_unit_cube.normalize());
+        let miss = Ray3d::new(Vec3::new(2.01, 0., 10.), Dir3::NEG_Z);
+        assert_eq!(ray_sphere_intersection(miss, Vec3::ZERO, 2.), None);
 
-            uvs.push([u, v]);
+        // The sphere is behind the ray
+        let away = Ray3d::new(Vec3::new(0., 0., 10.), Dir3::Z);
+        assert_eq!(ray_sphere_intersection(away, Vec3::ZERO, 2.), None);
+    }
+
+    #[test]
+    fn nadir_distance_per_pixel() {
+        let (radius, height, fov, pixels) = (1000., 100., 1., 100);
+        let projection = PerspectiveProjection {
+            fov,
+            aspect_ratio: 1.,
+            near: 0.1,
+            far: 10_000.,
+        };
+        let mut camera = Camera::default();
+        camera.computed.clip_from_view = projection.get_clip_from_view();
+        camera.computed.target_info = Some(RenderTargetInfo {
+            physical_size: UVec2::splat(pixels),
+            scale_factor: 1.,
+        });
+        let transform = GlobalTransform::from(
+            Transform::from_xyz(0., 0., radius + height).looking_at(Vec3::ZERO, Vec3::Y),
+        );
+
+        let distance = ground_distance_per_pixel(&camera, &transform, Vec3::ZERO, radius).unwrap();
+        let expected = 2. * height * (fov / 2.).tan() / pixels as f32;
+        assert!(
+            (distance - expected).abs() < expected * 0.01,
+            "{distance} instead of {expected}"
+        );
+    }
 
-            if x != resolution - 1 && y != resolution - 1 {
-                // First triangle
-                indicies.push(i);
-                indicies.push(i + resolution);
-                indicies.push(i + resolution + 1);
+    #[test]
+    fn cube_sphere_counts() {
+        for resolution in [2, 3, 16, 65] {
+            for (offset, size) in [((1., 1.), 2.), ((0., 1.), 1.), ((-0.5, 0.25), 0.25)] {
+                for skirt in [false, true] {
+                    let builder = CubeSphereBuilder::new(Vec3::Y)
+                        .resolution(resolution)
+                        .patch(offset, size)
+                        .skirt(skirt);
+                    let mesh = builder.build();
+                    let cells = (resolution - 1) as usize;
+                    let (skirt_vertices, skirt_indices) = if skirt {
+                        (4 * resolution as usize, 4 * cells * 6)
+                    } else {
+                        (0, 0)
+                    };
+                    assert_eq!(
+                        builder.vertex_count(),
+                        (resolution * resolution) as usize + skirt_vertices
+                    );
+                    assert_eq!(builder.index_count(), cells * cells * 6 + skirt_indices);
+                    assert_eq!(mesh.count_vertices(), builder.vertex_count());
+                    assert_eq!(mesh.indices().unwrap().len(), builder.index_count());
+                    assert!(
+                        mesh.indices()
+                            .unwrap()
+                            .iter()
+                            .all(|index| index < mesh.count_vertices())
+                    );
+                }
+            }
+        }
+    }
 
-                // Second triangle
-                indicies.push(i);
-                indicies.push(i + resolution + 1);
-                indicies.push(i + 1);
+    #[test]
+    fn cube_sphere_vertices_on_radius() {
+        for face in [
+            Vec3::X,
+            Vec3::NEG_X,
+            Vec3::Y,
+            Vec3::NEG_Y,
+            Vec3::Z,
+            Vec3::NEG_Z,
+        ] {
+            for uv_mode in [UvMode::Equirectangular, UvMode::Patch] {
+                let mesh = CubeSphereBuilder::new(face)
+                    .radius(6.5)
+                    .resolution(9)
+                    .uv_mode(uv_mode)
+                    .build();
+                let positions = mesh
+                    .attribute(Mesh::ATTRIBUTE_POSITION)
+                    .and_then(|positions| positions.as_float3())
+                    .unwrap();
+                for position in positions {
+                    let length = Vec3::from_array(*position).length();
+                    assert!((length - 6.5).abs() < 1e-4, "{position:?} is {length} away");
+                }
             }
         }
     }
-    let indicies = mesh::Indices::U32(indicies);
-    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::all());
-    mesh.insert_indices(indicies);
-    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, verticies);
-    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
-    // Insert the UV attribute along with our uv vec
-    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
-    mesh.generate_tangents().unwrap();
-    mesh
+
+    #[test]
+    fn skirts_hang_below_the_edges() {
+        let builder = CubeSphereBuilder::new(Vec3::Y)
+            .radius(2.)
+            .resolution(5)
+            .patch((0., 1.), 1.)
+            .skirt(true);
+        let mesh = builder.build();
+        let positions = mesh
+            .attribute(Mesh::ATTRIBUTE_POSITION)
+            .and_then(|positions| positions.as_float3())
+            .unwrap();
+        let (grid, skirts) = positions.split_at(25);
+        // Bottom, top, left and right edge
+        let edge_vertices = (0..5)
+            .chain(20..25)
+            .chain((0..5).map(|i| i * 5))
+            .chain((0..5).map(|i| i * 5 + 4));
+        for (skirt, edge) in skirts.iter().zip(edge_vertices) {
+            let (skirt, edge) = (Vec3::from_array(*skirt), Vec3::from_array(grid[edge]));
+            assert!(skirt.normalize().abs_diff_eq(edge.normalize(), 1e-6));
+            assert!((skirt.length() - 2. * (1. - SKIRT_DEPTH)).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn grid_indices_switch_to_u32_above_256() {
+        assert!(matches!(grid_indices(256, false), mesh::Indices::U16(_)));
+        assert!(matches!(grid_indices(257, false), mesh::Indices::U32(_)));
+        // The skirt vertices come on top of the grid
+        assert!(matches!(grid_indices(254, true), mesh::Indices::U16(_)));
+        assert!(matches!(grid_indices(255, true), mesh::Indices::U32(_)));
+
+        // The last index of the largest 16 bit grid still fits
+        let mesh::Indices::U16(indices) = grid_indices(256, false) else {
+            unreachable!();
+        };
+        assert_eq!(indices.iter().max(), Some(&u16::MAX));
+    }
 }
@@ -0,0 +1,163 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    ecs::{
+        change_detection::DetectChanges,
+        component::Component,
+        entity::Entity,
+        message::MessageReader,
+        query::With,
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Query, Res, ResMut},
+    },
+    state::{condition::in_state, state::OnExit},
+};
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+
+use crate::{
+    EARTH_RADIUS_KM,
+    flight::spawn_great_circle,
+    gui::{format_coordinates, format_distance},
+    marker::{MarkerLabel, spawn_marker},
+    math::{Coordinates, haversine_distance, initial_bearing},
+    observer::EarthClicked,
+    state::ToolMode,
+};
+
+/// Points clicked with the ruler of `ToolMode::Measuring`, a third click starts over.
+#[derive(Resource, Debug, Default)]
+pub struct MeasureState {
+    pub points: Vec<Coordinates>,
+}
+
+impl MeasureState {
+    /// Both ends of the measured arc, once the second point was clicked.
+    pub fn ends(&self) -> Option<(Coordinates, Coordinates)> {
+        match self.points[..] {
+            [from, to] => Some((from, to)),
+            _ => None,
+        }
+    }
+
+    /// Great circle distance between the two points in kilometers.
+    pub fn distance_km(&self) -> Option<f32> {
+        self.ends()
+            .map(|(from, to)| haversine_distance(from, to, EARTH_RADIUS_KM))
+    }
+
+    /// Heading to set off on from the first point towards the second, in degrees from north.
+    pub fn bearing(&self) -> Option<f32> {
+        self.ends()
+            .map(|(from, to)| initial_bearing(from, to).to_degrees())
+    }
+}
+
+/// Markers and the arc drawn for the `MeasureState`.
+#[derive(Component)]
+struct MeasureGizmo;
+
+pub struct MeasurePlugin;
+
+impl Plugin for MeasurePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MeasureState>()
+            .add_systems(OnExit(ToolMode::Measuring), clear_measurement)
+            .add_systems(
+                Update,
+                (add_measure_point, draw_measurement)
+                    .chain()
+                    .run_if(in_state(ToolMode::Measuring)),
+            )
+            .add_systems(
+                EguiPrimaryContextPass,
+                display_ruler.run_if(in_state(ToolMode::Measuring)),
+            );
+    }
+}
+
+fn add_measure_point(mut state: ResMut<MeasureState>, mut clicks: MessageReader<EarthClicked>) {
+    let Some(click) = clicks.read().last() else {
+        return;
+    };
+    let Ok(coordinates) = Coordinates::from_degrees(click.lat, click.lon) else {
+        return;
+    };
+    if state.points.len() >= 2 {
+        state.points.clear();
+    }
+    state.points.push(coordinates);
+}
+
+/// Replaces the markers and the arc whenever the points change.
+fn draw_measurement(
+    mut commands: Commands,
+    state: Res<MeasureState>,
+    gizmos: Query<Entity, With<MeasureGizmo>>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+    for entity in &gizmos {
+        commands.entity(entity).despawn();
+    }
+
+    for (point, label) in state.points.iter().zip(["A", "B"]) {
+        let (latitude, longitude) = point.as_degrees();
+        if let Ok(marker) = spawn_marker(&mut commands, latitude, longitude) {
+            commands
+                .entity(marker)
+                .insert((MarkerLabel(label.to_string()), MeasureGizmo));
+        }
+    }
+    if let Some((from, to)) = state.ends() {
+        let line = spawn_great_circle(&mut commands, from, to);
+        commands.entity(line).insert(MeasureGizmo);
+    }
+}
+
+fn clear_measurement(
+    mut commands: Commands,
+    mut state: ResMut<MeasureState>,
+    gizmos: Query<Entity, With<MeasureGizmo>>,
+) {
+    state.points.clear();
+    for entity in &gizmos {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn display_ruler(
+    mut contexts: EguiContexts,
+    mut state: ResMut<MeasureState>,
+) -> bevy::prelude::Result {
+    let ctx = contexts.ctx_mut()?;
+
+    egui::Window::new("Ruler")
+        .resizable(false)
+        .anchor(egui::Align2::LEFT_TOP, [10., 40.])
+        .show(ctx, |ui| {
+            for (point, label) in state.points.iter().zip(["A", "B"]) {
+                ui.label(format!("{label}: {}", format_coordinates(*point)));
+            }
+            match (state.distance_km(), state.bearing()) {
+                (Some(distance_km), Some(bearing)) => {
+                    ui.separator();
+                    ui.heading(format_distance(distance_km));
+                    ui.label(format!("Initial bearing: {bearing:.1}°"));
+                }
+                _ => {
+                    ui.label(if state.points.is_empty() {
+                        "Click the first point on the globe"
+                    } else {
+                        "Click the second point on the globe"
+                    });
+                }
+            }
+            ui.separator();
+            if ui.button("Clear").clicked() {
+                state.points.clear();
+            }
+        });
+
+    Ok(())
+}
@@ -0,0 +1,168 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    app::{App, Plugin, Startup},
+    asset::RenderAssetUsages,
+    ecs::system::Res,
+    log::warn,
+    mesh::{Mesh, MeshVertexAttribute, PrimitiveTopology, VertexAttributeValues},
+};
+
+use crate::{EarthConfig, chunk::ChunkKey, math::grid_indices};
+
+/// Bumped whenever `CubeSphereBuilder` builds different meshes, which discards the cache.
+const CACHE_VERSION: u32 = 4;
+
+const MAGIC: [u8; 4] = *b"BEMC";
+
+/// Magic, version and resolution.
+const HEADER_LEN: usize = 12;
+
+/// Floats per vertex: position, normal, uv and tangent.
+const FLOATS_PER_VERTEX: usize = 3 + 3 + 2 + 4;
+
+/// Loads the 24 coarsest chunk meshes from `EarthConfig::mesh_cache` instead of generating them
+/// again, as long as they were built with the same radius, resolution and `CACHE_VERSION`.
+///
+/// The finer chunks are only generated as the camera comes close, and caching every one of them
+/// down to `EarthConfig::max_depth` would let the cache grow without bound.
+pub struct MeshCachePlugin;
+
+impl Plugin for MeshCachePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, prune_stale_versions);
+    }
+}
+
+fn version_dir(root: &Path, version: u32) -> PathBuf {
+    root.join(format!("v{version}"))
+}
+
+/// Removes the meshes of other cache versions, which will never be read again.
+fn prune_stale_versions(config: Res<EarthConfig>) {
+    let Some(root) = &config.mesh_cache else {
+        return;
+    };
+    let Ok(entries) = fs::read_dir(root) else {
+        return;
+    };
+    let current = version_dir(root, CACHE_VERSION);
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path != current
+            && let Err(err) = fs::remove_dir_all(&path)
+        {
+            warn!(
+                "Failed to remove stale mesh cache {}: {err}",
+                path.display()
+            );
+        }
+    }
+}
+
+/// File of the mesh of `key` in the cache at `root`, keyed by everything its vertices depend on,
+/// or `None` for the finer chunks, which aren't cached.
+pub fn cache_path(root: &Path, key: ChunkKey, radius: f32, resolution: u32) -> Option<PathBuf> {
+    (key.depth == 0).then(|| {
+        version_dir(root, CACHE_VERSION)
+            .join(format!("{:08x}-{resolution}", radius.to_bits()))
+            .join(format!("{}-{}.bin", key.face, key.index))
+    })
+}
+
+/// Reads a mesh written by `store`, or `None` if it isn't cached or the file is damaged.
+pub fn load(path: &Path) -> Option<Mesh> {
+    let bytes = fs::read(path).ok()?;
+    if bytes.len() < HEADER_LEN || bytes[..4] != MAGIC {
+        return None;
+    }
+    let word = |index: usize| {
+        let start = 4 * index;
+        u32::from_le_bytes(bytes[start..start + 4].try_into().unwrap())
+    };
+    if word(1) != CACHE_VERSION {
+        return None;
+    }
+    let resolution = word(2);
+    let vertices = resolution
+        .checked_mul(resolution)?
+        .checked_add(resolution.checked_mul(4)?)? as usize;
+    if bytes.len() != HEADER_LEN + 4 * vertices * FLOATS_PER_VERTEX {
+        return None;
+    }
+
+    let mut words = bytes[HEADER_LEN..]
+        .chunks_exact(4)
+        .map(|word| <[u8; 4]>::try_from(word).unwrap());
+    let mut floats =
+        |count: usize| -> Vec<f32> { words.by_ref().take(count).map(f32::from_le_bytes).collect() };
+    let positions: Vec<[f32; 3]> = floats(vertices * 3)
+        .chunks_exact(3)
+        .map(|v| [v[0], v[1], v[2]])
+        .collect();
+    let normals: Vec<[f32; 3]> = floats(vertices * 3)
+        .chunks_exact(3)
+        .map(|v| [v[0], v[1], v[2]])
+        .collect();
+    let uvs: Vec<[f32; 2]> = floats(vertices * 2)
+        .chunks_exact(2)
+        .map(|v| [v[0], v[1]])
+        .collect();
+    let tangents: Vec<[f32; 4]> = floats(vertices * 4)
+        .chunks_exact(4)
+        .map(|v| [v[0], v[1], v[2], v[3]])
+        .collect();
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::all());
+    mesh.insert_indices(grid_indices(resolution, true));
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_TANGENT, tangents);
+    Some(mesh)
+}
+
+/// Writes the positions, normals, uvs and tangents of `mesh` as little endian, through a
+/// temporary file so an interrupted write never leaves a damaged mesh behind.
+///
+/// The mesh has to be a square grid with skirts, its indices are the `grid_indices` of its
+/// resolution and aren't stored.
+pub fn store(path: &Path, mesh: &Mesh) -> Result<(), String> {
+    let attribute = |id: MeshVertexAttribute| match mesh.attribute(id) {
+        Some(VertexAttributeValues::Float32x2(values)) => Ok(values.as_flattened()),
+        Some(VertexAttributeValues::Float32x3(values)) => Ok(values.as_flattened()),
+        Some(VertexAttributeValues::Float32x4(values)) => Ok(values.as_flattened()),
+        _ => Err(format!("Mesh has no {} to cache", id.name)),
+    };
+    let positions = attribute(Mesh::ATTRIBUTE_POSITION)?;
+    let normals = attribute(Mesh::ATTRIBUTE_NORMAL)?;
+    let uvs = attribute(Mesh::ATTRIBUTE_UV_0)?;
+    let tangents = attribute(Mesh::ATTRIBUTE_TANGENT)?;
+
+    // A grid of `resolution` squared vertices, and `4 * resolution` more for the skirts
+    let vertices = mesh.count_vertices();
+    let resolution = ((vertices + 4) as f64).sqrt() as u32 - 2;
+    if (resolution * resolution + 4 * resolution) as usize != vertices {
+        return Err(format!(
+            "Mesh of {vertices} vertices isn't a square grid with skirts"
+        ));
+    }
+
+    let mut bytes = Vec::with_capacity(HEADER_LEN + 4 * vertices * FLOATS_PER_VERTEX);
+    bytes.extend(MAGIC);
+    for word in [CACHE_VERSION, resolution] {
+        bytes.extend(word.to_le_bytes());
+    }
+    for value in [positions, normals, uvs, tangents].concat() {
+        bytes.extend(value.to_le_bytes());
+    }
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+    }
+    let partial = path.with_extension("part");
+    fs::write(&partial, bytes).map_err(|err| err.to_string())?;
+    fs::rename(&partial, path).map_err(|err| err.to_string())
+}
@@ -0,0 +1,186 @@
+use std::collections::HashSet;
+
+use bevy::{
+    app::{App, Plugin, Startup, Update},
+    asset::{Assets, Handle, RenderAssetUsages},
+    camera::visibility::Visibility,
+    color::Color,
+    ecs::{
+        change_detection::{DetectChanges, DetectChangesMut},
+        component::Component,
+        entity::Entity,
+        hierarchy::ChildOf,
+        lifecycle::RemovedComponents,
+        query::{With, Without},
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Query, Res, ResMut},
+        world::Ref,
+    },
+    mesh::{Indices, Mesh, Mesh3d, PrimitiveTopology},
+    pbr::{MeshMaterial3d, StandardMaterial},
+    picking::Pickable,
+    state::condition::in_state,
+};
+
+use crate::{
+    component::{Chunk, Unrevealed},
+    depth::draped_material,
+    state::GameState,
+};
+
+/// Draws the edges or vertices of the chunk meshes, to inspect their density at runtime.
+///
+/// The lines and points follow the generated meshes, so they don't match the surface while it
+/// is displaced by the height map.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MeshView {
+    #[default]
+    Surface,
+    /// Triangle edges on top of the surface
+    Wireframe,
+    /// Only the vertices, with the surface hidden
+    Points,
+}
+
+impl MeshView {
+    pub const ALL: [MeshView; 3] = [MeshView::Surface, MeshView::Wireframe, MeshView::Points];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            MeshView::Surface => "Surface",
+            MeshView::Wireframe => "Wireframe",
+            MeshView::Points => "Points",
+        }
+    }
+
+    /// Visibility of the chunk meshes themselves, which the point view hides.
+    pub fn chunk_visibility(&self) -> Visibility {
+        if *self == MeshView::Points {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        }
+    }
+}
+
+/// Lines or points drawn for `chunk`, spawned next to it below the Earth.
+#[derive(Component)]
+struct ChunkInspection {
+    chunk: Entity,
+}
+
+#[derive(Resource, Default)]
+struct InspectionMaterial(Handle<StandardMaterial>);
+
+pub struct MeshViewPlugin;
+
+impl Plugin for MeshViewPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MeshView>()
+            .init_resource::<InspectionMaterial>()
+            .add_systems(Startup, add_material)
+            .add_systems(
+                Update,
+                update_mesh_view.run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+fn add_material(
+    mut material: ResMut<InspectionMaterial>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    material.0 = materials.add(draped_material(StandardMaterial {
+        base_color: Color::srgb(0.2, 1., 0.4),
+        unlit: true,
+        ..Default::default()
+    }));
+}
+
+/// Line list of every triangle edge of `mesh`, shared edges only once.
+fn edges(mesh: &Mesh) -> Option<Mesh> {
+    let positions = mesh
+        .attribute(Mesh::ATTRIBUTE_POSITION)?
+        .as_float3()?
+        .to_vec();
+    let indices: Vec<u32> = mesh.indices()?.iter().map(|index| index as u32).collect();
+
+    let mut edges = HashSet::new();
+    for triangle in indices.chunks_exact(3) {
+        for (a, b) in [
+            (triangle[0], triangle[1]),
+            (triangle[1], triangle[2]),
+            (triangle[2], triangle[0]),
+        ] {
+            edges.insert((a.min(b), a.max(b)));
+        }
+    }
+    let indices = edges.into_iter().flat_map(|(a, b)| [a, b]).collect();
+
+    Some(
+        Mesh::new(PrimitiveTopology::LineList, RenderAssetUsages::RENDER_WORLD)
+            .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+            .with_inserted_indices(Indices::U32(indices)),
+    )
+}
+
+fn vertices(mesh: &Mesh) -> Option<Mesh> {
+    let positions = mesh
+        .attribute(Mesh::ATTRIBUTE_POSITION)?
+        .as_float3()?
+        .to_vec();
+    Some(
+        Mesh::new(
+            PrimitiveTopology::PointList,
+            RenderAssetUsages::RENDER_WORLD,
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions),
+    )
+}
+
+/// Rebuilds the lines or points of every chunk when the view changes, and of single chunks when
+/// their mesh is replaced or they are revealed.
+fn update_mesh_view(
+    mut commands: Commands,
+    view: Res<MeshView>,
+    material: Res<InspectionMaterial>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut chunks: Query<
+        (Entity, Ref<Mesh3d>, &ChildOf, &mut Visibility),
+        (With<Chunk>, Without<Unrevealed>),
+    >,
+    inspections: Query<(Entity, &ChunkInspection)>,
+    mut revealed: RemovedComponents<Unrevealed>,
+) {
+    let revealed: HashSet<Entity> = revealed.read().collect();
+    let mut rebuilt = HashSet::new();
+    for (entity, mesh, parent, mut visibility) in &mut chunks {
+        if !view.is_changed() && !mesh.is_changed() && !revealed.contains(&entity) {
+            continue;
+        }
+        rebuilt.insert(entity);
+        visibility.set_if_neq(view.chunk_visibility());
+
+        let derived = meshes.get(&mesh.0).and_then(|mesh| match *view {
+            MeshView::Surface => None,
+            MeshView::Wireframe => edges(mesh),
+            MeshView::Points => vertices(mesh),
+        });
+        if let Some(derived) = derived {
+            commands.spawn((
+                ChunkInspection { chunk: entity },
+                Mesh3d(meshes.add(derived)),
+                MeshMaterial3d(material.0.clone()),
+                Pickable::IGNORE,
+                ChildOf(parent.parent()),
+            ));
+        }
+    }
+
+    for (entity, inspection) in &inspections {
+        if rebuilt.contains(&inspection.chunk) || !chunks.contains(inspection.chunk) {
+            commands.entity(entity).despawn();
+        }
+    }
+}
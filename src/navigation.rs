@@ -0,0 +1,370 @@
+use std::time::Duration;
+
+use bevy::{
+    app::{App, Plugin, Update},
+    camera::Projection,
+    ecs::{
+        entity::Entity,
+        message::{Message, MessageReader, MessageWriter},
+        query::{With, Without},
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Query, Res, ResMut, Single},
+    },
+    math::{Vec2, Vec3},
+    state::{
+        condition::in_state,
+        state::{NextState, State},
+    },
+    time::{Time, Timer, TimerMode},
+    transform::components::{GlobalTransform, Transform},
+};
+
+use crate::{
+    EarthConfig, MAX_FOV, MIN_FOV,
+    component::{Earth, FovAnimation, MainCamera, RotationAnimation, ZoomAnimation},
+    free_flight::FreeFlight,
+    input::{Action, Actions},
+    math::{Coordinates, rotation_to_center},
+    observer::{EarthDoubleClicked, OrbitCamera},
+    settings::EarthSettings,
+    space::SpaceView,
+    state::{GameState, ToolMode},
+};
+
+const NAVIGATION_SECONDS: f32 = 1.5;
+
+/// Radians per second while a rotate key is held.
+const KEYBOARD_ROTATION_SPEED: f32 = 1.;
+
+/// Wheel steps per second while a zoom key is held.
+const KEYBOARD_ZOOM_STEPS: f32 = 6.;
+
+/// Rate at which the field of view approaches its target after a wheel step, per second.
+const ZOOM_SMOOTHING: f32 = 12.;
+
+/// Share of the ground in view that is still in view after flying to a double-clicked point.
+const DOUBLE_CLICK_ZOOM: f32 = 0.4;
+
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub enum Navigate {
+    NorthPole,
+    SouthPole,
+    /// The point opposite to the current view center
+    Antipode,
+    /// Brings a point of the surface to the view center
+    Location(Coordinates),
+}
+
+/// Flies the camera to a point of the surface: the globe turns it to the view center while the
+/// field of view eases to show as much ground as looking down from `altitude`, in km.
+///
+/// The altitude is converted to the field of view of the camera in its current orbit, so it is
+/// reached exactly when that orbit lies at the altitude, and only approximated from elsewhere.
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub struct FlyTo {
+    pub coordinates: Coordinates,
+    pub altitude: f32,
+    pub duration: Duration,
+}
+
+/// Fly-to requests through `Commands`.
+pub trait EarthCommands {
+    /// Sends a `FlyTo`.
+    fn fly_to(&mut self, coordinates: Coordinates, altitude: f32, duration: Duration);
+}
+
+impl EarthCommands for Commands<'_, '_> {
+    fn fly_to(&mut self, coordinates: Coordinates, altitude: f32, duration: Duration) {
+        self.write_message(FlyTo {
+            coordinates,
+            altitude,
+            duration,
+        });
+    }
+}
+
+/// Whether double-clicking the globe flies to the clicked point, zooming in.
+#[derive(Resource, Debug)]
+pub struct FlyToOnDoubleClick(pub bool);
+
+impl Default for FlyToOnDoubleClick {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+pub struct NavigationPlugin;
+
+impl Plugin for NavigationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<Navigate>()
+            .add_message::<FlyTo>()
+            .init_resource::<FlyToOnDoubleClick>()
+            .add_systems(
+                Update,
+                (
+                    (navigation_hotkeys, keyboard_navigation)
+                        .run_if(|mode: Res<State<ToolMode>>| mode.allows_navigation()),
+                    // Other tools take clicks for themselves
+                    fly_to_double_click.run_if(in_state(ToolMode::Idle)),
+                    toggle_measuring,
+                    start_navigation,
+                    start_fly_to,
+                    animate_rotation,
+                    animate_zoom,
+                    animate_fov,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Field of view showing as much ground from a camera `camera_altitude` above the surface as the
+/// widest one shows from `altitude`.
+fn fov_for_altitude(altitude: f32, camera_altitude: f32) -> f32 {
+    let extent = altitude / camera_altitude.max(f32::EPSILON) * (MAX_FOV / 2.).tan();
+    (2. * extent.atan()).clamp(MIN_FOV, MAX_FOV)
+}
+
+/// Altitude the widest field of view shows as much ground from as `fov` does from
+/// `camera_altitude`, the inverse of `fov_for_altitude`.
+pub fn altitude_for_fov(fov: f32, camera_altitude: f32) -> f32 {
+    camera_altitude * (fov / 2.).tan() / (MAX_FOV / 2.).tan()
+}
+
+fn navigation_hotkeys(actions: Actions, mut navigate: MessageWriter<Navigate>) {
+    if actions.just_pressed(Action::NorthPole) {
+        navigate.write(Navigate::NorthPole);
+    }
+    if actions.just_pressed(Action::SouthPole) {
+        navigate.write(Navigate::SouthPole);
+    }
+    if actions.just_pressed(Action::Antipode) {
+        navigate.write(Navigate::Antipode);
+    }
+}
+
+fn keyboard_navigation(
+    mut commands: Commands,
+    actions: Actions,
+    time: Res<Time>,
+    earth: Single<(Entity, &GlobalTransform), With<Earth>>,
+    camera: Single<(Entity, &mut Transform, &mut Projection, &OrbitCamera), With<MainCamera>>,
+    space: Res<SpaceView>,
+    flight: Res<FreeFlight>,
+    settings: Res<EarthSettings>,
+) {
+    let axis = |positive: Action, negative: Action| {
+        actions.pressed(positive) as i8 as f32 - actions.pressed(negative) as i8 as f32
+    };
+    let rotation = Vec2::new(
+        axis(Action::RotateRight, Action::RotateLeft),
+        axis(Action::RotateDown, Action::RotateUp),
+    );
+    let zoom = axis(Action::ZoomIn, Action::ZoomOut);
+
+    let (camera, mut transform, mut projection, orbit) = camera.into_inner();
+    // Space view and free flight own the camera's position
+    if rotation != Vec2::ZERO && !space.is_active() && !flight.active {
+        let (entity, earth) = *earth;
+        commands.entity(entity).remove::<RotationAnimation>();
+
+        // Orbit the camera the way the globe would turn under the keys
+        let step = KEYBOARD_ROTATION_SPEED * settings.rotation_sensitivity * time.delta_secs();
+        orbit.orbit(
+            &mut transform,
+            earth.translation(),
+            -rotation.x * step,
+            rotation.y * step,
+        );
+    }
+
+    if zoom == 0. {
+        return;
+    }
+    commands
+        .entity(camera)
+        .remove::<(ZoomAnimation, FovAnimation)>();
+    if let Projection::Perspective(ref mut perspective) = *projection {
+        perspective.fov = settings.zoom_fov(
+            perspective.fov,
+            zoom * KEYBOARD_ZOOM_STEPS * time.delta_secs(),
+        );
+    }
+}
+
+fn animate_zoom(
+    mut commands: Commands,
+    time: Res<Time>,
+    camera: Single<(Entity, &mut Projection, &ZoomAnimation), With<MainCamera>>,
+) {
+    let (entity, mut projection, animation) = camera.into_inner();
+    let Projection::Perspective(ref mut perspective) = *projection else {
+        commands.entity(entity).remove::<ZoomAnimation>();
+        return;
+    };
+
+    // Frame rate independent exponential approach
+    let t = 1. - (-ZOOM_SMOOTHING * time.delta_secs()).exp();
+    perspective.fov += (animation.target_fov - perspective.fov) * t;
+    if (animation.target_fov - perspective.fov).abs() < 1e-4 {
+        perspective.fov = animation.target_fov;
+        commands.entity(entity).remove::<ZoomAnimation>();
+    }
+}
+
+fn toggle_measuring(
+    actions: Actions,
+    mode: Res<State<ToolMode>>,
+    mut next_mode: ResMut<NextState<ToolMode>>,
+) {
+    if actions.just_pressed(Action::Measure) {
+        next_mode.set(if **mode == ToolMode::Measuring {
+            ToolMode::Idle
+        } else {
+            ToolMode::Measuring
+        });
+    }
+}
+
+fn start_navigation(
+    mut commands: Commands,
+    mut navigate: MessageReader<Navigate>,
+    earth: Single<(Entity, &Transform), With<Earth>>,
+    camera: Single<&Transform, With<MainCamera>>,
+) {
+    let Some(&target) = navigate.read().last() else {
+        return;
+    };
+    let (entity, transform) = earth.into_inner();
+
+    let view = (camera.translation - transform.translation).normalize();
+    let center = match target {
+        Navigate::NorthPole => Vec3::Y,
+        Navigate::SouthPole => Vec3::NEG_Y,
+        Navigate::Antipode => -(transform.rotation.inverse() * view),
+        Navigate::Location(coordinates) => coordinates.direction(),
+    };
+
+    commands.entity(entity).insert(RotationAnimation {
+        from: transform.rotation,
+        to: rotation_to_center(center, transform.rotation, view, camera.up().into()),
+        timer: Timer::from_seconds(NAVIGATION_SECONDS, TimerMode::Once),
+    });
+}
+
+fn fly_to_double_click(
+    mut commands: Commands,
+    mut clicks: MessageReader<EarthDoubleClicked>,
+    enabled: Res<FlyToOnDoubleClick>,
+    config: Res<EarthConfig>,
+    camera: Single<(&Transform, &Projection), With<MainCamera>>,
+    earth: Single<&Transform, (With<Earth>, Without<MainCamera>)>,
+) {
+    let Some(click) = clicks.read().last() else {
+        return;
+    };
+    let (transform, projection) = *camera;
+    if !enabled.0 {
+        return;
+    }
+    let Projection::Perspective(perspective) = projection else {
+        return;
+    };
+    let Ok(coordinates) = Coordinates::from_degrees(click.lat, click.lon) else {
+        return;
+    };
+
+    let camera_altitude = transform.translation.distance(earth.translation) - config.radius;
+    let altitude = altitude_for_fov(perspective.fov, camera_altitude) * config.km_per_unit();
+    commands.fly_to(
+        coordinates,
+        altitude * DOUBLE_CLICK_ZOOM,
+        Duration::from_secs_f32(NAVIGATION_SECONDS),
+    );
+}
+
+/// Starts the rotation of the globe and the zoom of a `FlyTo`, stopping the orbit of the camera
+/// so the point ends up centered.
+fn start_fly_to(
+    mut commands: Commands,
+    mut requests: MessageReader<FlyTo>,
+    config: Res<EarthConfig>,
+    earth: Single<(Entity, &Transform), With<Earth>>,
+    camera: Single<
+        (Entity, &Transform, &Projection, &mut OrbitCamera),
+        (With<MainCamera>, Without<Earth>),
+    >,
+) {
+    let Some(&request) = requests.read().last() else {
+        return;
+    };
+    let (earth, earth_transform) = earth.into_inner();
+    let (camera, transform, projection, mut orbit) = camera.into_inner();
+    orbit.velocity = Vec2::ZERO;
+
+    let view = (transform.translation - earth_transform.translation).normalize();
+    let center = request.coordinates.direction();
+    commands.entity(earth).insert(RotationAnimation {
+        from: earth_transform.rotation,
+        to: rotation_to_center(
+            center,
+            earth_transform.rotation,
+            view,
+            transform.up().into(),
+        ),
+        timer: Timer::new(request.duration, TimerMode::Once),
+    });
+
+    let Projection::Perspective(perspective) = projection else {
+        return;
+    };
+    let camera_altitude =
+        transform.translation.distance(earth_transform.translation) - config.radius;
+    commands
+        .entity(camera)
+        .remove::<ZoomAnimation>()
+        .insert(FovAnimation {
+            from: perspective.fov,
+            to: fov_for_altitude(request.altitude / config.km_per_unit(), camera_altitude),
+            timer: Timer::new(request.duration, TimerMode::Once),
+        });
+}
+
+fn animate_rotation(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut animations: Query<(Entity, &mut Transform, &mut RotationAnimation)>,
+) {
+    for (entity, mut transform, mut animation) in &mut animations {
+        animation.timer.tick(time.delta());
+
+        let t = animation.timer.fraction();
+        let eased = t * t * (3. - 2. * t);
+        transform.rotation = animation.from.slerp(animation.to, eased);
+
+        if animation.timer.is_finished() {
+            commands.entity(entity).remove::<RotationAnimation>();
+        }
+    }
+}
+
+fn animate_fov(
+    mut commands: Commands,
+    time: Res<Time>,
+    camera: Single<(Entity, &mut Projection, &mut FovAnimation), With<MainCamera>>,
+) {
+    let (entity, mut projection, mut animation) = camera.into_inner();
+    animation.timer.tick(time.delta());
+    if let Projection::Perspective(ref mut perspective) = *projection {
+        // Eased like the rotation it accompanies
+        let t = animation.timer.fraction();
+        let eased = t * t * (3. - 2. * t);
+        perspective.fov = animation.from + (animation.to - animation.from) * eased;
+    }
+    if animation.timer.is_finished() {
+        commands.entity(entity).remove::<FovAnimation>();
+    }
+}
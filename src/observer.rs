@@ -1,27 +1,334 @@
-use std::f32::consts::PI;
+use std::f32::consts::{PI, TAU};
 
 use bevy::{
-    camera::{Camera, Projection},
+    camera::Projection,
     ecs::{
+        component::Component,
+        entity::Entity,
+        message::{Message, MessageWriter},
         observer::On,
         query::With,
-        system::{Query, Single},
+        system::{Commands, Local, Query, Res, ResMut, Single},
     },
-    picking::events::{Drag, Pointer, Scroll},
-    transform::components::Transform,
+    math::{Quat, Vec2, Vec3},
+    picking::{
+        events::{Click, Drag, DragEnd, DragStart, Move, Out, Pointer, Press, Scroll},
+        pointer::PointerButton,
+    },
+    state::state::State,
+    time::{Real, Time},
+    transform::components::{GlobalTransform, Transform},
+};
+
+use crate::{
+    MAX_FOV,
+    component::{Earth, FovAnimation, MainCamera, RotationAnimation, ZoomAnimation},
+    free_flight::FreeFlight,
+    math::Coordinates,
+    resource::{CursorHit, PointerOverUi},
+    selection::RectangleSelection,
+    settings::EarthSettings,
+    space::SpaceView,
+    state::ToolMode,
 };
 
-pub fn rotate_earth(drag: On<Pointer<Drag>>, mut transforms: Query<&mut Transform>) {
-    if let Ok(mut transform) = transforms.get_mut(drag.entity) {
-        transform.rotate_y(drag.delta.x * 0.02);
-        transform.rotate_x(drag.delta.y * 0.02);
+/// Sent when the globe is clicked with the primary button, with the point under the pointer in
+/// degrees.
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub struct EarthClicked {
+    pub lat: f32,
+    pub lon: f32,
+}
+
+/// Sent on the second of two clicks on the globe in quick succession, with the point under the
+/// pointer in degrees. Both clicks are sent as `EarthClicked` too.
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub struct EarthDoubleClicked {
+    pub lat: f32,
+    pub lon: f32,
+}
+
+/// Pixels the pointer may move between press and release for it to still count as a click.
+const CLICK_SLOP: f32 = 4.;
+
+/// Seconds within which a second click makes a double-click.
+const DOUBLE_CLICK_SECONDS: f32 = 0.4;
+
+/// Where the pointer last went down on an entity, so the release of a drag isn't taken for a
+/// click.
+#[derive(Component)]
+pub struct PressedAt(pub Vec2);
+
+/// Radians the camera orbits per dragged pixel at the widest field of view.
+const ORBIT_PER_PIXEL: f32 = 0.02;
+
+/// A drag released after resting for longer than this leaves the camera still.
+const RELEASE_SECONDS: f32 = 0.08;
+
+/// Angular speed below which the orbit comes to rest, in radians per second.
+const REST_SPEED: f32 = 1e-3;
+
+/// Orbits the camera around the globe's center on drag instead of turning the `Earth`, so
+/// markers, lights and other layers keep their place in the world.
+///
+/// The camera keeps turning after a flick, slowing down by `damping`, and never pitches over the
+/// poles past `max_pitch`.
+#[derive(Component, Debug)]
+pub struct OrbitCamera {
+    /// Elevation limit above and below the equator, in radians
+    pub max_pitch: f32,
+    /// Rate the orbit slows down at after a release, per second
+    pub damping: f32,
+    /// Yaw and pitch speed in radians per second
+    pub velocity: Vec2,
+    dragging: bool,
+    /// Real time of the last drag step
+    last_drag: f32,
+}
+
+impl Default for OrbitCamera {
+    fn default() -> Self {
+        Self {
+            max_pitch: 85f32.to_radians(),
+            damping: 6.,
+            velocity: Vec2::ZERO,
+            dragging: false,
+            last_drag: 0.,
+        }
+    }
+}
+
+impl OrbitCamera {
+    pub fn is_moving(&self) -> bool {
+        !self.dragging && self.velocity.length() > REST_SPEED
+    }
+
+    /// Turns the camera around `center`, by `yaw` about the world's Y axis and `pitch` towards it,
+    /// keeping the elevation within `max_pitch`.
+    pub fn orbit(&self, transform: &mut Transform, center: Vec3, yaw: f32, pitch: f32) {
+        let offset = transform.translation - center;
+        let elevation = (offset.y / offset.length()).clamp(-1., 1.).asin();
+        let pitch = (elevation + pitch).clamp(-self.max_pitch, self.max_pitch) - elevation;
+        let right = transform.right();
+        transform.rotate_around(
+            center,
+            Quat::from_rotation_y(yaw) * Quat::from_axis_angle(*right, -pitch),
+        );
+    }
+}
+
+/// Marks an entity while a drag that started on an egui window moves across it.
+#[derive(Component)]
+pub struct UiDrag;
+
+/// Drags keep going to the globe once the pointer leaves the window they started on, so whether
+/// they belong to the UI is decided when they start.
+pub fn capture_ui_drag(
+    start: On<Pointer<DragStart>>,
+    mut commands: Commands,
+    over_ui: Res<PointerOverUi>,
+) {
+    if **over_ui {
+        commands.entity(start.entity).insert(UiDrag);
     }
 }
 
-pub fn zoom(scroll: On<Pointer<Scroll>>, camera: Single<&mut Projection, With<Camera>>) {
-    if let Projection::Perspective(ref mut perspective) = *camera.into_inner() {
-        let delta_zoom = -scroll.y * 0.05;
+pub fn release_ui_drag(end: On<Pointer<DragEnd>>, mut commands: Commands) {
+    commands.entity(end.entity).remove::<UiDrag>();
+}
+
+/// Drags the camera around the globe, at a speed following the field of view so the ground
+/// roughly keeps up with the pointer when zoomed in.
+pub fn orbit_camera(
+    drag: On<Pointer<Drag>>,
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    camera: Single<(&mut Transform, &Projection, &mut OrbitCamera), With<MainCamera>>,
+    earth: Single<&GlobalTransform, With<Earth>>,
+    ui_drags: Query<(), With<UiDrag>>,
+    mode: Option<Res<State<ToolMode>>>,
+    rectangle: Res<RectangleSelection>,
+    space: Res<SpaceView>,
+    flight: Res<FreeFlight>,
+    settings: Res<EarthSettings>,
+) {
+    if mode.is_some_and(|mode| !mode.allows_navigation() || mode.captures_drag())
+        || ui_drags.contains(drag.entity)
+        || rectangle.is_dragging()
+        || space.is_active()
+        || flight.active
+    {
+        return;
+    }
+
+    // Manual input always wins over a running animation
+    commands.entity(drag.entity).remove::<RotationAnimation>();
+
+    let (mut transform, projection, mut orbit) = camera.into_inner();
+    let fov = match projection {
+        Projection::Perspective(perspective) => perspective.fov,
+        _ => MAX_FOV,
+    };
+    let angles = Vec2::new(-drag.delta.x, drag.delta.y)
+        * ORBIT_PER_PIXEL
+        * settings.rotation_sensitivity
+        * fov
+        / MAX_FOV;
+    orbit.orbit(&mut transform, earth.translation(), angles.x, angles.y);
+
+    orbit.velocity = angles / time.delta_secs().max(1e-3);
+    orbit.dragging = true;
+    orbit.last_drag = time.elapsed_secs();
+}
+
+/// Lets the camera coast on after a flick, unless the pointer rested before letting go.
+pub fn release_orbit(
+    _end: On<Pointer<DragEnd>>,
+    time: Res<Time<Real>>,
+    mut orbit: Single<&mut OrbitCamera>,
+) {
+    orbit.dragging = false;
+    if time.elapsed_secs() - orbit.last_drag > RELEASE_SECONDS {
+        orbit.velocity = Vec2::ZERO;
+    }
+}
+
+/// Carries the orbit on after a release, slowing it down by its damping.
+pub fn orbit_inertia(
+    time: Res<Time<Real>>,
+    camera: Single<(&mut Transform, &mut OrbitCamera), With<MainCamera>>,
+    earth: Single<&GlobalTransform, With<Earth>>,
+    space: Res<SpaceView>,
+    flight: Res<FreeFlight>,
+) {
+    let (mut transform, mut orbit) = camera.into_inner();
+    if !orbit.is_moving() {
+        return;
+    }
+    if space.is_active() || flight.active {
+        orbit.velocity = Vec2::ZERO;
+        return;
+    }
+
+    let step = orbit.velocity * time.delta_secs();
+    orbit.orbit(&mut transform, earth.translation(), step.x, step.y);
+    orbit.velocity *= (-orbit.damping * time.delta_secs()).exp();
+}
+
+/// Retargets the camera's `ZoomAnimation`, so quick wheel steps accumulate instead of each one
+/// starting from wherever the eased field of view happens to be.
+///
+/// The camera also orbits towards the point under the cursor by as much as the view narrows, so
+/// that point stays put on screen while zooming in.
+pub fn zoom(
+    scroll: On<Pointer<Scroll>>,
+    mut commands: Commands,
+    camera: Single<
+        (
+            Entity,
+            &Transform,
+            &Projection,
+            Option<&ZoomAnimation>,
+            &mut OrbitCamera,
+        ),
+        With<MainCamera>,
+    >,
+    earth: Single<&GlobalTransform, With<Earth>>,
+    cursor: Res<CursorHit>,
+    mode: Option<Res<State<ToolMode>>>,
+    space: Res<SpaceView>,
+    over_ui: Res<PointerOverUi>,
+    settings: Res<EarthSettings>,
+) {
+    // In space view the wheel drives the transition back to the globe instead
+    if mode.is_some_and(|mode| !mode.allows_navigation()) || space.is_active() || **over_ui {
+        return;
+    }
+
+    let (entity, transform, projection, animation, mut orbit) = camera.into_inner();
+    let Projection::Perspective(perspective) = projection else {
+        return;
+    };
+    let from = animation.map_or(perspective.fov, |animation| animation.target_fov);
+    let target_fov = settings.zoom_fov(from, scroll.y);
+    commands
+        .entity(entity)
+        .remove::<FovAnimation>()
+        .insert(ZoomAnimation { target_fov });
+
+    let Some(hit) = **cursor else {
+        return;
+    };
+    let center = earth.translation();
+    let offset = transform.translation - center;
+    let toward = earth.transform_point(hit) - center;
+    let yaw = toward.x.atan2(toward.z) - offset.x.atan2(offset.z);
+    // Shortest way around the globe
+    let yaw = (yaw + PI).rem_euclid(TAU) - PI;
+    let pitch = (toward.y / toward.length()).asin() - (offset.y / offset.length()).asin();
+
+    // The eased orbit covers `velocity / damping` in total, alongside the eased field of view
+    let narrowed = 1. - (target_fov / 2.).tan() / (from / 2.).tan();
+    orbit.velocity += Vec2::new(yaw, pitch) * narrowed * orbit.damping;
+}
+
+pub fn track_cursor(
+    hover: On<Pointer<Move>>,
+    transforms: Query<&GlobalTransform>,
+    mut cursor: ResMut<CursorHit>,
+) {
+    let (Some(position), Ok(transform)) = (hover.hit.position, transforms.get(hover.entity)) else {
+        return;
+    };
+
+    // Store the hit in the Earth's local space so it stays valid while the globe rotates
+    **cursor = Some(transform.affine().inverse().transform_point3(position));
+}
+
+pub fn clear_cursor(_out: On<Pointer<Out>>, mut cursor: ResMut<CursorHit>) {
+    **cursor = None;
+}
+
+pub fn record_press(press: On<Pointer<Press>>, mut commands: Commands) {
+    commands
+        .entity(press.entity)
+        .insert(PressedAt(press.pointer_location.position));
+}
+
+pub fn report_click(
+    click: On<Pointer<Click>>,
+    transforms: Query<&GlobalTransform>,
+    pressed: Query<&PressedAt>,
+    over_ui: Res<PointerOverUi>,
+    time: Res<Time<Real>>,
+    mut last_click: Local<Option<(f32, Vec2)>>,
+    mut clicked: MessageWriter<EarthClicked>,
+    mut double_clicked: MessageWriter<EarthDoubleClicked>,
+) {
+    let dragged = pressed
+        .get(click.entity)
+        .is_ok_and(|pressed| pressed.0.distance(click.pointer_location.position) > CLICK_SLOP);
+    if **over_ui || dragged || click.button != PointerButton::Primary {
+        return;
+    }
+    let (Some(position), Ok(transform)) = (click.hit.position, transforms.get(click.entity)) else {
+        return;
+    };
+
+    let local = transform.affine().inverse().transform_point3(position);
+    let (lat, lon) = Coordinates::from(local).as_degrees();
+    clicked.write(EarthClicked { lat, lon });
 
-        perspective.fov = (perspective.fov + delta_zoom).clamp(0.05, PI / 4.);
+    let now = time.elapsed_secs();
+    let location = click.pointer_location.position;
+    if let Some((at, previous)) = *last_click
+        && now - at < DOUBLE_CLICK_SECONDS
+        && previous.distance(location) < CLICK_SLOP
+    {
+        double_clicked.write(EarthDoubleClicked { lat, lon });
+        // A third click starts over
+        *last_click = None;
+    } else {
+        *last_click = Some((now, location));
     }
 }
@@ -0,0 +1,99 @@
+use bevy::{
+    asset::{Assets, Handle, RenderAssetUsages},
+    camera::{Camera, Camera3d, PerspectiveProjection, Projection, RenderTarget},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::With,
+        system::{Commands, Query},
+    },
+    image::Image,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages},
+    transform::components::Transform,
+};
+
+/// A square image `size` pixels wide for a camera to render into and egui to show, with `usage`
+/// on top of that, e.g. `COPY_SRC` to read it back.
+pub fn offscreen_target(
+    images: &mut Assets<Image>,
+    size: u32,
+    usage: TextureUsages,
+) -> Handle<Image> {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.texture_descriptor.usage = TextureUsages::TEXTURE_BINDING
+        | TextureUsages::COPY_DST
+        | TextureUsages::RENDER_ATTACHMENT
+        | usage;
+    images.add(image)
+}
+
+/// Image of an offscreen view, created the first time it is shown and kept while it is hidden.
+#[derive(Debug, Clone)]
+pub struct OffscreenTarget {
+    /// Width and height in pixels
+    pub size: u32,
+    image: Option<Handle<Image>>,
+}
+
+impl OffscreenTarget {
+    pub fn new(size: u32) -> Self {
+        Self { size, image: None }
+    }
+
+    /// The image, unless the view was never shown.
+    pub fn image(&self) -> Option<&Handle<Image>> {
+        self.image.as_ref()
+    }
+
+    fn get_or_create(&mut self, images: &mut Assets<Image>) -> Handle<Image> {
+        self.image
+            .get_or_insert_with(|| offscreen_target(images, self.size, TextureUsages::empty()))
+            .clone()
+    }
+}
+
+/// Spawns a camera with `marker` rendering into `target` while `enabled`, and despawns it again
+/// once disabled, so a hidden view costs nothing.
+///
+/// The camera renders before the main one, its transform and projection are left to the view.
+pub fn sync_offscreen_camera<M: Component>(
+    commands: &mut Commands,
+    enabled: bool,
+    cameras: &Query<Entity, With<M>>,
+    target: &mut OffscreenTarget,
+    images: &mut Assets<Image>,
+    marker: M,
+    camera: Camera,
+) {
+    if !enabled {
+        for camera in cameras {
+            commands.entity(camera).despawn();
+        }
+        return;
+    }
+    if !cameras.is_empty() {
+        return;
+    }
+
+    commands.spawn((
+        marker,
+        Camera3d::default(),
+        Camera {
+            target: RenderTarget::Image(target.get_or_create(images).into()),
+            order: -1,
+            ..camera
+        },
+        Transform::default(),
+        Projection::Perspective(PerspectiveProjection::default()),
+    ));
+}
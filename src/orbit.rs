@@ -0,0 +1,175 @@
+use std::f32::consts::TAU;
+
+use bevy::{
+    app::{App, Plugin, Update},
+    asset::Assets,
+    camera::visibility::Visibility,
+    color::Color,
+    ecs::{
+        change_detection::DetectChanges,
+        component::Component,
+        entity::Entity,
+        name::Name,
+        query::{With, Without},
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Query, Res, ResMut, Single},
+    },
+    math::{Quat, Vec3},
+    mesh::{Mesh, Mesh3d},
+    pbr::{MeshMaterial3d, StandardMaterial},
+    picking::Pickable,
+    state::{condition::in_state, state::OnEnter},
+    transform::components::Transform,
+};
+
+use crate::{
+    EarthConfig, LIGHT_ROTATION_SPEED,
+    chunk::FACES,
+    component::Earth,
+    math::CubeSphereBuilder,
+    resource::{EarthTexture, SimulationTime},
+    space::SpaceView,
+    state::GameState,
+};
+
+/// Days the Moon takes to go around the Earth once, against the stars.
+const SIDEREAL_MONTH_DAYS: f32 = 27.32;
+
+/// Tilt of the Moon's orbit against the ecliptic, which the demo sun circles in the equator.
+const MOON_INCLINATION: f32 = 5.14;
+
+/// Vertices along each edge of the Moon's cube faces.
+const MOON_RESOLUTION: u32 = 32;
+
+/// A body circling its parent, with one side always turned towards it like the Moon.
+///
+/// The orbit is a circle tilted around the X axis, so it crosses the parent's equator on the
+/// X axis.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct OrbitingBody {
+    pub parent: Entity,
+    /// Distance from the parent's center in world units
+    pub radius: f32,
+    /// Simulated seconds per revolution
+    pub period: f32,
+    /// Tilt of the orbit against the parent's equator, in radians
+    pub inclination: f32,
+}
+
+impl OrbitingBody {
+    /// Offset from the parent after `elapsed` simulated seconds.
+    pub fn offset(&self, elapsed: f32) -> Vec3 {
+        let angle = TAU * (elapsed / self.period).fract();
+        Quat::from_rotation_x(self.inclination)
+            * Vec3::new(angle.sin(), 0., angle.cos())
+            * self.radius
+    }
+}
+
+/// The Moon, sized and placed as the `SpaceScale` wants it.
+#[derive(Component)]
+pub struct Moon;
+
+pub struct OrbitPlugin;
+
+impl Plugin for OrbitPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::PostLoading), spawn_moon)
+            .add_systems(
+                Update,
+                (
+                    scale_moon.run_if(in_state(GameState::Playing)),
+                    advance_orbits,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Puts the Moon into orbit around a freshly loaded globe, lit by the same light so its phases
+/// follow from the geometry.
+fn spawn_moon(
+    mut commands: Commands,
+    earth: Single<Entity, With<Earth>>,
+    textures: Res<EarthTexture>,
+    space: Res<SpaceView>,
+    config: Res<EarthConfig>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let material = materials.add(StandardMaterial {
+        base_color: if textures.moon.is_some() {
+            Color::WHITE
+        } else {
+            Color::srgb(0.55, 0.55, 0.53)
+        },
+        base_color_texture: textures.moon.clone(),
+        perceptual_roughness: 1.,
+        ..Default::default()
+    });
+    let (radius, size) = space.scale.moon(&config);
+
+    commands
+        .spawn((
+            Moon,
+            Name::new("Moon"),
+            OrbitingBody {
+                parent: *earth,
+                radius,
+                period: SIDEREAL_MONTH_DAYS * TAU / LIGHT_ROTATION_SPEED,
+                inclination: MOON_INCLINATION.to_radians(),
+            },
+            Transform::from_scale(Vec3::splat(size)),
+            Visibility::Inherited,
+        ))
+        .with_children(|moon| {
+            for normal in FACES {
+                let face = CubeSphereBuilder::new(normal)
+                    .radius(1.)
+                    .resolution(MOON_RESOLUTION)
+                    .build();
+                moon.spawn((
+                    Mesh3d(meshes.add(face)),
+                    MeshMaterial3d(material.clone()),
+                    Pickable::IGNORE,
+                ));
+            }
+        });
+}
+
+fn scale_moon(
+    space: Res<SpaceView>,
+    config: Res<EarthConfig>,
+    mut moons: Query<(&mut OrbitingBody, &mut Transform), With<Moon>>,
+) {
+    if !space.is_changed() {
+        return;
+    }
+    let (radius, size) = space.scale.moon(&config);
+    for (mut orbit, mut transform) in &mut moons {
+        orbit.radius = radius;
+        transform.scale = Vec3::splat(size);
+    }
+}
+
+/// Moves every body along its orbit, turning the same side towards its parent, and takes the
+/// ones whose parent is gone along with it.
+fn advance_orbits(
+    mut commands: Commands,
+    simulation: Res<SimulationTime>,
+    mut bodies: Query<(Entity, &OrbitingBody, &mut Transform)>,
+    parents: Query<&Transform, Without<OrbitingBody>>,
+) {
+    for (entity, orbit, mut transform) in &mut bodies {
+        let Ok(parent) = parents.get(orbit.parent) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+        let offset = orbit.offset(simulation.elapsed_secs());
+        transform.translation = parent.translation + offset;
+        // Longitude zero of the albedo, on the +Z side, faces the parent
+        transform.rotation = Transform::IDENTITY
+            .looking_to(offset, Quat::from_rotation_x(orbit.inclination) * Vec3::Y)
+            .rotation;
+    }
+}
@@ -0,0 +1,77 @@
+use bevy::{
+    app::{App, Plugin, PreUpdate},
+    ecs::{
+        hierarchy::ChildOf,
+        query::{With, Without},
+        resource::Resource,
+        system::{Query, Res, ResMut, Single},
+    },
+    math::{DVec3, Vec3},
+    prelude::{Deref, DerefMut},
+    transform::components::Transform,
+};
+
+use crate::{
+    EarthConfig,
+    component::{MainCamera, SimulatedTransform},
+};
+
+/// How far the camera may drift from the render origin before the world is shifted back, in
+/// radii of the globe.
+const REBASE_DISTANCE: f32 = 0.1;
+
+/// Position of the render origin in the absolute scene, in double precision.
+///
+/// The camera is kept near the origin, where f32 has the most precision, by moving every root
+/// entity instead. Absolute positions are `origin + translation`.
+#[derive(Resource, Default, Debug, Clone, Copy, Deref, DerefMut)]
+pub struct WorldOrigin(pub DVec3);
+
+impl WorldOrigin {
+    pub fn to_absolute(&self, translation: Vec3) -> DVec3 {
+        self.0 + translation.as_dvec3()
+    }
+
+    pub fn to_render(&self, absolute: DVec3) -> Vec3 {
+        (absolute - self.0).as_vec3()
+    }
+}
+
+pub struct OriginPlugin;
+
+impl Plugin for OriginPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WorldOrigin>()
+            .add_systems(PreUpdate, rebase_origin);
+    }
+}
+
+/// Moves the camera back to the origin once it drifted too far, shifting the rest of the scene
+/// along so nothing visibly moves.
+///
+/// Only root entities are moved, children follow their parents. Offsets are accumulated in
+/// `WorldOrigin` in f64, so repeated rebasing doesn't pile up rounding errors.
+fn rebase_origin(
+    mut origin: ResMut<WorldOrigin>,
+    config: Res<EarthConfig>,
+    mut camera: Single<&mut Transform, (With<MainCamera>, Without<ChildOf>)>,
+    mut roots: Query<
+        (&mut Transform, Option<&mut SimulatedTransform>),
+        (Without<MainCamera>, Without<ChildOf>),
+    >,
+) {
+    let offset = camera.translation;
+    if offset.length() < REBASE_DISTANCE * config.radius {
+        return;
+    }
+
+    **origin += offset.as_dvec3();
+    camera.translation = Vec3::ZERO;
+    for (mut transform, simulated) in &mut roots {
+        transform.translation -= offset;
+        if let Some(mut simulated) = simulated {
+            simulated.previous.translation -= offset;
+            simulated.current.translation -= offset;
+        }
+    }
+}
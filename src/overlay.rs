@@ -0,0 +1,385 @@
+use std::{
+    f32::consts::PI,
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    app::{App, Plugin, Update},
+    asset::{Assets, Handle},
+    camera::visibility::Visibility,
+    color::Color,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        hierarchy::ChildOf,
+        query::{With, Without},
+        system::{Commands, Query, Res, ResMut, Single},
+    },
+    log::warn,
+    math::{Vec3, primitives::Circle},
+    mesh::{Mesh, Mesh3d, MeshBuilder},
+    pbr::{MeshMaterial3d, StandardMaterial},
+    state::state::OnEnter,
+    transform::components::Transform,
+};
+use serde::Deserialize;
+
+use crate::{
+    EarthConfig,
+    component::{Billboard, Draped, Earth},
+    layer::{LayerDefinition, LayerDownloads, LayerKind, OVERLAYS_DIR},
+    math::{Coordinates, great_circle_point},
+    pack::EarthPacks,
+    polyline::{LineJoin, Polyline, PolylineMaterial},
+    state::GameState,
+    toast::Toasts,
+};
+
+/// Longest stretch of a line between two vertices in radians, longer ones are split to follow
+/// the great circle instead of cutting through the globe.
+const MAX_SEGMENT_ANGLE: f32 = PI / 180.;
+
+/// Colors given to the discovered layers in turn.
+const PALETTE: [Color; 4] = [
+    Color::srgb(1., 0.85, 0.2),
+    Color::srgb(0.3, 0.8, 1.),
+    Color::srgb(1., 0.4, 0.6),
+    Color::srgb(0.5, 1., 0.4),
+];
+
+/// `[longitude, latitude]` in degrees, possibly followed by an altitude that is ignored.
+type Position = Vec<f64>;
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type")]
+enum Geometry {
+    Point {
+        coordinates: Position,
+    },
+    MultiPoint {
+        coordinates: Vec<Position>,
+    },
+    LineString {
+        coordinates: Vec<Position>,
+    },
+    MultiLineString {
+        coordinates: Vec<Vec<Position>>,
+    },
+    /// Outer ring followed by its holes, each closed by repeating the first position
+    Polygon {
+        coordinates: Vec<Vec<Position>>,
+    },
+    MultiPolygon {
+        coordinates: Vec<Vec<Vec<Position>>>,
+    },
+    GeometryCollection {
+        geometries: Vec<Geometry>,
+    },
+}
+
+#[derive(Deserialize, Debug)]
+struct Feature {
+    geometry: Option<Geometry>,
+}
+
+/// The parts of RFC 7946 that can be drawn, properties are ignored.
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type")]
+enum GeoJson {
+    FeatureCollection {
+        features: Vec<Feature>,
+    },
+    Feature {
+        geometry: Option<Geometry>,
+    },
+    #[serde(untagged)]
+    Geometry(Geometry),
+}
+
+/// Lines and points of a GeoJSON file on the unit sphere.
+#[derive(Debug, Default)]
+struct Shapes {
+    /// Points of each line, and whether it closes back on its first point
+    lines: Vec<(Vec<Vec3>, bool)>,
+    points: Vec<Vec3>,
+}
+
+fn direction(position: &Position) -> Option<Vec3> {
+    let [longitude, latitude, ..] = position[..] else {
+        return None;
+    };
+    Coordinates::from_degrees(latitude as f32, longitude as f32)
+        .ok()
+        .map(|coordinates| coordinates.direction())
+}
+
+/// Adds the great circle points between consecutive positions that lie far apart.
+fn densify(positions: &[Position]) -> Vec<Vec3> {
+    let directions: Vec<Vec3> = positions.iter().filter_map(direction).collect();
+    let mut points = Vec::with_capacity(directions.len());
+    for pair in directions.windows(2) {
+        let steps = (pair[0].angle_between(pair[1]) / MAX_SEGMENT_ANGLE)
+            .ceil()
+            .max(1.) as usize;
+        points.extend(
+            (0..steps).map(|step| great_circle_point(pair[0], pair[1], step as f32 / steps as f32)),
+        );
+    }
+    points.extend(directions.last());
+    points
+}
+
+impl Shapes {
+    fn add_line(&mut self, positions: &[Position]) {
+        let points = densify(positions);
+        if points.len() >= 2 {
+            self.lines.push((points, false));
+        }
+    }
+
+    fn add_ring(&mut self, positions: &[Position]) {
+        let mut points = densify(positions);
+        // The closing position repeats the first one, the closed polyline draws that segment
+        if points.len() > 1 && points.first() == points.last() {
+            points.pop();
+        }
+        if points.len() >= 2 {
+            self.lines.push((points, true));
+        }
+    }
+
+    fn add(&mut self, geometry: &Geometry) {
+        match geometry {
+            Geometry::Point { coordinates } => self.points.extend(direction(coordinates)),
+            Geometry::MultiPoint { coordinates } => {
+                self.points.extend(coordinates.iter().filter_map(direction))
+            }
+            Geometry::LineString { coordinates } => self.add_line(coordinates),
+            Geometry::MultiLineString { coordinates } => {
+                for line in coordinates {
+                    self.add_line(line);
+                }
+            }
+            Geometry::Polygon { coordinates } => {
+                for ring in coordinates {
+                    self.add_ring(ring);
+                }
+            }
+            Geometry::MultiPolygon { coordinates } => {
+                for ring in coordinates.iter().flatten() {
+                    self.add_ring(ring);
+                }
+            }
+            Geometry::GeometryCollection { geometries } => {
+                for geometry in geometries {
+                    self.add(geometry);
+                }
+            }
+        }
+    }
+
+    fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let geojson: GeoJson = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        let mut shapes = Shapes::default();
+        match &geojson {
+            GeoJson::FeatureCollection { features } => {
+                for geometry in features
+                    .iter()
+                    .filter_map(|feature| feature.geometry.as_ref())
+                {
+                    shapes.add(geometry);
+                }
+            }
+            GeoJson::Feature { geometry } => {
+                if let Some(geometry) = geometry {
+                    shapes.add(geometry);
+                }
+            }
+            GeoJson::Geometry(geometry) => shapes.add(geometry),
+        }
+        Ok(shapes)
+    }
+
+    /// One mesh for every line, scaled from the unit sphere onto the globe.
+    fn line_mesh(&self, radius: f32) -> Option<Mesh> {
+        let mut lines = self.lines.iter().map(|(points, closed)| {
+            let line = Polyline::new(points.iter().map(|point| *point * radius));
+            if *closed { line.closed() } else { line }.build()
+        });
+        let mut mesh = lines.next()?;
+        for line in lines {
+            if let Err(err) = mesh.merge(&line) {
+                warn!("Skipping a line that can't be merged: {err}");
+            }
+        }
+        Some(mesh)
+    }
+}
+
+/// Lines, polygon outlines and points of a GeoJSON file, draped over the globe.
+///
+/// Spawn it on its own entity; once loaded, the entity is parented to the globe and toggled
+/// through its `Visibility`, so any number of layers can be shown independently. The layers in
+/// the `overlays` folder of the active pack are spawned hidden when the globe is ready.
+#[derive(Component, Debug, Clone)]
+pub struct GeoJsonLayer {
+    pub name: String,
+    pub path: PathBuf,
+    pub color: Color,
+    /// Width of the lines in pixels
+    pub width: f32,
+    /// Diameter of the points in pixels
+    pub point_size: f32,
+}
+
+impl GeoJsonLayer {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        Self {
+            name: path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            path,
+            color: PALETTE[0],
+            width: 1.5,
+            point_size: 8.,
+        }
+    }
+
+    /// A layer declared in `layers.ron`, drawn from `path`.
+    pub fn from_definition(definition: &LayerDefinition, path: PathBuf) -> Self {
+        let style = &definition.style;
+        Self {
+            name: definition.name.clone(),
+            path,
+            color: style.color.map_or(PALETTE[0], |[red, green, blue]| {
+                Color::srgb(red, green, blue)
+            }),
+            width: style.width,
+            point_size: style.point_size,
+        }
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+}
+
+pub struct GeoJsonPlugin;
+
+impl Plugin for GeoJsonPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Playing), discover_geojson)
+            .add_systems(Update, load_geojson_layers);
+    }
+}
+
+/// Spawns a hidden layer for every `.geojson` file in the pack's `overlays` folder, and the
+/// GeoJSON layers of its `layers.ron` whose source is at hand. The layers of a previous globe
+/// were despawned along with it.
+fn discover_geojson(
+    mut commands: Commands,
+    packs: Res<EarthPacks>,
+    downloads: Res<LayerDownloads>,
+) {
+    let pack = packs.active();
+    let mut files: Vec<_> = std::fs::read_dir(pack.root.join(OVERLAYS_DIR))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "geojson"))
+        .filter(|path| {
+            let file = path.file_name().unwrap_or_default().to_string_lossy();
+            !pack.layers.declares(&format!("{OVERLAYS_DIR}/{file}"))
+        })
+        .collect();
+    files.sort();
+
+    let mut colors = PALETTE.iter().cycle();
+    for path in files {
+        commands.spawn((
+            GeoJsonLayer::new(path).with_color(*colors.next().unwrap()),
+            Visibility::Hidden,
+        ));
+    }
+
+    // Remote sources still downloading are spawned once they arrive
+    for definition in &pack.layers.layers {
+        if definition.kind != LayerKind::GeoJson || downloads.is_pending(definition) {
+            continue;
+        }
+        let mut layer = GeoJsonLayer::from_definition(definition, definition.path(pack));
+        if definition.style.color.is_none() {
+            layer = layer.with_color(*colors.next().unwrap());
+        }
+        commands.spawn((layer, definition.visibility()));
+    }
+}
+
+/// Builds the meshes of layers not parented to the globe yet, which waits for the globe to
+/// exist. Layers that fail to load stay on the globe empty, so they aren't retried every frame.
+fn load_geojson_layers(
+    mut commands: Commands,
+    layers: Query<(Entity, &GeoJsonLayer), Without<ChildOf>>,
+    earth: Option<Single<Entity, With<Earth>>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut line_materials: ResMut<Assets<PolylineMaterial>>,
+    mut point_materials: ResMut<Assets<StandardMaterial>>,
+    mut toasts: ResMut<Toasts>,
+    config: Res<EarthConfig>,
+) {
+    let Some(earth) = earth else {
+        return;
+    };
+
+    for (entity, layer) in &layers {
+        commands
+            .entity(entity)
+            .insert((Transform::default(), ChildOf(*earth)))
+            .insert_if_new(Visibility::default());
+
+        let shapes = match Shapes::load(&layer.path) {
+            Ok(shapes) => shapes,
+            Err(err) => {
+                toasts.error(format!("Failed to load {}: {err}", layer.path.display()));
+                continue;
+            }
+        };
+
+        if let Some(mesh) = shapes.line_mesh(config.radius) {
+            let material =
+                PolylineMaterial::new(layer.color, layer.width).with_join(LineJoin::Round);
+            commands.spawn((
+                Mesh3d(meshes.add(mesh)),
+                MeshMaterial3d(line_materials.add(material)),
+                Draped::default(),
+                Transform::default(),
+                ChildOf(entity),
+            ));
+        }
+
+        if shapes.points.is_empty() {
+            continue;
+        }
+        let dot: Handle<Mesh> = meshes.add(Circle::new(0.5));
+        let material = point_materials.add(StandardMaterial {
+            base_color: layer.color,
+            unlit: true,
+            ..Default::default()
+        });
+        for point in &shapes.points {
+            commands.spawn((
+                Mesh3d(dot.clone()),
+                MeshMaterial3d(material.clone()),
+                Transform::from_translation(*point * config.radius),
+                Billboard {
+                    size: layer.point_size,
+                },
+                ChildOf(entity),
+            ));
+        }
+    }
+}
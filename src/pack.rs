@@ -0,0 +1,244 @@
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use bevy::{
+    asset::{AssetPath, io::file::FileAssetReader},
+    ecs::resource::Resource,
+    log::{error, warn},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    layer::{LAYERS_FILE, LayerInfo, LayerManifest},
+    texture::MetallicRoughnessLayout,
+};
+
+/// Files an asset pack has to contain to be usable.
+pub const REQUIRED_FILES: [&str; 3] = ["world.png", "specular_map_inverted_8k.png", "height.png"];
+
+/// Extension of the GPU compressed textures preferred over the files a pack is required to have.
+const COMPRESSED_EXTENSION: &str = "ktx2";
+
+/// Directory scanned for additional packs, next to the default asset folder.
+const PACKS_DIR: &str = "packs";
+
+/// Optional per-pack settings, read from `pack.ron` in the pack root.
+const MANIFEST_FILE: &str = "pack.ron";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PackManifest {
+    #[serde(default)]
+    pub metallic_roughness: MetallicRoughnessLayout,
+    /// Where the base imagery of the pack comes from
+    #[serde(default = "LayerInfo::blue_marble")]
+    pub imagery: LayerInfo,
+}
+
+impl Default for PackManifest {
+    fn default() -> Self {
+        Self {
+            metallic_roughness: Default::default(),
+            imagery: LayerInfo::blue_marble(),
+        }
+    }
+}
+
+/// A directory with the textures the Earth needs, either the bundled `assets` folder or an
+/// external one (possibly extracted from a zipped "earth pack").
+#[derive(Debug, Clone)]
+pub struct EarthPack {
+    pub name: String,
+    pub root: PathBuf,
+    /// Whether this is the default asset folder, loaded through the regular asset source
+    pub builtin: bool,
+    pub missing: Vec<&'static str>,
+    pub manifest: PackManifest,
+    /// Layers declared in `layers.ron`
+    pub layers: LayerManifest,
+}
+
+impl EarthPack {
+    fn new(name: String, root: PathBuf, builtin: bool) -> Self {
+        let missing = REQUIRED_FILES
+            .into_iter()
+            .filter(|file| texture_file(&root, file).is_none())
+            .collect();
+
+        let manifest = std::fs::read_to_string(root.join(MANIFEST_FILE))
+            .ok()
+            .and_then(|manifest| {
+                ron::from_str(&manifest)
+                    .inspect_err(|err| warn!("Ignoring invalid {MANIFEST_FILE} in {name}: {err}"))
+                    .ok()
+            })
+            .unwrap_or_default();
+
+        let layers = LayerManifest::load(&root).unwrap_or_else(|err| {
+            warn!("Ignoring invalid {LAYERS_FILE} in {name}: {err}");
+            LayerManifest::default()
+        });
+
+        Self {
+            name,
+            root,
+            builtin,
+            missing,
+            manifest,
+            layers,
+        }
+    }
+
+    /// Opens a pack directory or zip archive, extracting archives into the temp dir once.
+    ///
+    /// The extracted folder is keyed by the archive's size and modification time, so a changed
+    /// archive is extracted again. Archives are extracted next to it first and renamed into place,
+    /// so an interrupted extraction is never taken for a complete pack.
+    pub fn open(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string());
+
+        if path.is_dir() {
+            return Ok(Self::new(name, path.canonicalize()?, false));
+        }
+
+        let metadata = std::fs::metadata(path)?;
+        let modified = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        let packs = std::env::temp_dir().join("bevy-earth-packs");
+        let key = format!("{name}-{:x}-{modified:x}", metadata.len());
+        let extracted = packs.join(&key);
+        if !extracted.is_dir() {
+            let partial = packs.join(format!("{key}.part{}", std::process::id()));
+            if partial.exists() {
+                std::fs::remove_dir_all(&partial)?;
+            }
+            let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+            archive.extract(&partial)?;
+            // Another instance may have finished the same extraction in the meantime
+            if let Err(err) = std::fs::rename(&partial, &extracted) {
+                let _ = std::fs::remove_dir_all(&partial);
+                if !extracted.is_dir() {
+                    return Err(err.into());
+                }
+            }
+        }
+        Ok(Self::new(name, extracted, false))
+    }
+
+    /// See `texture_file`.
+    pub fn texture_file(&self, file: &str) -> Option<String> {
+        texture_file(&self.root, file)
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.missing.is_empty()
+    }
+
+    pub fn asset_path(&self, file: &str) -> AssetPath<'static> {
+        if self.builtin {
+            AssetPath::from(file.to_string())
+        } else {
+            AssetPath::from(self.root.join(file))
+        }
+    }
+}
+
+/// The `.ktx2` version of the texture `file` if `root` has one, `file` itself if only that exists,
+/// or `None` without either.
+///
+/// Compressed textures upload without stalling and bring their mipmaps, which keep the 8k and 21k
+/// maps from shimmering when zoomed out. They can be made with KTX-Software, e.g.
+/// `toktx --t2 --encode uastc --zcmp --genmipmap world.ktx2 world.png`, adding
+/// `--assign_oetf linear` for the height and specular maps. A compressed specular map isn't
+/// repacked, it has to be in the glTF layout already.
+pub fn texture_file(root: &Path, file: &str) -> Option<String> {
+    let compressed = Path::new(file)
+        .with_extension(COMPRESSED_EXTENSION)
+        .to_string_lossy()
+        .into_owned();
+    [compressed, file.to_string()]
+        .into_iter()
+        .find(|file| root.join(file).is_file())
+}
+
+#[derive(Resource, Debug)]
+pub struct EarthPacks {
+    pub available: Vec<EarthPack>,
+    pub selected: usize,
+    /// Set once the pack to load is decided, either on the command line or in the GUI
+    pub confirmed: bool,
+}
+
+impl EarthPacks {
+    /// Lists the bundled assets and everything in `packs/`, honouring `--assets <dir|zip>`.
+    pub fn discover() -> Self {
+        let base = FileAssetReader::get_base_path();
+        let mut available = vec![EarthPack::new(
+            "Default".to_string(),
+            base.join("assets"),
+            true,
+        )];
+
+        if let Ok(entries) = std::fs::read_dir(base.join(PACKS_DIR)) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if !path.is_dir() && path.extension().is_none_or(|ext| ext != "zip") {
+                    continue;
+                }
+                match EarthPack::open(&path) {
+                    Ok(pack) => available.push(pack),
+                    Err(err) => warn!("Skipping asset pack {}: {err}", path.display()),
+                }
+            }
+        }
+
+        let mut packs = Self {
+            available,
+            selected: 0,
+            confirmed: false,
+        };
+
+        let mut args = std::env::args().skip_while(|arg| arg != "--assets").skip(1);
+        if let Some(path) = args.next() {
+            match EarthPack::open(Path::new(&path)) {
+                Ok(pack) if pack.is_valid() => {
+                    packs.available.push(pack);
+                    packs.selected = packs.available.len() - 1;
+                    packs.confirmed = true;
+                }
+                Ok(pack) => error!("Asset pack {path} is missing {:?}", pack.missing),
+                Err(err) => error!("Failed to open asset pack {path}: {err}"),
+            }
+        }
+
+        packs.refresh();
+        packs
+    }
+
+    /// Re-checks the contents of every pack, e.g. after missing files were downloaded.
+    pub fn refresh(&mut self) {
+        for pack in &mut self.available {
+            *pack = EarthPack::new(pack.name.clone(), pack.root.clone(), pack.builtin);
+        }
+
+        // Nothing to choose from
+        if self.available.len() == 1 && self.active().is_valid() {
+            self.confirmed = true;
+        }
+    }
+
+    pub fn active(&self) -> &EarthPack {
+        &self.available[self.selected]
+    }
+
+    pub fn active_mut(&mut self) -> &mut EarthPack {
+        &mut self.available[self.selected]
+    }
+}
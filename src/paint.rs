@@ -0,0 +1,373 @@
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+use bevy::{
+    app::{App, Plugin, Startup, Update},
+    asset::{Assets, Handle, RenderAssetUsages},
+    ecs::{
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Local, Res, ResMut},
+    },
+    image::Image,
+    input::{ButtonInput, mouse::MouseButton},
+    math::Vec3,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+    state::condition::in_state,
+};
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+
+use crate::{
+    EARTH_RADIUS_KM, EarthConfig,
+    layer::{BlendMode, LayerInfo, RasterLayer, RasterLayers},
+    material::EarthMaterial,
+    math::{Coordinates, great_circle_point},
+    resource::{CursorHit, EarthMaterialTemplate, PointerOverUi},
+    state::{GameState, ToolMode},
+};
+
+/// Size of the equirectangular canvas the brush paints into.
+const CANVAS_SIZE: (u32, u32) = (4096, 2048);
+
+/// Distance from the shore within which land counts as coast.
+const COAST_KM: f32 = 25.;
+
+/// Roughness in the ocean mask below which a texel is water.
+const WATER_ROUGHNESS: f32 = 0.5;
+
+/// Name of the raster layer showing the canvas.
+const LAYER_NAME: &str = "Painting";
+
+/// Where on the surface a brush stroke leaves paint, judged by the ocean mask.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BrushMask {
+    #[default]
+    Anywhere,
+    Land,
+    Ocean,
+    /// Land within `COAST_KM` of the water
+    Coast,
+}
+
+impl BrushMask {
+    pub const ALL: [BrushMask; 4] = [
+        BrushMask::Anywhere,
+        BrushMask::Land,
+        BrushMask::Ocean,
+        BrushMask::Coast,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            BrushMask::Anywhere => "Anywhere",
+            BrushMask::Land => "Land only",
+            BrushMask::Ocean => "Ocean only",
+            BrushMask::Coast => "Coast only",
+        }
+    }
+}
+
+/// Brush of `ToolMode::Drawing`, painting onto a canvas shown as the bottom raster layer.
+#[derive(Resource)]
+pub struct Paint {
+    /// sRGB color of the brush
+    pub color: [f32; 3],
+    pub radius_km: f32,
+    pub mask: BrushMask,
+    canvas: Handle<Image>,
+}
+
+impl Default for Paint {
+    fn default() -> Self {
+        Self {
+            color: [1., 0.3, 0.1],
+            radius_km: 100.,
+            mask: BrushMask::Anywhere,
+            canvas: Handle::default(),
+        }
+    }
+}
+
+pub struct PaintPlugin;
+
+impl Plugin for PaintPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Paint>()
+            .add_systems(Startup, create_canvas)
+            .add_systems(
+                Update,
+                (
+                    add_paint_layer,
+                    paint_strokes.run_if(in_state(ToolMode::Drawing)),
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                EguiPrimaryContextPass,
+                display_brush.run_if(in_state(ToolMode::Drawing)),
+            );
+    }
+}
+
+fn create_canvas(mut paint: ResMut<Paint>, mut images: ResMut<Assets<Image>>) {
+    let (width, height) = CANVAS_SIZE;
+    paint.canvas = images.add(Image::new_fill(
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    ));
+}
+
+/// Puts the canvas back at the bottom of the layers whenever they were rediscovered, so it gets
+/// the first overlay slot.
+fn add_paint_layer(paint: Res<Paint>, mut layers: ResMut<RasterLayers>) {
+    if layers.0.iter().any(|layer| layer.image == paint.canvas) {
+        return;
+    }
+    layers.0.insert(
+        0,
+        RasterLayer {
+            name: LAYER_NAME.to_string(),
+            image: paint.canvas.clone(),
+            info: LayerInfo::default(),
+            opacity: 1.,
+            blend: BlendMode::Normal,
+            visible: true,
+        },
+    );
+}
+
+/// The ocean mask of the Earth material, read on the CPU to constrain the brush.
+struct OceanMask<'a> {
+    image: &'a Image,
+    width: u32,
+    height: u32,
+    /// Texels between a coastal land texel and the water it has to see
+    coast: u32,
+}
+
+impl<'a> OceanMask<'a> {
+    fn new(image: &'a Image) -> Self {
+        let (width, height) = (image.width(), image.height());
+        let coast = (COAST_KM / EARTH_RADIUS_KM * width as f32 / TAU)
+            .round()
+            .max(1.) as u32;
+        Self {
+            image,
+            width,
+            height,
+            coast,
+        }
+    }
+
+    fn is_water(&self, column: i64, row: i64) -> bool {
+        let column = column.rem_euclid(self.width as i64) as u32;
+        let row = row.clamp(0, self.height as i64 - 1) as u32;
+        self.image
+            .get_color_at(column, row)
+            .is_ok_and(|color| color.to_linear().green < WATER_ROUGHNESS)
+    }
+
+    /// Whether the texel of the mask at the same UV as `(u, v)` lets the brush paint.
+    fn allows(&self, mask: BrushMask, u: f32, v: f32) -> bool {
+        let column = (u * self.width as f32) as i64;
+        let row = (v * self.height as f32) as i64;
+        let water = self.is_water(column, row);
+        match mask {
+            BrushMask::Anywhere => true,
+            BrushMask::Land => !water,
+            BrushMask::Ocean => water,
+            BrushMask::Coast => {
+                let reach = self.coast as i64;
+                !water
+                    && [
+                        (-1, 0),
+                        (1, 0),
+                        (0, -1),
+                        (0, 1),
+                        (-1, -1),
+                        (1, 1),
+                        (-1, 1),
+                        (1, -1),
+                    ]
+                    .into_iter()
+                    .any(|(x, y)| self.is_water(column + x * reach, row + y * reach))
+            }
+        }
+    }
+}
+
+/// Texels of the canvas covered by a round dab of the brush centered on the local direction
+/// `center`, with its coverage fading out over the outer fifth of the radius.
+fn dab(center: Vec3, paint: &Paint, mask: Option<&OceanMask>) -> Vec<(usize, f32)> {
+    let (width, height) = CANVAS_SIZE;
+    let radius = (paint.radius_km / EARTH_RADIUS_KM).min(FRAC_PI_2);
+    let center = center.normalize();
+    let Coordinates {
+        latitude,
+        longitude,
+    } = Coordinates::from(center);
+
+    let texel = |fraction: f32, size: u32| (fraction * size as f32) as i64;
+    let north = (latitude + radius).min(FRAC_PI_2);
+    let south = (latitude - radius).max(-FRAC_PI_2);
+    let rows = texel(0.5 - north / PI, height).max(0)
+        ..=texel(0.5 - south / PI, height).min(height as i64 - 1);
+    // Around a pole the dab covers every longitude
+    let spread = if north >= FRAC_PI_2 || south <= -FRAC_PI_2 {
+        PI
+    } else {
+        radius / north.cos().min(south.cos())
+    };
+    let columns = if spread >= PI {
+        0..=width as i64 - 1
+    } else {
+        texel((longitude - spread) / TAU + 0.5, width)
+            ..=texel((longitude + spread) / TAU + 0.5, width)
+    };
+
+    let mut covered = Vec::new();
+    for row in rows {
+        let v = (row as f32 + 0.5) / height as f32;
+        let latitude = PI * (0.5 - v);
+        for column in columns.clone() {
+            let column = column.rem_euclid(width as i64);
+            let u = (column as f32 + 0.5) / width as f32;
+            let direction = Coordinates {
+                latitude,
+                longitude: TAU * (u - 0.5),
+            }
+            .direction();
+
+            let distance = direction.angle_between(center) / radius;
+            if distance > 1. || mask.is_some_and(|mask| !mask.allows(paint.mask, u, v)) {
+                continue;
+            }
+            covered.push((
+                row as usize * width as usize + column as usize,
+                ((1. - distance) / 0.2).min(1.),
+            ));
+        }
+    }
+    covered
+}
+
+/// Blends the brush color over the canvas, keeping the paint already there underneath.
+fn apply_dab(data: &mut [u8], covered: &[(usize, f32)], color: [f32; 3]) {
+    let color = color.map(|channel| channel.clamp(0., 1.) * 255.);
+    for &(index, coverage) in covered {
+        let texel = &mut data[index * 4..index * 4 + 4];
+        let alpha = texel[3] as f32 / 255.;
+        let out = coverage + alpha * (1. - coverage);
+        for channel in 0..3 {
+            let blended = (color[channel] * coverage
+                + texel[channel] as f32 * alpha * (1. - coverage))
+                / out.max(f32::EPSILON);
+            texel[channel] = blended as u8;
+        }
+        texel[3] = (out * 255.) as u8;
+    }
+}
+
+/// Paints along the cursor while the primary button is held, filling the gaps between frames so
+/// fast strokes stay continuous.
+fn paint_strokes(
+    paint: Res<Paint>,
+    mut images: ResMut<Assets<Image>>,
+    materials: Res<Assets<EarthMaterial>>,
+    template: Res<EarthMaterialTemplate>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    cursor: Res<CursorHit>,
+    over_ui: Res<PointerOverUi>,
+    config: Res<EarthConfig>,
+    mut last: Local<Option<Vec3>>,
+) {
+    let Some(hit) = cursor.filter(|_| buttons.pressed(MouseButton::Left) && !**over_ui) else {
+        *last = None;
+        return;
+    };
+
+    // Sampled before the canvas is borrowed, both live in the same `Assets<Image>`
+    let mask = if paint.mask == BrushMask::Anywhere {
+        None
+    } else {
+        let Some(image) = materials
+            .get(&**template)
+            .and_then(|material| material.extension.ocean_mask.as_ref())
+            .and_then(|handle| images.get(handle))
+        else {
+            return;
+        };
+        Some(OceanMask::new(image))
+    };
+
+    let radius = paint.radius_km / config.km_per_unit();
+    let from = last.unwrap_or(hit);
+    let steps = (from.distance(hit) / (radius / 2.)).ceil().max(1.) as usize;
+    let dabs: Vec<_> = (1..=steps)
+        .map(|step| great_circle_point(from, hit, step as f32 / steps as f32))
+        .map(|point| dab(point, &paint, mask.as_ref()))
+        .collect();
+
+    if let Some(data) = images
+        .get_mut(&paint.canvas)
+        .and_then(|image| image.data.as_mut())
+    {
+        for covered in &dabs {
+            apply_dab(data, covered, paint.color);
+        }
+    }
+    *last = Some(hit);
+}
+
+fn display_brush(
+    mut contexts: EguiContexts,
+    mut paint: ResMut<Paint>,
+    mut images: ResMut<Assets<Image>>,
+) -> bevy::prelude::Result {
+    let ctx = contexts.ctx_mut()?;
+
+    let mut clear = false;
+    egui::Window::new("Brush")
+        .resizable(false)
+        .anchor(egui::Align2::LEFT_TOP, [10., 40.])
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Color");
+                ui.color_edit_button_rgb(&mut paint.color);
+            });
+            ui.add(
+                egui::Slider::new(&mut paint.radius_km, 5.0..=2000.)
+                    .logarithmic(true)
+                    .suffix(" km")
+                    .text("Radius"),
+            );
+            egui::ComboBox::from_label("Paint on")
+                .selected_text(paint.mask.label())
+                .show_ui(ui, |ui| {
+                    for mask in BrushMask::ALL {
+                        ui.selectable_value(&mut paint.mask, mask, mask.label());
+                    }
+                });
+            ui.separator();
+            if ui.button("Clear").clicked() {
+                clear = true;
+            }
+        });
+
+    if clear
+        && let Some(data) = images
+            .get_mut(&paint.canvas)
+            .and_then(|image| image.data.as_mut())
+    {
+        data.fill(0);
+    }
+
+    Ok(())
+}
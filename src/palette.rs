@@ -0,0 +1,288 @@
+use std::{cmp::Reverse, sync::Arc};
+
+use bevy::{
+    app::{App, Plugin, Update},
+    ecs::{
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, IntoSystem, Res, ResMut, SystemId},
+        world::World,
+    },
+    input::{ButtonInput, keyboard::KeyCode},
+    log::warn,
+    state::condition::in_state,
+};
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+
+use crate::{
+    input::{Action, KeyBindings, TriggeredActions},
+    state::GameState,
+};
+
+/// Rows listed at once, the best matches first.
+const MAX_RESULTS: usize = 12;
+
+/// Something the command palette can run, with the label it is found by.
+#[derive(Clone)]
+pub struct PaletteCommand {
+    pub label: String,
+    /// Key action it triggers, whose key is shown next to it
+    action: Option<Action>,
+    run: Arc<dyn Fn(&mut World) + Send + Sync>,
+}
+
+impl PaletteCommand {
+    pub fn new(label: impl Into<String>, run: impl Fn(&mut World) + Send + Sync + 'static) -> Self {
+        Self {
+            label: label.into(),
+            action: None,
+            run: Arc::new(run),
+        }
+    }
+
+    /// Triggers a key action as if its key was pressed.
+    pub fn action(action: Action) -> Self {
+        Self {
+            action: Some(action),
+            ..Self::new(action.label(), move |world| {
+                world.resource_mut::<TriggeredActions>().trigger(action);
+            })
+        }
+    }
+}
+
+/// Everything listed by the command palette.
+///
+/// Plugins register fixed commands as one-shot systems, and providers for commands that come
+/// and go with the data, such as one per layer, which are asked each time the palette opens.
+/// See `RegisterCommand`.
+#[derive(Resource, Default)]
+pub struct ActionRegistry {
+    commands: Vec<PaletteCommand>,
+    providers: Vec<SystemId<(), Vec<PaletteCommand>>>,
+}
+
+/// Registration of commands for the palette while building the app.
+pub trait RegisterCommand {
+    /// Lists `system` in the palette under `label`.
+    fn register_command<M>(
+        &mut self,
+        label: impl Into<String>,
+        system: impl IntoSystem<(), (), M> + 'static,
+    ) -> &mut Self;
+
+    /// Lists the commands returned by `provider` whenever the palette opens.
+    fn register_command_provider<M>(
+        &mut self,
+        provider: impl IntoSystem<(), Vec<PaletteCommand>, M> + 'static,
+    ) -> &mut Self;
+}
+
+impl RegisterCommand for App {
+    fn register_command<M>(
+        &mut self,
+        label: impl Into<String>,
+        system: impl IntoSystem<(), (), M> + 'static,
+    ) -> &mut Self {
+        let label = label.into();
+        let world = self.world_mut();
+        let system = world.register_system(system);
+        let command = PaletteCommand::new(label.clone(), move |world| {
+            if let Err(err) = world.run_system(system) {
+                warn!("Command {label:?} failed: {err}");
+            }
+        });
+        world
+            .get_resource_or_init::<ActionRegistry>()
+            .commands
+            .push(command);
+        self
+    }
+
+    fn register_command_provider<M>(
+        &mut self,
+        provider: impl IntoSystem<(), Vec<PaletteCommand>, M> + 'static,
+    ) -> &mut Self {
+        let world = self.world_mut();
+        let provider = world.register_system(provider);
+        world
+            .get_resource_or_init::<ActionRegistry>()
+            .providers
+            .push(provider);
+        self
+    }
+}
+
+/// Fuzzy search over every registered command, opened with Ctrl+P.
+#[derive(Resource, Default)]
+pub struct CommandPalette {
+    pub open: bool,
+    query: String,
+    /// Index into the matches of `query`
+    selected: usize,
+    /// Commands collected when the palette opened
+    commands: Vec<PaletteCommand>,
+}
+
+pub struct CommandPalettePlugin;
+
+impl Plugin for CommandPalettePlugin {
+    fn build(&self, app: &mut App) {
+        app.world_mut()
+            .get_resource_or_init::<ActionRegistry>()
+            .commands
+            .extend(Action::ALL.map(PaletteCommand::action));
+
+        app.init_resource::<CommandPalette>()
+            .add_systems(Update, toggle_palette.run_if(in_state(GameState::Playing)))
+            .add_systems(
+                EguiPrimaryContextPass,
+                display_palette
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(|palette: Res<CommandPalette>| palette.open),
+            );
+    }
+}
+
+/// Score of `label` for what was typed, or `None` unless all characters of `query` appear in it
+/// in order. Runs of consecutive characters and characters starting a word score higher.
+fn fuzzy_score(query: &str, label: &str) -> Option<i32> {
+    let label: Vec<char> = label.chars().flat_map(char::to_lowercase).collect();
+    let mut score = 0;
+    let mut start = 0;
+    let mut previous = None;
+    for wanted in query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+    {
+        let found = start + label[start..].iter().position(|&c| c == wanted)?;
+        score += 1;
+        if found > 0 && previous == Some(found - 1) {
+            score += 4;
+        }
+        if found == 0 || !label[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        previous = Some(found);
+        start = found + 1;
+    }
+    Some(score)
+}
+
+/// Collects the commands of the registry and its providers into the palette.
+fn collect_commands(world: &mut World) {
+    let registry = world.resource::<ActionRegistry>();
+    let mut commands = registry.commands.clone();
+    for provider in registry.providers.clone() {
+        match world.run_system(provider) {
+            Ok(provided) => commands.extend(provided),
+            Err(err) => warn!("Failed to list palette commands: {err}"),
+        }
+    }
+    commands.sort_by(|a, b| a.label.cmp(&b.label));
+    world.resource_mut::<CommandPalette>().commands = commands;
+}
+
+fn toggle_palette(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut palette: ResMut<CommandPalette>,
+) {
+    if !keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+        || !keyboard.just_pressed(KeyCode::KeyP)
+    {
+        return;
+    }
+
+    palette.open = !palette.open;
+    if palette.open {
+        palette.query.clear();
+        palette.selected = 0;
+        commands.queue(collect_commands);
+    }
+}
+
+fn display_palette(
+    mut contexts: EguiContexts,
+    mut commands: Commands,
+    mut palette: ResMut<CommandPalette>,
+    bindings: Res<KeyBindings>,
+) -> bevy::prelude::Result {
+    let ctx = contexts.ctx_mut()?;
+
+    let palette = &mut *palette;
+    let mut matches: Vec<(i32, usize)> = palette
+        .commands
+        .iter()
+        .enumerate()
+        .filter_map(|(index, command)| {
+            fuzzy_score(&palette.query, &command.label).map(|score| (score, index))
+        })
+        .collect();
+    matches.sort_by_key(|&(score, index)| (Reverse(score), index));
+    matches.truncate(MAX_RESULTS);
+
+    let (up, down, enter, escape) = ctx.input(|input| {
+        (
+            input.key_pressed(egui::Key::ArrowUp),
+            input.key_pressed(egui::Key::ArrowDown),
+            input.key_pressed(egui::Key::Enter),
+            input.key_pressed(egui::Key::Escape),
+        )
+    });
+    if down {
+        palette.selected += 1;
+    }
+    if up {
+        palette.selected = palette.selected.saturating_sub(1);
+    }
+    palette.selected = palette.selected.min(matches.len().saturating_sub(1));
+
+    let mut chosen = enter.then_some(palette.selected);
+    egui::Window::new("Command palette")
+        .title_bar(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_TOP, [0., 60.])
+        .show(ctx, |ui| {
+            ui.set_width(420.);
+            let field = ui.add(
+                egui::TextEdit::singleline(&mut palette.query)
+                    .hint_text("Type a command")
+                    .desired_width(f32::INFINITY),
+            );
+            field.request_focus();
+            if field.changed() {
+                palette.selected = 0;
+            }
+
+            ui.separator();
+            if matches.is_empty() {
+                ui.label("No matching command");
+            }
+            for (row, &(_, index)) in matches.iter().enumerate() {
+                let command = &palette.commands[index];
+                ui.horizontal(|ui| {
+                    let label = ui.selectable_label(row == palette.selected, &command.label);
+                    if label.clicked() {
+                        chosen = Some(row);
+                    }
+                    if let Some(action) = command.action {
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            ui.weak(bindings.label(action));
+                        });
+                    }
+                });
+            }
+        });
+
+    if let Some(&(_, index)) = chosen.and_then(|row| matches.get(row)) {
+        let run = palette.commands[index].run.clone();
+        commands.queue(move |world: &mut World| run(world));
+        palette.open = false;
+    } else if escape {
+        palette.open = false;
+    }
+
+    Ok(())
+}
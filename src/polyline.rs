@@ -0,0 +1,353 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    asset::{Asset, Assets, RenderAssetUsages},
+    camera::Camera,
+    color::LinearRgba,
+    ecs::{
+        query::With,
+        system::{Res, ResMut, Single},
+    },
+    math::{Vec3, Vec4},
+    mesh::{
+        Indices, Mesh, MeshBuilder, MeshVertexAttribute, MeshVertexBufferLayoutRef,
+        PrimitiveTopology,
+    },
+    pbr::{Material, MaterialPipeline, MaterialPipelineKey, MaterialPlugin},
+    reflect::Reflect,
+    render::{
+        alpha::AlphaMode,
+        render_resource::{
+            AsBindGroup, RenderPipelineDescriptor, ShaderType, SpecializedMeshPipelineError,
+            VertexFormat,
+        },
+    },
+    shader::ShaderRef,
+    transform::components::GlobalTransform,
+};
+
+use crate::{
+    EarthConfig,
+    component::{Earth, MainCamera},
+    depth::DRAPED_DEPTH_BIAS,
+    math::ground_distance_per_pixel,
+};
+
+const SHADER_PATH: &str = "shaders/polyline.wgsl";
+
+/// The other end of the segment a vertex belongs to.
+pub const ATTRIBUTE_OTHER: MeshVertexAttribute =
+    MeshVertexAttribute::new("Polyline_Other", 871_204_001, VertexFormat::Float32x3);
+
+/// The point after this vertex's end of the segment, or the end itself at the ends of the line.
+pub const ATTRIBUTE_NEIGHBOR: MeshVertexAttribute =
+    MeshVertexAttribute::new("Polyline_Neighbor", 871_204_002, VertexFormat::Float32x3);
+
+/// x = side of the line (-1 or 1), y = 0 at the start of the segment and 1 at its end.
+pub const ATTRIBUTE_SEGMENT: MeshVertexAttribute =
+    MeshVertexAttribute::new("Polyline_Segment", 871_204_003, VertexFormat::Float32x2);
+
+/// Distance from the start of the line, in local units.
+pub const ATTRIBUTE_DISTANCE: MeshVertexAttribute =
+    MeshVertexAttribute::new("Polyline_Distance", 871_204_004, VertexFormat::Float32);
+
+/// How consecutive segments are connected.
+#[derive(Reflect, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineJoin {
+    /// Sharp corners, falling back to a bevel past the miter limit
+    #[default]
+    Miter,
+    /// Rounded corners and caps
+    Round,
+}
+
+impl LineJoin {
+    fn shader_id(&self) -> u32 {
+        match self {
+            LineJoin::Miter => 0,
+            LineJoin::Round => 1,
+        }
+    }
+}
+
+/// A line through `points`, meshed as one quad per segment that the vertex shader expands to a
+/// constant width on screen.
+#[derive(Debug, Clone, Default)]
+pub struct Polyline {
+    pub points: Vec<Vec3>,
+    /// Connects the last point back to the first
+    pub closed: bool,
+}
+
+impl Polyline {
+    pub fn new(points: impl IntoIterator<Item = Vec3>) -> Self {
+        Self {
+            points: points.into_iter().collect(),
+            closed: false,
+        }
+    }
+
+    pub fn closed(mut self) -> Self {
+        self.closed = true;
+        self
+    }
+}
+
+impl MeshBuilder for Polyline {
+    fn build(&self) -> Mesh {
+        let points = &self.points;
+        let count = points.len();
+        let segments = match count {
+            0 | 1 => 0,
+            _ if self.closed => count,
+            _ => count - 1,
+        };
+        let point = |index: usize| points[index % count];
+        // Points past the ends of an open line repeat the end, which the shader treats as a cap
+        let before = |index: usize| {
+            if index > 0 || self.closed {
+                point(index + count - 1)
+            } else {
+                point(index)
+            }
+        };
+        let after = |index: usize| {
+            if index + 1 < count || self.closed {
+                point(index + 1)
+            } else {
+                point(index)
+            }
+        };
+
+        let mut positions = Vec::with_capacity(segments * 4);
+        let mut others = Vec::with_capacity(segments * 4);
+        let mut neighbors = Vec::with_capacity(segments * 4);
+        let mut sides = Vec::with_capacity(segments * 4);
+        let mut distances = Vec::with_capacity(segments * 4);
+        let mut indices = Vec::with_capacity(segments * 6);
+
+        let mut distance = 0.;
+        for segment in 0..segments {
+            let (start, end) = (point(segment), point(segment + 1));
+            let length = start.distance(end);
+
+            // The outward direction flips between both ends, so does the side to keep the quad
+            for side in [-1., 1.] {
+                positions.push(start.to_array());
+                others.push(end.to_array());
+                neighbors.push(before(segment).to_array());
+                sides.push([-side, 0.]);
+                distances.push(distance);
+            }
+            for side in [-1., 1.] {
+                positions.push(end.to_array());
+                others.push(start.to_array());
+                neighbors.push(after(segment + 1).to_array());
+                sides.push([side, 1.]);
+                distances.push(distance + length);
+            }
+
+            let base = segment as u32 * 4;
+            indices.extend([base, base + 1, base + 2, base + 2, base + 1, base + 3]);
+            distance += length;
+        }
+
+        Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(ATTRIBUTE_OTHER, others)
+        .with_inserted_attribute(ATTRIBUTE_NEIGHBOR, neighbors)
+        .with_inserted_attribute(ATTRIBUTE_SEGMENT, sides)
+        .with_inserted_attribute(ATTRIBUTE_DISTANCE, distances)
+        .with_inserted_indices(Indices::U32(indices))
+    }
+}
+
+#[derive(ShaderType, Reflect, Debug, Clone, Copy)]
+pub struct PolylineUniform {
+    pub color: LinearRgba,
+    /// Width in pixels
+    pub width: f32,
+    /// `LineJoin` of the corners
+    pub join: u32,
+    /// Longest miter relative to the width before the corner is beveled
+    pub miter_limit: f32,
+    /// Ground distance covered by one pixel, kept current by `update_pixel_scale`
+    pub world_per_pixel: f32,
+    /// Up to two dash and gap pairs in pixels, a solid line while the first dash is zero
+    pub dash: Vec4,
+    /// Drawn in the gaps between dashes, transparent by default
+    pub gap_color: LinearRgba,
+    /// Pixels per second the dashes move along the line, negative to move backwards
+    pub dash_speed: f32,
+    /// Color at the end of the line, blended from `color` at its start
+    pub end_color: LinearRgba,
+    /// Total length of the line in local units, needed for gradients and pulses
+    pub line_length: f32,
+    /// x = pulses per second travelling from start to end, y = tail length as a fraction of the
+    /// line, z = brightness added at the head; no pulse while x is zero
+    pub pulse: Vec4,
+}
+
+#[derive(Asset, AsBindGroup, Reflect, Debug, Clone)]
+pub struct PolylineMaterial {
+    #[uniform(0)]
+    pub uniform: PolylineUniform,
+}
+
+impl PolylineMaterial {
+    pub fn new(color: impl Into<LinearRgba>, width: f32) -> Self {
+        let color = color.into();
+        Self {
+            uniform: PolylineUniform {
+                color,
+                width,
+                join: LineJoin::default().shader_id(),
+                miter_limit: 4.,
+                world_per_pixel: 1.,
+                dash: Vec4::ZERO,
+                gap_color: LinearRgba::NONE,
+                dash_speed: 0.,
+                end_color: color,
+                line_length: 1.,
+                pulse: Vec4::ZERO,
+            },
+        }
+    }
+
+    pub fn with_join(mut self, join: LineJoin) -> Self {
+        self.uniform.join = join.shader_id();
+        self
+    }
+
+    /// Dash and gap length in pixels.
+    pub fn with_dash(self, dash: f32, gap: f32) -> Self {
+        self.with_dash_pattern([dash, gap, 0., 0.])
+    }
+
+    /// Two dash and gap pairs in pixels, e.g. for dash-dot lines.
+    pub fn with_dash_pattern(mut self, pattern: [f32; 4]) -> Self {
+        self.uniform.dash = Vec4::from_array(pattern);
+        self
+    }
+
+    pub fn with_gap_color(mut self, color: impl Into<LinearRgba>) -> Self {
+        self.uniform.gap_color = color.into();
+        self
+    }
+
+    /// Moves the dashes towards the end of the line, showing its direction.
+    pub fn animated(mut self, pixels_per_second: f32) -> Self {
+        self.uniform.dash_speed = pixels_per_second;
+        self
+    }
+
+    /// Blends from the line's color at its start to `end_color`, over a line of `line_length`.
+    pub fn with_gradient(mut self, end_color: impl Into<LinearRgba>, line_length: f32) -> Self {
+        self.uniform.end_color = end_color.into();
+        self.uniform.line_length = line_length;
+        self
+    }
+
+    /// Sends a bright pulse from start to end every `1 / per_second` seconds, easing in and out
+    /// at both ends. Needs the `line_length` set by `with_gradient`.
+    pub fn with_pulse(mut self, per_second: f32, tail: f32, brightness: f32) -> Self {
+        self.uniform.pulse = Vec4::new(per_second, tail, brightness, 0.);
+        self
+    }
+
+    /// Black and white dashes crawling along the line, for selection outlines.
+    pub fn marching_ants(width: f32) -> Self {
+        Self::new(LinearRgba::BLACK, width)
+            .with_dash(6., 6.)
+            .with_gap_color(LinearRgba::WHITE)
+            .animated(20.)
+    }
+}
+
+impl Material for PolylineMaterial {
+    fn vertex_shader() -> ShaderRef {
+        SHADER_PATH.into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        SHADER_PATH.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        // Edges are antialiased through the alpha channel
+        AlphaMode::Blend
+    }
+
+    fn depth_bias(&self) -> f32 {
+        DRAPED_DEPTH_BIAS
+    }
+
+    // The prepass and shadow shaders don't know how to expand the quads
+    fn enable_prepass() -> bool {
+        false
+    }
+
+    fn enable_shadows() -> bool {
+        false
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayoutRef,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        let vertex_layout = layout.0.get_layout(&[
+            Mesh::ATTRIBUTE_POSITION.at_shader_location(0),
+            ATTRIBUTE_OTHER.at_shader_location(1),
+            ATTRIBUTE_NEIGHBOR.at_shader_location(2),
+            ATTRIBUTE_SEGMENT.at_shader_location(3),
+            ATTRIBUTE_DISTANCE.at_shader_location(4),
+        ])?;
+        descriptor.vertex.buffers = vec![vertex_layout];
+        // The winding of the expanded quads depends on the direction of the line on screen
+        descriptor.primitive.cull_mode = None;
+        Ok(())
+    }
+}
+
+pub struct PolylinePlugin;
+
+impl Plugin for PolylinePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(MaterialPlugin::<PolylineMaterial>::default())
+            .add_systems(Update, update_pixel_scale);
+    }
+}
+
+/// Keeps dash lengths in pixels by telling the materials how much ground a pixel covers.
+fn update_pixel_scale(
+    config: Res<EarthConfig>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera>>,
+    earth: Single<&GlobalTransform, With<Earth>>,
+    mut materials: ResMut<Assets<PolylineMaterial>>,
+) {
+    let (camera, transform) = *camera;
+    let Some(world_per_pixel) =
+        ground_distance_per_pixel(camera, transform, earth.translation(), config.radius)
+    else {
+        return;
+    };
+
+    // Only touch dashed lines, and only when the scale moved noticeably
+    let stale: Vec<_> = materials
+        .iter()
+        .filter(|(_, material)| {
+            material.uniform.dash.x > 0.
+                && (material.uniform.world_per_pixel / world_per_pixel - 1.).abs() > 0.01
+        })
+        .map(|(id, _)| id)
+        .collect();
+    for id in stale {
+        if let Some(material) = materials.get_mut(id) {
+            material.uniform.world_per_pixel = world_per_pixel;
+        }
+    }
+}
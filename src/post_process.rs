@@ -0,0 +1,371 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    asset::AssetServer,
+    core_pipeline::{
+        FullscreenShader,
+        core_3d::graph::{Core3d, Node3d},
+    },
+    ecs::{
+        change_detection::DetectChanges,
+        component::Component,
+        entity::Entity,
+        query::{QueryItem, With},
+        resource::Resource,
+        system::{Commands, Res, Single},
+        world::World,
+    },
+    image::BevyDefault,
+    math::Vec3,
+    render::{
+        RenderApp, RenderStartup,
+        extract_component::{
+            ComponentUniforms, DynamicUniformIndex, ExtractComponent, ExtractComponentPlugin,
+            UniformComponentPlugin,
+        },
+        render_graph::{
+            NodeRunError, RenderGraphContext, RenderGraphExt, RenderLabel, ViewNode, ViewNodeRunner,
+        },
+        render_resource::{
+            BindGroupEntries, BindGroupLayout, BindGroupLayoutEntries, CachedRenderPipelineId,
+            ColorTargetState, ColorWrites, FragmentState, Operations, PipelineCache,
+            RenderPassColorAttachment, RenderPassDescriptor, RenderPipelineDescriptor, Sampler,
+            SamplerBindingType, SamplerDescriptor, ShaderStages, ShaderType, TextureFormat,
+            TextureSampleType,
+            binding_types::{sampler, texture_2d, uniform_buffer},
+        },
+        renderer::{RenderContext, RenderDevice},
+        view::ViewTarget,
+    },
+    time::Time,
+};
+
+use crate::component::MainCamera;
+
+const SHADER_PATH: &str = "shaders/post_process.wgsl";
+
+/// A fullscreen pass of the `PostProcessing` stack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PostEffect {
+    /// Crisper edges, countering the blur of upscaled imagery
+    Sharpen,
+    /// Color fringes growing towards the edges, like a cheap lens
+    ChromaticAberration,
+    /// Animated noise, like film stock
+    FilmGrain,
+    /// Darkened corners, drawing the eye to the center
+    Vignette,
+}
+
+impl PostEffect {
+    pub const ALL: [PostEffect; 4] = [
+        PostEffect::Sharpen,
+        PostEffect::ChromaticAberration,
+        PostEffect::FilmGrain,
+        PostEffect::Vignette,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PostEffect::Sharpen => "Sharpen",
+            PostEffect::ChromaticAberration => "Chromatic aberration",
+            PostEffect::FilmGrain => "Film grain",
+            PostEffect::Vignette => "Vignette",
+        }
+    }
+
+    fn entry_point(&self) -> &'static str {
+        match self {
+            PostEffect::Sharpen => "sharpen",
+            PostEffect::ChromaticAberration => "chromatic_aberration",
+            PostEffect::FilmGrain => "film_grain",
+            PostEffect::Vignette => "vignette",
+        }
+    }
+
+    fn default_strength(&self) -> f32 {
+        match self {
+            PostEffect::Sharpen => 0.3,
+            PostEffect::ChromaticAberration => 0.5,
+            PostEffect::FilmGrain => 0.05,
+            PostEffect::Vignette => 0.4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PostEffectSettings {
+    pub effect: PostEffect,
+    pub enabled: bool,
+    /// From zero, no change, to one
+    pub strength: f32,
+}
+
+/// Fullscreen effects applied to the main camera after the tonemapping, in the order of the
+/// stack. Each enabled effect is a pass of its own reading the output of the one before.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct PostProcessing {
+    pub stack: Vec<PostEffectSettings>,
+}
+
+impl Default for PostProcessing {
+    fn default() -> Self {
+        Self {
+            stack: PostEffect::ALL
+                .into_iter()
+                .map(|effect| PostEffectSettings {
+                    effect,
+                    enabled: false,
+                    strength: effect.default_strength(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl PostProcessing {
+    /// Swaps the effect at `index` with the one applied before it.
+    pub fn move_up(&mut self, index: usize) {
+        if index > 0 && index < self.stack.len() {
+            self.stack.swap(index - 1, index);
+        }
+    }
+
+    fn strength(&self, effect: PostEffect) -> f32 {
+        self.stack
+            .iter()
+            .find(|settings| settings.effect == effect)
+            .map_or(0., |settings| settings.strength)
+    }
+}
+
+#[derive(Component, ShaderType, Debug, Clone, Copy, Default)]
+struct PostProcessUniform {
+    sharpen: f32,
+    aberration: f32,
+    grain: f32,
+    vignette: f32,
+    /// Seconds, to animate the grain
+    time: f32,
+    // WebGL2 wants uniforms in multiples of 16 bytes
+    _padding: Vec3,
+}
+
+/// The passes the main camera runs, copied over from `PostProcessing`.
+#[derive(Component, Debug, Clone)]
+struct PostProcessCamera {
+    passes: Vec<PostEffect>,
+    uniform: PostProcessUniform,
+}
+
+/// The enabled effects of a view in the render world, in order.
+#[derive(Component, Debug, Clone)]
+struct PostProcessPasses(Vec<PostEffect>);
+
+impl ExtractComponent for PostProcessCamera {
+    type QueryData = &'static Self;
+    type QueryFilter = ();
+    type Out = (PostProcessPasses, PostProcessUniform);
+
+    fn extract_component(camera: QueryItem<Self::QueryData>) -> Option<Self::Out> {
+        (!camera.passes.is_empty())
+            .then(|| (PostProcessPasses(camera.passes.clone()), camera.uniform))
+    }
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+struct PostProcessLabel;
+
+pub struct PostProcessPlugin;
+
+impl Plugin for PostProcessPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PostProcessing>()
+            .add_plugins((
+                ExtractComponentPlugin::<PostProcessCamera>::default(),
+                UniformComponentPlugin::<PostProcessUniform>::default(),
+            ))
+            .add_systems(Update, apply_post_processing);
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .add_systems(RenderStartup, init_post_process_pipelines)
+            .add_render_graph_node::<ViewNodeRunner<PostProcessNode>>(Core3d, PostProcessLabel)
+            .add_render_graph_edges(
+                Core3d,
+                (
+                    Node3d::Tonemapping,
+                    PostProcessLabel,
+                    Node3d::EndMainPassPostProcessing,
+                ),
+            );
+    }
+}
+
+fn apply_post_processing(
+    mut commands: Commands,
+    settings: Res<PostProcessing>,
+    time: Res<Time>,
+    camera: Single<Entity, With<MainCamera>>,
+) {
+    let passes = settings
+        .stack
+        .iter()
+        .filter(|settings| settings.enabled && settings.strength > 0.)
+        .map(|settings| settings.effect)
+        .collect::<Vec<_>>();
+    // Only the grain changes from frame to frame
+    if !settings.is_changed() && !passes.contains(&PostEffect::FilmGrain) {
+        return;
+    }
+
+    commands.entity(*camera).insert(PostProcessCamera {
+        passes,
+        uniform: PostProcessUniform {
+            sharpen: settings.strength(PostEffect::Sharpen),
+            aberration: settings.strength(PostEffect::ChromaticAberration),
+            grain: settings.strength(PostEffect::FilmGrain),
+            vignette: settings.strength(PostEffect::Vignette),
+            time: time.elapsed_secs_wrapped(),
+            _padding: Vec3::ZERO,
+        },
+    });
+}
+
+/// A pipeline per effect and texture format, since the main texture is only HDR for cameras
+/// asking for it.
+#[derive(Resource)]
+struct PostProcessPipelines {
+    layout: BindGroupLayout,
+    sampler: Sampler,
+    pipelines: Vec<(PostEffect, TextureFormat, CachedRenderPipelineId)>,
+}
+
+impl PostProcessPipelines {
+    fn get(&self, effect: PostEffect, format: TextureFormat) -> Option<CachedRenderPipelineId> {
+        self.pipelines
+            .iter()
+            .find(|(e, f, _)| *e == effect && *f == format)
+            .map(|(_, _, id)| *id)
+    }
+}
+
+fn init_post_process_pipelines(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    asset_server: Res<AssetServer>,
+    fullscreen_shader: Res<FullscreenShader>,
+    pipeline_cache: Res<PipelineCache>,
+) {
+    let layout = render_device.create_bind_group_layout(
+        "post_process_bind_group_layout",
+        &BindGroupLayoutEntries::sequential(
+            ShaderStages::FRAGMENT,
+            (
+                texture_2d(TextureSampleType::Float { filterable: true }),
+                sampler(SamplerBindingType::Filtering),
+                uniform_buffer::<PostProcessUniform>(true),
+            ),
+        ),
+    );
+    let sampler = render_device.create_sampler(&SamplerDescriptor::default());
+    let shader = asset_server.load(SHADER_PATH);
+
+    let mut pipelines = Vec::new();
+    for effect in PostEffect::ALL {
+        for format in [
+            TextureFormat::bevy_default(),
+            ViewTarget::TEXTURE_FORMAT_HDR,
+        ] {
+            let id = pipeline_cache.queue_render_pipeline(RenderPipelineDescriptor {
+                label: Some(format!("post_process_{}_pipeline", effect.entry_point()).into()),
+                layout: vec![layout.clone()],
+                vertex: fullscreen_shader.to_vertex_state(),
+                fragment: Some(FragmentState {
+                    shader: shader.clone(),
+                    entry_point: Some(effect.entry_point().into()),
+                    targets: vec![Some(ColorTargetState {
+                        format,
+                        blend: None,
+                        write_mask: ColorWrites::ALL,
+                    })],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            });
+            pipelines.push((effect, format, id));
+        }
+    }
+
+    commands.insert_resource(PostProcessPipelines {
+        layout,
+        sampler,
+        pipelines,
+    });
+}
+
+#[derive(Default)]
+struct PostProcessNode;
+
+impl ViewNode for PostProcessNode {
+    type ViewQuery = (
+        &'static ViewTarget,
+        &'static PostProcessPasses,
+        &'static DynamicUniformIndex<PostProcessUniform>,
+    );
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (view_target, passes, uniform_index): QueryItem<Self::ViewQuery>,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let post_process = world.resource::<PostProcessPipelines>();
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let uniforms = world.resource::<ComponentUniforms<PostProcessUniform>>();
+        let Some(uniform_binding) = uniforms.uniforms().binding() else {
+            return Ok(());
+        };
+
+        for &effect in &passes.0 {
+            let Some(pipeline) = post_process
+                .get(effect, view_target.main_texture_format())
+                .and_then(|id| pipeline_cache.get_render_pipeline(id))
+            else {
+                // Still compiling
+                continue;
+            };
+
+            // Flips the main textures, so the next pass reads this one's output
+            let target = view_target.post_process_write();
+            let bind_group = render_context.render_device().create_bind_group(
+                "post_process_bind_group",
+                &post_process.layout,
+                &BindGroupEntries::sequential((
+                    target.source,
+                    &post_process.sampler,
+                    uniform_binding.clone(),
+                )),
+            );
+            let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+                label: Some("post_process_pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: target.destination,
+                    depth_slice: None,
+                    resolve_target: None,
+                    ops: Operations::default(),
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            render_pass.set_render_pipeline(pipeline);
+            render_pass.set_bind_group(0, &bind_group, &[uniform_index.index()]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,117 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    ecs::{
+        message::MessageWriter,
+        query::{Or, With},
+        resource::Resource,
+        schedule::{IntoScheduleConfigs, SystemCondition, common_conditions::resource_changed},
+        system::{Query, Res, ResMut, Single},
+    },
+    input::{ButtonInput, keyboard::KeyCode, mouse::MouseButton},
+    state::{
+        condition::{in_state, state_changed},
+        state::State,
+    },
+    window::RequestRedraw,
+    winit::WinitSettings,
+};
+
+use crate::{
+    component::{ComputeMesh, FovAnimation, RotationAnimation, ZoomAnimation},
+    download::Downloads,
+    layer::LayerDownloads,
+    observer::OrbitCamera,
+    replay::Replay,
+    resource::SimulationTime,
+    sky::SkyTexture,
+    snapshot::SnapshotRunner,
+    space::SpaceView,
+    state::{GameState, ToolMode},
+    tiles::TileStream,
+};
+
+/// Renders only while something changes on screen, so the globe costs next to nothing while it
+/// sits idle.
+///
+/// Input wakes the app up, and systems below keep it rendering while anything animates. The
+/// running simulation moves the sun, so the savings only kick in once it is paused.
+#[derive(Resource, Debug, Default)]
+pub struct PowerSaving {
+    pub enabled: bool,
+}
+
+pub struct PowerSavingPlugin;
+
+impl Plugin for PowerSavingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PowerSaving>().add_systems(
+            Update,
+            (
+                apply_update_mode
+                    .run_if(resource_changed::<PowerSaving>.or(state_changed::<GameState>)),
+                keep_animating
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(|power: Res<PowerSaving>| power.enabled),
+            ),
+        );
+    }
+}
+
+/// Loading drives tasks and asset events, which don't wake the app, so only the globe waits for
+/// input.
+fn apply_update_mode(
+    power: Res<PowerSaving>,
+    state: Res<State<GameState>>,
+    mut settings: ResMut<WinitSettings>,
+) {
+    *settings = if power.enabled && *state.get() == GameState::Playing {
+        WinitSettings::desktop_app()
+    } else {
+        WinitSettings::game()
+    };
+}
+
+/// Requests another frame as long as anything on screen moves on its own.
+fn keep_animating(
+    mut redraw: MessageWriter<RequestRedraw>,
+    simulation: Res<SimulationTime>,
+    space: Res<SpaceView>,
+    replay: Res<Replay>,
+    snapshots: Res<SnapshotRunner>,
+    downloads: Res<Downloads>,
+    layer_downloads: Res<LayerDownloads>,
+    tiles: Res<TileStream>,
+    sky: Res<SkyTexture>,
+    orbit: Single<&OrbitCamera>,
+    mode: Res<State<ToolMode>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    animations: Query<
+        (),
+        Or<(
+            With<RotationAnimation>,
+            With<ZoomAnimation>,
+            With<FovAnimation>,
+            With<ComputeMesh>,
+        )>,
+    >,
+) {
+    let animating = !simulation.paused
+        || (space.is_active() && !space.is_in_space())
+        || replay.is_playing()
+        || snapshots.is_running()
+        || downloads.is_busy()
+        // Tasks finishing don't wake the app, what they bring would only show on the next input
+        || layer_downloads.is_busy()
+        || tiles.pending() > 0
+        || sky.is_preparing()
+        || orbit.is_moving()
+        || **mode == ToolMode::Touring
+        // Held keys and drags move the camera without sending further events
+        || keyboard.get_pressed().next().is_some()
+        || mouse.get_pressed().next().is_some()
+        || !animations.is_empty();
+    if animating {
+        redraw.write(RequestRedraw);
+    }
+}
@@ -0,0 +1,233 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    ecs::{
+        change_detection::DetectChangesMut,
+        entity::Entity,
+        query::With,
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Local, Res, ResMut, Single},
+    },
+    light::{CascadeShadowConfigBuilder, DirectionalLight, DirectionalLightShadowMap},
+    render::view::Msaa,
+    state::condition::in_state,
+    time::Time,
+};
+
+use crate::{
+    CAMERA_DISTANCE, EarthConfig,
+    component::{MainCamera, RotatingLight},
+    state::GameState,
+};
+
+/// Frame time above which quality is lowered, 50 frames per second.
+const SLOW_FRAME: f32 = 1. / 50.;
+
+/// Frame time below which there is headroom to raise quality again, loose enough that a
+/// display synced to 60 Hz still counts.
+const FAST_FRAME: f32 = 1. / 58.;
+
+/// Seconds the frame time has to stay over budget before quality is lowered.
+const LOWER_AFTER: f32 = 3.;
+
+/// Seconds the frame time has to stay under budget before quality is raised, doubled for every
+/// raise that had to be taken back.
+const RAISE_AFTER: f32 = 10.;
+
+/// Weight of the newest frame in the smoothed frame time.
+const SMOOTHING: f32 = 0.1;
+
+/// Rendering cost, from the cheapest to the best looking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum QualityLevel {
+    /// No anti-aliasing and coarser chunks
+    Low,
+    #[default]
+    Medium,
+    /// Shadows from the sun
+    High,
+    /// Larger shadow maps with more cascades, and finer chunks
+    Ultra,
+}
+
+impl QualityLevel {
+    pub const ALL: [QualityLevel; 4] = [
+        QualityLevel::Low,
+        QualityLevel::Medium,
+        QualityLevel::High,
+        QualityLevel::Ultra,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            QualityLevel::Low => "Low",
+            QualityLevel::Medium => "Medium",
+            QualityLevel::High => "High",
+            QualityLevel::Ultra => "Ultra",
+        }
+    }
+
+    fn lower(self) -> Option<Self> {
+        Self::ALL.into_iter().rev().find(|&level| level < self)
+    }
+
+    fn higher(self) -> Option<Self> {
+        Self::ALL.into_iter().find(|&level| level > self)
+    }
+
+    /// Multiplier of the vertex spacing on screen the level of detail settles for.
+    pub fn lod_scale(&self) -> f32 {
+        match self {
+            QualityLevel::Low => 2.,
+            QualityLevel::Medium | QualityLevel::High => 1.,
+            QualityLevel::Ultra => 0.5,
+        }
+    }
+
+    fn msaa(&self) -> Msaa {
+        match self {
+            QualityLevel::Low => Msaa::Off,
+            _ => Msaa::Sample4,
+        }
+    }
+
+    /// Shadow map resolution and cascade count, `None` without shadows.
+    fn shadows(&self) -> Option<(usize, usize)> {
+        match self {
+            QualityLevel::Low | QualityLevel::Medium => None,
+            QualityLevel::High => Some((2048, 2)),
+            QualityLevel::Ultra => Some((4096, 4)),
+        }
+    }
+}
+
+/// Scales rendering quality with the frame rate: lowered when frames take too long for a few
+/// seconds, and raised back up to `preferred` while there is headroom.
+#[derive(Resource, Debug, Default)]
+pub struct Quality {
+    /// Level chosen in the GUI, the best the automatic scaling returns to
+    pub preferred: QualityLevel,
+    /// Whether the level follows the frame rate, otherwise it stays at `preferred`
+    pub automatic: bool,
+    /// Level currently applied
+    level: QualityLevel,
+    /// Smoothed frame time in seconds
+    frame_time: f32,
+    /// Seconds the frame time has been over budget
+    slow: f32,
+    /// Seconds the frame time has been under budget
+    fast: f32,
+    /// Whether the last change raised the level
+    raised: bool,
+    /// Raises taken back in a row
+    backoff: u32,
+}
+
+impl Quality {
+    pub fn level(&self) -> QualityLevel {
+        self.level
+    }
+
+    pub fn frame_time(&self) -> f32 {
+        self.frame_time
+    }
+
+    fn set_level(&mut self, level: QualityLevel) {
+        self.raised = level > self.level;
+        self.level = level;
+        self.slow = 0.;
+        self.fast = 0.;
+    }
+}
+
+pub struct QualityPlugin;
+
+impl Plugin for QualityPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Quality>().add_systems(
+            Update,
+            (scale_quality, apply_quality)
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+fn scale_quality(time: Res<Time>, mut quality: ResMut<Quality>) {
+    let delta = time.delta_secs();
+    quality.frame_time = if quality.frame_time == 0. {
+        delta
+    } else {
+        quality.frame_time + (delta - quality.frame_time) * SMOOTHING
+    };
+
+    if !quality.automatic {
+        return;
+    }
+    if quality.frame_time > SLOW_FRAME {
+        quality.slow += delta;
+        quality.fast = 0.;
+    } else if quality.frame_time < FAST_FRAME {
+        quality.fast += delta;
+        quality.slow = 0.;
+    }
+
+    if quality.slow > LOWER_AFTER
+        && let Some(lower) = quality.level.lower()
+    {
+        if quality.raised {
+            quality.backoff += 1;
+        }
+        quality.set_level(lower);
+    } else if quality.fast > RAISE_AFTER * 2f32.powi(quality.backoff.min(6) as i32)
+        && quality.level < quality.preferred
+        && let Some(higher) = quality.level.higher()
+    {
+        quality.set_level(higher);
+    }
+}
+
+/// Applies the current level to the camera and the sun whenever it, or the preferred level
+/// without automatic scaling, changes.
+fn apply_quality(
+    mut commands: Commands,
+    mut quality: ResMut<Quality>,
+    mut shadow_map: ResMut<DirectionalLightShadowMap>,
+    msaa: Single<&mut Msaa, With<MainCamera>>,
+    light: Single<(Entity, &mut DirectionalLight), With<RotatingLight>>,
+    config: Res<EarthConfig>,
+    mut applied: Local<Option<QualityLevel>>,
+) {
+    // Without automatic scaling, or once the preferred level is lowered, follow it right away
+    if quality.level > quality.preferred
+        || (!quality.automatic && quality.level != quality.preferred)
+    {
+        let preferred = quality.preferred;
+        quality.backoff = 0;
+        quality.set_level(preferred);
+    }
+    if *applied == Some(quality.level) {
+        return;
+    }
+    *applied = Some(quality.level);
+
+    msaa.into_inner().set_if_neq(quality.level.msaa());
+
+    let (entity, mut light) = light.into_inner();
+    match quality.level.shadows() {
+        Some((size, cascades)) => {
+            light.shadows_enabled = true;
+            shadow_map.size = size;
+            commands.entity(entity).insert(
+                CascadeShadowConfigBuilder {
+                    num_cascades: cascades,
+                    first_cascade_far_bound: config.radius * 0.5,
+                    maximum_distance: CAMERA_DISTANCE * config.radius * 2.,
+                    ..Default::default()
+                }
+                .build(),
+            );
+        }
+        None => light.shadows_enabled = false,
+    }
+}
@@ -0,0 +1,316 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    color::LinearRgba,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        message::MessageReader,
+        query::With,
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Query, Res, ResMut},
+    },
+    log::error,
+    state::{
+        condition::in_state,
+        state::{OnEnter, OnExit},
+    },
+};
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    EARTH_RADIUS_KM,
+    component::Marker,
+    flight::FlightPath,
+    marker::MarkerLabel,
+    math::{Coordinates, great_circle_distance},
+    observer::EarthClicked,
+    pack::EarthPacks,
+    state::ToolMode,
+};
+
+/// Additional places of a pack, asked for along with the built-in ones.
+const PLACES_FILE: &str = "places.ron";
+
+/// Answers closer than this count as correct and keep the streak going.
+const CORRECT_KM: f32 = 250.;
+
+/// Points for an answer right on the spot, falling off linearly with the distance.
+const MAX_POINTS: f32 = 1000.;
+
+/// Distance at which an answer is no longer worth any points.
+const ZERO_POINTS_KM: f32 = 2500.;
+
+/// Places every quiz can ask for, as (name, latitude, longitude) in degrees.
+const BUILTIN_PLACES: [(&str, f32, f32); 40] = [
+    ("Paris, France", 48.8566, 2.3522),
+    ("London, United Kingdom", 51.5074, -0.1278),
+    ("Tokyo, Japan", 35.6762, 139.6503),
+    ("New York, United States", 40.7128, -74.006),
+    ("Sydney, Australia", -33.8688, 151.2093),
+    ("Cairo, Egypt", 30.0444, 31.2357),
+    ("Rio de Janeiro, Brazil", -22.9068, -43.1729),
+    ("Moscow, Russia", 55.7558, 37.6173),
+    ("Beijing, China", 39.9042, 116.4074),
+    ("Mumbai, India", 19.076, 72.8777),
+    ("Cape Town, South Africa", -33.9249, 18.4241),
+    ("Buenos Aires, Argentina", -34.6037, -58.3816),
+    ("Mexico City, Mexico", 19.4326, -99.1332),
+    ("Los Angeles, United States", 34.0522, -118.2437),
+    ("Toronto, Canada", 43.6532, -79.3832),
+    ("Lagos, Nigeria", 6.5244, 3.3792),
+    ("Nairobi, Kenya", -1.2921, 36.8219),
+    ("Istanbul, Turkey", 41.0082, 28.9784),
+    ("Bangkok, Thailand", 13.7563, 100.5018),
+    ("Singapore", 1.3521, 103.8198),
+    ("Jakarta, Indonesia", -6.2088, 106.8456),
+    ("Lima, Peru", -12.0464, -77.0428),
+    ("Reykjavík, Iceland", 64.1466, -21.9426),
+    ("Anchorage, United States", 61.2181, -149.9003),
+    ("Honolulu, United States", 21.3069, -157.8583),
+    ("Auckland, New Zealand", -36.8485, 174.7633),
+    ("Dubai, United Arab Emirates", 25.2048, 55.2708),
+    ("Tehran, Iran", 35.6892, 51.389),
+    ("Madrid, Spain", 40.4168, -3.7038),
+    ("Rome, Italy", 41.9028, 12.4964),
+    ("Berlin, Germany", 52.52, 13.405),
+    ("Stockholm, Sweden", 59.3293, 18.0686),
+    ("Seoul, South Korea", 37.5665, 126.978),
+    ("Manila, Philippines", 14.5995, 120.9842),
+    ("Santiago, Chile", -33.4489, -70.6693),
+    ("Bogotá, Colombia", 4.711, -74.0721),
+    ("Kinshasa, DR Congo", -4.4419, 15.2663),
+    ("Addis Ababa, Ethiopia", 8.9806, 38.7578),
+    ("Perth, Australia", -31.9505, 115.8605),
+    ("Ulaanbaatar, Mongolia", 47.8864, 106.9057),
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Place {
+    pub name: String,
+    pub latitude: f32,
+    pub longitude: f32,
+}
+
+/// How far off the last answer was.
+#[derive(Debug, Clone, Copy)]
+struct Answer {
+    distance_km: f32,
+    points: u32,
+}
+
+/// A round of the geography quiz, running while `ToolMode::Quiz` is active.
+///
+/// Each question names a place to click on the globe. Answers score by their distance along the
+/// surface, and the correct place is highlighted until the next question.
+#[derive(Resource, Default)]
+pub struct Quiz {
+    places: Vec<Place>,
+    /// Index into `places` of the place asked for
+    question: Option<usize>,
+    /// Outcome of the current question, once answered
+    answer: Option<Answer>,
+    pub score: u32,
+    /// Correct answers in a row
+    pub streak: u32,
+    pub best_streak: u32,
+    pub rounds: u32,
+    /// State of the xorshift generator picking the questions
+    seed: u64,
+}
+
+impl Quiz {
+    /// Picks a random place other than the one just asked for.
+    fn next_question(&mut self) {
+        self.answer = None;
+        if self.places.is_empty() {
+            self.question = None;
+            return;
+        }
+
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+        let mut next = (self.seed % self.places.len() as u64) as usize;
+        if Some(next) == self.question && self.places.len() > 1 {
+            next = (next + 1) % self.places.len();
+        }
+        self.question = Some(next);
+    }
+
+    fn place(&self) -> Option<&Place> {
+        self.question.map(|index| &self.places[index])
+    }
+}
+
+/// The marker and arc revealing the answer, despawned with the next question.
+#[derive(Component)]
+struct QuizHighlight;
+
+pub struct QuizPlugin;
+
+impl Plugin for QuizPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Quiz>()
+            .add_systems(OnEnter(ToolMode::Quiz), start_quiz)
+            .add_systems(OnExit(ToolMode::Quiz), clear_highlights)
+            .add_systems(Update, answer_question.run_if(in_state(ToolMode::Quiz)))
+            .add_systems(
+                EguiPrimaryContextPass,
+                display_quiz.run_if(in_state(ToolMode::Quiz)),
+            );
+    }
+}
+
+/// Built-in places followed by those of the active pack.
+fn load_places(packs: &EarthPacks) -> Vec<Place> {
+    let mut places: Vec<Place> = BUILTIN_PLACES
+        .into_iter()
+        .map(|(name, latitude, longitude)| Place {
+            name: name.to_string(),
+            latitude,
+            longitude,
+        })
+        .collect();
+
+    let path = packs.active().root.join(PLACES_FILE);
+    if let Ok(serialized) = std::fs::read_to_string(&path) {
+        match ron::from_str::<Vec<Place>>(&serialized) {
+            Ok(extra) => places.extend(extra),
+            Err(err) => error!("Failed to parse {}: {err}", path.display()),
+        }
+    }
+    places
+}
+
+fn start_quiz(mut quiz: ResMut<Quiz>, packs: Res<EarthPacks>) {
+    let seed = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(1, |elapsed| elapsed.as_nanos() as u64);
+    *quiz = Quiz {
+        places: load_places(&packs),
+        best_streak: quiz.best_streak,
+        // Xorshift gets stuck at zero
+        seed: seed.max(1),
+        ..Default::default()
+    };
+    quiz.next_question();
+}
+
+fn clear_highlights(mut commands: Commands, highlights: Query<Entity, With<QuizHighlight>>) {
+    for entity in &highlights {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn answer_question(
+    mut commands: Commands,
+    mut quiz: ResMut<Quiz>,
+    mut clicks: MessageReader<EarthClicked>,
+) {
+    let Some(click) = clicks.read().last() else {
+        return;
+    };
+    if quiz.answer.is_some() {
+        return;
+    }
+    let Some(place) = quiz.place().cloned() else {
+        return;
+    };
+    let (Ok(clicked), Ok(target)) = (
+        Coordinates::from_degrees(click.lat, click.lon),
+        Coordinates::from_degrees(place.latitude, place.longitude),
+    ) else {
+        return;
+    };
+
+    let distance_km =
+        great_circle_distance(clicked.direction(), target.direction(), EARTH_RADIUS_KM);
+    let points = (MAX_POINTS * (1. - distance_km / ZERO_POINTS_KM)).max(0.) as u32;
+    let correct = distance_km < CORRECT_KM;
+
+    quiz.rounds += 1;
+    quiz.score += points;
+    if correct {
+        quiz.streak += 1;
+        quiz.best_streak = quiz.best_streak.max(quiz.streak);
+    } else {
+        quiz.streak = 0;
+    }
+    quiz.answer = Some(Answer {
+        distance_km,
+        points,
+    });
+
+    commands.spawn((
+        Marker {
+            coordinates: target,
+        },
+        MarkerLabel(place.name),
+        QuizHighlight,
+    ));
+    let color = if correct {
+        LinearRgba::rgb(0.2, 1., 0.3)
+    } else {
+        LinearRgba::rgb(1., 0.3, 0.2)
+    };
+    commands.spawn((
+        FlightPath {
+            colors: (color, color),
+            ..FlightPath::new(clicked, target)
+        },
+        QuizHighlight,
+    ));
+}
+
+fn display_quiz(
+    mut contexts: EguiContexts,
+    mut commands: Commands,
+    mut quiz: ResMut<Quiz>,
+    highlights: Query<Entity, With<QuizHighlight>>,
+) -> bevy::prelude::Result {
+    let ctx = contexts.ctx_mut()?;
+
+    let mut next = false;
+    egui::Window::new("Quiz")
+        .anchor(egui::Align2::CENTER_TOP, [0., 40.])
+        .resizable(false)
+        .collapsible(false)
+        .show(ctx, |ui| {
+            let Some(place) = quiz.place() else {
+                ui.label("No places to ask for");
+                return;
+            };
+            ui.heading(format!("Find {}", place.name));
+
+            match quiz.answer {
+                Some(answer) => {
+                    ui.label(format!(
+                        "{:.0} km off, {} points",
+                        answer.distance_km, answer.points
+                    ));
+                    next = ui.button("Next question").clicked();
+                }
+                None => {
+                    ui.label("Click it on the globe");
+                }
+            }
+
+            ui.separator();
+            ui.label(format!("Score: {} in {} rounds", quiz.score, quiz.rounds));
+            ui.label(format!(
+                "Streak: {} (best {})",
+                quiz.streak, quiz.best_streak
+            ));
+        });
+
+    if next {
+        for entity in &highlights {
+            commands.entity(entity).despawn();
+        }
+        quiz.next_question();
+    }
+
+    Ok(())
+}
@@ -0,0 +1,261 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    ecs::{
+        message::{Message, MessageReader, MessageWriter},
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Res, ResMut},
+    },
+    state::condition::in_state,
+    time::{Real, Time},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    input::{Action, Actions},
+    observer::EarthClicked,
+    session::{SessionAccess, ViewState},
+    state::GameState,
+    toast::Toasts,
+};
+
+const REPLAY_PATH: &str = "replay.ron";
+
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayCommand {
+    StartRecording,
+    /// Stops recording (saving it to disk) or playback
+    Stop,
+    Play,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub enum Recorded {
+    View(ViewState),
+    Click { latitude: f32, longitude: f32 },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct ReplayEntry {
+    /// Seconds since the recording started
+    pub time: f32,
+    pub event: Recorded,
+}
+
+#[derive(Resource, Default)]
+pub enum Replay {
+    #[default]
+    Idle,
+    Recording {
+        started: f32,
+        entries: Vec<ReplayEntry>,
+        last_view: Option<ViewState>,
+    },
+    Playing {
+        started: f32,
+        entries: Vec<ReplayEntry>,
+        next: usize,
+    },
+}
+
+impl Replay {
+    pub fn is_recording(&self) -> bool {
+        matches!(self, Replay::Recording { .. })
+    }
+
+    pub fn is_playing(&self) -> bool {
+        matches!(self, Replay::Playing { .. })
+    }
+}
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Replay>()
+            .add_message::<ReplayCommand>()
+            .add_systems(
+                Update,
+                (
+                    replay_hotkeys,
+                    handle_replay_commands,
+                    (record_view, record_click).run_if(|replay: Res<Replay>| replay.is_recording()),
+                    play_replay.run_if(|replay: Res<Replay>| replay.is_playing()),
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+fn replay_hotkeys(
+    actions: Actions,
+    replay: Res<Replay>,
+    mut commands: MessageWriter<ReplayCommand>,
+) {
+    if actions.just_pressed(Action::ToggleRecording) {
+        commands.write(if replay.is_recording() {
+            ReplayCommand::Stop
+        } else {
+            ReplayCommand::StartRecording
+        });
+    }
+    if actions.just_pressed(Action::ToggleReplay) {
+        commands.write(if replay.is_playing() {
+            ReplayCommand::Stop
+        } else {
+            ReplayCommand::Play
+        });
+    }
+}
+
+fn handle_replay_commands(
+    mut commands: MessageReader<ReplayCommand>,
+    mut replay: ResMut<Replay>,
+    time: Res<Time<Real>>,
+    mut toasts: ResMut<Toasts>,
+) {
+    for command in commands.read() {
+        let started = time.elapsed_secs();
+        match command {
+            ReplayCommand::StartRecording => {
+                *replay = Replay::Recording {
+                    started,
+                    entries: Vec::new(),
+                    last_view: None,
+                };
+            }
+            ReplayCommand::Stop => {
+                if let Replay::Recording { entries, .. } = &*replay {
+                    match save_replay(entries) {
+                        Ok(()) => toasts.info(format!(
+                            "Saved {} replay entries to {REPLAY_PATH}",
+                            entries.len()
+                        )),
+                        Err(err) => toasts.error(format!("Failed to save replay: {err}")),
+                    }
+                }
+                *replay = Replay::Idle;
+            }
+            ReplayCommand::Play => match load_replay() {
+                Ok(entries) => {
+                    *replay = Replay::Playing {
+                        started,
+                        entries,
+                        next: 0,
+                    }
+                }
+                Err(err) => toasts.error(format!("Failed to load replay: {err}")),
+            },
+        }
+    }
+}
+
+fn save_replay(entries: &[ReplayEntry]) -> Result<(), Box<dyn std::error::Error>> {
+    let serialized = ron::ser::to_string_pretty(entries, ron::ser::PrettyConfig::default())?;
+    std::fs::write(REPLAY_PATH, serialized)?;
+    Ok(())
+}
+
+fn load_replay() -> Result<Vec<ReplayEntry>, Box<dyn std::error::Error>> {
+    let serialized = std::fs::read_to_string(REPLAY_PATH)?;
+    Ok(ron::from_str(&serialized)?)
+}
+
+fn record_view(mut replay: ResMut<Replay>, time: Res<Time<Real>>, session: SessionAccess) {
+    let Replay::Recording {
+        started,
+        entries,
+        last_view,
+    } = &mut *replay
+    else {
+        return;
+    };
+    let Some(view) = session.capture() else {
+        return;
+    };
+
+    // Only changes are stored, except for the simulation clock which always moves
+    let changed = last_view.is_none_or(|last| {
+        ViewState {
+            simulation_elapsed: view.simulation_elapsed,
+            ..last
+        } != view
+    });
+    if changed {
+        entries.push(ReplayEntry {
+            time: time.elapsed_secs() - *started,
+            event: Recorded::View(view),
+        });
+        *last_view = Some(view);
+    }
+}
+
+/// Records the clicks the live `EarthClicked` reports, so drags and other buttons are left out
+/// just like they are when playing back.
+fn record_click(
+    mut replay: ResMut<Replay>,
+    time: Res<Time<Real>>,
+    mut clicked: MessageReader<EarthClicked>,
+) {
+    let Replay::Recording {
+        started, entries, ..
+    } = &mut *replay
+    else {
+        return;
+    };
+    for click in clicked.read() {
+        entries.push(ReplayEntry {
+            time: time.elapsed_secs() - *started,
+            event: Recorded::Click {
+                latitude: click.lat,
+                longitude: click.lon,
+            },
+        });
+    }
+}
+
+/// Applies the recorded views as they come due, and sends each recorded click as an
+/// `EarthClicked` so the tools respond to it as to a live one.
+fn play_replay(
+    mut replay: ResMut<Replay>,
+    time: Res<Time<Real>>,
+    mut session: SessionAccess,
+    mut clicked: MessageWriter<EarthClicked>,
+) {
+    let Replay::Playing {
+        started,
+        entries,
+        next,
+    } = &mut *replay
+    else {
+        return;
+    };
+    let now = time.elapsed_secs() - *started;
+
+    // Apply the newest view that is due this frame
+    let mut latest_view = None;
+    while let Some(entry) = entries.get(*next).filter(|entry| entry.time <= now) {
+        match entry.event {
+            Recorded::View(view) => latest_view = Some(view),
+            Recorded::Click {
+                latitude,
+                longitude,
+            } => {
+                clicked.write(EarthClicked {
+                    lat: latitude,
+                    lon: longitude,
+                });
+            }
+        }
+        *next += 1;
+    }
+
+    if let Some(view) = latest_view {
+        session.apply(&view);
+    }
+
+    if *next >= entries.len() {
+        *replay = Replay::Idle;
+    }
+}
@@ -1,22 +1,48 @@
 use bevy::{
-    asset::Handle, ecs::resource::Resource, image::Image, pbr::StandardMaterial, prelude::Deref,
+    asset::Handle,
+    ecs::resource::Resource,
+    image::Image,
+    math::Vec3,
+    prelude::{Deref, DerefMut},
+    tasks::Task,
 };
 
+use crate::material::EarthMaterial;
+
 #[derive(Resource)]
 pub struct EarthTexture {
     pub base_color: Handle<Image>,
     pub metallic_roughness: Handle<Image>,
     pub normal_map: Handle<Image>,
+    /// Emissive texture for the night side, if the pack has one
+    pub night_lights: Option<Handle<Image>>,
+    /// Cloud coverage for the `CloudLayer` and its shadows, if the pack has one
+    pub clouds: Option<Handle<Image>>,
+    /// Albedo of the Moon, if the pack has one
+    pub moon: Option<Handle<Image>>,
+    /// Whether `metallic_roughness` was converted to the glTF channel layout
+    pub repacked: bool,
+    /// `metallic_roughness` being repacked in the background
+    pub repacking: Option<Task<Result<Image, String>>>,
 }
 
 #[derive(Resource, Default)]
 pub struct LoadingProgress {
     pub mesh: usize,
     pub texture: usize,
+    /// Fraction of the missing textures downloaded in `GameState::DownloadAssets`
+    pub download: f32,
+    /// Texture being downloaded
+    pub downloading: Option<String>,
 }
 
+/// Material shared by every chunk, and the template for chunks with `MaterialOverrides`.
 #[derive(Resource, Deref)]
-pub struct BoxMaterialHandle(pub Handle<StandardMaterial>);
+pub struct EarthMaterialTemplate(pub Handle<EarthMaterial>);
+
+/// Surface point under the cursor, in the Earth's local space.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct CursorHit(pub Option<Vec3>);
 
 impl LoadingProgress {
     pub fn progress(&self) -> f32 {
@@ -27,3 +53,69 @@ impl LoadingProgress {
         self.texture >= 3 && self.mesh >= 24
     }
 }
+
+/// Whether the local north/east/up axes are drawn at the hovered surface point.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct ShowNorthArrow(pub bool);
+
+/// Whether egui is using the pointer, because it hovers a window or drags one of its widgets.
+///
+/// Picking still hits the globe behind egui windows, so systems and observers reacting to the
+/// pointer check this to leave such input to the UI.
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Deref)]
+pub struct PointerOverUi(pub bool);
+
+/// Whether egui is using the keyboard, because a text field has the focus. Key bindings are
+/// ignored meanwhile, so typing doesn't move the camera.
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Deref)]
+pub struct KeyboardOverUi(pub bool);
+
+/// Clock of the simulated world, advanced only in fixed steps.
+#[derive(Resource)]
+pub struct SimulationTime {
+    /// Seconds of simulated time since startup
+    pub elapsed: f64,
+    /// Simulated seconds covered by the last fixed step
+    pub delta: f64,
+    /// Simulated seconds per real second
+    pub speed: f32,
+    pub paused: bool,
+    /// Fixed steps to run while paused
+    pub pending_steps: u32,
+}
+
+impl Default for SimulationTime {
+    fn default() -> Self {
+        Self {
+            elapsed: 0.,
+            delta: 0.,
+            speed: 1.,
+            paused: false,
+            pending_steps: 0,
+        }
+    }
+}
+
+impl SimulationTime {
+    pub const MIN_SPEED: f32 = 1. / 16.;
+    pub const MAX_SPEED: f32 = 1024.;
+
+    pub fn elapsed_secs(&self) -> f32 {
+        self.elapsed as f32
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    /// Advances one fixed step on the next tick if the simulation is paused.
+    pub fn step(&mut self) {
+        if self.paused {
+            self.pending_steps += 1;
+        }
+    }
+
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed.clamp(Self::MIN_SPEED, Self::MAX_SPEED);
+    }
+}
@@ -0,0 +1,518 @@
+use std::f64::consts::TAU;
+
+use bevy::{
+    app::{App, Plugin, PostUpdate, Startup, Update},
+    asset::{Assets, Handle},
+    camera::{Camera, Projection, visibility::Visibility},
+    color::{Color, LinearRgba},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        hierarchy::ChildOf,
+        query::{With, Without},
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Query, Res, ResMut, Single},
+    },
+    log::warn,
+    math::{Vec3, primitives::Circle},
+    mesh::{Mesh, Mesh3d, MeshBuilder},
+    pbr::{MeshMaterial3d, StandardMaterial},
+    picking::Pickable,
+    state::{condition::in_state, state::OnEnter},
+    transform::{TransformSystems, components::Transform},
+};
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+
+use crate::{
+    EarthConfig,
+    component::{Draped, Earth, MainCamera},
+    marker::MarkerLabel,
+    math::Coordinates,
+    pack::EarthPacks,
+    polyline::{LineJoin, Polyline, PolylineMaterial},
+    state::GameState,
+    sun::{J2000_UNIX_SECS, SECS_PER_DAY, SunClock, SunMode, days_from_civil},
+    toast::Toasts,
+};
+
+/// Directory of a pack with `.tle` files of satellites to track. The bundled pack has a few
+/// crewed stations, whose elements age quickly; current ones are published by CelesTrak.
+const SATELLITES_DIR: &str = "satellites";
+
+/// Gravitational parameter of the Earth in km³/s².
+const MU: f64 = 398_600.441_8;
+
+/// Equatorial radius in km and second zonal harmonic of the Earth, which make orbits precess.
+const EQUATORIAL_RADIUS_KM: f64 = 6378.137;
+const J2: f64 = 1.082_63e-3;
+
+/// Diameter of the dot drawn at each satellite, in logical pixels.
+const DOT_PIXELS: f32 = 8.;
+
+const SATELLITE_COLOR: Color = Color::srgb(1., 0.85, 0.2);
+
+/// Seconds of `SunClock` between the samples of a ground track.
+const TRACK_STEP_SECS: f64 = 30.;
+
+/// Seconds the `SunClock` has to move before a ground track is drawn again.
+const TRACK_REBUILD_SECS: f64 = 60.;
+
+/// Mean orbital elements of a two-line element set.
+///
+/// Propagated as a Kepler orbit whose node and perigee drift under the Earth's oblateness,
+/// ignoring drag. Good to a few tens of kilometers for low orbits within days of the epoch,
+/// drifting further the older the elements get.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrbitalElements {
+    /// Unix time the elements were measured at
+    pub epoch: f64,
+    /// In radians, like the angles below
+    pub inclination: f64,
+    pub right_ascension: f64,
+    pub eccentricity: f64,
+    pub argument_of_perigee: f64,
+    pub mean_anomaly: f64,
+    /// Revolutions per day
+    pub mean_motion: f64,
+}
+
+impl OrbitalElements {
+    /// Reads the two data lines of a TLE, without checking their checksums.
+    pub fn from_tle(line1: &str, line2: &str) -> Result<Self, String> {
+        if !line1.starts_with("1 ") || !line2.starts_with("2 ") {
+            return Err("Expected lines starting with 1 and 2".to_string());
+        }
+        let field = |line: &str, start: usize, end: usize| {
+            line.get(start..end)
+                .map(str::trim)
+                .ok_or_else(|| format!("Line too short: {line:?}"))
+        };
+        let number = |line: &str, start: usize, end: usize| {
+            let text = field(line, start, end)?;
+            text.parse::<f64>()
+                .map_err(|err| format!("Invalid number {text:?}: {err}"))
+        };
+        let angle = |start: usize, end: usize| number(line2, start, end).map(f64::to_radians);
+
+        // Two digit years from 57 on are in the 1900s, when Sputnik launched
+        let year = number(line1, 18, 20)? as i64;
+        let year = if year < 57 { 2000 + year } else { 1900 + year };
+        let day_of_year = number(line1, 20, 32)?;
+        let epoch = (days_from_civil(year, 1, 1) as f64 + day_of_year - 1.) * SECS_PER_DAY;
+
+        Ok(Self {
+            epoch,
+            inclination: angle(8, 16)?,
+            right_ascension: angle(17, 25)?,
+            // The leading decimal point is implied
+            eccentricity: number(line2, 26, 33)? / 1e7,
+            argument_of_perigee: angle(34, 42)?,
+            mean_anomaly: angle(43, 51)?,
+            mean_motion: number(line2, 52, 63)?,
+        })
+    }
+
+    /// Seconds per revolution.
+    pub fn period(&self) -> f64 {
+        SECS_PER_DAY / self.mean_motion
+    }
+
+    /// Position at `unix_secs` in km from the Earth's center, in the globe's local space: Y
+    /// towards the north pole and Z through longitude zero.
+    pub fn position(&self, unix_secs: f64) -> Vec3 {
+        let elapsed = unix_secs - self.epoch;
+        let motion = self.mean_motion * TAU / SECS_PER_DAY;
+        let semi_major_axis = (MU / (motion * motion)).cbrt();
+        let e = self.eccentricity;
+
+        // Secular drift of the node and the perigee
+        let semi_latus_rectum = semi_major_axis * (1. - e * e);
+        let drift = 1.5 * J2 * (EQUATORIAL_RADIUS_KM / semi_latus_rectum).powi(2) * motion;
+        let (sin_i, cos_i) = self.inclination.sin_cos();
+        let node = self.right_ascension - drift * cos_i * elapsed;
+        let perigee = self.argument_of_perigee + drift * (2. - 2.5 * sin_i * sin_i) * elapsed;
+
+        let mean_anomaly = (self.mean_anomaly + motion * elapsed).rem_euclid(TAU);
+        let mut eccentric_anomaly = mean_anomaly;
+        for _ in 0..8 {
+            eccentric_anomaly -= (eccentric_anomaly - e * eccentric_anomaly.sin() - mean_anomaly)
+                / (1. - e * eccentric_anomaly.cos());
+        }
+        let true_anomaly = 2.
+            * ((1. + e).sqrt() * (eccentric_anomaly / 2.).sin())
+                .atan2((1. - e).sqrt() * (eccentric_anomaly / 2.).cos());
+        let radius = semi_major_axis * (1. - e * eccentric_anomaly.cos());
+
+        // From the orbital plane to the equator, with X towards the vernal equinox
+        let latitude_argument = perigee + true_anomaly;
+        let (sin_u, cos_u) = latitude_argument.sin_cos();
+        let (sin_node, cos_node) = node.sin_cos();
+        let x = radius * (cos_node * cos_u - sin_node * sin_u * cos_i);
+        let y = radius * (sin_node * cos_u + cos_node * sin_u * cos_i);
+        let z = radius * sin_u * sin_i;
+
+        // Turned along with the Earth by the sidereal time at Greenwich
+        let days = (unix_secs - J2000_UNIX_SECS) / SECS_PER_DAY;
+        let sidereal = (280.460_618_37 + 360.985_647_366_29 * days).to_radians();
+        let (sin_t, cos_t) = sidereal.sin_cos();
+        let east = -x * sin_t + y * cos_t;
+        let greenwich = x * cos_t + y * sin_t;
+        Vec3::new(east as f32, z as f32, greenwich as f32)
+    }
+}
+
+/// A satellite drawn as a dot above the globe where it is at the `SunClock`, so it only moves
+/// in the real time `SunMode`.
+///
+/// Spawn it on its own entity with a `Visibility` to toggle it; it is parented to the globe once
+/// there is one. The satellites in the `satellites` folder of the bundled and the active pack
+/// are spawned hidden when the globe is ready.
+#[derive(Component, Debug, Clone)]
+pub struct Satellite {
+    pub name: String,
+    pub elements: OrbitalElements,
+    /// Whether its path over the ground during the next revolution is drawn
+    pub ground_track: bool,
+}
+
+impl Satellite {
+    /// Reads every satellite of a TLE file, in the three line format with names or two lines
+    /// named by catalog number. Entries that can't be read are skipped with a warning.
+    pub fn parse_tle(text: &str) -> Vec<Satellite> {
+        let lines: Vec<&str> = text
+            .lines()
+            .map(str::trim_end)
+            .filter(|line| !line.is_empty())
+            .collect();
+        let mut satellites = Vec::new();
+        let mut index = 0;
+        while index + 1 < lines.len() {
+            let (name, line1, line2, length) = if lines[index].starts_with("1 ") {
+                let number = lines[index].get(2..7).unwrap_or_default().trim();
+                (number, lines[index], lines[index + 1], 2)
+            } else if let Some(&line2) = lines.get(index + 2) {
+                (lines[index].trim(), lines[index + 1], line2, 3)
+            } else {
+                break;
+            };
+            index += length;
+
+            match OrbitalElements::from_tle(line1, line2) {
+                Ok(elements) => satellites.push(Satellite {
+                    name: name.to_string(),
+                    elements,
+                    ground_track: false,
+                }),
+                Err(err) => warn!("Skipping satellite {name}: {err}"),
+            }
+        }
+        satellites
+    }
+}
+
+/// The dot drawn at a `Satellite`, kept the same size on screen as the camera zooms.
+#[derive(Component)]
+struct SatelliteDot;
+
+/// The ground track of a `Satellite`, a separate child of the globe so it doesn't move along.
+#[derive(Component)]
+struct TrackLine {
+    satellite: Entity,
+    material: Handle<PolylineMaterial>,
+    /// `SunClock` time the track starts at
+    built_at: f64,
+}
+
+#[derive(Resource)]
+struct SatelliteAssets {
+    dot: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+#[derive(Resource, Default)]
+pub struct SatellitesPanel {
+    pub open: bool,
+}
+
+pub struct SatellitePlugin;
+
+impl Plugin for SatellitePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SatellitesPanel>()
+            .add_systems(Startup, create_satellite_assets)
+            .add_systems(OnEnter(GameState::Playing), discover_satellites)
+            .add_systems(
+                Update,
+                (attach_satellites, move_satellites, update_ground_tracks)
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                PostUpdate,
+                scale_dots
+                    .before(TransformSystems::Propagate)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                EguiPrimaryContextPass,
+                display_satellites_panel
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(|panel: Res<SatellitesPanel>| panel.open),
+            );
+    }
+}
+
+fn create_satellite_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(SatelliteAssets {
+        dot: meshes.add(Circle::new(0.5)),
+        material: materials.add(StandardMaterial {
+            base_color: SATELLITE_COLOR,
+            unlit: true,
+            ..Default::default()
+        }),
+    });
+}
+
+/// Spawns a hidden satellite for every entry of the `.tle` files in the `satellites` folder of
+/// the bundled pack and the active one. The satellites of a previous globe were despawned along
+/// with it.
+fn discover_satellites(mut commands: Commands, packs: Res<EarthPacks>, mut toasts: ResMut<Toasts>) {
+    let mut roots = vec![&packs.available[0].root];
+    if packs.active().root != packs.available[0].root {
+        roots.push(&packs.active().root);
+    }
+
+    let mut files: Vec<_> = roots
+        .into_iter()
+        .filter_map(|root| std::fs::read_dir(root.join(SATELLITES_DIR)).ok())
+        .flat_map(|entries| entries.flatten().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "tle"))
+        .collect();
+    files.sort();
+
+    for path in files {
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(err) => {
+                toasts.error(format!("Failed to read {}: {err}", path.display()));
+                continue;
+            }
+        };
+        for satellite in Satellite::parse_tle(&text) {
+            commands.spawn((satellite, Visibility::Hidden));
+        }
+    }
+}
+
+/// Parents satellites to the globe, which waits for the globe to exist, and gives them a dot
+/// and a label.
+fn attach_satellites(
+    mut commands: Commands,
+    satellites: Query<(Entity, &Satellite), Without<ChildOf>>,
+    earth: Option<Single<Entity, With<Earth>>>,
+    assets: Res<SatelliteAssets>,
+) {
+    let Some(earth) = earth else {
+        return;
+    };
+
+    for (entity, satellite) in &satellites {
+        commands
+            .entity(entity)
+            .insert((
+                Transform::default(),
+                MarkerLabel(satellite.name.clone()),
+                ChildOf(*earth),
+            ))
+            .insert_if_new(Visibility::default())
+            .with_child((
+                Mesh3d(assets.dot.clone()),
+                MeshMaterial3d(assets.material.clone()),
+                Transform::default(),
+                Pickable::IGNORE,
+                SatelliteDot,
+            ));
+    }
+}
+
+fn move_satellites(
+    clock: Res<SunClock>,
+    config: Res<EarthConfig>,
+    mut satellites: Query<(&Satellite, &mut Transform), With<ChildOf>>,
+) {
+    for (satellite, mut transform) in &mut satellites {
+        transform.translation = satellite.elements.position(clock.unix_secs) / config.km_per_unit();
+    }
+}
+
+/// Points on the ground of a globe of `radius` below a satellite from `start` on for one
+/// revolution.
+fn track_points(elements: &OrbitalElements, start: f64, radius: f32) -> Vec<Vec3> {
+    let steps = (elements.period() / TRACK_STEP_SECS).ceil() as usize;
+    (0..=steps)
+        .map(|step| {
+            let position = elements.position(start + step as f64 * TRACK_STEP_SECS);
+            position.normalize() * radius
+        })
+        .collect()
+}
+
+/// Draws the next revolution of the satellites shown with `ground_track`, fading out towards its
+/// end, and drops the tracks of the others.
+fn update_ground_tracks(
+    mut commands: Commands,
+    clock: Res<SunClock>,
+    config: Res<EarthConfig>,
+    satellites: Query<(Entity, &Satellite, &Visibility)>,
+    mut lines: Query<(Entity, &mut TrackLine)>,
+    earth: Single<Entity, With<Earth>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<PolylineMaterial>>,
+) {
+    let shown = |entity: Entity| {
+        satellites
+            .get(entity)
+            .ok()
+            .filter(|(_, satellite, visibility)| {
+                satellite.ground_track && **visibility != Visibility::Hidden
+            })
+            .map(|(_, satellite, _)| satellite)
+    };
+
+    for (entity, mut line) in &mut lines {
+        let Some(satellite) = shown(line.satellite) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+        if (clock.unix_secs - line.built_at).abs() < TRACK_REBUILD_SECS {
+            continue;
+        }
+        line.built_at = clock.unix_secs;
+
+        let points = track_points(&satellite.elements, clock.unix_secs, config.radius);
+        if let Some(material) = materials.get_mut(&line.material) {
+            material.uniform.line_length = points
+                .windows(2)
+                .map(|pair| pair[0].distance(pair[1]))
+                .sum();
+        }
+        commands
+            .entity(entity)
+            .insert(Mesh3d(meshes.add(Polyline::new(points).build())));
+    }
+
+    for (entity, _, _) in &satellites {
+        if shown(entity).is_none() || lines.iter().any(|(_, line)| line.satellite == entity) {
+            continue;
+        }
+        let color = LinearRgba::from(SATELLITE_COLOR);
+        let material = materials.add(
+            PolylineMaterial::new(color, 1.5)
+                .with_join(LineJoin::Round)
+                .with_gradient(LinearRgba { alpha: 0., ..color }, 0.),
+        );
+        // Drawn on the next update
+        commands.spawn((
+            TrackLine {
+                satellite: entity,
+                material: material.clone(),
+                built_at: f64::NEG_INFINITY,
+            },
+            MeshMaterial3d(material),
+            Draped::default(),
+            Transform::default(),
+            Visibility::default(),
+            ChildOf(*earth),
+        ));
+    }
+}
+
+/// Turns the dots towards the camera and scales them to `DOT_PIXELS`.
+///
+/// Like billboards, satellites sit in the globe's local space, so the camera is brought into it.
+fn scale_dots(
+    camera: Single<(&Camera, &Transform, &Projection), (With<MainCamera>, Without<SatelliteDot>)>,
+    earth: Single<&Transform, (With<Earth>, Without<SatelliteDot>)>,
+    satellites: Query<&Transform, (With<Satellite>, Without<SatelliteDot>)>,
+    mut dots: Query<(&ChildOf, &mut Transform), With<SatelliteDot>>,
+) {
+    let (camera, camera_transform, projection) = *camera;
+    let (Projection::Perspective(perspective), Some(viewport)) =
+        (projection, camera.logical_viewport_size())
+    else {
+        return;
+    };
+
+    let camera_position = earth
+        .compute_affine()
+        .inverse()
+        .transform_point3(camera_transform.translation);
+    let rotation = earth.rotation.inverse() * camera_transform.rotation;
+    let pixel_at_unit = 2. * (perspective.fov / 2.).tan() / viewport.y;
+
+    for (parent, mut transform) in &mut dots {
+        let Ok(satellite) = satellites.get(parent.parent()) else {
+            continue;
+        };
+        let size = DOT_PIXELS * pixel_at_unit * camera_position.distance(satellite.translation);
+        *transform = Transform::from_rotation(rotation).with_scale(Vec3::splat(size));
+    }
+}
+
+fn display_satellites_panel(
+    mut contexts: EguiContexts,
+    mut panel: ResMut<SatellitesPanel>,
+    mut satellites: Query<(&mut Satellite, &mut Visibility, &Transform)>,
+    mode: Res<SunMode>,
+    config: Res<EarthConfig>,
+) -> bevy::prelude::Result {
+    let ctx = contexts.ctx_mut()?;
+
+    egui::Window::new("Satellites")
+        .open(&mut panel.open)
+        .resizable(false)
+        .show(ctx, |ui| {
+            if satellites.is_empty() {
+                ui.label(format!(
+                    "No satellites found in the pack's {SATELLITES_DIR} folder"
+                ));
+                return;
+            }
+            if *mode == SunMode::Demo {
+                ui.label("Positions follow the sun clock, which only runs with the real time sun");
+                ui.separator();
+            }
+
+            egui::Grid::new("satellites").striped(true).show(ui, |ui| {
+                for (mut satellite, mut visibility, transform) in &mut satellites {
+                    let mut visible = *visibility != Visibility::Hidden;
+                    if ui.checkbox(&mut visible, satellite.name.as_str()).changed() {
+                        *visibility = if visible {
+                            Visibility::Inherited
+                        } else {
+                            Visibility::Hidden
+                        };
+                    }
+                    let mut ground_track = satellite.ground_track;
+                    if ui.checkbox(&mut ground_track, "Ground track").changed() {
+                        satellite.ground_track = ground_track;
+                    }
+
+                    let (latitude, longitude) =
+                        Coordinates::from(transform.translation).as_degrees();
+                    let altitude =
+                        (transform.translation.length() - config.radius) * config.km_per_unit();
+                    ui.label(format!("{latitude:.1}°, {longitude:.1}°"));
+                    ui.label(format!("{altitude:.0} km"));
+                    ui.end_row();
+                }
+            });
+        });
+
+    Ok(())
+}
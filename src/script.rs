@@ -0,0 +1,521 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{
+    app::{App, Plugin, Update},
+    camera::visibility::Visibility,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::With,
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Local, Query, Res, ResMut},
+    },
+    state::{
+        condition::in_state,
+        state::{NextState, State},
+    },
+    time::{Real, Time},
+};
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+use rhai::{Engine, EvalAltResult};
+
+use crate::{
+    component::Marker,
+    flight::{FlightPath, spawn_great_circle},
+    globe_layer::GlobeLayers,
+    layer::RasterLayers,
+    marker::MarkerLabel,
+    math::Coordinates,
+    navigation::EarthCommands,
+    overlay::GeoJsonLayer,
+    palette::{PaletteCommand, RegisterCommand},
+    state::{GameState, ToolMode},
+    toast::Toasts,
+};
+
+/// Folder of the `.rhai` scripts listed in the scripts panel and the palette.
+const SCRIPTS_DIR: &str = "scripts";
+
+/// Operations a script may take before it is stopped, so an endless loop can't freeze the app.
+const MAX_OPERATIONS: u64 = 10_000_000;
+
+/// Seconds a `fly_to` without a duration takes.
+const DEFAULT_FLIGHT_SECONDS: f64 = 2.;
+
+/// What a script asked for, applied in order once it has run.
+#[derive(Debug, Clone)]
+enum ScriptCommand {
+    Marker {
+        coordinates: Coordinates,
+        label: Option<String>,
+    },
+    Arc {
+        from: Coordinates,
+        to: Coordinates,
+    },
+    Route {
+        from: Coordinates,
+        to: Coordinates,
+    },
+    /// Shows or hides a layer by name, toggling it without `visible`
+    Layer {
+        name: String,
+        visible: Option<bool>,
+    },
+    FlyTo {
+        coordinates: Coordinates,
+        altitude: f32,
+        duration: Duration,
+    },
+    /// Holds back the commands after it, for tours
+    Wait(Duration),
+    Print(String),
+    /// Removes everything scripts spawned
+    Clear,
+}
+
+/// Markers and arcs spawned by scripts, removed again by `clear()`.
+#[derive(Component)]
+pub struct ScriptSpawned;
+
+/// Runs Rhai scripts that place markers and arcs, toggle layers and fly the camera, without
+/// recompiling the crate.
+///
+/// A script runs to its end at once, recording what it asks for. The recorded commands are then
+/// applied frame by frame, pausing at each `wait(seconds)`, so a script can animate a tour. From
+/// its first `wait` to its end a tour runs in `ToolMode::Touring`:
+///
+/// ```rhai
+/// for i in 0..500 {
+///     marker(random(-60.0, 70.0), random(-180.0, 180.0));
+/// }
+/// fly_to(48.86, 2.35, 800.0, 3.0);
+/// wait(4.0);
+/// fly_to(40.71, -74.0, 800.0, 3.0);
+/// ```
+///
+/// Numbers are floats, `marker(10, 20)` has to be written `marker(10.0, 20.0)`.
+#[derive(Resource, Default)]
+pub struct ScriptRunner {
+    queue: VecDeque<ScriptCommand>,
+    /// Real time the queue goes on at after a `wait`
+    resume_at: f32,
+    /// Name of the script whose commands are queued
+    running: Option<String>,
+}
+
+impl ScriptRunner {
+    /// Runs `source`, replacing what a previous script still had queued.
+    pub fn run(&mut self, name: &str, source: &str) -> Result<(), String> {
+        self.queue = evaluate(source)?.into();
+        self.resume_at = 0.;
+        self.running = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Drops the commands still queued, what was spawned already stays.
+    pub fn stop(&mut self) {
+        self.queue.clear();
+        self.running = None;
+    }
+
+    pub fn running(&self) -> Option<&str> {
+        self.running.as_deref()
+    }
+
+    /// Whether the running script is a tour, i.e. it reached a `wait` and still has commands
+    /// queued or is waiting for their time.
+    pub fn is_touring(&self) -> bool {
+        self.running.is_some() && self.resume_at > 0.
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct ScriptsPanel {
+    pub open: bool,
+}
+
+pub struct ScriptPlugin;
+
+impl Plugin for ScriptPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScriptRunner>()
+            .init_resource::<ScriptsPanel>()
+            .add_systems(
+                Update,
+                (apply_script_commands, follow_tour)
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                EguiPrimaryContextPass,
+                display_scripts_panel
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(|panel: Res<ScriptsPanel>| panel.open),
+            )
+            .register_command("Stop script", |mut runner: ResMut<ScriptRunner>| {
+                runner.stop();
+            })
+            .register_command_provider(script_commands);
+    }
+}
+
+fn coordinates(latitude: f64, longitude: f64) -> Result<Coordinates, Box<EvalAltResult>> {
+    Ok(Coordinates::from_degrees(
+        latitude as f32,
+        longitude as f32,
+    )?)
+}
+
+fn duration(seconds: f64) -> Result<Duration, Box<EvalAltResult>> {
+    Duration::try_from_secs_f64(seconds).map_err(|err| format!("{seconds} s: {err}").into())
+}
+
+/// Runs `source` and returns what it asked for.
+fn evaluate(source: &str) -> Result<Vec<ScriptCommand>, String> {
+    let commands = Rc::new(RefCell::new(Vec::new()));
+    let emit = {
+        let commands = commands.clone();
+        move |command: ScriptCommand| commands.borrow_mut().push(command)
+    };
+
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+
+    let print = emit.clone();
+    engine.on_print(move |text| print(ScriptCommand::Print(text.to_string())));
+
+    let marker = emit.clone();
+    engine.register_fn("marker", move |latitude: f64, longitude: f64| {
+        marker(ScriptCommand::Marker {
+            coordinates: coordinates(latitude, longitude)?,
+            label: None,
+        });
+        Ok::<_, Box<EvalAltResult>>(())
+    });
+    let marker = emit.clone();
+    engine.register_fn(
+        "marker",
+        move |latitude: f64, longitude: f64, label: &str| {
+            marker(ScriptCommand::Marker {
+                coordinates: coordinates(latitude, longitude)?,
+                label: Some(label.to_string()),
+            });
+            Ok::<_, Box<EvalAltResult>>(())
+        },
+    );
+
+    let arc = emit.clone();
+    engine.register_fn(
+        "arc",
+        move |from_latitude: f64, from_longitude: f64, to_latitude: f64, to_longitude: f64| {
+            arc(ScriptCommand::Arc {
+                from: coordinates(from_latitude, from_longitude)?,
+                to: coordinates(to_latitude, to_longitude)?,
+            });
+            Ok::<_, Box<EvalAltResult>>(())
+        },
+    );
+    let route = emit.clone();
+    engine.register_fn(
+        "route",
+        move |from_latitude: f64, from_longitude: f64, to_latitude: f64, to_longitude: f64| {
+            route(ScriptCommand::Route {
+                from: coordinates(from_latitude, from_longitude)?,
+                to: coordinates(to_latitude, to_longitude)?,
+            });
+            Ok::<_, Box<EvalAltResult>>(())
+        },
+    );
+
+    for (function, visible) in [
+        ("show_layer", Some(true)),
+        ("hide_layer", Some(false)),
+        ("toggle_layer", None),
+    ] {
+        let layer = emit.clone();
+        engine.register_fn(function, move |name: &str| {
+            layer(ScriptCommand::Layer {
+                name: name.to_string(),
+                visible,
+            });
+        });
+    }
+
+    let fly_to = emit.clone();
+    engine.register_fn(
+        "fly_to",
+        move |latitude: f64, longitude: f64, altitude: f64, seconds: f64| {
+            fly_to(ScriptCommand::FlyTo {
+                coordinates: coordinates(latitude, longitude)?,
+                altitude: altitude as f32,
+                duration: duration(seconds)?,
+            });
+            Ok::<_, Box<EvalAltResult>>(())
+        },
+    );
+    let fly_to = emit.clone();
+    engine.register_fn(
+        "fly_to",
+        move |latitude: f64, longitude: f64, altitude: f64| {
+            fly_to(ScriptCommand::FlyTo {
+                coordinates: coordinates(latitude, longitude)?,
+                altitude: altitude as f32,
+                duration: duration(DEFAULT_FLIGHT_SECONDS)?,
+            });
+            Ok::<_, Box<EvalAltResult>>(())
+        },
+    );
+
+    let wait = emit.clone();
+    engine.register_fn("wait", move |seconds: f64| {
+        wait(ScriptCommand::Wait(duration(seconds)?));
+        Ok::<_, Box<EvalAltResult>>(())
+    });
+    let clear = emit.clone();
+    engine.register_fn("clear", move || clear(ScriptCommand::Clear));
+
+    // Xorshift, seeded from the clock so every run differs
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(1, |elapsed| elapsed.as_nanos() as u64)
+        | 1;
+    let state = Rc::new(Cell::new(seed));
+    let next = move || {
+        let mut x = state.get();
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        state.set(x);
+        (x >> 11) as f64 / (1u64 << 53) as f64
+    };
+    engine.register_fn("random", next.clone());
+    engine.register_fn("random", move |min: f64, max: f64| {
+        min + next() * (max - min)
+    });
+
+    engine.run(source).map_err(|err| err.to_string())?;
+    drop(engine);
+    Ok(commands.take())
+}
+
+/// The `.rhai` files in `scripts`, sorted by name.
+fn script_files() -> Vec<PathBuf> {
+    let mut files: Vec<_> = std::fs::read_dir(SCRIPTS_DIR)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "rhai"))
+        .collect();
+    files.sort();
+    files
+}
+
+fn script_name(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn run_file(runner: &mut ScriptRunner, path: &Path) -> Result<(), String> {
+    let source = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    runner.run(&script_name(path), &source)
+}
+
+/// A palette command per script, running it.
+fn script_commands() -> Vec<PaletteCommand> {
+    script_files()
+        .into_iter()
+        .map(|path| {
+            PaletteCommand::new(format!("Run script {}", script_name(&path)), move |world| {
+                if let Err(err) = run_file(&mut world.resource_mut::<ScriptRunner>(), &path) {
+                    world
+                        .resource_mut::<Toasts>()
+                        .error(format!("Script {} failed: {err}", script_name(&path)));
+                }
+            })
+        })
+        .collect()
+}
+
+/// Applies the queued commands up to the next `wait`.
+fn apply_script_commands(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut runner: ResMut<ScriptRunner>,
+    mut layers: ResMut<RasterLayers>,
+    mut vector_layers: Query<(&GeoJsonLayer, &mut Visibility)>,
+    mut globe_layers: ResMut<GlobeLayers>,
+    spawned: Query<Entity, With<ScriptSpawned>>,
+    mut toasts: ResMut<Toasts>,
+) {
+    let now = time.elapsed_secs();
+    if runner.running.is_none() || now < runner.resume_at {
+        return;
+    }
+
+    while let Some(command) = runner.queue.pop_front() {
+        match command {
+            ScriptCommand::Marker { coordinates, label } => {
+                let mut marker = commands.spawn((Marker { coordinates }, ScriptSpawned));
+                if let Some(label) = label {
+                    marker.insert(MarkerLabel(label));
+                }
+            }
+            ScriptCommand::Arc { from, to } => {
+                commands.spawn((FlightPath::new(from, to), ScriptSpawned));
+            }
+            ScriptCommand::Route { from, to } => {
+                let route = spawn_great_circle(&mut commands, from, to);
+                commands.entity(route).insert(ScriptSpawned);
+            }
+            ScriptCommand::Layer { name, visible } => {
+                let mut found = false;
+                for layer in layers.0.iter_mut().filter(|layer| layer.name == name) {
+                    layer.visible = visible.unwrap_or(!layer.visible);
+                    found = true;
+                }
+                for (_, mut visibility) in vector_layers
+                    .iter_mut()
+                    .filter(|(layer, _)| layer.name == name)
+                {
+                    let shown = visible.unwrap_or(*visibility == Visibility::Hidden);
+                    *visibility = if shown {
+                        Visibility::Inherited
+                    } else {
+                        Visibility::Hidden
+                    };
+                    found = true;
+                }
+                if let Some(registered) = globe_layers.get_mut(&name) {
+                    registered.visible = visible.unwrap_or(!registered.visible);
+                    found = true;
+                }
+                if !found {
+                    toasts.warning(format!("Script: no layer named {name}"));
+                }
+            }
+            ScriptCommand::FlyTo {
+                coordinates,
+                altitude,
+                duration,
+            } => commands.fly_to(coordinates, altitude, duration),
+            ScriptCommand::Wait(duration) => {
+                runner.resume_at = now + duration.as_secs_f32();
+                return;
+            }
+            ScriptCommand::Print(text) => toasts.info(text),
+            ScriptCommand::Clear => {
+                for entity in &spawned {
+                    commands.entity(entity).despawn();
+                }
+            }
+        }
+    }
+    runner.running = None;
+}
+
+/// Enters `ToolMode::Touring` while a tour runs, so manual navigation doesn't fight it, and goes
+/// back to `ToolMode::Idle` once it ended or was stopped.
+fn follow_tour(
+    runner: Res<ScriptRunner>,
+    mode: Res<State<ToolMode>>,
+    mut next_mode: ResMut<NextState<ToolMode>>,
+) {
+    let touring = **mode == ToolMode::Touring;
+    if runner.is_touring() && !touring {
+        next_mode.set(ToolMode::Touring);
+    } else if !runner.is_touring() && touring {
+        next_mode.set(ToolMode::Idle);
+    }
+}
+
+fn display_scripts_panel(
+    mut contexts: EguiContexts,
+    mut commands: Commands,
+    mut panel: ResMut<ScriptsPanel>,
+    mut runner: ResMut<ScriptRunner>,
+    spawned: Query<Entity, With<ScriptSpawned>>,
+    mut toasts: ResMut<Toasts>,
+    mut source: Local<String>,
+) -> bevy::prelude::Result {
+    let ctx = contexts.ctx_mut()?;
+
+    let files = script_files();
+    let mut run = None;
+    let mut run_source = false;
+    let mut stop = false;
+    let mut clear = false;
+    egui::Window::new("Scripts")
+        .open(&mut panel.open)
+        .default_width(360.)
+        .show(ctx, |ui| {
+            for (index, path) in files.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    if ui.small_button("▶").on_hover_text("Run").clicked() {
+                        run = Some(index);
+                    }
+                    ui.label(script_name(path));
+                });
+            }
+            if files.is_empty() {
+                ui.label(format!("No .rhai files in the {SCRIPTS_DIR} folder"));
+            }
+
+            ui.separator();
+            ui.add(
+                egui::TextEdit::multiline(&mut *source)
+                    .code_editor()
+                    .desired_rows(6)
+                    .hint_text("marker(48.86, 2.35, \"Paris\");"),
+            );
+            ui.horizontal(|ui| {
+                run_source = ui
+                    .add_enabled(!source.trim().is_empty(), egui::Button::new("Run"))
+                    .clicked();
+                if let Some(name) = runner.running() {
+                    ui.label(format!("Running {name}"));
+                    stop = ui.button("Stop").clicked();
+                }
+            });
+            clear = ui
+                .add_enabled(
+                    !spawned.is_empty(),
+                    egui::Button::new(format!("Remove {} spawned", spawned.iter().count())),
+                )
+                .on_hover_text("Markers and arcs placed by scripts")
+                .clicked();
+        });
+
+    let result = if let Some(path) = run.map(|index| &files[index]) {
+        run_file(&mut runner, path).map_err(|err| (script_name(path), err))
+    } else if run_source {
+        runner
+            .run("editor script", &source)
+            .map_err(|err| ("editor script".to_string(), err))
+    } else {
+        Ok(())
+    };
+    if let Err((name, err)) = result {
+        toasts.error(format!("Script {name} failed: {err}"));
+    }
+
+    if stop {
+        runner.stop();
+    }
+    if clear {
+        for entity in &spawned {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,409 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    asset::{Asset, Assets},
+    camera::Camera,
+    color::LinearRgba,
+    ecs::{
+        change_detection::DetectChanges,
+        component::Component,
+        entity::Entity,
+        hierarchy::ChildOf,
+        message::{Message, MessageReader, MessageWriter},
+        query::{Has, With},
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Query, Res, ResMut, Single},
+    },
+    input::{ButtonInput, keyboard::KeyCode, mouse::MouseButton},
+    math::{Rect, Vec2, Vec3},
+    mesh::{Mesh, Mesh3d, MeshVertexBufferLayoutRef},
+    pbr::{Material, MaterialPipeline, MaterialPipelineKey, MaterialPlugin, MeshMaterial3d},
+    picking::{
+        Pickable,
+        events::{Click, Pointer},
+        hover::HoverMap,
+        pointer::{PointerButton, PointerId},
+    },
+    reflect::Reflect,
+    render::{
+        alpha::AlphaMode,
+        render_resource::{
+            AsBindGroup, RenderPipelineDescriptor, ShaderType, SpecializedMeshPipelineError,
+        },
+    },
+    shader::ShaderRef,
+    state::condition::in_state,
+    transform::components::{GlobalTransform, Transform},
+    window::{PrimaryWindow, Window},
+};
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+
+use crate::{
+    EarthConfig,
+    component::{Billboard, Draped, Earth, MainCamera, Selectable},
+    depth::DRAPED_DEPTH_BIAS,
+    resource::PointerOverUi,
+    state::GameState,
+};
+
+const SHADER_PATH: &str = "shaders/outline.wgsl";
+
+/// Features under the mouse and picked by the last click or rectangle.
+#[derive(Resource, Debug, Default, Clone, PartialEq)]
+pub struct Selection {
+    pub hovered: Option<Entity>,
+    pub selected: Option<Entity>,
+    /// Everything inside the last selection rectangle
+    pub group: Vec<Entity>,
+}
+
+/// Written when a selection rectangle is released, with every feature inside it.
+#[derive(Message, Debug, Clone, PartialEq)]
+pub struct MultiSelected(pub Vec<Entity>);
+
+/// Shift-drag across the screen, selecting the features inside once released.
+#[derive(Resource, Debug, Default)]
+pub struct RectangleSelection {
+    /// Corner the drag started at, in logical window pixels
+    start: Option<Vec2>,
+    end: Vec2,
+}
+
+impl RectangleSelection {
+    pub fn is_dragging(&self) -> bool {
+        self.start.is_some()
+    }
+
+    fn rect(&self) -> Option<Rect> {
+        self.start.map(|start| Rect::from_corners(start, self.end))
+    }
+}
+
+/// Shell drawn around a highlighted feature, a child of it.
+#[derive(Component)]
+struct Outline;
+
+#[derive(ShaderType, Reflect, Debug, Clone, Copy)]
+pub struct OutlineUniform {
+    pub color: LinearRgba,
+    /// Point of the mesh the shell is pushed away from on screen, in its local space
+    pub center: Vec3,
+    /// Pixels the shell reaches beyond the silhouette
+    pub width: f32,
+    /// Non-zero to fade the shell out radially by its UVs, a soft glow rather than a rim
+    pub glow: u32,
+}
+
+/// The mesh of a feature again, expanded in screen space away from its center and drawn behind
+/// it, so only the part sticking out around its silhouette shows.
+///
+/// This works for shapes that are roughly convex around their center, which markers and most
+/// polygons are. Meshes need UVs.
+#[derive(Asset, AsBindGroup, Reflect, Debug, Clone)]
+pub struct OutlineMaterial {
+    #[uniform(0)]
+    pub uniform: OutlineUniform,
+    /// Slightly below the highlighted feature's bias
+    pub depth_bias: f32,
+}
+
+impl Material for OutlineMaterial {
+    fn vertex_shader() -> ShaderRef {
+        SHADER_PATH.into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        SHADER_PATH.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Blend
+    }
+
+    fn depth_bias(&self) -> f32 {
+        self.depth_bias
+    }
+
+    fn enable_prepass() -> bool {
+        false
+    }
+
+    fn enable_shadows() -> bool {
+        false
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayoutRef,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        let vertex_layout = layout.0.get_layout(&[
+            Mesh::ATTRIBUTE_POSITION.at_shader_location(0),
+            Mesh::ATTRIBUTE_UV_0.at_shader_location(1),
+        ])?;
+        descriptor.vertex.buffers = vec![vertex_layout];
+        descriptor.primitive.cull_mode = None;
+        Ok(())
+    }
+}
+
+/// How a highlighted feature is outlined.
+struct OutlineStyle {
+    color: LinearRgba,
+    width: f32,
+}
+
+const HOVERED: OutlineStyle = OutlineStyle {
+    color: LinearRgba::new(1., 1., 1., 0.6),
+    width: 4.,
+};
+
+const SELECTED: OutlineStyle = OutlineStyle {
+    color: LinearRgba::new(1., 0.75, 0.1, 0.9),
+    width: 6.,
+};
+
+pub struct SelectionPlugin;
+
+impl Plugin for SelectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(MaterialPlugin::<OutlineMaterial>::default())
+            .init_resource::<Selection>()
+            .init_resource::<RectangleSelection>()
+            .add_message::<MultiSelected>()
+            .add_systems(
+                Update,
+                (
+                    track_hover,
+                    select_on_click,
+                    select_in_rectangle,
+                    update_outlines,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                EguiPrimaryContextPass,
+                draw_rectangle
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(|rectangle: Res<RectangleSelection>| rectangle.is_dragging()),
+            );
+    }
+}
+
+fn track_hover(
+    hover_map: Res<HoverMap>,
+    over_ui: Res<PointerOverUi>,
+    selectable: Query<(), With<Selectable>>,
+    mut selection: ResMut<Selection>,
+) {
+    let hovered = hover_map
+        .get(&PointerId::Mouse)
+        .filter(|_| !**over_ui)
+        .and_then(|hits| {
+            hits.keys()
+                .copied()
+                .find(|&entity| selectable.contains(entity))
+        });
+
+    if selection.hovered != hovered {
+        selection.hovered = hovered;
+    }
+}
+
+fn shift_pressed(keyboard: &ButtonInput<KeyCode>) -> bool {
+    keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+}
+
+/// Clicking a feature selects it, clicking anything else clears the selection.
+///
+/// Shift-clicks are left alone, they end selection rectangles.
+fn select_on_click(
+    mut clicks: MessageReader<Pointer<Click>>,
+    over_ui: Res<PointerOverUi>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    selectable: Query<(), With<Selectable>>,
+    mut selection: ResMut<Selection>,
+) {
+    for click in clicks.read() {
+        if click.button != PointerButton::Primary || **over_ui || shift_pressed(&keyboard) {
+            continue;
+        }
+        let selected = selectable.contains(click.entity).then_some(click.entity);
+        if selection.selected != selected || !selection.group.is_empty() {
+            selection.selected = selected;
+            selection.group.clear();
+        }
+    }
+}
+
+/// Selects every feature on the camera-facing side of the globe that projects into the
+/// rectangle, once the shift-drag is released.
+fn select_in_rectangle(
+    mut rectangle: ResMut<RectangleSelection>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    over_ui: Res<PointerOverUi>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera>>,
+    earth: Single<&GlobalTransform, With<Earth>>,
+    features: Query<(Entity, &GlobalTransform), With<Selectable>>,
+    mut selection: ResMut<Selection>,
+    mut selected: MessageWriter<MultiSelected>,
+    config: Res<EarthConfig>,
+) {
+    let cursor = window.cursor_position();
+    if mouse.just_pressed(MouseButton::Left)
+        && shift_pressed(&keyboard)
+        && !**over_ui
+        && let Some(cursor) = cursor
+    {
+        rectangle.start = Some(cursor);
+        rectangle.end = cursor;
+        return;
+    }
+
+    if !rectangle.is_dragging() {
+        return;
+    }
+    if let Some(cursor) = cursor {
+        rectangle.end = cursor;
+    }
+    if mouse.pressed(MouseButton::Left) {
+        return;
+    }
+    let Some(rect) = rectangle.rect() else {
+        return;
+    };
+    rectangle.start = None;
+
+    let (camera, camera_transform) = *camera;
+    let center = earth.translation();
+    let eye = camera_transform.translation() - center;
+    let group: Vec<Entity> = features
+        .iter()
+        .filter(|(_, transform)| {
+            let position = transform.translation();
+            // In front of the horizon plane, as seen from the camera
+            let facing = (position - center).normalize_or_zero().dot(eye) > config.radius;
+            facing
+                && camera
+                    .world_to_viewport(camera_transform, position)
+                    .is_ok_and(|point| rect.contains(point))
+        })
+        .map(|(entity, _)| entity)
+        .collect();
+
+    selection.selected = None;
+    selection.group = group.clone();
+    selected.write(MultiSelected(group));
+}
+
+fn draw_rectangle(
+    mut contexts: EguiContexts,
+    rectangle: Res<RectangleSelection>,
+) -> bevy::prelude::Result {
+    let ctx = contexts.ctx_mut()?;
+    let Some(rect) = rectangle.rect() else {
+        return Ok(());
+    };
+
+    let rect = egui::Rect::from_min_max(
+        egui::pos2(rect.min.x, rect.min.y),
+        egui::pos2(rect.max.x, rect.max.y),
+    );
+    let painter = ctx.layer_painter(egui::LayerId::new(
+        egui::Order::Foreground,
+        egui::Id::new("selection_rectangle"),
+    ));
+    painter.rect_filled(rect, 0., egui::Color32::from_white_alpha(24));
+    painter.rect_stroke(
+        rect,
+        0.,
+        egui::Stroke::new(1., egui::Color32::WHITE),
+        egui::StrokeKind::Inside,
+    );
+
+    Ok(())
+}
+
+fn update_outlines(
+    mut commands: Commands,
+    mut selection: ResMut<Selection>,
+    shells: Query<Entity, With<Outline>>,
+    targets: Query<(&Mesh3d, Has<Draped>, Has<Billboard>)>,
+    meshes: Res<Assets<Mesh>>,
+    mut materials: ResMut<Assets<OutlineMaterial>>,
+) {
+    // Features can be despawned while highlighted
+    if selection
+        .selected
+        .is_some_and(|entity| !targets.contains(entity))
+    {
+        selection.selected = None;
+    }
+    if selection
+        .group
+        .iter()
+        .any(|&entity| !targets.contains(entity))
+    {
+        selection.group.retain(|&entity| targets.contains(entity));
+    }
+    if !selection.is_changed() {
+        return;
+    }
+
+    for shell in &shells {
+        commands.entity(shell).despawn();
+    }
+
+    let highlighted: Vec<(Entity, &OutlineStyle)> = selection
+        .selected
+        .iter()
+        .chain(&selection.group)
+        .map(|&entity| (entity, &SELECTED))
+        .chain(
+            selection
+                .hovered
+                .filter(|entity| {
+                    selection.selected != Some(*entity) && !selection.group.contains(entity)
+                })
+                .map(|entity| (entity, &HOVERED)),
+        )
+        .collect();
+    for (target, style) in highlighted {
+        let Ok((mesh, draped, billboard)) = targets.get(target) else {
+            continue;
+        };
+        let center = meshes
+            .get(&mesh.0)
+            .and_then(Mesh::compute_aabb)
+            .map_or(Vec3::ZERO, |aabb| aabb.center.into());
+
+        let material = materials.add(OutlineMaterial {
+            uniform: OutlineUniform {
+                color: style.color,
+                center,
+                width: style.width,
+                glow: billboard as u32,
+            },
+            depth_bias: if draped { DRAPED_DEPTH_BIAS - 1. } else { -1. },
+        });
+        // Billboards are transparent too, nudged back so the shell sorts behind
+        let transform = if billboard {
+            Transform::from_xyz(0., 0., -1e-3)
+        } else {
+            Transform::default()
+        };
+
+        commands.spawn((
+            Outline,
+            Mesh3d(mesh.0.clone()),
+            MeshMaterial3d(material),
+            transform,
+            Pickable::IGNORE,
+            ChildOf(target),
+        ));
+    }
+}
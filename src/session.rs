@@ -0,0 +1,256 @@
+use std::{path::PathBuf, time::Duration};
+
+use bevy::{
+    app::{App, AppExit, Last, Plugin, Update},
+    camera::Projection,
+    ecs::{
+        message::MessageReader,
+        query::{With, Without},
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Local, Query, Res, ResMut, SystemParam},
+    },
+    log::{error, info, warn},
+    state::{
+        condition::in_state,
+        state::{OnEnter, OnExit},
+    },
+    time::{Real, Time, Timer, TimerMode},
+    transform::components::Transform,
+};
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    component::{Earth, MainCamera},
+    pack::{EarthPack, EarthPacks},
+    resource::{ShowNorthArrow, SimulationTime},
+    state::GameState,
+};
+
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Bumped whenever `Session` changes in an incompatible way.
+const SESSION_VERSION: u32 = 1;
+
+/// Everything about the view that user interaction can change.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ViewState {
+    pub camera: Transform,
+    pub earth: Transform,
+    pub fov: f32,
+    pub simulation_elapsed: f64,
+    pub simulation_speed: f32,
+    pub paused: bool,
+    pub north_arrow: bool,
+}
+
+/// Serialized form of a whole session.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Session {
+    pub version: u32,
+    pub view: ViewState,
+}
+
+impl Session {
+    pub fn new(view: ViewState) -> Self {
+        Self {
+            version: SESSION_VERSION,
+            view,
+        }
+    }
+
+    pub fn save(&self, path: &std::path::Path) -> Result<(), Box<dyn std::error::Error>> {
+        let serialized = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        std::fs::write(path, serialized)?;
+        Ok(())
+    }
+
+    pub fn load(path: &std::path::Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let session: Session = ron::from_str(&std::fs::read_to_string(path)?)?;
+        if session.version != SESSION_VERSION {
+            return Err(format!("Unsupported session version {}", session.version).into());
+        }
+        Ok(session)
+    }
+}
+
+/// Read and write access to the parts of the world captured by a `ViewState`.
+#[derive(SystemParam)]
+pub struct SessionAccess<'w, 's> {
+    camera: Query<
+        'w,
+        's,
+        (&'static mut Transform, &'static mut Projection),
+        (With<MainCamera>, Without<Earth>),
+    >,
+    earth: Query<'w, 's, &'static mut Transform, With<Earth>>,
+    simulation: ResMut<'w, SimulationTime>,
+    north_arrow: ResMut<'w, ShowNorthArrow>,
+}
+
+impl SessionAccess<'_, '_> {
+    pub fn capture(&self) -> Option<ViewState> {
+        let (camera, projection) = self.camera.single().ok()?;
+        let earth = self.earth.single().ok()?;
+
+        Some(ViewState {
+            camera: *camera,
+            earth: *earth,
+            fov: match projection {
+                Projection::Perspective(perspective) => perspective.fov,
+                _ => 0.,
+            },
+            simulation_elapsed: self.simulation.elapsed,
+            simulation_speed: self.simulation.speed,
+            paused: self.simulation.paused,
+            north_arrow: **self.north_arrow,
+        })
+    }
+
+    pub fn apply(&mut self, view: &ViewState) {
+        if let Ok((mut camera, mut projection)) = self.camera.single_mut() {
+            *camera = view.camera;
+            if let Projection::Perspective(ref mut perspective) = *projection {
+                perspective.fov = view.fov;
+            }
+        }
+        if let Ok(mut earth) = self.earth.single_mut() {
+            *earth = view.earth;
+        }
+
+        self.simulation.elapsed = view.simulation_elapsed;
+        self.simulation.speed = view.simulation_speed;
+        self.simulation.paused = view.paused;
+        **self.north_arrow = view.north_arrow;
+    }
+}
+
+/// Session left behind by a run that did not exit cleanly.
+#[derive(Resource, Default)]
+struct PendingRestore(Option<Session>);
+
+/// File the session is autosaved to while the globe is shown, next to the other persisted files
+/// and one per pack, so the view of one pack is never restored into another.
+#[derive(Resource, Default)]
+struct AutosavePath(Option<PathBuf>);
+
+fn autosave_path(pack: &EarthPack) -> PathBuf {
+    PathBuf::from(format!("autosave-{}.ron", pack.name))
+}
+
+pub struct SessionPlugin;
+
+impl Plugin for SessionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PendingRestore>()
+            .init_resource::<AutosavePath>()
+            // Checked before the first autosave of the pack can be written
+            .add_systems(OnEnter(GameState::Playing), find_autosave)
+            .add_systems(OnExit(GameState::Playing), remove_autosave)
+            .add_systems(Update, autosave.run_if(in_state(GameState::Playing)))
+            .add_systems(
+                EguiPrimaryContextPass,
+                display_restore_dialog
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(|pending: Res<PendingRestore>| pending.0.is_some()),
+            )
+            .add_systems(Last, remove_autosave_on_exit);
+    }
+}
+
+fn find_autosave(
+    mut pending: ResMut<PendingRestore>,
+    mut autosave: ResMut<AutosavePath>,
+    packs: Res<EarthPacks>,
+) {
+    let path = autosave_path(packs.active());
+    autosave.0 = Some(path.clone());
+    pending.0 = None;
+    if !path.exists() {
+        return;
+    }
+
+    match Session::load(&path) {
+        Ok(session) => pending.0 = Some(session),
+        Err(err) => warn!("Ignoring unreadable autosave {}: {err}", path.display()),
+    }
+}
+
+/// Leaving the globe, e.g. for another pack, ends its session cleanly. A crashed session the user
+/// didn't decide on yet stays for the next time the pack is shown.
+fn remove_autosave(pending: Res<PendingRestore>, mut autosave: ResMut<AutosavePath>) {
+    if let Some(path) = autosave.0.take()
+        && pending.0.is_none()
+    {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+fn autosave(
+    path: Res<AutosavePath>,
+    time: Res<Time<Real>>,
+    mut timer: Local<Option<Timer>>,
+    pending: Res<PendingRestore>,
+    session: SessionAccess,
+) {
+    // Never overwrite the crashed session before the user has decided what to do with it
+    if pending.0.is_some() {
+        return;
+    }
+
+    let timer = timer.get_or_insert_with(|| Timer::new(AUTOSAVE_INTERVAL, TimerMode::Repeating));
+    if !timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    if let Some(path) = &path.0
+        && let Some(view) = session.capture()
+        && let Err(err) = Session::new(view).save(path)
+    {
+        error!("Autosave failed: {err}");
+    }
+}
+
+fn remove_autosave_on_exit(mut exit: MessageReader<AppExit>, path: Res<AutosavePath>) {
+    if exit.read().next().is_some()
+        && let Some(path) = &path.0
+        && std::fs::remove_file(path).is_ok()
+    {
+        info!("Removed autosave after clean exit");
+    }
+}
+
+fn display_restore_dialog(
+    mut contexts: EguiContexts,
+    mut pending: ResMut<PendingRestore>,
+    mut session: SessionAccess,
+) -> bevy::prelude::Result {
+    let ctx = contexts.ctx_mut()?;
+
+    let mut decision = None;
+    egui::Window::new("Restore session")
+        .anchor(egui::Align2::CENTER_CENTER, [0., 0.])
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.label("The previous session did not exit cleanly. Restore it?");
+            ui.horizontal(|ui| {
+                if ui.button("Restore").clicked() {
+                    decision = Some(true);
+                }
+                if ui.button("Discard").clicked() {
+                    decision = Some(false);
+                }
+            });
+        });
+
+    if let Some(restore) = decision {
+        let restored = pending.0.take();
+        if restore && let Some(restored) = restored {
+            session.apply(&restored.view);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,202 @@
+use bevy::{
+    app::{App, AppExit, Last, Plugin, PostStartup, Update},
+    ecs::{
+        change_detection::DetectChangesMut,
+        message::MessageReader,
+        query::With,
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Local, Query, Res, ResMut},
+    },
+    light::DirectionalLight,
+    log::error,
+    state::condition::in_state,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    EarthConfig, MAX_FOV, MIN_FOV,
+    atmosphere::Atmosphere,
+    clouds::Clouds,
+    component::RotatingLight,
+    graticule::Graticule,
+    input::{Action, Actions},
+    material::MaterialSettings,
+    math::zoom_fov,
+    state::GameState,
+    toast::Toasts,
+};
+
+const SETTINGS_PATH: &str = "settings.ron";
+
+/// Parameters tuned in the settings panel, persisted to `settings.ron`.
+///
+/// The mesh resolution, the exaggeration and the layer toggles mirror `EarthConfig`,
+/// `MaterialSettings`, `Clouds`, `Atmosphere` and `Graticule`, which stay in charge of them so
+/// the View menu and workspaces keep working. A saved file is applied to them on startup.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct EarthSettings {
+    /// Vertices along each edge of a chunk, see `EarthConfig::resolution`
+    pub resolution: u32,
+    /// Multiplier of how fast dragging and the keys turn the globe
+    pub rotation_sensitivity: f32,
+    /// Multiplier of how far each wheel notch and the keys zoom
+    pub zoom_sensitivity: f32,
+    /// Multiplier of how fast the light circles the globe in the demo sun mode
+    pub light_speed: f32,
+    /// Illuminance of the sun in lux
+    pub illuminance: f32,
+    /// Vertical exaggeration of the displacement, see `MaterialSettings::exaggeration`
+    pub exaggeration: f32,
+    pub clouds: bool,
+    pub atmosphere: bool,
+    pub graticule: bool,
+    /// Narrowest field of view zooming in reaches, in radians
+    pub min_fov: f32,
+    /// Widest field of view zooming out reaches, in radians. Below `MAX_FOV` the wheel no longer
+    /// leads into the space view.
+    pub max_fov: f32,
+    /// Keeps the `HttpClient` off the network, for venues without one. Tiles then come from
+    /// their cache only.
+    pub offline: bool,
+}
+
+impl Default for EarthSettings {
+    fn default() -> Self {
+        Self {
+            resolution: EarthConfig::default().resolution,
+            rotation_sensitivity: 1.,
+            zoom_sensitivity: 1.,
+            light_speed: 1.,
+            illuminance: 10000.,
+            exaggeration: MaterialSettings::default().exaggeration,
+            clouds: true,
+            atmosphere: true,
+            graticule: false,
+            min_fov: MIN_FOV,
+            max_fov: MAX_FOV,
+            offline: false,
+        }
+    }
+}
+
+impl EarthSettings {
+    fn load() -> Option<Self> {
+        let serialized = std::fs::read_to_string(SETTINGS_PATH).ok()?;
+        ron::from_str(&serialized)
+            .inspect_err(|err| error!("Failed to parse {SETTINGS_PATH}: {err}"))
+            .ok()
+    }
+
+    fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let serialized = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        std::fs::write(SETTINGS_PATH, serialized)?;
+        Ok(())
+    }
+
+    /// Field of view after zooming by `steps` wheel notches, within the configured limits.
+    pub fn zoom_fov(&self, fov: f32, steps: f32) -> f32 {
+        zoom_fov(fov, steps * self.zoom_sensitivity).clamp(self.min_fov, self.max_fov)
+    }
+}
+
+/// The settings side panel, toggled with `Action::ToggleSettings`.
+#[derive(Resource, Default)]
+pub struct SettingsPanel {
+    pub open: bool,
+}
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        // Without a saved file the settings start out from the resources they mirror
+        let saved = EarthSettings::load();
+        if saved.is_some() {
+            app.add_systems(PostStartup, restore_settings);
+        }
+
+        app.insert_resource(saved.unwrap_or_default())
+            .init_resource::<SettingsPanel>()
+            .add_systems(
+                Update,
+                (
+                    toggle_settings_panel.run_if(in_state(GameState::Playing)),
+                    mirror_settings,
+                    apply_illuminance,
+                ),
+            )
+            .add_systems(Last, save_settings);
+    }
+}
+
+fn restore_settings(
+    settings: Res<EarthSettings>,
+    mut config: ResMut<EarthConfig>,
+    mut material: ResMut<MaterialSettings>,
+    mut clouds: ResMut<Clouds>,
+    mut atmosphere: ResMut<Atmosphere>,
+    mut graticule: ResMut<Graticule>,
+) {
+    config.resolution = settings.resolution;
+    material.exaggeration = settings.exaggeration;
+    clouds.enabled = settings.clouds;
+    atmosphere.enabled = settings.atmosphere;
+    graticule.enabled = settings.graticule;
+}
+
+/// Follows the resources the settings mirror, however they were changed.
+fn mirror_settings(
+    mut settings: ResMut<EarthSettings>,
+    config: Res<EarthConfig>,
+    material: Res<MaterialSettings>,
+    clouds: Res<Clouds>,
+    atmosphere: Res<Atmosphere>,
+    graticule: Res<Graticule>,
+) {
+    let mirrored = EarthSettings {
+        resolution: config.resolution,
+        exaggeration: material.exaggeration,
+        clouds: clouds.enabled,
+        atmosphere: atmosphere.enabled,
+        graticule: graticule.enabled,
+        ..*settings
+    };
+    settings.set_if_neq(mirrored);
+}
+
+fn apply_illuminance(
+    settings: Res<EarthSettings>,
+    mut lights: Query<&mut DirectionalLight, With<RotatingLight>>,
+) {
+    for mut light in &mut lights {
+        if light.illuminance != settings.illuminance {
+            light.illuminance = settings.illuminance;
+        }
+    }
+}
+
+fn toggle_settings_panel(actions: Actions, mut panel: ResMut<SettingsPanel>) {
+    if actions.just_pressed(Action::ToggleSettings) {
+        panel.open = !panel.open;
+    }
+}
+
+/// Saves the settings when the panel closes, or on exit while it is open.
+fn save_settings(
+    mut exit: MessageReader<AppExit>,
+    settings: Res<EarthSettings>,
+    panel: Res<SettingsPanel>,
+    mut toasts: ResMut<Toasts>,
+    mut was_open: Local<bool>,
+) {
+    let exiting = exit.read().next().is_some();
+    let closed = *was_open && !panel.open;
+    *was_open = panel.open;
+    if (closed || exiting && panel.open)
+        && let Err(err) = settings.save()
+    {
+        toasts.error(format!("Failed to save {SETTINGS_PATH}: {err}"));
+    }
+}
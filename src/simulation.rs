@@ -0,0 +1,134 @@
+use std::f64::consts::TAU;
+
+use bevy::{
+    app::{App, FixedFirst, Plugin, RunFixedMainLoop, RunFixedMainLoopSystems, Update},
+    ecs::{
+        query::{With, Without},
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Local, Query, Res, ResMut},
+    },
+    state::condition::in_state,
+    time::{Fixed, Time},
+    transform::components::Transform,
+};
+
+use crate::{
+    component::{Earth, RotationAnimation, SimulatedTransform},
+    input::{Action, Actions},
+    resource::SimulationTime,
+    state::GameState,
+};
+
+/// Simulated seconds of one turn of the Earth relative to the stars.
+const SIDEREAL_DAY_SECS: f64 = 86_164.0905;
+
+/// Real seconds a frame may take before its simulated time is considered a jump of the clock.
+const MAX_FRAME_SECS: f64 = 0.25;
+
+/// Turns the Earth around its axis with the simulation clock, once per sidereal day at 1x.
+///
+/// Markers, overlays and everything else parented to the Earth turn along with it.
+#[derive(Resource, Default)]
+pub struct EarthSpin {
+    pub enabled: bool,
+}
+
+/// Runs time-driven systems on `FixedUpdate` against a `SimulationTime` that is decoupled from
+/// the frame clock, and interpolates their output for rendering.
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SimulationTime>()
+            .init_resource::<EarthSpin>()
+            .add_systems(
+                FixedFirst,
+                (store_previous_transforms, advance_simulation_time),
+            )
+            .add_systems(Update, simulation_hotkeys)
+            .add_systems(Update, spin_earth.run_if(in_state(GameState::Playing)))
+            .add_systems(
+                RunFixedMainLoop,
+                interpolate_transforms.in_set(RunFixedMainLoopSystems::AfterFixedMainLoop),
+            );
+    }
+}
+
+fn advance_simulation_time(time: Res<Time<Fixed>>, mut simulation: ResMut<SimulationTime>) {
+    let running = if simulation.paused {
+        let step = simulation.pending_steps > 0;
+        simulation.pending_steps = simulation.pending_steps.saturating_sub(1);
+        step
+    } else {
+        true
+    };
+
+    simulation.delta = if running {
+        time.delta_secs_f64() * simulation.speed as f64
+    } else {
+        0.
+    };
+    simulation.elapsed += simulation.delta;
+}
+
+fn simulation_hotkeys(actions: Actions, mut simulation: ResMut<SimulationTime>) {
+    if actions.just_pressed(Action::TogglePause) {
+        simulation.toggle_pause();
+    }
+    if actions.just_pressed(Action::Step) {
+        simulation.step();
+    }
+    if actions.just_pressed(Action::SpeedUp) {
+        let speed = simulation.speed * 2.;
+        simulation.set_speed(speed);
+    }
+    if actions.just_pressed(Action::SlowDown) {
+        let speed = simulation.speed / 2.;
+        simulation.set_speed(speed);
+    }
+}
+
+/// Turns the Earth by the simulated time passed since the last frame, on top of whatever
+/// navigation did to it. Animated navigation takes over the rotation while it lasts.
+fn spin_earth(
+    spin: Res<EarthSpin>,
+    simulation: Res<SimulationTime>,
+    time: Res<Time<Fixed>>,
+    mut last: Local<Option<f64>>,
+    mut earth: Query<&mut Transform, (With<Earth>, Without<RotationAnimation>)>,
+) {
+    // Between the last two fixed steps, like the `SimulatedTransform`s
+    let now = simulation.elapsed - simulation.delta * (1. - time.overstep_fraction_f64());
+    let elapsed = now - last.replace(now).unwrap_or(now);
+
+    // Jumps of the clock, such as restored sessions, don't turn the globe
+    if !spin.enabled || elapsed <= 0. || elapsed > simulation.speed as f64 * MAX_FRAME_SECS {
+        return;
+    }
+
+    let angle = (elapsed / SIDEREAL_DAY_SECS * TAU) as f32;
+    for mut transform in &mut earth {
+        // Positive turns carry the surface eastward
+        transform.rotate_local_y(angle);
+    }
+}
+
+fn store_previous_transforms(mut transforms: Query<&mut SimulatedTransform>) {
+    for mut simulated in &mut transforms {
+        simulated.previous = simulated.current;
+    }
+}
+
+fn interpolate_transforms(
+    time: Res<Time<Fixed>>,
+    mut transforms: Query<(&mut Transform, &SimulatedTransform)>,
+) {
+    let t = time.overstep_fraction();
+    for (mut transform, simulated) in &mut transforms {
+        let (previous, current) = (simulated.previous, simulated.current);
+        transform.translation = previous.translation.lerp(current.translation, t);
+        transform.rotation = previous.rotation.slerp(current.rotation, t);
+        transform.scale = previous.scale.lerp(current.scale, t);
+    }
+}
@@ -0,0 +1,325 @@
+use std::f32::consts::{PI, TAU};
+
+use bevy::{
+    app::{App, Plugin, Update},
+    asset::{AssetServer, Assets, Handle, RenderAssetUsages},
+    color::Color,
+    core_pipeline::Skybox,
+    ecs::{
+        change_detection::DetectChanges,
+        entity::Entity,
+        query::With,
+        resource::Resource,
+        schedule::{IntoScheduleConfigs, SystemCondition},
+        system::{Commands, Res, ResMut, Single},
+    },
+    image::Image,
+    log::warn,
+    math::{Quat, Vec3},
+    render::render_resource::{
+        Extent3d, TextureDimension, TextureFormat, TextureViewDescriptor, TextureViewDimension,
+    },
+    state::{condition::in_state, state::OnEnter},
+    tasks::{AsyncComputeTaskPool, Task, futures},
+};
+
+use crate::{EarthConfig, component::MainCamera, pack::EarthPacks, state::GameState};
+
+/// Pixels along the edge of each face of the generated star field.
+const STAR_FACE_SIZE: u32 = 1024;
+
+/// Stars scattered over the generated star field, about as many as the naked eye sees.
+const STAR_COUNT: usize = 8000;
+
+/// Largest face a star map is converted to, whatever its resolution.
+const MAX_FACE_SIZE: u32 = 2048;
+
+/// Stars behind the globe, from the pack's star map or generated when it has none.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct StarField {
+    pub enabled: bool,
+    /// Luminance of the brightest stars in cd/m²
+    pub brightness: f32,
+    /// Turn of the sky around the vertical axis, in degrees
+    pub rotation: f32,
+}
+
+impl Default for StarField {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            brightness: 400.,
+            rotation: 0.,
+        }
+    }
+}
+
+/// The star map of the active pack, on its way to a cubemap for the `Skybox`.
+#[derive(Resource, Default)]
+pub struct SkyTexture {
+    /// The pack's star map while it loads
+    source: Option<Handle<Image>>,
+    /// An equirectangular map being split into faces
+    converting: Option<Task<Image>>,
+    cubemap: Option<Handle<Image>>,
+}
+
+impl SkyTexture {
+    /// Whether the star map is still loading or being split into faces.
+    pub fn is_preparing(&self) -> bool {
+        self.source.is_some() || self.converting.is_some()
+    }
+}
+
+pub struct SkyPlugin;
+
+impl Plugin for SkyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<StarField>()
+            .init_resource::<SkyTexture>()
+            .add_systems(OnEnter(GameState::Loading), load_sky)
+            .add_systems(
+                Update,
+                (prepare_sky, apply_sky)
+                    .chain()
+                    .run_if(in_state(GameState::Loading).or(in_state(GameState::Playing))),
+            );
+    }
+}
+
+/// Direction through a point of a cubemap face, in the +X, -X, +Y, -Y, +Z, -Z layer order, with
+/// `u` and `v` from -1 to 1 across and down the face.
+fn face_direction(face: u32, u: f32, v: f32) -> Vec3 {
+    match face {
+        0 => Vec3::new(1., -v, -u),
+        1 => Vec3::new(-1., -v, u),
+        2 => Vec3::new(u, 1., v),
+        3 => Vec3::new(u, -1., -v),
+        4 => Vec3::new(u, -v, 1.),
+        _ => Vec3::new(-u, -v, -1.),
+    }
+    .normalize()
+}
+
+/// Face and point on it a direction goes through, the inverse of `face_direction`.
+fn face_point(direction: Vec3) -> (u32, f32, f32) {
+    let Vec3 { x, y, z } = direction;
+    let abs = direction.abs();
+    if abs.x >= abs.y && abs.x >= abs.z {
+        if x > 0. {
+            (0, -z / abs.x, -y / abs.x)
+        } else {
+            (1, z / abs.x, -y / abs.x)
+        }
+    } else if abs.y >= abs.z {
+        if y > 0. {
+            (2, x / abs.y, z / abs.y)
+        } else {
+            (3, x / abs.y, -z / abs.y)
+        }
+    } else if z > 0. {
+        (4, x / abs.z, -y / abs.z)
+    } else {
+        (5, -x / abs.z, -y / abs.z)
+    }
+}
+
+fn new_cubemap(size: u32) -> Image {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: 6,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    image.texture_view_descriptor = Some(TextureViewDescriptor {
+        dimension: Some(TextureViewDimension::Cube),
+        ..Default::default()
+    });
+    image
+}
+
+/// Offset of a pixel in the data of a cubemap made by `new_cubemap`.
+fn pixel_offset(size: u32, face: u32, u: f32, v: f32) -> usize {
+    let pixel = |t: f32| (((t + 1.) / 2. * size as f32) as u32).min(size - 1);
+    (((face * size + pixel(v)) * size + pixel(u)) * 4) as usize
+}
+
+/// Splits a star map covering 360° by 180° into the faces of a cubemap.
+fn equirectangular_to_cubemap(source: &Image) -> Image {
+    let (width, height) = (source.width(), source.height());
+    let size = (width / 4).clamp(1, MAX_FACE_SIZE);
+    let mut cubemap = new_cubemap(size);
+    let Some(data) = cubemap.data.as_mut() else {
+        return cubemap;
+    };
+
+    for face in 0..6 {
+        for row in 0..size {
+            for column in 0..size {
+                let u = (column as f32 + 0.5) / size as f32 * 2. - 1.;
+                let v = (row as f32 + 0.5) / size as f32 * 2. - 1.;
+                let direction = face_direction(face, u, v);
+                // Same longitude convention as `Coordinates`
+                let longitude = direction.x.atan2(direction.z);
+                let latitude = direction.y.clamp(-1., 1.).asin();
+                let x = ((0.5 + longitude / TAU) * width as f32) as u32;
+                let y = ((0.5 - latitude / PI) * height as f32) as u32;
+                let color = source
+                    .get_color_at(x.min(width - 1), y.min(height - 1))
+                    .unwrap_or(Color::BLACK);
+                let offset = pixel_offset(size, face, u, v);
+                data[offset..offset + 4].copy_from_slice(&color.to_srgba().to_u8_array());
+            }
+        }
+    }
+    cubemap
+}
+
+/// Next value of a xorshift generator, from 0 to 1.
+fn next_random(state: &mut u32) -> f32 {
+    *state ^= *state << 13;
+    *state ^= *state >> 17;
+    *state ^= *state << 5;
+    *state as f32 / u32::MAX as f32
+}
+
+/// A point cloud of stars of random brightness and tint, the same every time.
+fn generate_star_field() -> Image {
+    let mut cubemap = new_cubemap(STAR_FACE_SIZE);
+    let Some(data) = cubemap.data.as_mut() else {
+        return cubemap;
+    };
+
+    let mut state = 0x2545_f491;
+    for _ in 0..STAR_COUNT {
+        // Uniform over the sphere
+        let height = next_random(&mut state) * 2. - 1.;
+        let angle = next_random(&mut state) * TAU;
+        let ring = (1. - height * height).sqrt();
+        let direction = Vec3::new(ring * angle.cos(), height, ring * angle.sin());
+
+        // Faint stars are far more common than bright ones
+        let brightness = next_random(&mut state).powi(6) * 0.9 + 0.1;
+        // From orange to white
+        let warmth = next_random(&mut state);
+        let tint = [1., 0.9 + 0.1 * warmth, 0.75 + 0.25 * warmth];
+
+        let (face, u, v) = face_point(direction);
+        let offset = pixel_offset(STAR_FACE_SIZE, face, u, v);
+        for (channel, tint) in tint.into_iter().enumerate() {
+            let value = (brightness * tint * 255.) as u8;
+            data[offset + channel] = data[offset + channel].max(value);
+        }
+    }
+    cubemap
+}
+
+/// Starts loading the active pack's star map, or generates the stars right away without one.
+fn load_sky(
+    mut sky: ResMut<SkyTexture>,
+    mut images: ResMut<Assets<Image>>,
+    asset_server: Res<AssetServer>,
+    packs: Res<EarthPacks>,
+    config: Res<EarthConfig>,
+) {
+    let pack = packs.active();
+    *sky = SkyTexture::default();
+    if pack.root.join(&config.sky).is_file() {
+        sky.source = Some(asset_server.load(pack.asset_path(&config.sky)));
+    } else {
+        sky.cubemap = Some(images.add(generate_star_field()));
+    }
+}
+
+/// Turns the loaded star map into a cubemap, by its layout: six square faces stacked vertically
+/// are used as they are, a map twice as wide as high is taken for equirectangular.
+fn prepare_sky(
+    mut sky: ResMut<SkyTexture>,
+    mut images: ResMut<Assets<Image>>,
+    asset_server: Res<AssetServer>,
+) {
+    if let Some(task) = &mut sky.converting {
+        if let Some(cubemap) = futures::check_ready(task) {
+            sky.converting = None;
+            sky.cubemap = Some(images.add(cubemap));
+        }
+        return;
+    }
+
+    let Some(handle) = &sky.source else {
+        return;
+    };
+    if asset_server.load_state(handle).is_failed() {
+        warn!("Failed to load the star map, generating stars instead");
+        sky.source = None;
+        sky.cubemap = Some(images.add(generate_star_field()));
+        return;
+    }
+    let Some(image) = images.get_mut(handle) else {
+        return;
+    };
+
+    let handle = handle.clone();
+    sky.source = None;
+    let (width, height) = (image.width(), image.height());
+    if height == width * 6 {
+        match image.reinterpret_stacked_2d_as_array(6) {
+            Ok(()) => {
+                image.texture_view_descriptor = Some(TextureViewDescriptor {
+                    dimension: Some(TextureViewDimension::Cube),
+                    ..Default::default()
+                });
+                sky.cubemap = Some(handle);
+                return;
+            }
+            Err(err) => warn!("Can't use the star map as a cubemap: {err}"),
+        }
+    } else if width == height * 2 {
+        let source = image.clone();
+        sky.converting = Some(
+            AsyncComputeTaskPool::get().spawn(async move { equirectangular_to_cubemap(&source) }),
+        );
+        return;
+    } else {
+        warn!(
+            "The star map is {width} × {height}, neither equirectangular nor six stacked faces, \
+             generating stars instead"
+        );
+    }
+    sky.cubemap = Some(images.add(generate_star_field()));
+}
+
+fn apply_sky(
+    mut commands: Commands,
+    stars: Res<StarField>,
+    sky: Res<SkyTexture>,
+    camera: Single<(Entity, Option<&mut Skybox>), With<MainCamera>>,
+) {
+    let (entity, skybox) = camera.into_inner();
+    let image = sky.cubemap.as_ref().filter(|_| stars.enabled);
+    match (image, skybox) {
+        (Some(image), Some(mut skybox)) => {
+            if stars.is_changed() || skybox.image != *image {
+                skybox.image = image.clone();
+                skybox.brightness = stars.brightness;
+                skybox.rotation = Quat::from_rotation_y(stars.rotation.to_radians());
+            }
+        }
+        (Some(image), None) => {
+            commands.entity(entity).insert(Skybox {
+                image: image.clone(),
+                brightness: stars.brightness,
+                rotation: Quat::from_rotation_y(stars.rotation.to_radians()),
+            });
+        }
+        (None, Some(_)) => {
+            commands.entity(entity).remove::<Skybox>();
+        }
+        (None, None) => {}
+    }
+}
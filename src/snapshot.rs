@@ -0,0 +1,445 @@
+use std::path::{Path, PathBuf};
+
+use bevy::{
+    app::{App, Plugin, Update},
+    asset::{Assets, Handle},
+    camera::{Camera, Camera3d, PerspectiveProjection, Projection, RenderTarget},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        observer::On,
+        query::With,
+        resource::Resource,
+        system::{Commands, Query, Res, ResMut},
+    },
+    image::Image,
+    log::error,
+    math::{Quat, Vec3},
+    render::{
+        render_resource::TextureUsages,
+        view::screenshot::{Screenshot, ScreenshotCaptured, save_to_disk},
+    },
+    state::condition::in_state,
+};
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+use image::{Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    math::{Coordinates, rotation_to_center},
+    offscreen::offscreen_target,
+    palette::RegisterCommand,
+    session::{SessionAccess, ViewState},
+    state::GameState,
+};
+
+/// Reference images, and the views to render from `views.ron` if it exists.
+const SNAPSHOTS_DIR: &str = "snapshots";
+
+/// Snapshots are rendered offscreen at a fixed size, independent of the window and its UI.
+const SNAPSHOT_SIZE: u32 = 512;
+
+/// Frames to wait after moving to a view, so animations, clip planes and materials catch up.
+const SETTLE_FRAMES: u32 = 30;
+
+/// Per-pixel difference in pixelmatch's YIQ metric, as a share of the largest possible one, up
+/// to which two pixels count as equal.
+const PIXEL_THRESHOLD: f32 = 0.1;
+
+/// Largest value of the YIQ difference, between black and white.
+const MAX_YIQ_DELTA: f32 = 35215.;
+
+/// Share of differing pixels a snapshot may have and still pass.
+const MISMATCH_TOLERANCE: f32 = 0.005;
+
+/// A canned camera position to render the globe from.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SnapshotView {
+    pub name: String,
+    /// Degrees of the point in the center of the view
+    pub latitude: f32,
+    pub longitude: f32,
+    pub fov: f32,
+}
+
+impl SnapshotView {
+    fn new(name: &str, latitude: f32, longitude: f32, fov: f32) -> Self {
+        Self {
+            name: name.to_string(),
+            latitude,
+            longitude,
+            fov,
+        }
+    }
+
+    fn defaults() -> Vec<Self> {
+        vec![
+            Self::new("atlantic", 20., -30., std::f32::consts::FRAC_PI_4),
+            Self::new("north_pole", 90., 0., std::f32::consts::FRAC_PI_4),
+            Self::new("himalaya", 28., 86., 0.15),
+            Self::new("pacific_dateline", 0., 180., 0.4),
+        ]
+    }
+
+    fn load_all() -> Vec<Self> {
+        let path = Path::new(SNAPSHOTS_DIR).join("views.ron");
+        let Ok(serialized) = std::fs::read_to_string(&path) else {
+            return Self::defaults();
+        };
+        ron::from_str(&serialized).unwrap_or_else(|err| {
+            error!("Failed to parse {}: {err}", path.display());
+            Self::defaults()
+        })
+    }
+}
+
+fn reference_path(name: &str) -> PathBuf {
+    Path::new(SNAPSHOTS_DIR).join(format!("{name}.png"))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SnapshotStatus {
+    Passed,
+    /// Share of pixels that differ beyond `PIXEL_THRESHOLD`
+    Failed(f32),
+    /// There was no reference yet, the snapshot became it
+    Recorded,
+    Error(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct SnapshotResult {
+    pub name: String,
+    pub status: SnapshotStatus,
+    /// Kept to accept it as the new reference
+    image: Option<RgbaImage>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Phase {
+    #[default]
+    Idle,
+    Settling {
+        view: usize,
+        frames: u32,
+    },
+    Capturing {
+        view: usize,
+    },
+}
+
+/// Developer tool rendering the globe from every `SnapshotView` and comparing the renders with
+/// reference images, to catch shader and mesh regressions without comparing by eye.
+#[derive(Resource, Default)]
+pub struct SnapshotRunner {
+    pub open: bool,
+    views: Vec<SnapshotView>,
+    phase: Phase,
+    pub results: Vec<SnapshotResult>,
+    target: Handle<Image>,
+    captured: Option<Image>,
+    /// View of the user before the run, restored afterwards
+    restore: Option<ViewState>,
+}
+
+impl SnapshotRunner {
+    pub fn is_running(&self) -> bool {
+        self.phase != Phase::Idle
+    }
+}
+
+/// Offscreen camera rendering the snapshot, despawned once captured.
+#[derive(Component)]
+struct SnapshotCamera;
+
+pub struct SnapshotPlugin;
+
+impl Plugin for SnapshotPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SnapshotRunner>()
+            .add_systems(Update, run_snapshots.run_if(in_state(GameState::Playing)))
+            .add_systems(
+                EguiPrimaryContextPass,
+                display_snapshots
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(|runner: Res<SnapshotRunner>| runner.open),
+            )
+            .register_command("Export screenshot", export_screenshot);
+    }
+}
+
+/// Saves what the window shows, UI included, as `screenshot-<unix time>.png` in the working
+/// directory.
+fn export_screenshot(mut commands: Commands) {
+    let unix_secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    commands
+        .spawn(Screenshot::primary_window())
+        .observe(save_to_disk(format!("screenshot-{unix_secs}.png")));
+}
+
+fn yiq(pixel: Rgba<u8>) -> Vec3 {
+    let [r, g, b, _] = pixel.0.map(f32::from);
+    Vec3::new(
+        r * 0.298_895_3 + g * 0.586_622_5 + b * 0.114_482_2,
+        r * 0.595_978 - g * 0.274_176_1 - b * 0.321_801_9,
+        r * 0.211_470_2 - g * 0.522_617_1 + b * 0.311_146_9,
+    )
+}
+
+/// Difference of two pixels weighted by how much each YIQ channel matters to the eye.
+fn perceptual_delta(a: Rgba<u8>, b: Rgba<u8>) -> f32 {
+    let delta = yiq(a) - yiq(b);
+    0.5053 * delta.x * delta.x + 0.299 * delta.y * delta.y + 0.1957 * delta.z * delta.z
+}
+
+/// Share of differing pixels, and an image with them in red over a faded `current`.
+fn compare(reference: &RgbaImage, current: &RgbaImage) -> Result<(f32, RgbaImage), String> {
+    if reference.dimensions() != current.dimensions() {
+        return Err(format!(
+            "Size {:?} differs from the reference's {:?}",
+            current.dimensions(),
+            reference.dimensions()
+        ));
+    }
+
+    let limit = MAX_YIQ_DELTA * PIXEL_THRESHOLD * PIXEL_THRESHOLD;
+    let mut differing = 0;
+    let diff = RgbaImage::from_fn(current.width(), current.height(), |x, y| {
+        let (a, b) = (*reference.get_pixel(x, y), *current.get_pixel(x, y));
+        if perceptual_delta(a, b) > limit {
+            differing += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            let luma = (yiq(b).x * 0.3 + 255. * 0.7) as u8;
+            Rgba([luma, luma, luma, 255])
+        }
+    });
+
+    Ok((differing as f32 / current.pixels().len() as f32, diff))
+}
+
+fn evaluate(view: &SnapshotView, captured: Image) -> SnapshotResult {
+    let image = match captured.try_into_dynamic() {
+        Ok(image) => image.to_rgba8(),
+        Err(err) => {
+            return SnapshotResult {
+                name: view.name.clone(),
+                status: SnapshotStatus::Error(err.to_string()),
+                image: None,
+            };
+        }
+    };
+
+    let path = reference_path(&view.name);
+    let status = match image::open(&path) {
+        Err(_) => match save(&path, &image) {
+            Ok(()) => SnapshotStatus::Recorded,
+            Err(err) => SnapshotStatus::Error(err.to_string()),
+        },
+        Ok(reference) => match compare(&reference.to_rgba8(), &image) {
+            Ok((mismatch, _)) if mismatch <= MISMATCH_TOLERANCE => SnapshotStatus::Passed,
+            Ok((mismatch, diff)) => {
+                // Left next to the reference for a closer look
+                let saved = save(&path.with_extension("current.png"), &image)
+                    .and_then(|()| save(&path.with_extension("diff.png"), &diff));
+                if let Err(err) = saved {
+                    error!("Failed to save the diff of snapshot {}: {err}", view.name);
+                }
+                SnapshotStatus::Failed(mismatch)
+            }
+            Err(err) => SnapshotStatus::Error(err),
+        },
+    };
+
+    SnapshotResult {
+        name: view.name.clone(),
+        status,
+        image: Some(image),
+    }
+}
+
+fn save(path: &Path, image: &RgbaImage) -> Result<(), Box<dyn std::error::Error>> {
+    std::fs::create_dir_all(SNAPSHOTS_DIR)?;
+    image.save(path)?;
+    Ok(())
+}
+
+fn store_capture(captured: On<ScreenshotCaptured>, mut runner: ResMut<SnapshotRunner>) {
+    runner.captured = Some(captured.image.clone());
+}
+
+/// Steps through the views, one phase per frame: move there, let the scene settle, render it
+/// offscreen, then compare the capture once it is read back.
+fn run_snapshots(
+    mut commands: Commands,
+    mut runner: ResMut<SnapshotRunner>,
+    mut session: SessionAccess,
+    cameras: Query<Entity, With<SnapshotCamera>>,
+) {
+    match runner.phase {
+        Phase::Idle => {}
+        Phase::Settling { view, frames } => {
+            let Some(current) = session.capture() else {
+                return;
+            };
+            if frames == 0 {
+                let target = &runner.views[view];
+                let center = Coordinates::from_degrees(target.latitude, target.longitude)
+                    .map_or(Vec3::Z, |coordinates| coordinates.direction());
+                let direction = (current.camera.translation - current.earth.translation)
+                    .try_normalize()
+                    .unwrap_or(Vec3::Z);
+                let mut state = current;
+                state.earth.rotation = rotation_to_center(
+                    center,
+                    Quat::IDENTITY,
+                    direction,
+                    current.camera.up().into(),
+                );
+                state.fov = target.fov;
+                // A fixed time of day, so the lighting matches the reference
+                state.simulation_elapsed = 0.;
+                state.paused = true;
+                state.north_arrow = false;
+                session.apply(&state);
+            }
+
+            if frames < SETTLE_FRAMES {
+                runner.phase = Phase::Settling {
+                    view,
+                    frames: frames + 1,
+                };
+                return;
+            }
+
+            commands.spawn((
+                SnapshotCamera,
+                Camera3d::default(),
+                Camera {
+                    target: RenderTarget::Image(runner.target.clone().into()),
+                    order: -1,
+                    ..Default::default()
+                },
+                current.camera,
+                Projection::Perspective(PerspectiveProjection {
+                    fov: current.fov,
+                    ..Default::default()
+                }),
+            ));
+            commands
+                .spawn(Screenshot::image(runner.target.clone()))
+                .observe(store_capture);
+            runner.phase = Phase::Capturing { view };
+        }
+        Phase::Capturing { view } => {
+            let Some(captured) = runner.captured.take() else {
+                return;
+            };
+            for camera in &cameras {
+                commands.entity(camera).despawn();
+            }
+
+            let result = evaluate(&runner.views[view], captured);
+            runner.results.push(result);
+
+            runner.phase = if view + 1 < runner.views.len() {
+                Phase::Settling {
+                    view: view + 1,
+                    frames: 0,
+                }
+            } else {
+                if let Some(restore) = runner.restore.take() {
+                    session.apply(&restore);
+                }
+                Phase::Idle
+            };
+        }
+    }
+}
+
+fn display_snapshots(
+    mut contexts: EguiContexts,
+    mut runner: ResMut<SnapshotRunner>,
+    mut images: ResMut<Assets<Image>>,
+    session: SessionAccess,
+) -> bevy::prelude::Result {
+    let ctx = contexts.ctx_mut()?;
+
+    let mut open = runner.open;
+    let mut start = false;
+    let mut accept = None;
+    egui::Window::new("Snapshots")
+        .open(&mut open)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.add_enabled_ui(!runner.is_running(), |ui| {
+                if ui.button("Run all").clicked() {
+                    start = true;
+                }
+            });
+            if let Phase::Settling { view, .. } | Phase::Capturing { view } = runner.phase {
+                ui.label(format!("Rendering {}", runner.views[view].name));
+            }
+            ui.separator();
+
+            egui::Grid::new("snapshot_results")
+                .num_columns(3)
+                .show(ui, |ui| {
+                    for (index, result) in runner.results.iter().enumerate() {
+                        ui.label(&result.name);
+                        match &result.status {
+                            SnapshotStatus::Passed => {
+                                ui.colored_label(egui::Color32::GREEN, "Passed");
+                            }
+                            SnapshotStatus::Recorded => {
+                                ui.label("Recorded as reference");
+                            }
+                            SnapshotStatus::Failed(mismatch) => {
+                                ui.colored_label(
+                                    egui::Color32::RED,
+                                    format!("Failed, {:.2}% differ", mismatch * 100.),
+                                );
+                            }
+                            SnapshotStatus::Error(err) => {
+                                ui.colored_label(egui::Color32::RED, err);
+                            }
+                        }
+                        let acceptable = matches!(
+                            result.status,
+                            SnapshotStatus::Failed(_) | SnapshotStatus::Error(_)
+                        ) && result.image.is_some();
+                        if acceptable && ui.button("Accept").clicked() {
+                            accept = Some(index);
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+    runner.open = open;
+
+    if start && let Some(view) = session.capture() {
+        if runner.target == Handle::default() {
+            runner.target = offscreen_target(&mut images, SNAPSHOT_SIZE, TextureUsages::COPY_SRC);
+        }
+        runner.views = SnapshotView::load_all();
+        runner.results.clear();
+        runner.restore = Some(view);
+        if !runner.views.is_empty() {
+            runner.phase = Phase::Settling { view: 0, frames: 0 };
+        }
+    }
+
+    if let Some(index) = accept {
+        let result = &mut runner.results[index];
+        if let Some(image) = &result.image {
+            match save(&reference_path(&result.name), image) {
+                Ok(()) => result.status = SnapshotStatus::Recorded,
+                Err(err) => error!("Failed to accept snapshot {}: {err}", result.name),
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,290 @@
+use bevy::{
+    app::{App, Plugin, Startup, Update},
+    asset::Assets,
+    camera::{Camera, Projection, visibility::Visibility},
+    color::{Color, LinearRgba},
+    ecs::{
+        change_detection::DetectChangesMut,
+        component::Component,
+        entity::Entity,
+        message::MessageReader,
+        query::{With, Without},
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Res, ResMut, Single},
+    },
+    input::mouse::MouseWheel,
+    math::{Quat, Vec3, primitives::Sphere},
+    mesh::{Mesh, Mesh3d},
+    pbr::{MeshMaterial3d, StandardMaterial},
+    picking::Pickable,
+    state::{condition::in_state, state::State},
+    time::Time,
+    transform::components::Transform,
+};
+
+use crate::{
+    EarthConfig, MAX_FOV,
+    component::{Earth, FovAnimation, MainCamera, RotatingLight, ZoomAnimation},
+    free_flight::FreeFlight,
+    input::{Action, Actions},
+    resource::PointerOverUi,
+    state::{GameState, ToolMode},
+};
+
+/// Duration of the pull back from the globe into space, and of the way back.
+const TRANSITION_SECONDS: f32 = 2.;
+
+/// Distance of the camera from the middle of the Earth-Sun line in space view, in Earth-Sun
+/// distances, so the Sun and the Earth both fit into the widest field of view.
+const SPACE_VIEW_DISTANCE: f32 = 1.5;
+
+/// How distances and sizes of the Sun and the Moon relate to the Earth.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpaceScale {
+    /// Distances shrunk so all three bodies are recognizable at once
+    #[default]
+    Compressed,
+    /// Real distances and radii, where the Earth and the Moon are barely more than a pixel
+    True,
+}
+
+impl SpaceScale {
+    /// Distance from the Earth's center and radius of the Sun, in world units.
+    fn sun(&self, config: &EarthConfig) -> (f32, f32) {
+        let km = config.km_per_unit();
+        match self {
+            SpaceScale::Compressed => (config.radius * 40., config.radius * 4.),
+            SpaceScale::True => (149_597_870. / km, 696_340. / km),
+        }
+    }
+
+    /// Distance from the Earth's center and radius of the Moon, in world units.
+    pub fn moon(&self, config: &EarthConfig) -> (f32, f32) {
+        let km = config.km_per_unit();
+        match self {
+            SpaceScale::Compressed => (config.radius * 8., config.radius * 0.27),
+            SpaceScale::True => (384_400. / km, 1_737.4 / km),
+        }
+    }
+}
+
+/// Zoomed out view of the Sun, the Earth and the Moon, entered by zooming beyond the widest
+/// field of view.
+#[derive(Resource, Debug, Default)]
+pub struct SpaceView {
+    pub scale: SpaceScale,
+    /// Whether the camera is heading into space, the transition follows over a few seconds
+    pub enabled: bool,
+    /// Progress of the transition, 0 in globe view and 1 in space view
+    progress: f32,
+    /// Camera offset from the globe, rotation and field of view when space view was entered,
+    /// restored on the way back
+    globe_pose: Option<(Vec3, Quat, f32)>,
+    /// Camera offset from the globe and rotation where free flight handed the camera back, used
+    /// instead of the overview as the space end of the transition
+    flight_pose: Option<(Vec3, Quat)>,
+}
+
+impl SpaceView {
+    /// Whether the camera is in space view or on its way there or back.
+    pub fn is_active(&self) -> bool {
+        self.enabled || self.globe_pose.is_some()
+    }
+
+    /// Whether the transition into space has finished.
+    pub fn is_in_space(&self) -> bool {
+        self.enabled && self.progress >= 1.
+    }
+
+    /// Returns to the globe from the camera pose left by free flight, rather than the overview.
+    pub fn return_from(&mut self, offset: Vec3, rotation: Quat) {
+        self.enabled = false;
+        self.flight_pose = Some((offset, rotation));
+    }
+
+    /// Ends space view on the spot, leaving the camera where it is.
+    pub fn leave(&mut self) {
+        self.enabled = false;
+        self.progress = 0.;
+        self.globe_pose = None;
+        self.flight_pose = None;
+    }
+
+    /// Distance from the Earth's center to the far side of every body shown.
+    pub fn extent(&self, config: &EarthConfig) -> f32 {
+        let (distance, radius) = self.scale.sun(config);
+        distance + radius
+    }
+}
+
+/// The Sun shown in space view. The Moon is an `OrbitingBody` of its own.
+#[derive(Component)]
+struct Sun;
+
+pub struct SpacePlugin;
+
+impl Plugin for SpacePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpaceView>()
+            .add_systems(Startup, spawn_sun)
+            .add_systems(
+                Update,
+                (
+                    (space_view_hotkeys, zoom_out_to_space)
+                        .run_if(|mode: Res<State<ToolMode>>| mode.allows_navigation()),
+                    animate_space_view,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+fn spawn_sun(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn((
+        Sun,
+        Mesh3d(meshes.add(Sphere::new(1.).mesh().uv(64, 32))),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            base_color: Color::srgb(1., 0.9, 0.6),
+            emissive: LinearRgba::rgb(8., 6., 3.),
+            unlit: true,
+            ..Default::default()
+        })),
+        Transform::default(),
+        Visibility::Hidden,
+        Pickable::IGNORE,
+    ));
+}
+
+fn space_view_hotkeys(actions: Actions, mut space: ResMut<SpaceView>) {
+    if actions.just_pressed(Action::ToggleSpaceView) {
+        space.enabled = !space.enabled;
+    }
+}
+
+/// Scrolling out at the widest field of view leaves the globe for space, scrolling in returns.
+fn zoom_out_to_space(
+    mut wheel: MessageReader<MouseWheel>,
+    camera: Single<&Projection, With<MainCamera>>,
+    mut space: ResMut<SpaceView>,
+    over_ui: Res<PointerOverUi>,
+) {
+    let scroll: f32 = wheel.read().map(|wheel| wheel.y).sum();
+    if **over_ui {
+        return;
+    }
+    let Projection::Perspective(perspective) = *camera else {
+        return;
+    };
+
+    if scroll < 0. && !space.enabled && perspective.fov >= MAX_FOV - 1e-3 {
+        space.enabled = true;
+    } else if scroll > 0. && space.enabled {
+        space.enabled = false;
+    }
+}
+
+/// Moves the camera between its pose at the globe and the space view, and places the Sun.
+///
+/// The Sun lies opposite to where the light shines to. Once in space, the camera belongs to free
+/// flight if it is active.
+pub fn animate_space_view(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut space: ResMut<SpaceView>,
+    camera: Single<(Entity, &mut Transform, &mut Projection), (With<MainCamera>, Without<Sun>)>,
+    earth: Single<&Transform, (With<Earth>, Without<MainCamera>, Without<Sun>)>,
+    light: Single<&Transform, (With<RotatingLight>, Without<MainCamera>, Without<Sun>)>,
+    sun: Single<(&mut Transform, &mut Visibility), (With<Sun>, Without<Camera>)>,
+    flight: Res<FreeFlight>,
+    config: Res<EarthConfig>,
+) {
+    let (mut sun_transform, mut sun_visibility) = sun.into_inner();
+    if !space.is_active() {
+        sun_visibility.set_if_neq(Visibility::Hidden);
+        return;
+    }
+
+    let (entity, mut transform, mut projection) = camera.into_inner();
+    let Projection::Perspective(ref mut perspective) = *projection else {
+        return;
+    };
+    let center = earth.translation;
+
+    let (offset, rotation, fov) = match space.globe_pose {
+        Some(pose) => pose,
+        None => {
+            // A pending wheel zoom would fight over the field of view
+            commands
+                .entity(entity)
+                .remove::<(ZoomAnimation, FovAnimation)>();
+            let pose = (
+                transform.translation - center,
+                transform.rotation,
+                perspective.fov,
+            );
+            space.globe_pose = Some(pose);
+            pose
+        }
+    };
+
+    let step = time.delta_secs() / TRANSITION_SECONDS;
+    space.progress = if space.enabled {
+        (space.progress + step).min(1.)
+    } else {
+        (space.progress - step).max(0.)
+    };
+
+    if space.progress == 0. {
+        transform.translation = center + offset;
+        transform.rotation = rotation;
+        perspective.fov = fov;
+        space.leave();
+        return;
+    }
+
+    let sun_direction = *light.back();
+    let (distance, radius) = space.scale.sun(&config);
+    *sun_transform = Transform::from_translation(center + sun_direction * distance)
+        .with_scale(Vec3::splat(radius));
+    *sun_visibility = Visibility::Visible;
+
+    if flight.active {
+        return;
+    }
+
+    // Looking at the Earth-Sun line from its side and slightly above
+    let (sun_distance, _) = space.scale.sun(&config);
+    let focus = center + sun_direction * sun_distance * 0.5;
+    let side = sun_direction.cross(Vec3::Y).normalize_or_zero();
+    let eye = match space.flight_pose {
+        Some((flight_offset, _)) => center + flight_offset,
+        None => focus + (side + Vec3::Y * 0.5).normalize() * sun_distance * SPACE_VIEW_DISTANCE,
+    };
+
+    let eased = space.progress * space.progress * (3. - 2. * space.progress);
+    // The distance grows exponentially, so the pull back seems equally fast throughout
+    let ratio = eye.distance(center) / offset.length();
+    let weight = if ratio > 1. {
+        (ratio.powf(eased) - 1.) / (ratio - 1.)
+    } else {
+        eased
+    };
+
+    transform.translation = (center + offset).lerp(eye, weight);
+    let target = match space.flight_pose {
+        Some((_, flight_rotation)) => flight_rotation,
+        None => {
+            Transform::from_translation(transform.translation)
+                .looking_at(center.lerp(focus, weight), Vec3::Y)
+                .rotation
+        }
+    };
+    transform.rotation = rotation.slerp(target, eased);
+    perspective.fov = fov + (MAX_FOV - fov) * eased;
+}
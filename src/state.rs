@@ -1,10 +1,56 @@
-use bevy::state::state::States;
+use bevy::state::state::{States, SubStates};
 
 #[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub enum GameState {
     #[default]
     PreLoading,
+    /// Fetching the textures the chosen pack misses, see `DownloadPlugin`
+    DownloadAssets,
     Loading,
     PostLoading,
     Playing,
 }
+
+/// Active tool while the globe is interactive, each tool gates its own systems and input.
+#[derive(SubStates, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[source(GameState = GameState::Playing)]
+pub enum ToolMode {
+    #[default]
+    Idle,
+    Measuring,
+    Drawing,
+    /// Camera is driven by a script tour, manual navigation is disabled, see `ScriptRunner`
+    Touring,
+    /// Asks for places to click on the globe, see `quiz::Quiz`
+    Quiz,
+}
+
+impl ToolMode {
+    /// The tools the user can pick, `Touring` is only entered by tours.
+    pub const ALL: [ToolMode; 4] = [
+        ToolMode::Idle,
+        ToolMode::Measuring,
+        ToolMode::Drawing,
+        ToolMode::Quiz,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ToolMode::Idle => "Idle",
+            ToolMode::Measuring => "Measure",
+            ToolMode::Drawing => "Draw",
+            ToolMode::Touring => "Tour",
+            ToolMode::Quiz => "Quiz",
+        }
+    }
+
+    /// Whether the user can rotate and zoom the globe directly.
+    pub fn allows_navigation(&self) -> bool {
+        *self != ToolMode::Touring
+    }
+
+    /// Whether dragging across the globe is used by the tool instead of orbiting the camera.
+    pub fn captures_drag(&self) -> bool {
+        *self == ToolMode::Drawing
+    }
+}
@@ -0,0 +1,195 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use bevy::{
+    app::{App, Plugin},
+    asset::Assets,
+    camera::visibility::ViewVisibility,
+    ecs::{
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Query, Res, ResMut},
+    },
+    mesh::{Indices, Mesh, Mesh3d},
+    state::condition::in_state,
+};
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+
+use crate::{
+    chunk::ChunkMeshPool,
+    component::{Chunk, FacePatch},
+    lod::PatchCulling,
+    state::GameState,
+};
+
+/// Number of bars in the generation time histogram.
+const HISTOGRAM_BUCKETS: usize = 12;
+
+/// Time each chunk mesh took to generate on its worker, in the order they finished.
+#[derive(Resource, Default)]
+pub struct GenerationTimes(Vec<Duration>);
+
+impl GenerationTimes {
+    pub fn record(&mut self, duration: Duration) {
+        self.0.push(duration);
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct MeshStatsPanel {
+    pub open: bool,
+}
+
+pub struct MeshStatsPlugin;
+
+impl Plugin for MeshStatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GenerationTimes>()
+            .init_resource::<MeshStatsPanel>()
+            .add_systems(
+                EguiPrimaryContextPass,
+                display_mesh_stats
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(|panel: Res<MeshStatsPanel>| panel.open),
+            );
+    }
+}
+
+/// `bytes` in MiB.
+fn mebibytes(bytes: usize) -> String {
+    format!("{:.1} MiB", bytes as f64 / (1024. * 1024.))
+}
+
+/// Bars of `times` spread over equally wide buckets between the fastest and the slowest.
+fn histogram(ui: &mut egui::Ui, times: &[Duration]) {
+    let (Some(min), Some(max)) = (times.iter().min(), times.iter().max()) else {
+        ui.label("No chunks generated yet");
+        return;
+    };
+    let (min, max) = (min.as_secs_f32(), max.as_secs_f32());
+    let mean = times.iter().sum::<Duration>().as_secs_f32() / times.len() as f32;
+    ui.label(format!(
+        "min {:.1} ms, mean {:.1} ms, max {:.1} ms",
+        min * 1000.,
+        mean * 1000.,
+        max * 1000.
+    ));
+
+    let mut buckets = [0; HISTOGRAM_BUCKETS];
+    let width = (max - min).max(f32::EPSILON) / HISTOGRAM_BUCKETS as f32;
+    for time in times {
+        let bucket = ((time.as_secs_f32() - min) / width) as usize;
+        buckets[bucket.min(HISTOGRAM_BUCKETS - 1)] += 1;
+    }
+    let highest = buckets.iter().copied().max().unwrap_or(1).max(1);
+
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(240., 80.), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let bar_width = rect.width() / HISTOGRAM_BUCKETS as f32;
+    for (index, count) in buckets.into_iter().enumerate() {
+        let height = rect.height() * count as f32 / highest as f32;
+        let left = rect.left() + bar_width * index as f32;
+        let bar = egui::Rect::from_min_max(
+            egui::pos2(left + 1., rect.bottom() - height),
+            egui::pos2(left + bar_width - 1., rect.bottom()),
+        );
+        painter.rect_filled(bar, 0., ui.visuals().selection.bg_fill);
+    }
+    ui.horizontal(|ui| {
+        ui.label(format!("{:.1} ms", min * 1000.));
+        ui.add_space(160.);
+        ui.label(format!("{:.1} ms", max * 1000.));
+    });
+}
+
+fn display_mesh_stats(
+    mut contexts: EguiContexts,
+    mut panel: ResMut<MeshStatsPanel>,
+    meshes: Res<Assets<Mesh>>,
+    pool: Res<ChunkMeshPool>,
+    times: Res<GenerationTimes>,
+    mut culling: ResMut<PatchCulling>,
+    chunks: Query<(&Chunk, &FacePatch, Option<&Mesh3d>, Option<&ViewVisibility>)>,
+) -> bevy::prelude::Result {
+    let ctx = contexts.ctx_mut()?;
+
+    let mut per_depth = BTreeMap::<u8, usize>::new();
+    let (mut vertices, mut triangles, mut visible, mut culled) = (0, 0, 0, 0);
+    let (mut vertex_bytes, mut index_bytes) = (0, 0);
+    for (chunk, patch, mesh, visibility) in &chunks {
+        *per_depth.entry(chunk.0.depth).or_default() += 1;
+        culled += patch.culled as usize;
+        visible += visibility.is_some_and(|visibility| visibility.get()) as usize;
+        if let Some(mesh) = mesh.and_then(|mesh| meshes.get(&mesh.0)) {
+            vertices += mesh.count_vertices();
+            triangles += mesh.indices().map_or(0, |indices| indices.len() / 3);
+            vertex_bytes += mesh.count_vertices() * mesh.get_vertex_size() as usize;
+            index_bytes += match mesh.indices() {
+                Some(Indices::U16(indices)) => indices.len() * 2,
+                Some(Indices::U32(indices)) => indices.len() * 4,
+                None => 0,
+            };
+        }
+    }
+
+    egui::Window::new("Mesh statistics")
+        .open(&mut panel.open)
+        .resizable(false)
+        .show(ctx, |ui| {
+            egui::CollapsingHeader::new("Chunks")
+                .default_open(true)
+                .show(ui, |ui| {
+                    egui::Grid::new("chunks_per_depth").show(ui, |ui| {
+                        for (depth, count) in &per_depth {
+                            ui.label(format!("LOD {depth}"));
+                            ui.label(count.to_string());
+                            ui.end_row();
+                        }
+                        ui.label("Pooled meshes");
+                        ui.label(format!("{} / {}", pool.len(), pool.capacity));
+                        ui.end_row();
+                        ui.label("Behind the horizon");
+                        ui.label(culled.to_string());
+                        ui.end_row();
+                    });
+                    let mut enabled = culling.enabled;
+                    if ui
+                        .checkbox(&mut enabled, "Hide chunks behind the horizon")
+                        .changed()
+                    {
+                        culling.enabled = enabled;
+                    }
+                });
+
+            egui::CollapsingHeader::new("Geometry")
+                .default_open(true)
+                .show(ui, |ui| {
+                    egui::Grid::new("geometry").show(ui, |ui| {
+                        ui.label("Vertices");
+                        ui.label(vertices.to_string());
+                        ui.end_row();
+                        ui.label("Triangles");
+                        ui.label(triangles.to_string());
+                        ui.end_row();
+                        ui.label("Draw calls")
+                            .on_hover_text("Visible chunks, before Bevy batches them");
+                        ui.label(visible.to_string());
+                        ui.end_row();
+                        ui.label("Vertex memory");
+                        ui.label(mebibytes(vertex_bytes));
+                        ui.end_row();
+                        ui.label("Index memory").on_hover_text(
+                            "Every chunk holds its own copy of the indices, which are 16 bit up to \
+                             254 vertices per edge along with the skirts",
+                        );
+                        ui.label(mebibytes(index_bytes));
+                        ui.end_row();
+                    });
+                });
+
+            egui::CollapsingHeader::new("Generation time").show(ui, |ui| {
+                histogram(ui, &times.0);
+            });
+        });
+
+    Ok(())
+}
@@ -0,0 +1,98 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    asset::Assets,
+    ecs::{
+        change_detection::DetectChanges,
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Local, Res, ResMut},
+    },
+    math::Vec4,
+    state::condition::in_state,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    material::EarthMaterial, resource::EarthMaterialTemplate, state::GameState, vector::VectorView,
+};
+
+/// Width of the glowing limb of the `VectorView`.
+const VECTOR_OUTLINE_WIDTH: f32 = 0.1;
+
+/// Infographic look of the globe: flat continents, cel-shaded oceans and an outlined limb,
+/// without the imagery, lighting or city lights. Overlays are still drawn on top.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct StylizedView {
+    pub enabled: bool,
+    /// Linear RGB colors
+    pub land: [f32; 3],
+    pub ocean: [f32; 3],
+    pub outline: [f32; 3],
+    /// Width of the outline, as the cosine of the angle between the surface and the view at
+    /// which it starts
+    pub outline_width: f32,
+    /// Steps the sunlight on the oceans falls off in
+    pub bands: u32,
+}
+
+impl Default for StylizedView {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            land: [0.8, 0.7, 0.45],
+            ocean: [0.2, 0.45, 0.7],
+            outline: [0.02, 0.04, 0.08],
+            outline_width: 0.15,
+            bands: 3,
+        }
+    }
+}
+
+pub struct StylizedPlugin;
+
+impl Plugin for StylizedPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<StylizedView>().add_systems(
+            Update,
+            apply_stylized_view.run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// Switches the Earth material over, whenever the settings change or the globe is reloaded.
+///
+/// The `VectorView` takes precedence, as a stylized globe of a single dark color.
+fn apply_stylized_view(
+    stylized: Res<StylizedView>,
+    vector: Res<VectorView>,
+    handle: Res<EarthMaterialTemplate>,
+    mut materials: ResMut<Assets<EarthMaterial>>,
+    mut applied: Local<Option<(StylizedView, VectorView)>>,
+) {
+    if *applied == Some((*stylized, *vector)) && !handle.is_changed() {
+        return;
+    }
+
+    let view = if vector.enabled {
+        StylizedView {
+            enabled: true,
+            land: vector.background,
+            ocean: vector.background,
+            outline: vector.color,
+            outline_width: VECTOR_OUTLINE_WIDTH,
+            bands: 1,
+        }
+    } else {
+        *stylized
+    };
+    if let Some(material) = materials.get_mut(&**handle) {
+        let uniform = &mut material.extension.uniform;
+        let [r, g, b] = view.land;
+        uniform.style_land = Vec4::new(r, g, b, view.enabled as u8 as f32);
+        let [r, g, b] = view.ocean;
+        uniform.style_ocean = Vec4::new(r, g, b, view.bands as f32);
+        let [r, g, b] = view.outline;
+        uniform.style_outline = Vec4::new(r, g, b, view.outline_width);
+        *applied = Some((*stylized, *vector));
+    }
+}
@@ -0,0 +1,208 @@
+use std::fmt;
+
+use bevy::{
+    app::{App, FixedUpdate, Plugin},
+    ecs::{
+        query::{With, Without},
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Res, ResMut, Single},
+    },
+    math::Vec3,
+    state::condition::in_state,
+    time::Time,
+    transform::components::Transform,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    EarthConfig, LIGHT_HEIGHT, LIGHT_ORBIT,
+    component::{Earth, RotatingLight, SimulatedTransform},
+    math::Coordinates,
+    state::GameState,
+};
+
+pub const SECS_PER_DAY: f64 = 86_400.;
+
+/// Unix time of the J2000.0 epoch, noon of January 1st 2000.
+pub const J2000_UNIX_SECS: f64 = 946_728_000.;
+
+/// Where the sun lighting the globe stands.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum SunMode {
+    /// Circles the globe every 4π seconds of simulation, regardless of any date
+    #[default]
+    Demo,
+    /// Stands where it does at the `SunClock`, which runs `speedup` times as fast as real time
+    Realtime { speedup: f32 },
+    /// Stands still where it does at the `SunClock`, which is only moved by hand
+    Fixed,
+}
+
+impl SunMode {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SunMode::Demo => "Demo",
+            SunMode::Realtime { .. } => "Real time",
+            SunMode::Fixed => "Fixed",
+        }
+    }
+}
+
+/// The moment in UTC the astronomical `SunMode`s show the sun for, starting at the system clock.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct SunClock {
+    /// Seconds since the Unix epoch
+    pub unix_secs: f64,
+}
+
+impl Default for SunClock {
+    fn default() -> Self {
+        Self::now()
+    }
+}
+
+/// Days since the Unix epoch of a date in the proleptic Gregorian calendar.
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Year, month and day of the date `days` after the Unix epoch, the inverse of
+/// `days_from_civil`.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+impl SunClock {
+    pub fn now() -> Self {
+        let unix_secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0., |elapsed| elapsed.as_secs_f64());
+        Self { unix_secs }
+    }
+
+    fn days(&self) -> i64 {
+        self.unix_secs.div_euclid(SECS_PER_DAY) as i64
+    }
+
+    pub fn seconds_of_day(&self) -> f64 {
+        self.unix_secs.rem_euclid(SECS_PER_DAY)
+    }
+
+    pub fn set_seconds_of_day(&mut self, seconds: f64) {
+        self.unix_secs = self.days() as f64 * SECS_PER_DAY + seconds.clamp(0., SECS_PER_DAY - 1.);
+    }
+
+    /// Days since January 1st, from zero.
+    pub fn day_of_year(&self) -> u32 {
+        let (year, _, _) = civil_from_days(self.days());
+        (self.days() - days_from_civil(year, 1, 1)) as u32
+    }
+
+    /// Moves to another day of the same year, keeping the time of day.
+    pub fn set_day_of_year(&mut self, day: u32) {
+        let (year, _, _) = civil_from_days(self.days());
+        let start = days_from_civil(year, 1, 1);
+        let last = days_from_civil(year + 1, 1, 1) - start - 1;
+        let days = start + (day as i64).min(last);
+        self.unix_secs = days as f64 * SECS_PER_DAY + self.seconds_of_day();
+    }
+
+    /// The point on the globe where the sun stands at the zenith.
+    ///
+    /// Follows the low precision formulas of the Astronomical Almanac, good to about a hundredth
+    /// of a degree for the years around 2000: the declination gives the latitude, and the
+    /// equation of time shifts the longitude away from where the mean sun would be at noon.
+    pub fn subsolar_point(&self) -> Coordinates {
+        let days = (self.unix_secs - J2000_UNIX_SECS) / SECS_PER_DAY;
+        let mean_anomaly = (357.529 + 0.985_600_28 * days).to_radians();
+        let mean_longitude = 280.459 + 0.985_647_36 * days;
+        let ecliptic_longitude =
+            (mean_longitude + 1.915 * mean_anomaly.sin() + 0.020 * (2. * mean_anomaly).sin())
+                .to_radians();
+        let obliquity = (23.439 - 0.000_000_36 * days).to_radians();
+
+        let declination = (obliquity.sin() * ecliptic_longitude.sin()).asin();
+        let right_ascension = (obliquity.cos() * ecliptic_longitude.sin())
+            .atan2(ecliptic_longitude.cos())
+            .to_degrees();
+        // How far the true sun runs ahead of the mean sun, in degrees
+        let equation_of_time = (mean_longitude - right_ascension + 180.).rem_euclid(360.) - 180.;
+
+        let hours = self.seconds_of_day() / 3600.;
+        let longitude = (-15. * (hours - 12.) - equation_of_time + 180.).rem_euclid(360.) - 180.;
+        Coordinates {
+            latitude: declination as f32,
+            longitude: longitude.to_radians() as f32,
+        }
+    }
+}
+
+impl fmt::Display for SunClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (year, month, day) = civil_from_days(self.days());
+        let minutes = (self.seconds_of_day() / 60.) as u32;
+        write!(
+            f,
+            "{year}-{month:02}-{day:02} {:02}:{:02} UTC",
+            minutes / 60,
+            minutes % 60
+        )
+    }
+}
+
+pub struct SunPlugin;
+
+impl Plugin for SunPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SunMode>()
+            .init_resource::<SunClock>()
+            .add_systems(
+                FixedUpdate,
+                (advance_sun_clock, place_sun)
+                    .chain()
+                    .run_if(|mode: Res<SunMode>| *mode != SunMode::Demo)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+fn advance_sun_clock(mode: Res<SunMode>, time: Res<Time>, mut clock: ResMut<SunClock>) {
+    if let SunMode::Realtime { speedup } = *mode {
+        clock.unix_secs += time.delta_secs_f64() * speedup as f64;
+    }
+}
+
+/// Shines the light from the subsolar point, turned along with the globe.
+fn place_sun(
+    clock: Res<SunClock>,
+    config: Res<EarthConfig>,
+    earth: Single<&Transform, (With<Earth>, Without<RotatingLight>)>,
+    mut light: Single<&mut SimulatedTransform, With<RotatingLight>>,
+) {
+    let direction = earth.rotation * clock.subsolar_point().direction();
+    let distance = Vec3::new(LIGHT_ORBIT, LIGHT_HEIGHT, 0.).length() * config.radius;
+    light.current = Transform::from_translation(earth.translation + direction * distance)
+        .looking_at(earth.translation, Vec3::Y);
+}
@@ -0,0 +1,170 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    asset::{AssetServer, Assets, RenderAssetUsages},
+    ecs::{
+        schedule::IntoScheduleConfigs,
+        system::{Res, ResMut},
+    },
+    image::Image,
+    log::{error, warn},
+    state::condition::in_state,
+    tasks::{AsyncComputeTaskPool, futures},
+};
+use image::{DynamicImage, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    material::EarthMaterial,
+    pack::EarthPacks,
+    resource::{EarthMaterialTemplate, EarthTexture},
+    state::GameState,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum ChannelSource {
+    Red,
+    Green,
+    Blue,
+    Alpha,
+    Luminance,
+    Constant(f32),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ChannelMapping {
+    pub source: ChannelSource,
+    #[serde(default)]
+    pub invert: bool,
+}
+
+impl ChannelMapping {
+    fn sample(&self, pixel: &Rgba<u8>) -> u8 {
+        let [r, g, b, a] = pixel.0;
+        let value = match self.source {
+            ChannelSource::Red => r,
+            ChannelSource::Green => g,
+            ChannelSource::Blue => b,
+            ChannelSource::Alpha => a,
+            ChannelSource::Luminance => {
+                (0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32).round() as u8
+            }
+            ChannelSource::Constant(value) => (value.clamp(0., 1.) * 255.).round() as u8,
+        };
+        if self.invert { 255 - value } else { value }
+    }
+}
+
+/// Where roughness and metallic come from in a pack's specular texture.
+///
+/// The glTF convention the `StandardMaterial` expects is green = roughness and blue = metallic,
+/// which a plain specular map doesn't follow.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct MetallicRoughnessLayout {
+    pub roughness: ChannelMapping,
+    pub metallic: ChannelMapping,
+}
+
+impl Default for MetallicRoughnessLayout {
+    fn default() -> Self {
+        // The bundled map is already inverted, so water is dark (smooth) and land bright (rough)
+        Self {
+            roughness: ChannelMapping {
+                source: ChannelSource::Red,
+                invert: false,
+            },
+            metallic: ChannelMapping {
+                source: ChannelSource::Constant(0.),
+                invert: false,
+            },
+        }
+    }
+}
+
+impl MetallicRoughnessLayout {
+    /// Rebuilds `image` as a linear RGBA texture with roughness in green and metallic in blue.
+    pub fn repack(&self, image: &Image) -> Result<Image, String> {
+        let source = image
+            .clone()
+            .try_into_dynamic()
+            .map_err(|err| err.to_string())?
+            .to_rgba8();
+
+        let mut packed = RgbaImage::new(source.width(), source.height());
+        for (target, pixel) in packed.pixels_mut().zip(source.pixels()) {
+            *target = Rgba([
+                0,
+                self.roughness.sample(pixel),
+                self.metallic.sample(pixel),
+                255,
+            ]);
+        }
+
+        let mut repacked = Image::from_dynamic(
+            DynamicImage::ImageRgba8(packed),
+            false,
+            RenderAssetUsages::default(),
+        );
+        repacked.sampler = image.sampler.clone();
+        Ok(repacked)
+    }
+}
+
+pub struct TexturePlugin;
+
+impl Plugin for TexturePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, repack_textures.run_if(in_state(GameState::Loading)));
+    }
+}
+
+/// Repacks into a new image rather than in place, so the source asset stays untouched when it
+/// is still cached while the Earth gets recreated. The texture can be 8k wide, so the pixels are
+/// shuffled on the `AsyncComputeTaskPool` and the task polled every frame.
+fn repack_textures(
+    mut textures: ResMut<EarthTexture>,
+    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<EarthMaterial>>,
+    template: Res<EarthMaterialTemplate>,
+    asset_server: Res<AssetServer>,
+    packs: Res<EarthPacks>,
+) {
+    if let Some(task) = &mut textures.repacking {
+        let Some(repacked) = futures::check_ready(task) else {
+            return;
+        };
+        textures.repacking = None;
+        match repacked {
+            Ok(repacked) => {
+                let handle = images.add(repacked);
+                if let Some(material) = materials.get_mut(&**template) {
+                    material.base.metallic_roughness_texture = Some(handle.clone());
+                    material.extension.ocean_mask = Some(handle);
+                }
+            }
+            Err(err) => error!("Failed to repack metallic/roughness texture: {err}"),
+        }
+        textures.repacked = true;
+        return;
+    }
+
+    if textures.repacked || !asset_server.is_loaded_with_dependencies(&textures.metallic_roughness)
+    {
+        return;
+    }
+
+    let layout = packs.active().manifest.metallic_roughness;
+    match images.get(&textures.metallic_roughness) {
+        // Compressed texels can't be read back, a `.ktx2` has to be in the glTF layout already
+        Some(image) if image.is_compressed() => {
+            warn!("Using the compressed metallic/roughness texture without repacking it");
+        }
+        Some(image) => {
+            let image = image.clone();
+            textures.repacking =
+                Some(AsyncComputeTaskPool::get().spawn(async move { layout.repack(&image) }));
+            return;
+        }
+        None => {}
+    }
+    textures.repacked = true;
+}
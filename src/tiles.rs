@@ -0,0 +1,387 @@
+use std::{
+    collections::{HashMap, HashSet},
+    f32::consts::{PI, TAU},
+    fs,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    app::{App, Plugin, Update},
+    asset::{Assets, Handle, RenderAssetUsages},
+    ecs::{
+        entity::Entity,
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Query, Res, ResMut},
+    },
+    image::{Image, ImageSampler},
+    log::warn,
+    math::{Rect, Vec2},
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+    state::condition::in_state,
+    tasks::{IoTaskPool, Task, futures},
+};
+use image::RgbaImage;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    EarthConfig,
+    chunk::ChunkKey,
+    component::{Chunk, MaterialOverrides},
+    http::HttpClient,
+    state::GameState,
+};
+
+/// Directory fetched tiles are kept in, so regions seen before still stream offline.
+const CACHE_DIR: &str = "tile_cache";
+
+/// Width of the image stitched for each chunk, about twice the vertices along its edge.
+const CHUNK_TEXELS: u32 = 512;
+
+/// Texels across a tile at which the zoom level is chosen, servers with larger tiles just add
+/// detail.
+const TILE_PIXELS: f32 = 256.;
+
+/// Most tiles stitched for a single chunk, chunks needing more get a coarser zoom level.
+const MAX_TILES: usize = 16;
+
+const MAX_ZOOM: u32 = 19;
+
+/// Chunks whose tiles are fetched at the same time.
+const MAX_PENDING: usize = 8;
+
+/// Latitude where Web Mercator ends, 85.0511 degrees.
+const MERCATOR_LIMIT: f32 = 1.484_422_2;
+
+/// Streams the base imagery from an XYZ or WMTS tile server for the chunks on the globe, as
+/// configured by `EarthConfig::tile_url`.
+///
+/// Each chunk gets the tiles of the zoom level matching its depth in the quadtree, stitched into
+/// one image drawn over the base color texture. The single texture stays underneath wherever
+/// tiles are missing: around the poles, across the antimeridian, and while the server is
+/// unreachable and nothing is cached.
+#[derive(Resource)]
+pub struct TileStream {
+    pub enabled: bool,
+    /// Stitched images of the chunks on the globe, and the UVs they cover
+    loaded: HashMap<ChunkKey, (Handle<Image>, Rect)>,
+    pending: HashMap<ChunkKey, Task<Result<(Image, Rect), String>>>,
+    /// Chunks left to the base color texture until the stream is re-enabled or goes back online
+    unavailable: HashSet<ChunkKey>,
+    /// Whether the `HttpClient` was offline when the stream last ran
+    offline: bool,
+}
+
+impl Default for TileStream {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            loaded: HashMap::new(),
+            pending: HashMap::new(),
+            unavailable: HashSet::new(),
+            offline: false,
+        }
+    }
+}
+
+impl TileStream {
+    /// Chunks whose tiles are being fetched.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Whether tiles only come from the cache, because of the offline mode.
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+}
+
+pub struct TilePlugin;
+
+impl Plugin for TilePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TileStream>().add_systems(
+            Update,
+            stream_tiles
+                .run_if(in_state(GameState::Playing))
+                .run_if(|config: Res<EarthConfig>| config.tile_url.is_some()),
+        );
+    }
+}
+
+/// Tiles covering a chunk at one zoom level.
+struct TilePlan {
+    zoom: u32,
+    columns: RangeInclusive<u32>,
+    rows: RangeInclusive<u32>,
+    /// Longitudes along `x` and latitudes along `y`, as in `ChunkKey::bounds`
+    bounds: Rect,
+    width: u32,
+    height: u32,
+}
+
+/// Position from 0 to 1 of `latitude` down the Web Mercator square.
+fn mercator_y(latitude: f32) -> f32 {
+    (1. - (latitude.tan() + 1. / latitude.cos()).ln() / PI) / 2.
+}
+
+fn mercator_x(longitude: f32) -> f32 {
+    (longitude + PI) / TAU
+}
+
+impl TilePlan {
+    fn new(key: ChunkKey) -> Option<Self> {
+        let bounds = key.bounds();
+        if bounds.width() >= PI || bounds.max.y > MERCATOR_LIMIT || bounds.min.y < -MERCATOR_LIMIT {
+            return None;
+        }
+
+        let ideal = (CHUNK_TEXELS as f32 * TAU / (TILE_PIXELS * bounds.width())).log2();
+        let mut zoom = (ideal.ceil().max(0.) as u32).min(MAX_ZOOM);
+        let plan = loop {
+            let tiles = (1u32 << zoom) as f32;
+            let last = (1u32 << zoom) - 1;
+            let index = |fraction: f32| ((fraction * tiles) as u32).min(last);
+            let columns = index(mercator_x(bounds.min.x))..=index(mercator_x(bounds.max.x));
+            let rows = index(mercator_y(bounds.max.y))..=index(mercator_y(bounds.min.y));
+            if columns.clone().count() * rows.clone().count() <= MAX_TILES || zoom == 0 {
+                break (zoom, columns, rows);
+            }
+            zoom -= 1;
+        };
+
+        let height = (CHUNK_TEXELS as f32 * bounds.height() / bounds.width()).ceil();
+        Some(Self {
+            zoom: plan.0,
+            columns: plan.1,
+            rows: plan.2,
+            bounds,
+            width: CHUNK_TEXELS,
+            height: height.clamp(1., 2. * CHUNK_TEXELS as f32) as u32,
+        })
+    }
+
+    /// Area of the globe's equirectangular UVs the stitched image covers.
+    fn uv_rect(&self) -> Rect {
+        let bounds = self.bounds;
+        Rect::from_corners(
+            Vec2::new(mercator_x(bounds.min.x), 0.5 - bounds.max.y / PI),
+            Vec2::new(mercator_x(bounds.max.x), 0.5 - bounds.min.y / PI),
+        )
+    }
+}
+
+/// Fills in the `{z}/{x}/{y}` placeholders of XYZ templates, or their WMTS equivalents.
+fn tile_url(template: &str, zoom: u32, column: u32, row: u32) -> String {
+    template
+        .replace("{z}", &zoom.to_string())
+        .replace("{TileMatrix}", &zoom.to_string())
+        .replace("{x}", &column.to_string())
+        .replace("{TileCol}", &column.to_string())
+        .replace("{y}", &row.to_string())
+        .replace("{TileRow}", &row.to_string())
+}
+
+/// Reads a tile from the cache, fetching and caching it first if it isn't there yet.
+fn fetch_tile(
+    client: &HttpClient,
+    template: &str,
+    cache: &Path,
+    zoom: u32,
+    column: u32,
+    row: u32,
+) -> Result<RgbaImage, String> {
+    let path = cache.join(format!("{zoom}/{column}/{row}"));
+    let bytes = match fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(_) => {
+            let bytes = client.get_bytes(&tile_url(template, zoom, column, row))?;
+            if let Some(parent) = path.parent() {
+                let _ = fs::create_dir_all(parent);
+            }
+            if let Err(err) = fs::write(&path, &bytes) {
+                warn!("Failed to cache tile {}: {err}", path.display());
+            }
+            bytes
+        }
+    };
+    image::load_from_memory(&bytes)
+        .map(|image| image.to_rgba8())
+        .map_err(|err| err.to_string())
+}
+
+/// Fetches the tiles of `plan` and reprojects them from Web Mercator into an equirectangular
+/// image, leaving the texels of missing tiles transparent.
+fn stitch(
+    client: &HttpClient,
+    plan: &TilePlan,
+    template: &str,
+    cache: &Path,
+) -> Result<Image, String> {
+    let mut tiles = HashMap::new();
+    let mut error = None;
+    for row in plan.rows.clone() {
+        for column in plan.columns.clone() {
+            match fetch_tile(client, template, cache, plan.zoom, column, row) {
+                Ok(tile) => {
+                    tiles.insert((column, row), tile);
+                }
+                Err(err) => error = Some(err),
+            }
+        }
+    }
+    if tiles.is_empty() {
+        return Err(error.unwrap_or_default());
+    }
+
+    let scale = (1u32 << plan.zoom) as f32;
+    let bounds = plan.bounds;
+    let mut data = vec![0; (plan.width * plan.height * 4) as usize];
+    for y in 0..plan.height {
+        let latitude = bounds.max.y - (y as f32 + 0.5) / plan.height as f32 * bounds.height();
+        let tile_y = mercator_y(latitude) * scale;
+        for x in 0..plan.width {
+            let longitude = bounds.min.x + (x as f32 + 0.5) / plan.width as f32 * bounds.width();
+            let tile_x = mercator_x(longitude) * scale;
+            let Some(tile) = tiles.get(&(tile_x as u32, tile_y as u32)) else {
+                continue;
+            };
+            let pixel = tile.get_pixel(
+                ((tile_x.fract() * tile.width() as f32) as u32).min(tile.width() - 1),
+                ((tile_y.fract() * tile.height() as f32) as u32).min(tile.height() - 1),
+            );
+            let index = ((y * plan.width + x) * 4) as usize;
+            data[index..index + 4].copy_from_slice(&pixel.0);
+        }
+    }
+
+    let mut image = Image::new(
+        Extent3d {
+            width: plan.width,
+            height: plan.height,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    // Clamped, so the edges of the chunk don't pick up the opposite side of the image
+    image.sampler = ImageSampler::linear();
+    Ok(image)
+}
+
+/// One cache directory per server, so switching templates doesn't mix their tiles.
+fn cache_dir(template: &str) -> PathBuf {
+    let digest = format!("{:x}", Sha256::digest(template.as_bytes()));
+    Path::new(CACHE_DIR).join(&digest[..16])
+}
+
+fn set_tile(
+    commands: &mut Commands,
+    entity: Entity,
+    overrides: Option<&MaterialOverrides>,
+    tile: Option<(Handle<Image>, Rect)>,
+) {
+    let overrides = MaterialOverrides {
+        base_color_tile: tile,
+        ..overrides.cloned().unwrap_or_default()
+    };
+    if overrides == MaterialOverrides::default() {
+        commands.entity(entity).remove::<MaterialOverrides>();
+    } else {
+        commands.entity(entity).insert(overrides);
+    }
+}
+
+/// Requests the tiles of chunks without their own yet and hands finished ones to the chunks.
+///
+/// Chunks split by the `LodPlugin` inherit the tile of their parent along with its
+/// `MaterialOverrides`, which stays until their own finer tile arrives.
+fn stream_tiles(
+    mut commands: Commands,
+    config: Res<EarthConfig>,
+    client: Res<HttpClient>,
+    mut stream: ResMut<TileStream>,
+    mut images: ResMut<Assets<Image>>,
+    chunks: Query<(Entity, &Chunk, Option<&MaterialOverrides>)>,
+) {
+    let Some(template) = &config.tile_url else {
+        return;
+    };
+    let stream = &mut *stream;
+
+    if !stream.enabled {
+        for (entity, _, overrides) in &chunks {
+            if overrides.is_some_and(|overrides| overrides.base_color_tile.is_some()) {
+                set_tile(&mut commands, entity, overrides, None);
+            }
+        }
+        stream.loaded.clear();
+        stream.pending.clear();
+        stream.unavailable.clear();
+        return;
+    }
+
+    // Back online, the chunks missing from the cache get another try
+    if stream.offline != client.is_offline() {
+        stream.offline = client.is_offline();
+        if !stream.offline {
+            stream.unavailable.clear();
+        }
+    }
+
+    // Dropping the task of a chunk that is gone cancels it
+    let keys: HashSet<ChunkKey> = chunks.iter().map(|(_, chunk, _)| chunk.0).collect();
+    stream.loaded.retain(|key, _| keys.contains(key));
+    stream.pending.retain(|key, _| keys.contains(key));
+
+    let finished: Vec<_> = stream
+        .pending
+        .iter_mut()
+        .filter_map(|(key, task)| futures::check_ready(task).map(|result| (*key, result)))
+        .collect();
+    for (key, result) in finished {
+        stream.pending.remove(&key);
+        match result {
+            Ok((image, rect)) => {
+                stream.loaded.insert(key, (images.add(image), rect));
+            }
+            Err(err) => {
+                if !client.is_offline() {
+                    warn!("No imagery tiles for chunk {key:?}: {err}");
+                }
+                stream.unavailable.insert(key);
+            }
+        }
+    }
+
+    for (entity, chunk, overrides) in &chunks {
+        let key = chunk.0;
+        if let Some(tile) = stream.loaded.get(&key) {
+            if overrides.and_then(|overrides| overrides.base_color_tile.as_ref()) != Some(tile) {
+                set_tile(&mut commands, entity, overrides, Some(tile.clone()));
+            }
+            continue;
+        }
+        if stream.pending.len() >= MAX_PENDING
+            || stream.pending.contains_key(&key)
+            || stream.unavailable.contains(&key)
+        {
+            continue;
+        }
+
+        let Some(plan) = TilePlan::new(key) else {
+            stream.unavailable.insert(key);
+            continue;
+        };
+        let template = template.clone();
+        let cache = cache_dir(&template);
+        let client = client.clone();
+        let task = IoTaskPool::get().spawn(async move {
+            stitch(&client, &plan, &template, &cache).map(|image| (image, plan.uv_rect()))
+        });
+        stream.pending.insert(key, task);
+    }
+}
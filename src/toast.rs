@@ -0,0 +1,233 @@
+use bevy::{
+    app::{App, Plugin},
+    ecs::{
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Res, ResMut},
+    },
+    log::{error, info, warn},
+    state::condition::in_state,
+    time::{Real, Time},
+};
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+
+use crate::state::GameState;
+
+/// Toasts on screen at once, the oldest make room for new ones.
+const MAX_VISIBLE: usize = 5;
+
+/// Toasts kept for the notifications panel.
+const MAX_HISTORY: usize = 200;
+
+/// Toasts fade out over their last this many seconds.
+const FADE_SECONDS: f32 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    /// Seconds a toast stays on screen, longer the more it matters.
+    fn duration(self) -> f32 {
+        match self {
+            Severity::Info => 4.,
+            Severity::Warning => 6.,
+            Severity::Error => 10.,
+        }
+    }
+
+    fn icon(self) -> &'static str {
+        match self {
+            Severity::Info => "ℹ",
+            Severity::Warning => "⚠",
+            Severity::Error => "❌",
+        }
+    }
+
+    fn color(self) -> egui::Color32 {
+        match self {
+            Severity::Info => egui::Color32::LIGHT_BLUE,
+            Severity::Warning => egui::Color32::from_rgb(240, 190, 60),
+            Severity::Error => egui::Color32::from_rgb(240, 80, 70),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub severity: Severity,
+    pub text: String,
+    /// Real time the toast was first shown at, stamped when it is drawn
+    shown_at: Option<f32>,
+}
+
+/// Timed messages shown in the bottom right corner, so importers, downloads and failures tell
+/// the user instead of only the log. Clicking a toast dismisses it, and the notifications panel
+/// lists them again.
+#[derive(Resource, Default)]
+pub struct Toasts {
+    /// Toasts on screen, oldest first
+    active: Vec<Toast>,
+    /// Every toast shown, oldest first
+    history: Vec<Toast>,
+}
+
+impl Toasts {
+    /// Shows `text` and writes it to the log at the matching level.
+    pub fn push(&mut self, severity: Severity, text: impl Into<String>) {
+        let text = text.into();
+        match severity {
+            Severity::Info => info!("{text}"),
+            Severity::Warning => warn!("{text}"),
+            Severity::Error => error!("{text}"),
+        }
+
+        let toast = Toast {
+            severity,
+            text,
+            shown_at: None,
+        };
+        if self.active.len() >= MAX_VISIBLE {
+            self.active.remove(0);
+        }
+        self.active.push(toast.clone());
+        if self.history.len() >= MAX_HISTORY {
+            self.history.remove(0);
+        }
+        self.history.push(toast);
+    }
+
+    pub fn info(&mut self, text: impl Into<String>) {
+        self.push(Severity::Info, text);
+    }
+
+    pub fn warning(&mut self, text: impl Into<String>) {
+        self.push(Severity::Warning, text);
+    }
+
+    pub fn error(&mut self, text: impl Into<String>) {
+        self.push(Severity::Error, text);
+    }
+
+    pub fn history(&self) -> &[Toast] {
+        &self.history
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct NotificationsPanel {
+    pub open: bool,
+}
+
+pub struct ToastPlugin;
+
+impl Plugin for ToastPlugin {
+    fn build(&self, app: &mut App) {
+        // Shown in every state, downloads fail before the globe exists
+        app.init_resource::<Toasts>()
+            .init_resource::<NotificationsPanel>()
+            .add_systems(
+                EguiPrimaryContextPass,
+                (
+                    display_toasts.run_if(|toasts: Res<Toasts>| !toasts.active.is_empty()),
+                    display_notifications_panel
+                        .run_if(in_state(GameState::Playing))
+                        .run_if(|panel: Res<NotificationsPanel>| panel.open),
+                ),
+            );
+    }
+}
+
+fn display_toasts(
+    mut contexts: EguiContexts,
+    mut toasts: ResMut<Toasts>,
+    time: Res<Time<Real>>,
+) -> bevy::prelude::Result {
+    let ctx = contexts.ctx_mut()?;
+    let now = time.elapsed_secs();
+
+    for toast in &mut toasts.active {
+        toast.shown_at.get_or_insert(now);
+    }
+    toasts.active.retain(|toast| {
+        toast
+            .shown_at
+            .is_none_or(|shown_at| now - shown_at < toast.severity.duration())
+    });
+
+    let mut dismissed = None;
+    egui::Area::new("Toasts".into())
+        .order(egui::Order::Foreground)
+        .anchor(egui::Align2::RIGHT_BOTTOM, [-12., -12.])
+        .show(ctx, |ui| {
+            for (index, toast) in toasts.active.iter().enumerate() {
+                let age = toast.shown_at.map_or(0., |shown_at| now - shown_at);
+                let opacity = ((toast.severity.duration() - age) / FADE_SECONDS).min(1.);
+                ui.scope(|ui| {
+                    ui.set_opacity(opacity);
+                    let frame = egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.set_max_width(320.);
+                        ui.horizontal(|ui| {
+                            ui.colored_label(toast.severity.color(), toast.severity.icon());
+                            ui.label(toast.text.as_str());
+                        });
+                    });
+                    if frame
+                        .response
+                        .interact(egui::Sense::click())
+                        .on_hover_text("Click to dismiss")
+                        .clicked()
+                    {
+                        dismissed = Some(index);
+                    }
+                });
+            }
+        });
+
+    if let Some(index) = dismissed {
+        toasts.active.remove(index);
+    }
+
+    Ok(())
+}
+
+fn display_notifications_panel(
+    mut contexts: EguiContexts,
+    mut panel: ResMut<NotificationsPanel>,
+    mut toasts: ResMut<Toasts>,
+) -> bevy::prelude::Result {
+    let ctx = contexts.ctx_mut()?;
+
+    let mut clear = false;
+    egui::Window::new("Notifications")
+        .open(&mut panel.open)
+        .default_width(360.)
+        .show(ctx, |ui| {
+            if toasts.history.is_empty() {
+                ui.label("No notifications yet");
+                return;
+            }
+            egui::ScrollArea::vertical()
+                .max_height(300.)
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    for toast in &toasts.history {
+                        ui.horizontal(|ui| {
+                            ui.colored_label(toast.severity.color(), toast.severity.icon());
+                            ui.label(toast.text.as_str());
+                        });
+                    }
+                });
+            ui.separator();
+            clear = ui.button("Clear").clicked();
+        });
+
+    if clear {
+        toasts.history.clear();
+    }
+
+    Ok(())
+}
@@ -0,0 +1,174 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    asset::{Assets, Handle},
+    camera::visibility::Visibility,
+    color::LinearRgba,
+    ecs::{
+        change_detection::{DetectChanges, DetectChangesMut},
+        component::Component,
+        entity::Entity,
+        hierarchy::ChildOf,
+        query::With,
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Query, Res, ResMut, Single},
+    },
+    log::warn,
+    math::Vec3,
+    mesh::{Mesh, Mesh3d, MeshBuilder},
+    pbr::MeshMaterial3d,
+    state::condition::in_state,
+    transform::components::Transform,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    EarthConfig,
+    borders::BorderCrossings,
+    component::{Draped, Earth},
+    math::Coordinates,
+    polyline::{LineJoin, Polyline, PolylineMaterial},
+    state::GameState,
+};
+
+/// Hologram look for dashboards: a dark sphere with glowing coastlines and graticule lines,
+/// hiding the imagery. The outlines come from the active pack's `countries.ron`.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct VectorView {
+    pub enabled: bool,
+    /// Linear RGB color of the lines and the limb
+    pub color: [f32; 3],
+    /// Linear RGB color of the sphere
+    pub background: [f32; 3],
+}
+
+impl Default for VectorView {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            color: [0.1, 1., 0.8],
+            background: [0.005, 0.01, 0.02],
+        }
+    }
+}
+
+/// The country outlines drawn in the vector view.
+#[derive(Component)]
+struct Coastlines(Handle<PolylineMaterial>);
+
+pub struct VectorPlugin;
+
+impl Plugin for VectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<VectorView>().add_systems(
+            Update,
+            (spawn_coastlines, update_coastlines)
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+fn on_antimeridian(&[longitude, _]: &[f32; 2]) -> bool {
+    longitude.abs() >= 180. - 1e-3
+}
+
+/// Lines along a ring of (longitude, latitude) pairs in degrees on a globe of `radius`, leaving
+/// out the edges along the antimeridian the polygons were split at.
+fn ring_lines(ring: &[[f32; 2]], radius: f32) -> Vec<Vec<Vec3>> {
+    let point = |&[longitude, latitude]: &[f32; 2]| {
+        Coordinates::from_degrees(latitude, longitude)
+            .ok()
+            .map(|coordinates| coordinates.direction() * radius)
+    };
+
+    let mut lines = Vec::new();
+    let mut line = Vec::new();
+    let closed = ring.iter().chain(ring.first());
+    for (index, position) in closed.enumerate() {
+        if index > 0 && on_antimeridian(&ring[index - 1]) && on_antimeridian(position) {
+            lines.push(std::mem::take(&mut line));
+        }
+        if let Some(point) = point(position)
+            && line.last() != Some(&point)
+        {
+            line.push(point);
+        }
+    }
+    lines.push(line);
+    lines.retain(|line| line.len() >= 2);
+    lines
+}
+
+fn coastline_mesh(borders: &BorderCrossings, radius: f32) -> Option<Mesh> {
+    let mut lines = borders
+        .countries()
+        .iter()
+        .flat_map(|country| &country.polygons)
+        .flat_map(|ring| ring_lines(ring, radius))
+        .map(|line| Polyline::new(line).build());
+    let mut mesh = lines.next()?;
+    for line in lines {
+        if let Err(err) = mesh.merge(&line) {
+            warn!("Skipping a coastline that can't be merged: {err}");
+        }
+    }
+    Some(mesh)
+}
+
+/// Builds the outlines the first time the view is enabled on a globe, which takes them along
+/// when it is despawned.
+fn spawn_coastlines(
+    mut commands: Commands,
+    view: Res<VectorView>,
+    borders: Res<BorderCrossings>,
+    coastlines: Query<(), With<Coastlines>>,
+    earth: Option<Single<Entity, With<Earth>>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<PolylineMaterial>>,
+    config: Res<EarthConfig>,
+) {
+    let Some(earth) = earth else {
+        return;
+    };
+    if !view.enabled || !coastlines.is_empty() {
+        return;
+    }
+    let Some(mesh) = coastline_mesh(&borders, config.radius) else {
+        return;
+    };
+
+    let [r, g, b] = view.color;
+    let material = materials
+        .add(PolylineMaterial::new(LinearRgba::rgb(r, g, b), 1.2).with_join(LineJoin::Round));
+    commands.spawn((
+        Coastlines(material.clone()),
+        Mesh3d(meshes.add(mesh)),
+        MeshMaterial3d(material),
+        Draped::default(),
+        Transform::default(),
+        Visibility::Hidden,
+        ChildOf(*earth),
+    ));
+}
+
+fn update_coastlines(
+    view: Res<VectorView>,
+    mut coastlines: Query<(&Coastlines, &mut Visibility)>,
+    mut materials: ResMut<Assets<PolylineMaterial>>,
+) {
+    for (coastlines, mut visibility) in &mut coastlines {
+        visibility.set_if_neq(if view.enabled {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+        if view.is_changed()
+            && let Some(material) = materials.get_mut(&coastlines.0)
+        {
+            let [r, g, b] = view.color;
+            material.uniform.color = LinearRgba::rgb(r, g, b);
+            material.uniform.end_color = material.uniform.color;
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use bevy::{
+    app::{App, AppExit, Last, Plugin, Update},
+    ecs::{
+        change_detection::DetectChangesMut,
+        message::MessageReader,
+        query::With,
+        resource::Resource,
+        schedule::{IntoScheduleConfigs, common_conditions::resource_changed},
+        system::{Res, ResMut, Single},
+    },
+    log::error,
+    math::IVec2,
+    window::{
+        MonitorSelection, PresentMode, PrimaryWindow, VideoModeSelection, Window, WindowMode,
+        WindowMoved, WindowPosition, WindowResized,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+const SETTINGS_PATH: &str = "window.ron";
+
+const TITLE: &str = "Bevy Earth";
+
+/// Window sizes offered in the GUI, in logical pixels.
+pub const RESOLUTIONS: [(u32, u32); 5] = [
+    (1280, 720),
+    (1600, 900),
+    (1920, 1080),
+    (2560, 1440),
+    (3840, 2160),
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DisplayMode {
+    #[default]
+    Windowed,
+    /// A borderless window covering the monitor
+    Borderless,
+    /// Exclusive fullscreen at the monitor's current video mode
+    Fullscreen,
+}
+
+impl DisplayMode {
+    pub const ALL: [DisplayMode; 3] = [
+        DisplayMode::Windowed,
+        DisplayMode::Borderless,
+        DisplayMode::Fullscreen,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DisplayMode::Windowed => "Windowed",
+            DisplayMode::Borderless => "Borderless",
+            DisplayMode::Fullscreen => "Fullscreen",
+        }
+    }
+
+    fn window_mode(&self) -> WindowMode {
+        match self {
+            DisplayMode::Windowed => WindowMode::Windowed,
+            DisplayMode::Borderless => WindowMode::BorderlessFullscreen(MonitorSelection::Current),
+            DisplayMode::Fullscreen => {
+                WindowMode::Fullscreen(MonitorSelection::Current, VideoModeSelection::Current)
+            }
+        }
+    }
+}
+
+/// Mode and geometry of the primary window, remembered across runs.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct WindowSettings {
+    pub mode: DisplayMode,
+    pub vsync: bool,
+    /// Size of the window while windowed, in logical pixels
+    pub resolution: (u32, u32),
+    /// Position of the window while windowed, left to the OS until it is first moved
+    pub position: Option<(i32, i32)>,
+}
+
+impl Default for WindowSettings {
+    fn default() -> Self {
+        Self {
+            mode: DisplayMode::Windowed,
+            vsync: true,
+            resolution: RESOLUTIONS[0],
+            position: None,
+        }
+    }
+}
+
+impl WindowSettings {
+    fn load() -> Self {
+        let Ok(serialized) = std::fs::read_to_string(SETTINGS_PATH) else {
+            return Self::default();
+        };
+        ron::from_str(&serialized).unwrap_or_else(|err| {
+            error!("Failed to parse {SETTINGS_PATH}: {err}");
+            Self::default()
+        })
+    }
+
+    fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let serialized = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        std::fs::write(SETTINGS_PATH, serialized)?;
+        Ok(())
+    }
+}
+
+pub struct WindowSettingsPlugin;
+
+impl Plugin for WindowSettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(WindowSettings::load())
+            .add_systems(
+                Update,
+                (
+                    track_geometry,
+                    apply_window_settings.run_if(resource_changed::<WindowSettings>),
+                )
+                    .chain(),
+            )
+            .add_systems(Last, save_on_exit);
+    }
+}
+
+fn apply_window_settings(
+    settings: Res<WindowSettings>,
+    window: Single<&mut Window, With<PrimaryWindow>>,
+) {
+    let mut window = window.into_inner();
+    window.title = TITLE.into();
+    window.mode = settings.mode.window_mode();
+    window.present_mode = if settings.vsync {
+        PresentMode::AutoVsync
+    } else {
+        PresentMode::AutoNoVsync
+    };
+
+    if settings.mode == DisplayMode::Windowed {
+        let (width, height) = settings.resolution;
+        if window.resolution.width() != width as f32 || window.resolution.height() != height as f32
+        {
+            window.resolution.set(width as f32, height as f32);
+        }
+        if let Some((x, y)) = settings.position {
+            window.position = WindowPosition::At(IVec2::new(x, y));
+        }
+    }
+
+    if let Err(err) = settings.save() {
+        error!("Failed to save {SETTINGS_PATH}: {err}");
+    }
+}
+
+/// Follows the window as it is resized or moved while windowed, without applying it back.
+fn track_geometry(
+    mut settings: ResMut<WindowSettings>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    mut resized: MessageReader<WindowResized>,
+    mut moved: MessageReader<WindowMoved>,
+) {
+    let resize = resized
+        .read()
+        .last()
+        .map(|resize| (resize.width, resize.height));
+    let position = moved.read().last().map(|moved| moved.position);
+    if window.mode != WindowMode::Windowed {
+        return;
+    }
+
+    let settings = settings.bypass_change_detection();
+    if let Some((width, height)) = resize {
+        settings.resolution = (width.round() as u32, height.round() as u32);
+    }
+    if let Some(position) = position {
+        settings.position = Some((position.x, position.y));
+    }
+}
+
+fn save_on_exit(mut exit: MessageReader<AppExit>, settings: Res<WindowSettings>) {
+    if exit.read().next().is_some()
+        && let Err(err) = settings.save()
+    {
+        error!("Failed to save {SETTINGS_PATH}: {err}");
+    }
+}
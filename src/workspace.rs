@@ -0,0 +1,357 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    camera::visibility::Visibility,
+    ecs::{
+        change_detection::DetectChangesMut,
+        query::Query,
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{In, Local, Res, ResMut, SystemParam},
+    },
+    log::{error, warn},
+    state::condition::in_state,
+};
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    globe_layer::{GlobeLayerState, GlobeLayers},
+    grading::ColorGradingSettings,
+    input::{Action, Actions, KeyBindings},
+    layer::{BlendMode, RasterLayers},
+    lighting::LightingMode,
+    overlay::GeoJsonLayer,
+    palette::{PaletteCommand, RegisterCommand},
+    session::{SessionAccess, ViewState},
+    state::GameState,
+    stylized::StylizedView,
+    sun::{SunClock, SunMode},
+    toast::Toasts,
+    vector::VectorView,
+};
+
+const WORKSPACES_PATH: &str = "workspaces.ron";
+
+/// Actions switching to the workspaces in order, the number keys by default.
+const SWITCH_ACTIONS: [Action; 9] = [
+    Action::Workspace1,
+    Action::Workspace2,
+    Action::Workspace3,
+    Action::Workspace4,
+    Action::Workspace5,
+    Action::Workspace6,
+    Action::Workspace7,
+    Action::Workspace8,
+    Action::Workspace9,
+];
+
+/// How a raster layer is shown, matched to the pack's layers by name.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LayerState {
+    pub name: String,
+    pub visible: bool,
+    pub opacity: f32,
+    pub blend: BlendMode,
+}
+
+/// The looks a workspace brings along.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct WorkspaceStyle {
+    pub lighting: LightingMode,
+    pub grading: ColorGradingSettings,
+    pub stylized: StylizedView,
+    pub vector: VectorView,
+}
+
+/// A prepared scenario: the view, the layers shown over the globe, their styles and the time.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Workspace {
+    pub name: String,
+    pub view: ViewState,
+    /// Raster layers from bottom to top. Layers of the pack missing here are hidden.
+    pub layers: Vec<LayerState>,
+    /// Names of the GeoJSON layers shown
+    pub vector_layers: Vec<String>,
+    /// Layers of other crates. Registered layers missing here are hidden.
+    #[serde(default)]
+    pub globe_layers: Vec<GlobeLayerState>,
+    pub style: WorkspaceStyle,
+    pub sun: SunMode,
+    pub clock: SunClock,
+}
+
+/// Saved workspaces, persisted to `workspaces.ron`. The first nine are bound to the
+/// `Action::Workspace1` to `Action::Workspace9` keys.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Workspaces(pub Vec<Workspace>);
+
+impl Workspaces {
+    fn load() -> Self {
+        let Ok(serialized) = std::fs::read_to_string(WORKSPACES_PATH) else {
+            return Self::default();
+        };
+        ron::from_str(&serialized).unwrap_or_else(|err| {
+            error!("Failed to parse {WORKSPACES_PATH}: {err}");
+            Self::default()
+        })
+    }
+
+    fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let serialized = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        std::fs::write(WORKSPACES_PATH, serialized)?;
+        Ok(())
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct WorkspacesPanel {
+    pub open: bool,
+}
+
+/// Read and write access to everything a `Workspace` captures.
+#[derive(SystemParam)]
+pub struct WorkspaceAccess<'w, 's> {
+    session: SessionAccess<'w, 's>,
+    layers: ResMut<'w, RasterLayers>,
+    vector_layers: Query<'w, 's, (&'static GeoJsonLayer, &'static mut Visibility)>,
+    globe_layers: ResMut<'w, GlobeLayers>,
+    lighting: ResMut<'w, LightingMode>,
+    grading: ResMut<'w, ColorGradingSettings>,
+    stylized: ResMut<'w, StylizedView>,
+    vector: ResMut<'w, VectorView>,
+    sun: ResMut<'w, SunMode>,
+    clock: ResMut<'w, SunClock>,
+}
+
+impl WorkspaceAccess<'_, '_> {
+    pub fn capture(&self, name: String) -> Option<Workspace> {
+        Some(Workspace {
+            name,
+            view: self.session.capture()?,
+            layers: self
+                .layers
+                .0
+                .iter()
+                .map(|layer| LayerState {
+                    name: layer.name.clone(),
+                    visible: layer.visible,
+                    opacity: layer.opacity,
+                    blend: layer.blend,
+                })
+                .collect(),
+            vector_layers: self
+                .vector_layers
+                .iter()
+                .filter(|(_, visibility)| **visibility != Visibility::Hidden)
+                .map(|(layer, _)| layer.name.clone())
+                .collect(),
+            globe_layers: self
+                .globe_layers
+                .0
+                .iter()
+                .map(|registered| GlobeLayerState {
+                    name: registered.layer.name().to_string(),
+                    visible: registered.visible,
+                    settings: registered.layer.serialize(),
+                })
+                .collect(),
+            style: WorkspaceStyle {
+                lighting: *self.lighting,
+                grading: *self.grading,
+                stylized: *self.stylized,
+                vector: *self.vector,
+            },
+            sun: *self.sun,
+            clock: *self.clock,
+        })
+    }
+
+    pub fn apply(&mut self, workspace: &Workspace) {
+        self.session.apply(&workspace.view);
+
+        let position = |name: &str| workspace.layers.iter().position(|saved| saved.name == name);
+        let mut layers = self.layers.clone();
+        for layer in &mut layers.0 {
+            match position(&layer.name) {
+                Some(index) => {
+                    let saved = &workspace.layers[index];
+                    layer.visible = saved.visible;
+                    layer.opacity = saved.opacity;
+                    layer.blend = saved.blend;
+                }
+                None => layer.visible = false,
+            }
+        }
+        // Layers the workspace doesn't know stay on top, in their order
+        layers
+            .0
+            .sort_by_key(|layer| position(&layer.name).unwrap_or(usize::MAX));
+        if layers != *self.layers {
+            *self.layers = layers;
+        }
+
+        for (layer, mut visibility) in &mut self.vector_layers {
+            visibility.set_if_neq(if workspace.vector_layers.contains(&layer.name) {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            });
+        }
+
+        for registered in &mut self.globe_layers.0 {
+            let saved = workspace
+                .globe_layers
+                .iter()
+                .find(|saved| saved.name == registered.layer.name());
+            registered.visible = saved.is_some_and(|saved| saved.visible);
+            if let Some(saved) = saved
+                && let Err(err) = registered.layer.deserialize(&saved.settings)
+            {
+                warn!("Failed to restore layer {}: {err}", saved.name);
+            }
+        }
+
+        let style = workspace.style;
+        self.lighting.set_if_neq(style.lighting);
+        self.grading.set_if_neq(style.grading);
+        self.stylized.set_if_neq(style.stylized);
+        self.vector.set_if_neq(style.vector);
+        self.sun.set_if_neq(workspace.sun);
+        self.clock.set_if_neq(workspace.clock);
+    }
+}
+
+pub struct WorkspacePlugin;
+
+impl Plugin for WorkspacePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Workspaces::load())
+            .init_resource::<WorkspacesPanel>()
+            .add_systems(
+                Update,
+                switch_workspace.run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                EguiPrimaryContextPass,
+                display_workspaces_panel
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(|panel: Res<WorkspacesPanel>| panel.open),
+            )
+            .register_command_provider(workspace_commands);
+    }
+}
+
+fn switch_workspace(actions: Actions, workspaces: Res<Workspaces>, mut access: WorkspaceAccess) {
+    let pressed = SWITCH_ACTIONS
+        .into_iter()
+        .position(|action| actions.just_pressed(action));
+    if let Some(workspace) = pressed.and_then(|index| workspaces.0.get(index)) {
+        access.apply(workspace);
+    }
+}
+
+fn apply_workspace(In(workspace): In<Workspace>, mut access: WorkspaceAccess) {
+    access.apply(&workspace);
+}
+
+/// A palette command per saved workspace, switching to it.
+fn workspace_commands(workspaces: Res<Workspaces>) -> Vec<PaletteCommand> {
+    workspaces
+        .0
+        .iter()
+        .map(|workspace| {
+            let workspace = workspace.clone();
+            PaletteCommand::new(
+                format!("Switch to workspace {}", workspace.name),
+                move |world| {
+                    if let Err(err) =
+                        world.run_system_cached_with(apply_workspace, workspace.clone())
+                    {
+                        warn!("Failed to switch to workspace {}: {err}", workspace.name);
+                    }
+                },
+            )
+        })
+        .collect()
+}
+
+fn display_workspaces_panel(
+    mut contexts: EguiContexts,
+    mut panel: ResMut<WorkspacesPanel>,
+    mut workspaces: ResMut<Workspaces>,
+    mut access: WorkspaceAccess,
+    bindings: Res<KeyBindings>,
+    mut toasts: ResMut<Toasts>,
+    mut new_name: Local<String>,
+) -> bevy::prelude::Result {
+    let ctx = contexts.ctx_mut()?;
+
+    let mut switch = None;
+    let mut update = None;
+    let mut remove = None;
+    let mut add = false;
+    egui::Window::new("Workspaces")
+        .open(&mut panel.open)
+        .resizable(false)
+        .show(ctx, |ui| {
+            egui::Grid::new("Workspaces").striped(true).show(ui, |ui| {
+                for (index, workspace) in workspaces.0.iter().enumerate() {
+                    let key = SWITCH_ACTIONS
+                        .get(index)
+                        .map_or_else(String::new, |&action| bindings.label(action));
+                    ui.label(key);
+                    if ui.button(workspace.name.as_str()).clicked() {
+                        switch = Some(index);
+                    }
+                    if ui
+                        .small_button("Update")
+                        .on_hover_text("Replace with the current view")
+                        .clicked()
+                    {
+                        update = Some(index);
+                    }
+                    if ui.small_button("🗑").on_hover_text("Delete").clicked() {
+                        remove = Some(index);
+                    }
+                    ui.end_row();
+                }
+            });
+            if workspaces.0.is_empty() {
+                ui.label("No workspaces yet");
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut *new_name).hint_text("Name"));
+                add = ui
+                    .add_enabled(!new_name.trim().is_empty(), egui::Button::new("Save view"))
+                    .on_hover_text("Camera, layers, styles and time")
+                    .clicked();
+            });
+        });
+
+    if let Some(index) = switch {
+        access.apply(&workspaces.0[index]);
+    }
+    let mut changed = false;
+    if let Some(index) = update
+        && let Some(workspace) = access.capture(workspaces.0[index].name.clone())
+    {
+        workspaces.0[index] = workspace;
+        changed = true;
+    }
+    if let Some(index) = remove {
+        workspaces.0.remove(index);
+        changed = true;
+    }
+    if add && let Some(workspace) = access.capture(new_name.trim().to_string()) {
+        workspaces.0.push(workspace);
+        new_name.clear();
+        changed = true;
+    }
+    if changed && let Err(err) = workspaces.save() {
+        toasts.error(format!("Failed to save {WORKSPACES_PATH}: {err}"));
+    }
+
+    Ok(())
+}